@@ -0,0 +1,145 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+};
+
+use crate::{config::AppGroup, discover::InstalledApp};
+
+/// Prefix of `apps` entries naming a group rather than an app, e.g. `group:games`
+pub const GROUP_PREFIX: &str = "group:";
+
+const GAME_CATEGORIES: &[&str] = &[
+    "public.app-category.games",
+    "public.app-category.action-games",
+    "public.app-category.adventure-games",
+    "public.app-category.arcade-games",
+    "public.app-category.board-games",
+    "public.app-category.card-games",
+    "public.app-category.casino-games",
+    "public.app-category.dice-games",
+    "public.app-category.educational-games",
+    "public.app-category.family-games",
+    "public.app-category.kids-games",
+    "public.app-category.music-games",
+    "public.app-category.puzzle-games",
+    "public.app-category.racing-games",
+    "public.app-category.role-playing-games",
+    "public.app-category.simulation-games",
+    "public.app-category.sports-games",
+    "public.app-category.strategy-games",
+    "public.app-category.trivia-games",
+    "public.app-category.word-games",
+];
+/// Launchers and studios whose apps don't always declare a category
+const GAME_BUNDLE_IDS: &[&str] = &[
+    "com.valvesoftware.",
+    "com.epicgames.",
+    "com.blizzard.",
+    "net.battle.",
+    "com.riotgames.",
+    "com.gog.",
+    "com.mojang.",
+    "com.codeweavers.CrossOver",
+];
+/// Safari is left out, it lives on the sealed system volume and can't be blocked
+const BROWSER_BUNDLE_IDS: &[&str] = &[
+    "com.google.Chrome",
+    "org.mozilla.firefox",
+    "company.thebrowser.",
+    "com.brave.Browser",
+    "com.microsoft.edgemac",
+    "com.operasoftware.",
+    "com.vivaldi.Vivaldi",
+    "org.chromium.Chromium",
+    "app.zen-browser.",
+    "org.torproject.torbrowser",
+];
+
+/// The group an `apps` entry like `group:games` names, `None` for an app path.
+pub fn group_name(app: &Path) -> Option<&str> {
+    app.to_str()?.strip_prefix(GROUP_PREFIX)
+}
+
+/// The group called `name`, from the config's `app-groups` or else the built-in ones
+/// (`games`, `browsers`).
+pub fn find(name: &str, groups: &HashMap<String, AppGroup>) -> Option<AppGroup> {
+    if let Some(group) = groups.get(name) {
+        return Some(group.clone());
+    }
+
+    let to_strings = |values: &[&str]| values.iter().map(|value| value.to_string()).collect();
+    match name {
+        "games" => Some(AppGroup {
+            bundle_ids: to_strings(GAME_BUNDLE_IDS),
+            categories: to_strings(GAME_CATEGORIES),
+        }),
+        "browsers" => Some(AppGroup {
+            bundle_ids: to_strings(BROWSER_BUNDLE_IDS),
+            categories: Vec::new(),
+        }),
+        _ => None,
+    }
+}
+
+/// Whether an installed app belongs to `group`, by bundle ID prefix or category.
+pub fn contains(group: &AppGroup, app: &InstalledApp) -> bool {
+    let by_bundle_id = app.bundle_id.as_ref().is_some_and(|bundle_id| {
+        group
+            .bundle_ids
+            .iter()
+            .any(|prefix| bundle_id.starts_with(prefix.as_str()))
+    });
+    let by_category = app
+        .category
+        .as_ref()
+        .is_some_and(|category| group.categories.contains(category));
+
+    by_bundle_id || by_category
+}
+
+/// The installed apps in any of `groups`, each with the name of the first group it's in.
+pub fn members(
+    groups: &BTreeMap<String, AppGroup>,
+    installed: &[InstalledApp],
+) -> BTreeMap<PathBuf, String> {
+    let mut members = BTreeMap::new();
+    for (name, group) in groups {
+        for app in installed.iter().filter(|app| contains(group, app)) {
+            members
+                .entry(app.path.clone())
+                .or_insert_with(|| name.clone());
+        }
+    }
+    members
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn games_match_by_category_or_launcher() {
+        let games = find("games", &HashMap::new()).unwrap();
+        let app = |bundle_id: &str, category: Option<&str>| InstalledApp {
+            path: PathBuf::from("/Applications/Some.app"),
+            bundle_id: Some(bundle_id.to_string()),
+            archs: None,
+            category: category.map(str::to_string),
+        };
+
+        assert!(contains(&games, &app("com.valvesoftware.steam", None)));
+        assert!(contains(
+            &games,
+            &app("com.example.chess", Some("public.app-category.board-games"))
+        ));
+        assert!(!contains(
+            &games,
+            &app(
+                "com.example.notes",
+                Some("public.app-category.productivity")
+            )
+        ));
+        assert_eq!(group_name(Path::new("group:games")), Some("games"));
+        assert_eq!(group_name(Path::new("/Applications/Steam.app")), None);
+    }
+}
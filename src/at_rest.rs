@@ -0,0 +1,255 @@
+use anyhow::{Context, Ok, Result};
+use std::{
+    fs::{self, OpenOptions},
+    io::{Read as _, Write as _},
+    os::unix::fs::OpenOptionsExt as _,
+    path::Path,
+    process::{Command, Stdio},
+    thread,
+};
+
+use crate::{block::data_dir, history, sandbox};
+
+/// In the data directory while the history and block state are written encrypted
+const MARKER_FILE: &str = "encrypted";
+/// First line of an encrypted file, the rest is base64 from `openssl enc`
+const HEADER: &str = "enough-encrypted:1\n";
+/// The key lives in the System keychain, which root reads without a prompt and a copy
+/// of the data directory doesn't include
+const KEYCHAIN: &str = "/Library/Keychains/System.keychain";
+const KEYCHAIN_SERVICE: &str = "enough-at-rest";
+/// Where the key is kept without a Keychain, only readable by root
+const KEY_FILE: &str = "/etc/enough/at-rest.key";
+/// How the key is handed to openssl, so it never shows up in the arguments
+const KEY_ENV_VAR: &str = "ENOUGH_AT_REST_KEY";
+
+/// Whether new writes of the history and block state are encrypted.
+pub fn enabled() -> bool {
+    data_dir().join(MARKER_FILE).exists()
+}
+
+/// Whether this process can get the key, which takes root.
+pub fn can_open() -> bool {
+    sandbox::root().is_some() || unsafe { libc::geteuid() } == 0
+}
+
+/// Whether the file at `path` was written encrypted.
+pub fn is_sealed(path: &Path) -> bool {
+    fs::read_to_string(path).is_ok_and(|content| content.starts_with(HEADER))
+}
+
+/// `content` encrypted if encryption is on, as is otherwise.
+pub fn seal(content: &str) -> Result<String> {
+    if !enabled() {
+        return Ok(content.to_string());
+    }
+    encrypt(content, &key()?)
+}
+
+/// `content` decrypted if it was written encrypted, as is otherwise.
+pub fn open(content: String) -> Result<String> {
+    if !content.starts_with(HEADER) {
+        return Ok(content);
+    }
+    decrypt(&content, &key()?)
+}
+
+/// Turns encryption on or off as the config asks, rewriting the users' histories to
+/// match. The key is kept when turning it off, for backups taken while it was on.
+pub fn apply(on: bool) -> Result<()> {
+    if on == enabled() {
+        return Ok(());
+    }
+
+    let key = match read_key()? {
+        Some(key) => key,
+        None if on => create_key()?,
+        None => anyhow::bail!("The key the history was encrypted with is gone"),
+    };
+    fs::create_dir_all(data_dir())?;
+    let mut histories = Vec::new();
+    for entry in fs::read_dir(data_dir())? {
+        let path = entry?.path();
+        if history::is_history_file(&path) {
+            let content = fs::read_to_string(&path)?;
+            let content = if content.starts_with(HEADER) {
+                decrypt(&content, &key)?
+            } else {
+                content
+            };
+            histories.push((path, content));
+        }
+    }
+
+    let marker = data_dir().join(MARKER_FILE);
+    if on {
+        fs::write(&marker, "")?;
+    } else {
+        fs::remove_file(&marker)?;
+    }
+    for (path, content) in histories {
+        let content = if on {
+            encrypt(&content, &key)?
+        } else {
+            content
+        };
+        fs::write(&path, content)
+            .with_context(|| format!("Failed to rewrite {}", path.display()))?;
+    }
+    Ok(())
+}
+
+fn encrypt(content: &str, key: &str) -> Result<String> {
+    let ciphertext = openssl("-e", content.as_bytes(), key)?;
+    Ok(format!(
+        "{}{}",
+        HEADER,
+        String::from_utf8(ciphertext)?.trim()
+    ))
+}
+
+fn decrypt(content: &str, key: &str) -> Result<String> {
+    let sealed = content.strip_prefix(HEADER).unwrap_or(content);
+    let plaintext = openssl("-d", format!("{}\n", sealed.trim()).as_bytes(), key)
+        .with_context(|| "Failed to decrypt, the key doesn't match the one it was written with")?;
+    Ok(String::from_utf8(plaintext)?)
+}
+
+/// Runs `openssl enc` one way or the other (`-e` or `-d`) on `input`.
+fn openssl(direction: &str, input: &[u8], key: &str) -> Result<Vec<u8>> {
+    let mut child = Command::new("openssl")
+        .args([
+            "enc",
+            "-aes-256-cbc",
+            "-pbkdf2",
+            "-md",
+            "sha256",
+            "-a",
+            "-A",
+        ])
+        .arg(direction)
+        .args(["-pass", &format!("env:{}", KEY_ENV_VAR)])
+        .env(KEY_ENV_VAR, key)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| "Failed to run openssl")?;
+    let mut stdin = child
+        .stdin
+        .take()
+        .with_context(|| "Failed to open openssl's stdin")?;
+
+    // written from another thread, a large input would fill the pipes both ways
+    let output = thread::scope(|scope| {
+        let writer = scope.spawn(move || stdin.write_all(input));
+        let output = child.wait_with_output();
+        let _ = writer.join();
+        output
+    })?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("openssl failed: {}", stderr.trim());
+    }
+    Ok(output.stdout)
+}
+
+fn key() -> Result<String> {
+    if !can_open() {
+        anyhow::bail!("The history and block state are encrypted, reading them needs sudo");
+    }
+    read_key()?.with_context(|| "The key the history and block state were encrypted with is gone")
+}
+
+fn uses_keychain() -> bool {
+    cfg!(target_os = "macos") && sandbox::root().is_none()
+}
+
+fn read_key() -> Result<Option<String>> {
+    if uses_keychain() {
+        let output = Command::new("security")
+            .args([
+                "find-generic-password",
+                "-s",
+                KEYCHAIN_SERVICE,
+                "-w",
+                KEYCHAIN,
+            ])
+            .output()
+            .with_context(|| "Failed to run security")?;
+        let key = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        return Ok((output.status.success() && !key.is_empty()).then_some(key));
+    }
+
+    let key_file = sandbox::path(KEY_FILE);
+    if !key_file.exists() {
+        return Ok(None);
+    }
+    Ok(Some(fs::read_to_string(key_file)?.trim().to_string()))
+}
+
+fn create_key() -> Result<String> {
+    let mut bytes = [0; 32];
+    fs::File::open("/dev/urandom")?.read_exact(&mut bytes)?;
+    let key = bytes
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+
+    if uses_keychain() {
+        // as a command on `security`'s stdin, so the key isn't in anyone's process list
+        let mut child = Command::new("security")
+            .arg("-i")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| "Failed to run security")?;
+        child
+            .stdin
+            .take()
+            .with_context(|| "Failed to open security's stdin")?
+            .write_all(
+                format!(
+                    "add-generic-password -s {} -a enough -w {} {}\n",
+                    KEYCHAIN_SERVICE, key, KEYCHAIN
+                )
+                .as_bytes(),
+            )?;
+        let output = child.wait_with_output()?;
+        if !output.status.success() || read_key()?.as_deref() != Some(key.as_str()) {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!("Failed to add the key to the Keychain: {}", stderr.trim());
+        }
+        return Ok(key);
+    }
+
+    let key_file = sandbox::path(KEY_FILE);
+    if let Some(parent) = key_file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(&key_file)
+        .with_context(|| format!("Failed to create {}", key_file.display()))?
+        .write_all(key.as_bytes())?;
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sealed_content_opens_back_with_the_same_key_only() -> Result<()> {
+        let content = "{\"profile_name\":\"work\"}\n".repeat(5000);
+        let sealed = encrypt(&content, "key")?;
+        assert!(sealed.starts_with(HEADER));
+        assert!(!sealed.contains("work"));
+        assert_eq!(decrypt(&sealed, "key")?, content);
+        assert!(decrypt(&sealed, "other").is_err());
+        Ok(())
+    }
+}
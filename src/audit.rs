@@ -0,0 +1,114 @@
+use anyhow::{Context, Ok, Result};
+use chrono::{DateTime, Days, Local, NaiveTime};
+use serde::{Deserialize, Serialize};
+use std::{
+    env,
+    fs::{self, OpenOptions},
+    io::Write as _,
+    path::PathBuf,
+};
+
+use crate::{block::data_dir, sandbox, user::invoking_uid};
+
+const AUDIT_LOG_FILE: &str = "audit.log";
+const LOCKOUT_FILE: &str = "lockout";
+
+/// A record of something that weakened a block, appended to the audit log.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub time: DateTime<Local>,
+    pub event: String,
+    pub user: Option<String>,
+    pub profile_name: String,
+    pub reason: String,
+}
+
+impl AuditRecord {
+    pub fn new(event: &str, profile_name: &str, reason: &str) -> Self {
+        Self {
+            time: Local::now(),
+            event: event.to_string(),
+            user: env::var("SUDO_USER").ok(),
+            profile_name: profile_name.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+pub fn audit_log_path() -> PathBuf {
+    data_dir().join(AUDIT_LOG_FILE)
+}
+
+/// Lockouts are per user, another user's emergency doesn't stop your blocks.
+fn lockout_path() -> PathBuf {
+    data_dir().join(format!("{}.{}", LOCKOUT_FILE, invoking_uid()))
+}
+
+/// Appends a record to the audit log. The log is flagged append-only, so entries can't
+/// be quietly edited out afterwards.
+pub fn append(record: &AuditRecord) -> Result<()> {
+    let path = audit_log_path();
+    fs::create_dir_all(data_dir())?;
+    let is_new = !path.exists();
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open audit log `{}`", path.display()))?;
+    writeln!(file, "{}", serde_json::to_string(record)?)?;
+
+    if is_new {
+        let output = sandbox::command("chflags")
+            .arg("uappnd")
+            .arg(&path)
+            .output()?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            eprintln!("Couldn't make the audit log append-only: {}", stderr.trim());
+        }
+    }
+
+    Ok(())
+}
+
+/// Prevents new blocks from being started until the end of the current day.
+pub fn lock_out_for_today() -> Result<DateTime<Local>> {
+    let until = Local::now()
+        .date_naive()
+        .checked_add_days(Days::new(1))
+        .and_then(|tomorrow| {
+            tomorrow
+                .and_time(NaiveTime::MIN)
+                .and_local_timezone(Local)
+                .earliest()
+        })
+        .with_context(|| "Failed to compute the end of the day")?;
+
+    fs::create_dir_all(data_dir())?;
+    fs::write(lockout_path(), until.timestamp().to_string())?;
+
+    Ok(until)
+}
+
+/// Returns when the current lockout ends, if one is in effect.
+pub fn lockout_until() -> Result<Option<DateTime<Local>>> {
+    let path = lockout_path();
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let secs = fs::read_to_string(&path)?
+        .trim()
+        .parse::<i64>()
+        .with_context(|| format!("Corrupted lockout file `{}`", path.display()))?;
+    let until = DateTime::from_timestamp(secs, 0)
+        .with_context(|| format!("Corrupted lockout file `{}`", path.display()))?
+        .with_timezone(&Local);
+
+    if until <= Local::now() {
+        return Ok(None);
+    }
+
+    Ok(Some(until))
+}
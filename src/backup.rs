@@ -0,0 +1,303 @@
+use anyhow::{Context, Ok, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::{
+    env, fs,
+    os::unix::fs as unix_fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+use uuid::Uuid;
+
+use crate::{block::data_dir, config::EnoughConfig, household::Household, user::invoking_uid};
+
+const MANIFEST_FILE: &str = "manifest.yaml";
+const CONFIG_FILE: &str = "enough.yaml";
+const DATA_DIR: &str = "data";
+/// Stands in for the uid in per-user file names, which differs between machines
+const UID_PLACEHOLDER: &str = "UID";
+/// Signed with this machine's key, which isn't backed up, so they wouldn't verify elsewhere
+const CONTRACTS_FILE_PREFIX: &str = "contracts.";
+
+/// What a backup holds, along with the secrets it deliberately leaves out.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct Manifest {
+    version: String,
+    created: DateTime<Local>,
+    /// Per-user data files, named with [`UID_PLACEHOLDER`] in place of the uid
+    data_files: Vec<String>,
+    /// Secrets the backup refers to but doesn't contain, with how to set them up again
+    secrets: Vec<String>,
+}
+
+/// Writes the config and the invoking user's data (history, achievements, usage, saved
+/// work context) to a `.tar.gz` archive at `output`.
+///
+/// Secrets are only referenced in the manifest: the household passphrase, the contract
+/// signing key (so contracts stay on this machine) and password commands in the config.
+pub fn backup(config_path: Option<PathBuf>, output: &Path) -> Result<()> {
+    let staging = staging_dir();
+    fs::create_dir_all(staging.join(DATA_DIR))?;
+    let result = (|| {
+        let config_path = EnoughConfig::resolve_path(config_path)?;
+        fs::copy(&config_path, staging.join(CONFIG_FILE))
+            .with_context(|| format!("Failed to copy {}", config_path.display()))?;
+
+        let (contracts, data_paths) = user_data_files()
+            .into_iter()
+            .partition::<Vec<_>, _>(|path| is_contracts_file(path));
+        let mut data_files = Vec::new();
+        for path in data_paths {
+            let Some(name) = generic_name(&path) else {
+                continue;
+            };
+            fs::copy(&path, staging.join(DATA_DIR).join(&name))
+                .with_context(|| format!("Failed to copy {}", path.display()))?;
+            data_files.push(name);
+        }
+
+        let mut secrets = Vec::new();
+        if Household::path().exists() {
+            secrets.push("the household passphrase, with `enough household setup`".to_string());
+        }
+        if !contracts.is_empty() {
+            secrets.push("contracts, signed with this machine's key, which stays here".to_string());
+        }
+        secrets.extend(password_commands(&fs::read_to_string(&config_path)?));
+
+        let manifest = Manifest {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            created: Local::now(),
+            data_files,
+            secrets,
+        };
+        fs::write(
+            staging.join(MANIFEST_FILE),
+            serde_yml::to_string(&manifest)?,
+        )?;
+
+        run_tar(
+            Command::new("tar")
+                .arg("-czf")
+                .arg(output)
+                .arg("-C")
+                .arg(&staging)
+                .arg("."),
+        )?;
+        Ok(manifest)
+    })();
+
+    fs::remove_dir_all(&staging)?;
+    let manifest = result?;
+
+    println!(
+        "Backed up the config and {} data files to {}",
+        manifest.data_files.len(),
+        output.display()
+    );
+    for secret in &manifest.secrets {
+        eprintln!("Not included: {}", secret);
+    }
+    Ok(())
+}
+
+/// Restores a backup for the invoking user, writing the config to `config_path` (the
+/// default location without one). Existing files are only replaced with `force`.
+pub fn restore(archive: &Path, config_path: Option<PathBuf>, force: bool) -> Result<()> {
+    let staging = staging_dir();
+    fs::create_dir_all(&staging)?;
+    let result = (|| {
+        run_tar(
+            Command::new("tar")
+                .arg("-xzf")
+                .arg(archive)
+                .arg("-C")
+                .arg(&staging),
+        )?;
+        let manifest = serde_yml::from_str::<Manifest>(
+            &fs::read_to_string(staging.join(MANIFEST_FILE))
+                .with_context(|| format!("{} isn't an enough backup", archive.display()))?,
+        )?;
+
+        let config_path = config_path.unwrap_or_else(EnoughConfig::default_config_path);
+        let data_dir = data_dir();
+        let uid = invoking_uid().to_string();
+        let data_paths = manifest
+            .data_files
+            .iter()
+            .filter(|name| !name.contains('/') && !name.contains(".."))
+            .map(|name| {
+                let restored = name
+                    .split('.')
+                    .map(|part| {
+                        if part == UID_PLACEHOLDER {
+                            uid.as_str()
+                        } else {
+                            part
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(".");
+                (staging.join(DATA_DIR).join(name), data_dir.join(restored))
+            })
+            .collect::<Vec<_>>();
+
+        if !force
+            && let Some(existing) = std::iter::once(&config_path)
+                .chain(data_paths.iter().map(|(_, to)| to))
+                .find(|path| path.exists())
+        {
+            anyhow::bail!(
+                "{} already exists, pass `--force` to replace it",
+                existing.display()
+            );
+        }
+
+        if let Some(parent) = config_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(staging.join(CONFIG_FILE), &config_path)?;
+        // restored through sudo, but the config is the user's to edit
+        unix_fs::chown(&config_path, Some(invoking_uid()), None)?;
+
+        fs::create_dir_all(&data_dir)?;
+        for (from, to) in &data_paths {
+            fs::copy(from, to).with_context(|| format!("Failed to restore {}", to.display()))?;
+        }
+
+        println!(
+            "Restored the config to {} and {} data files",
+            config_path.display(),
+            data_paths.len()
+        );
+        for secret in &manifest.secrets {
+            eprintln!("Set up again: {}", secret);
+        }
+
+        Ok(())
+    })();
+
+    fs::remove_dir_all(&staging)?;
+    result
+}
+
+/// The invoking user's files in the data directory, the ones with their uid in the name
+fn user_data_files() -> Vec<PathBuf> {
+    let uid = invoking_uid().to_string();
+    let mut paths = fs::read_dir(data_dir())
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.split('.').any(|part| part == uid))
+        })
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths
+}
+
+fn is_contracts_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with(CONTRACTS_FILE_PREFIX))
+}
+
+/// `path` with the uid in its name replaced by [`UID_PLACEHOLDER`]
+fn generic_name(path: &Path) -> Option<String> {
+    let uid = invoking_uid().to_string();
+    let name = path.file_name()?.to_str()?;
+    let generic = name
+        .split('.')
+        .map(|part| if part == uid { UID_PLACEHOLDER } else { part })
+        .collect::<Vec<_>>()
+        .join(".");
+    Some(generic)
+}
+
+/// Password commands in the config, whose secrets live outside of it
+fn password_commands(config: &str) -> Vec<String> {
+    config
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("password-command:"))
+        .map(|command| {
+            format!(
+                "whatever `{}` reads the password from, like a keychain item",
+                command.trim()
+            )
+        })
+        .collect()
+}
+
+fn staging_dir() -> PathBuf {
+    env::temp_dir().join(format!("enough-backup-{}", Uuid::new_v4()))
+}
+
+fn run_tar(command: &mut Command) -> Result<()> {
+    let output = command.output().with_context(|| "Failed to run tar")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("tar failed: {}", stderr.trim());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sandbox;
+
+    #[test]
+    fn a_backup_restores_the_config_and_data_it_saved() {
+        sandbox::for_tests();
+        let dir = env::temp_dir().join(format!("enough-test-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let config = dir.join("enough.yaml");
+        fs::write(
+            &config,
+            "profiles:\n  focus:\n    password-command: pass show enough\n",
+        )
+        .unwrap();
+        fs::create_dir_all(data_dir()).unwrap();
+        let name = format!("{}.{}.yaml", Uuid::new_v4(), invoking_uid());
+        let data = data_dir().join(&name);
+        fs::write(&data, "sessions: 3\n").unwrap();
+        let archive = dir.join("backup.tar.gz");
+
+        backup(Some(config.clone()), &archive).unwrap();
+        fs::remove_file(&data).unwrap();
+        let restored = dir.join("restored.yaml");
+        restore(&archive, Some(restored.clone()), true).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&restored).unwrap(),
+            fs::read_to_string(&config).unwrap()
+        );
+        assert_eq!(fs::read_to_string(&data).unwrap(), "sessions: 3\n");
+        assert!(restore(&archive, Some(restored), false).is_err());
+        fs::remove_file(&data).unwrap();
+    }
+
+    #[test]
+    fn names_drop_the_uid_and_password_commands_are_listed() {
+        let uid = invoking_uid();
+        assert_eq!(
+            generic_name(Path::new(&format!("/data/history.{}.yaml", uid))).as_deref(),
+            Some("history.UID.yaml")
+        );
+        assert!(is_contracts_file(Path::new(&format!(
+            "/data/contracts.{}.yaml",
+            uid
+        ))));
+        assert!(!is_contracts_file(Path::new("/data/history.yaml")));
+        let secrets = password_commands("a: 1\n    password-command: pass show enough\n");
+        assert_eq!(secrets.len(), 1);
+        assert!(secrets[0].contains("`pass show enough`"));
+    }
+}
@@ -1,235 +1,3471 @@
 use anyhow::{Context, Ok, Result};
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Local, NaiveTime, TimeDelta};
 use humantime_serde::re::humantime::format_duration;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, de::IgnoredAny};
 use std::{
-    fs,
-    path::PathBuf,
+    collections::{BTreeMap, HashMap},
+    env,
+    fmt::Display,
+    fs::{self, File, Permissions, TryLockError},
+    os::unix::fs::{self as unix_fs, PermissionsExt as _},
+    path::{Path, PathBuf},
     process::Command,
+    thread,
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
 use url::Url;
+use uuid::Uuid;
 
 use crate::{
-    config::Profile,
-    daemon::{EnoughDaemon, UnblockingDaemon},
+    app_groups, at_rest, audit,
+    clock::ClockAnchor,
+    config::{
+        AppGroup, ConfigSource, CoolDownKeep, EnoughConfig, Friction, HistoryPrivacy, Profile,
+        Push, PushEvent, Quota, SaveContext, StoredNames, Website, WebsitesBackend,
+    },
+    context::WorkContext,
+    daemon::{EnoughDaemon, UNBLOCK_TOKEN_FILE, UnblockingDaemon},
+    discover,
+    dns_proxy::DnsProxy,
+    events::{self, EventKind},
+    focus,
+    history::{self, HISTORY_SCHEMA_VERSION, Session},
+    hooks,
+    hosts::{self, HostsSection},
+    partner,
+    progress::{Progress, ProgressFormat},
+    resolver::ResolverFiles,
+    sandbox, scheduler, site_apps,
+    steps::{Direction, Step, StepRecord},
+    tabs,
+    time_format::TimeFormat,
+    together::Membership,
+    usage::{self, AppUsage, QuotaUsage},
+    user::{as_invoking_user, invoking_uid},
+};
+#[cfg(feature = "webhooks")]
+use crate::{audit::AuditRecord, notify};
+#[cfg(all(feature = "pf", target_os = "macos"))]
+use crate::{
+    pf::{self, PacketFilter},
+    vpn,
 };
 
-const HOSTS_FILE: &str = "/etc/hosts";
-const ENOUGH_MARKER_START: &str = "# ENOUGH BLOCK START";
-const ENOUGH_MARKER_END: &str = "# ENOUGH BLOCK END";
-const ENOUGH_STATE_DIR: &str = "/tmp/enough";
+/// Alongside the data, rather than in `/tmp`, so blocks survive a restart
+#[cfg(target_os = "macos")]
+const ENOUGH_STATE_DIR: &str = "/Library/Application Support/enough/state";
+#[cfg(target_os = "linux")]
+const ENOUGH_STATE_DIR: &str = "/var/lib/enough/state";
+/// Where states were kept before, still read so blocks started then can end
+#[cfg(target_os = "macos")]
+const LEGACY_STATE_DIRS: &[&str] = &["/tmp/enough"];
+#[cfg(target_os = "linux")]
+const LEGACY_STATE_DIRS: &[&str] = &["/tmp/enough", "/Library/Application Support/enough/state"];
+/// Version of the block state's layout, bumped when a field is renamed, removed or
+/// changes meaning
+pub const STATE_SCHEMA_VERSION: u32 = 1;
+/// Version of what `enough status --json` and `enough block --headless` print, same
+/// rules as [`STATE_SCHEMA_VERSION`]
+pub const STATUS_SCHEMA_VERSION: u32 = 1;
+/// Persistent data that must outlive a single block (audit log, lockouts)
+#[cfg(target_os = "macos")]
+const ENOUGH_DATA_DIR: &str = "/Library/Application Support/enough";
+#[cfg(target_os = "linux")]
+const ENOUGH_DATA_DIR: &str = "/var/lib/enough";
+const STATUS_SIDECAR_FILE: &str = "status";
+/// Under the invoking user's home
+const REMAINING_FILE: &str = ".cache/enough/remaining";
 const BLOCKED_APP_PERMS: &str = "000";
-const UNBLOCKED_APP_PERMS: &str = "755";
+const APP_PERMS_BACKUP_FILE: &str = "app_perms.yaml";
+/// Config edits made during a strict block, written once it ends
+const QUEUED_CONFIG_FILE: &str = "queued_config.yaml";
+/// Modes of the executables inside blocked app bundles, see [`bundle_executables`]
+const EXECUTABLES_PERMS_BACKUP_FILE: &str = "executables_perms.yaml";
+/// How deep helper apps are looked for under a bundle's `Contents`, frameworks keep
+/// them at `Frameworks/<name>.framework/Versions/A/Helpers`
+const HELPER_APPS_DEPTH: usize = 6;
+#[cfg(all(feature = "pf", target_os = "macos"))]
+const PF_SITES_ANCHOR: &str = "sites";
+#[cfg(all(feature = "pf", target_os = "macos"))]
+const PF_DOT_ANCHOR: &str = "dot";
+/// Drops DNS over TLS, which has a port of its own
+#[cfg(all(feature = "pf", target_os = "macos"))]
+const DOT_RULES: &str = "block drop out quick proto { tcp udp } from any to any port 853\n";
+/// Number of domains after which macOS resolvers start to noticeably slow down
+const DEFAULT_HOSTS_LIMIT: usize = 1000;
+const UNBLOCK_ATTEMPTS_FILE: &str = "unblock_attempts";
+/// First delay before a failed scheduled unblock is retried, doubling on every attempt
+const UNBLOCK_RETRY_DELAY: Duration = Duration::from_secs(2 * 60);
+const UNBLOCK_RETRY_MAX_DELAY: Duration = Duration::from_secs(30 * 60);
+/// How much earlier than planned a scheduled unblock may run before it counts as tampering
+const CLOCK_TOLERANCE: Duration = Duration::from_secs(2 * 60);
+/// How long an alert waits for a click, under the scheduler's interval so it doesn't
+/// hold the next check up
+const ALERT_TIMEOUT: Duration = Duration::from_secs(50);
+/// How many of the latest sessions a [`BlockSnapshot`] carries
+const SNAPSHOT_SESSIONS: usize = 10;
+/// A block of the same profile started this recently is taken as a repeat of it, like a
+/// keybinding firing twice, rather than a restart
+const REPEAT_DEBOUNCE: Duration = Duration::from_secs(10);
+/// How many times, and how long apart, the install watcher looks again at apps that
+/// are still being copied
+const INSTALL_SETTLE_ATTEMPTS: usize = 6;
+const INSTALL_SETTLE_DELAY: Duration = Duration::from_secs(5);
+/// Relative to the home dir
+const TRASH_DIR: &str = ".Trash";
+/// Changed by Homebrew and the App Store when they (re)install an app
+const REINSTALL_DIRS: &[&str] = &[
+    "/opt/homebrew/Caskroom",
+    "/usr/local/Caskroom",
+    "/Library/Receipts",
+];
+/// How long ago the Caskroom changed for a reinstall to be put down to Homebrew
+const RECENT_INSTALL: Duration = Duration::from_secs(5 * 60);
+/// Added to a strict block for every blocked app deleted during it
+const APP_REMOVAL_PENALTY: Duration = Duration::from_secs(30 * 60);
+/// How often the relaunch watcher looks for blocked apps running anyway
+const RELAUNCH_POLL_INTERVAL: Duration = Duration::from_secs(3);
+/// How long a job being replaced may be missing before the jobs guard reports it
+const JOBS_SETTLE_DELAY: Duration = Duration::from_secs(2);
+/// Hosts blocked along with popular websites, since the hosts file only matches exact
+/// names: their other subdomains and the hosts their pages and videos load from
+const KNOWN_SUBDOMAINS: &[(&str, &[&str])] = &[
+    (
+        "youtube.com",
+        &[
+            "m.youtube.com",
+            "music.youtube.com",
+            "gaming.youtube.com",
+            "tv.youtube.com",
+            "youtu.be",
+            "youtube-nocookie.com",
+            "www.youtube-nocookie.com",
+            "youtubei.googleapis.com",
+            "i.ytimg.com",
+            "s.ytimg.com",
+            "yt3.ggpht.com",
+            "googlevideo.com",
+            "redirector.googlevideo.com",
+        ],
+    ),
+    (
+        "reddit.com",
+        &[
+            "old.reddit.com",
+            "new.reddit.com",
+            "np.reddit.com",
+            "m.reddit.com",
+            "i.reddit.com",
+            "gateway.reddit.com",
+            "gql.reddit.com",
+            "oauth.reddit.com",
+            "redd.it",
+            "i.redd.it",
+            "v.redd.it",
+            "preview.redd.it",
+            "external-preview.redd.it",
+            "www.redditstatic.com",
+            "www.redditmedia.com",
+        ],
+    ),
+    (
+        "twitter.com",
+        &[
+            "mobile.twitter.com",
+            "api.twitter.com",
+            "x.com",
+            "www.x.com",
+            "api.x.com",
+            "t.co",
+            "abs.twimg.com",
+            "pbs.twimg.com",
+            "video.twimg.com",
+        ],
+    ),
+    (
+        "x.com",
+        &[
+            "mobile.x.com",
+            "api.x.com",
+            "twitter.com",
+            "www.twitter.com",
+            "mobile.twitter.com",
+            "t.co",
+            "abs.twimg.com",
+            "pbs.twimg.com",
+            "video.twimg.com",
+        ],
+    ),
+    (
+        "facebook.com",
+        &[
+            "m.facebook.com",
+            "mobile.facebook.com",
+            "web.facebook.com",
+            "touch.facebook.com",
+            "mbasic.facebook.com",
+            "graph.facebook.com",
+            "static.xx.fbcdn.net",
+            "scontent.xx.fbcdn.net",
+            "fb.com",
+            "www.fb.com",
+        ],
+    ),
+    (
+        "instagram.com",
+        &[
+            "i.instagram.com",
+            "graph.instagram.com",
+            "static.cdninstagram.com",
+            "scontent.cdninstagram.com",
+        ],
+    ),
+    (
+        "tiktok.com",
+        &[
+            "m.tiktok.com",
+            "vm.tiktok.com",
+            "vt.tiktok.com",
+            "tiktokcdn.com",
+            "tiktokv.com",
+        ],
+    ),
+    (
+        "twitch.tv",
+        &[
+            "m.twitch.tv",
+            "player.twitch.tv",
+            "clips.twitch.tv",
+            "gql.twitch.tv",
+            "static.twitchcdn.net",
+            "static-cdn.jtvnw.net",
+            "usher.ttvnw.net",
+        ],
+    ),
+    (
+        "netflix.com",
+        &[
+            "assets.nflxext.com",
+            "nflxvideo.net",
+            "nflximg.net",
+            "nflxso.net",
+        ],
+    ),
+    (
+        "linkedin.com",
+        &["m.linkedin.com", "static.licdn.com", "media.licdn.com"],
+    ),
+    (
+        "discord.com",
+        &[
+            "discordapp.com",
+            "www.discordapp.com",
+            "discord.gg",
+            "gateway.discord.gg",
+            "canary.discord.com",
+            "ptb.discord.com",
+            "cdn.discordapp.com",
+            "media.discordapp.net",
+        ],
+    ),
+    ("pinterest.com", &["i.pinimg.com", "s.pinimg.com", "pin.it"]),
+];
+/// Hosts of the public DNS over HTTPS resolvers browsers offer, blocked with `block-doh`
+/// so they fall back to the system's resolver
+const DOH_ENDPOINTS: &[&str] = &[
+    "cloudflare-dns.com",
+    "mozilla.cloudflare-dns.com",
+    "chrome.cloudflare-dns.com",
+    "1dot1dot1dot1.cloudflare-dns.com",
+    "one.one.one.one",
+    "dns.google",
+    "dns.google.com",
+    "dns64.dns.google",
+    "dns.quad9.net",
+    "dns9.quad9.net",
+    "dns10.quad9.net",
+    "dns11.quad9.net",
+    "doh.opendns.com",
+    "doh.familyshield.opendns.com",
+    "dns.nextdns.io",
+    "firefox.dns.nextdns.io",
+    "chromium.dns.nextdns.io",
+    "doh.cleanbrowsing.org",
+    "dns.adguard.com",
+    "dns.adguard-dns.com",
+    "doh.xfinity.com",
+    "dns.mullvad.net",
+    "doh.mullvad.net",
+    "freedns.controld.com",
+    "doh.dns.sb",
+    "dns.alidns.com",
+    "doh.pub",
+];
+
+/// The half of a profile a block is limited to, like when chmod-ing apps would get in the
+/// way of an update.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BlockScope {
+    Websites,
+    Apps,
+}
+
+impl BlockScope {
+    /// `profile` without what's out of scope.
+    fn narrow(self, profile: &Profile) -> Profile {
+        match self {
+            Self::Websites => Profile {
+                apps: Vec::new(),
+                app_limits: Vec::new(),
+                ..profile.clone()
+            },
+            Self::Apps => Profile {
+                websites: Vec::new(),
+                vpn_kill_switch: None,
+                close_tabs: None,
+                friction: None,
+                ..profile.clone()
+            },
+        }
+    }
+}
+
+impl Display for BlockScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Websites => write!(f, "websites"),
+            Self::Apps => write!(f, "apps"),
+        }
+    }
+}
+
+/// Items of a profile a block is limited to, or leaves out, from `--only` and `--except`.
+/// Websites are given by URL or host name, apps by path, name or `group:<name>`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ItemFilter {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub only: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub except: Vec<String>,
+}
+
+impl ItemFilter {
+    pub fn is_empty(&self) -> bool {
+        self.only.is_empty() && self.except.is_empty()
+    }
+
+    /// `profile` with the items of `only`, if there are any, minus the ones of `except`.
+    /// Items the profile doesn't list are an error, they're likely typos.
+    fn narrow(&self, profile: &Profile) -> Result<Profile> {
+        for item in self.only.iter().chain(&self.except) {
+            if !profile
+                .websites
+                .iter()
+                .any(|website| is_website(item, &website.url))
+                && !profile.apps.iter().any(|app| is_app(item, app))
+                && !profile
+                    .app_limits
+                    .iter()
+                    .any(|limit| is_app(item, &limit.app))
+            {
+                anyhow::bail!("`{}` isn't one of the profile's websites or apps", item);
+            }
+        }
+
+        let kept = |matches: &dyn Fn(&str) -> bool| {
+            (self.only.is_empty() || self.only.iter().any(|item| matches(item)))
+                && !self.except.iter().any(|item| matches(item))
+        };
+        let mut profile = profile.clone();
+        profile
+            .websites
+            .retain(|website| kept(&|item| is_website(item, &website.url)));
+        profile.apps.retain(|app| kept(&|item| is_app(item, app)));
+        profile
+            .app_limits
+            .retain(|limit| kept(&|item| is_app(item, &limit.app)));
+        Ok(profile)
+    }
+}
+
+impl Display for ItemFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.only.is_empty(), self.except.is_empty()) {
+            (false, true) => write!(f, "Only {}", self.only.join(", ")),
+            (true, false) => write!(f, "Without {}", self.except.join(", ")),
+            _ => write!(
+                f,
+                "Only {}, without {}",
+                self.only.join(", "),
+                self.except.join(", ")
+            ),
+        }
+    }
+}
+
+/// Whether `item` names the website at `url`, with or without its scheme and `www.`.
+fn is_website(item: &str, url: &Url) -> bool {
+    let host = Url::parse(item)
+        .ok()
+        .and_then(|item| item.host_str().map(str::to_string))
+        .unwrap_or_else(|| item.trim_end_matches('/').to_lowercase());
+    let bare = |host: &str| host.trim_start_matches("www.").to_string();
+    url.host_str()
+        .is_some_and(|url_host| bare(url_host) == bare(&host))
+}
+
+/// Whether `item` names `app`, by its path, its name with or without `.app`, or the
+/// group entry.
+fn is_app(item: &str, app: &Path) -> bool {
+    app == Path::new(item)
+        || app
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().eq_ignore_ascii_case(item))
+        || app
+            .file_stem()
+            .is_some_and(|stem| stem.to_string_lossy().eq_ignore_ascii_case(item))
+}
+
+/// Why a block attempt left things as they are instead of starting a block. Returned as
+/// an error from [`BlockManager::block_items`], for callers to tell apart from failures.
+#[derive(Debug, PartialEq, Eq)]
+pub enum BlockSkipped {
+    /// Another invocation is applying a block right now
+    InProgress,
+    /// The same profile was started moments ago
+    Repeated { profile_name: String },
+}
+
+impl Display for BlockSkipped {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InProgress => write!(f, "Another block is already being applied"),
+            Self::Repeated { profile_name } => {
+                write!(f, "Profile `{}` was started moments ago", profile_name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BlockSkipped {}
 
 pub struct BlockManager {
+    /// State of the invoking user, kept apart from other users' blocks
     pub state_dir: PathBuf,
+    uid: u32,
+    progress: Progress,
+    options: BlockOptions,
+    /// Handed over by the scheduled unblock, which may lift a strict block
+    unblock_token: Option<String>,
+    /// Whether the accountability partner's code was taken, which lifting the block
+    /// early needs if it was started with one
+    partner_code_taken: bool,
+}
+
+/// Settings from the config file that affect how blocks are applied, as opposed to
+/// what gets blocked.
+#[derive(Debug, Clone)]
+pub struct BlockOptions {
+    /// Domains beyond this count are kept out of the hosts file
+    pub hosts_limit: usize,
+    /// Block domains beyond `hosts_limit` through pf, instead of just warning
+    pub spill_to_pf: bool,
+    /// Block DNS over HTTPS endpoints and DNS over TLS during blocks with websites
+    pub block_doh: bool,
+    /// The config file the block was started from
+    pub config_source: Option<ConfigSource>,
+    pub websites_backend: WebsitesBackend,
+    /// Log block events to the local event log
+    pub events: bool,
+    /// How long the history is kept and how names are written to it
+    pub history: HistoryPrivacy,
+    /// How often to update the remaining-time file, if at all
+    pub remaining_file_interval: Option<Duration>,
+    pub app_groups: HashMap<String, AppGroup>,
+    /// Only print what blocking would do
+    pub dry_run: bool,
+    pub time_format: TimeFormat,
+    /// Only block the websites, or only the apps, of the profile
+    pub only: Option<BlockScope>,
+    /// Websites and apps of the profile left out of the block, or the only ones in it
+    pub items: ItemFilter,
+    /// The profile's `break-profile`, with its name
+    pub break_block: Option<(String, Profile)>,
+    /// The room of a group session the block is part of, see `enough together`
+    pub together: Option<Membership>,
+    /// Leave out everything needing a GUI session: notifications, hooks, tabs
+    pub headless: bool,
+    /// Started `--until` a time of day rather than for a duration
+    pub until: bool,
+    /// Told when tampering with the block is caught
+    pub accountability_webhook: Option<Url>,
+    /// Lifting the block early takes a code from the accountability partner
+    pub partner: bool,
+    /// Where the block's start, end and tampering are pushed to
+    pub push: Option<Push>,
+}
+
+impl Default for BlockOptions {
+    fn default() -> Self {
+        Self {
+            hosts_limit: DEFAULT_HOSTS_LIMIT,
+            spill_to_pf: cfg!(all(feature = "pf", target_os = "macos")),
+            block_doh: false,
+            config_source: None,
+            websites_backend: WebsitesBackend::default(),
+            events: false,
+            history: HistoryPrivacy::default(),
+            remaining_file_interval: None,
+            app_groups: HashMap::new(),
+            dry_run: false,
+            time_format: TimeFormat::default(),
+            only: None,
+            items: ItemFilter::default(),
+            break_block: None,
+            together: None,
+            headless: false,
+            until: false,
+            accountability_webhook: None,
+            partner: false,
+            push: None,
+        }
+    }
+}
+
+impl From<&EnoughConfig> for BlockOptions {
+    fn from(config: &EnoughConfig) -> Self {
+        Self {
+            hosts_limit: config.hosts_limit.unwrap_or(DEFAULT_HOSTS_LIMIT),
+            spill_to_pf: config.spill_to_pf && cfg!(all(feature = "pf", target_os = "macos")),
+            block_doh: config.block_doh,
+            config_source: config.source.clone(),
+            websites_backend: config.websites_backend,
+            events: config.events,
+            history: config.history,
+            remaining_file_interval: config.remaining_file.as_ref().map(|file| file.interval),
+            app_groups: config.app_groups.clone(),
+            dry_run: false,
+            time_format: config.time_format,
+            only: None,
+            items: ItemFilter::default(),
+            break_block: None,
+            together: None,
+            headless: false,
+            until: false,
+            accountability_webhook: config
+                .accountability
+                .as_ref()
+                .and_then(|accountability| accountability.webhook.clone()),
+            partner: config
+                .accountability
+                .as_ref()
+                .is_some_and(|accountability| accountability.partner),
+            push: config.push.clone(),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize)]
 struct BlockState {
+    /// [`STATE_SCHEMA_VERSION`] of the version that wrote it, 0 from before there was
+    /// one
+    #[serde(default, alias = "schema")]
+    schema_version: u32,
     profile_name: String,
     profile: Profile,
     unblock_time_secs: u64,
+    /// Whether the main block is over and only the cool-down part is left
+    #[serde(default)]
+    cooling_down: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    config_source: Option<ConfigSource>,
+    /// What the profile expanded to at block start, so later queries don't redo lookups
+    /// that may have changed since
+    #[serde(default)]
+    resolved: ResolvedBlocklist,
+    /// Set in strict mode, so the unblock doesn't go by the wall clock alone
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    clock_guard: Option<ClockGuard>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    exceptions: Vec<TempException>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    app_exceptions: Vec<AppException>,
+    /// How much of the profile's `app-allowance` was spent on `allow-app`
+    #[serde(default)]
+    app_allowance_used_secs: u64,
+    /// Kept for the daemons, which update the websites without the config
+    #[serde(default)]
+    websites_backend: WebsitesBackend,
+    /// The DNS over HTTPS endpoints are blocked along with the websites
+    #[serde(default)]
+    block_doh: bool,
+    /// UTC offset the unblock was scheduled in, since launchd goes by local time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    utc_offset_secs: Option<i32>,
+    /// Whether the daemons log events too
+    #[serde(default)]
+    events: bool,
+    /// How the daemons write down the apps they catch
+    #[serde(default)]
+    names: StoredNames,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    started_secs: Option<u64>,
+    /// Kept up to date with the seconds left, see [`BlockManager::write_remaining_file`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    remaining_file: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    remaining_interval_secs: Option<u64>,
+    /// For the notifications the daemons post
+    #[serde(default)]
+    time_format: TimeFormat,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    only: Option<BlockScope>,
+    #[serde(default, skip_serializing_if = "ItemFilter::is_empty")]
+    items: ItemFilter,
+    /// Blocked when this block ends
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    break_block: Option<BreakBlock>,
+    /// Left when the block is lifted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    together: Option<Membership>,
+    /// The config as a strict block was started from it, put back when an edit takes
+    /// blocked items out of the profile
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    config_backup: Option<String>,
+    /// Started without a GUI session to post notifications in
+    #[serde(default)]
+    headless: bool,
+    /// Started `--until` a time of day, so the status tells that time
+    #[serde(default)]
+    until: bool,
+    /// Kept for the guards, which report tampering without the config
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    accountability_webhook: Option<Url>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    push: Option<Push>,
+}
+
+/// A config edit held back by a strict block, see [`BlockManager::save_config`].
+#[derive(Serialize, Deserialize)]
+struct QueuedConfig {
+    path: PathBuf,
+    content: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct BreakBlock {
+    profile_name: String,
+    profile: Profile,
+}
+
+/// A domain let through the block for a while, see [`BlockManager::allow_temp`]
+#[derive(Serialize, Deserialize, Clone)]
+struct TempException {
+    /// Without `www.`, both variants are let through
+    domain: String,
+    until_secs: u64,
+}
+
+/// A blocked app let through for a while, see [`BlockManager::allow_app`]
+#[derive(Serialize, Deserialize, Clone)]
+struct AppException {
+    app: PathBuf,
+    until_secs: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ClockGuard {
+    anchor: ClockAnchor,
+    duration_secs: u64,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct ResolvedBlocklist {
+    /// Every domain of every website, allow windows aside
+    domains: Vec<String>,
+    /// Listed apps, along with site apps, group members and used up app limits
+    apps: Vec<PathBuf>,
+    /// Apps picked up through an app group, with the group's name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    grouped: BTreeMap<PathBuf, String>,
+    /// Definitions of the app groups, kept for the install watcher which runs without
+    /// the config
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    groups: BTreeMap<String, AppGroup>,
+    /// Group members installed during the block, and blocked as they showed up
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    auto_blocked: Vec<PathBuf>,
+    /// Blocked apps removed during a strict block, which already extended it
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    removed: Vec<PathBuf>,
+    /// Listed apps that were gone at block start, with where their bundle ID was found
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    moved: BTreeMap<PathBuf, PathBuf>,
+}
+
+impl BlockState {
+    /// Whether the block's time is up, going by wall clock and, where it can tell, by the
+    /// time that really passed since it started.
+    fn is_over(&self) -> bool {
+        let unblock_time = UNIX_EPOCH + Duration::from_secs(self.unblock_time_secs);
+        if SystemTime::now() + CLOCK_TOLERANCE < unblock_time {
+            return false;
+        }
+        self.cooling_down
+            || self.clock_guard.as_ref().is_none_or(|guard| {
+                guard.anchor.elapsed().is_none_or(|elapsed| {
+                    elapsed + CLOCK_TOLERANCE >= Duration::from_secs(guard.duration_secs)
+                })
+            })
+    }
+
+    /// Posts a notification, unless the block was started headless.
+    fn alert(&self, subtitle: &str, message: &str) -> Result<()> {
+        if self.headless {
+            return Ok(());
+        }
+        alert(subtitle, message)
+    }
+
+    /// Pushes `title` and `message` to the phone, if the config's `push` wants `event`.
+    /// Best-effort, like the notifications.
+    fn push(&self, event: PushEvent, title: &str, message: &str) {
+        #[cfg(feature = "webhooks")]
+        if let Some(push) = &self.push
+            && let Err(err) = notify::push(push, event, title, message)
+        {
+            eprintln!("{:#}", err);
+        }
+        #[cfg(not(feature = "webhooks"))]
+        let _ = (event, title, message);
+    }
+
+    /// Reports tampering caught on `target`: logs the event, flags the session in the
+    /// history and tells the accountability webhook and the phone. All of it is
+    /// best-effort, so the guards still put things back when reporting fails.
+    fn tampered(&self, target: &str, path: Option<&Path>) {
+        if self.events {
+            events::emit(EventKind::TamperDetected {
+                profile_name: self.profile_name.clone(),
+                target: target.to_string(),
+                path: path.and_then(|path| self.names.path(path)),
+            });
+        }
+        if let Err(err) = history::record_tamper(target) {
+            eprintln!("Couldn't flag the tampering in the history: {:#}", err);
+        }
+        self.push(
+            PushEvent::Tamper,
+            "Tampering caught",
+            &format!(
+                "`{}` of the `{}` block was undone",
+                target, self.profile_name
+            ),
+        );
+
+        #[cfg(feature = "webhooks")]
+        if let Some(webhook) = &self.accountability_webhook {
+            let reason = match path {
+                Some(path) => format!("{} ({})", target, path.display()),
+                None => target.to_string(),
+            };
+            let record = AuditRecord::new("tamper", &self.profile_name, &reason);
+            if let Err(err) = notify::post_webhook(webhook, &record) {
+                eprintln!("{:#}", err);
+            }
+        }
+    }
+
+    /// Whether the cool-down already lifted the websites.
+    fn websites_lifted(&self) -> bool {
+        self.cooling_down
+            && self
+                .profile
+                .cool_down
+                .as_ref()
+                .is_some_and(|cool_down| matches!(cool_down.keep, CoolDownKeep::Apps))
+    }
+
+    /// Whether the cool-down already lifted the apps.
+    fn apps_lifted(&self) -> bool {
+        self.cooling_down
+            && self
+                .profile
+                .cool_down
+                .as_ref()
+                .is_some_and(|cool_down| matches!(cool_down.keep, CoolDownKeep::Websites))
+    }
+
+    fn allowed_domains(&self) -> Vec<String> {
+        let now_secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        self.exceptions
+            .iter()
+            .filter(|exception| exception.until_secs > now_secs)
+            .map(|exception| exception.domain.clone())
+            .collect()
+    }
+
+    fn allowed_apps(&self) -> Vec<PathBuf> {
+        let now_secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        self.app_exceptions
+            .iter()
+            .filter(|exception| exception.until_secs > now_secs)
+            .map(|exception| exception.app.clone())
+            .collect()
+    }
 }
 
 impl BlockManager {
     pub fn new() -> Self {
+        let uid = invoking_uid();
+        // a block started before the state moved keeps it where it is, the unblock
+        // removes it along with the rest
+        let state_dir = LEGACY_STATE_DIRS
+            .iter()
+            .map(|dir| sandbox::path(dir).join(uid.to_string()))
+            .find(|legacy| legacy.exists())
+            .unwrap_or_else(|| state_root().join(uid.to_string()));
         Self {
-            state_dir: PathBuf::from(ENOUGH_STATE_DIR),
+            state_dir,
+            uid,
+            progress: Progress::default(),
+            options: BlockOptions::default(),
+            unblock_token: None,
+            partner_code_taken: false,
+        }
+    }
+
+    /// Users with a block in place right now, going by their state directories.
+    pub fn blocked_uids() -> Vec<u32> {
+        let mut uids = [ENOUGH_STATE_DIR]
+            .into_iter()
+            .chain(LEGACY_STATE_DIRS.iter().copied())
+            .filter_map(|dir| fs::read_dir(sandbox::path(dir)).ok())
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().join("current_block.yaml").exists())
+            .filter_map(|entry| entry.file_name().to_str()?.parse().ok())
+            .collect::<Vec<_>>();
+        uids.sort();
+        uids.dedup();
+        uids
+    }
+
+    pub fn with_progress(mut self, progress: Progress) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    pub fn with_options(mut self, options: BlockOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn with_unblock_token(mut self, token: Option<String>) -> Self {
+        self.unblock_token = token;
+        self
+    }
+
+    /// Takes the `code` the accountability partner read out, if the block needs one.
+    pub fn with_partner_code(mut self, code: &str) -> Result<Self> {
+        if let Some(secret) = self.partner_secret()? {
+            partner::redeem(&secret, code)?;
+            self.partner_code_taken = true;
+        }
+        Ok(self)
+    }
+
+    pub fn block_items(
+        &self,
+        profile_name: &str,
+        profile: &Profile,
+        duration: Duration,
+    ) -> Result<()> {
+        let profile = &match self.options.only {
+            Some(scope) => scope.narrow(profile),
+            None => profile.clone(),
+        };
+        if let Some(scope) = self.options.only
+            && profile.websites.is_empty()
+            && profile.apps.is_empty()
+            && profile.app_limits.is_empty()
+        {
+            anyhow::bail!("Profile `{}` has no {} to block", profile_name, scope);
+        }
+        let profile = &self.options.items.narrow(profile)?;
+        if !self.options.items.is_empty()
+            && profile.websites.is_empty()
+            && profile.apps.is_empty()
+            && profile.app_limits.is_empty()
+        {
+            anyhow::bail!(
+                "{} leaves nothing of profile `{}` to block",
+                self.options.items,
+                profile_name
+            );
+        }
+
+        let now = Local::now();
+        if let Some(window) = profile.allowed_start_window
+            && !window.contains(now.time())
+        {
+            anyhow::bail!(
+                "Profile `{}` can only be started between {} and {}, it's {} now",
+                profile_name,
+                window.start.format("%H:%M"),
+                window.end.format("%H:%M"),
+                now.format("%H:%M")
+            );
+        }
+
+        for website in &profile.websites {
+            if let Some(ignored) = website.ignored_part() {
+                self.progress.message(&format!(
+                    "Warning: only whole domains can be blocked, `{}` of {} is ignored",
+                    ignored,
+                    website.url.host_str().unwrap_or_default()
+                ));
+            }
+        }
+
+        // held until the block is in place, so concurrent invocations don't interleave
+        let _lock = self.lock()?;
+        if let Some(state) = self.read_block_state()?
+            && state.profile_name == profile_name
+            && state.started_secs.is_some_and(|started| {
+                now.timestamp().max(0) as u64 <= started + REPEAT_DEBOUNCE.as_secs()
+            })
+        {
+            return Err(BlockSkipped::Repeated {
+                profile_name: profile_name.to_string(),
+            }
+            .into());
+        }
+
+        if self.options.partner && partner::saved_secret()?.is_none() {
+            anyhow::bail!(
+                "`partner` is set but there's no secret, make one with `sudo enough code --new-secret`"
+            );
+        }
+        #[cfg(not(all(feature = "pf", target_os = "macos")))]
+        if profile.vpn_kill_switch.is_some() {
+            anyhow::bail!("The VPN kill switch needs pf, which only macOS builds with `pf` have");
+        }
+        #[cfg(not(all(feature = "pf", target_os = "macos")))]
+        if self.options.websites_backend == WebsitesBackend::Pf && !profile.websites.is_empty() {
+            anyhow::bail!(
+                "The pf websites backend needs pf, which only macOS builds with `pf` have"
+            );
         }
+
+        if profile.friction.is_some() && self.options.websites_backend != WebsitesBackend::Hosts {
+            anyhow::bail!("Soft blocks (`friction`) only work with the hosts backend");
+        }
+        if self.options.websites_backend == WebsitesBackend::Hosts && !profile.websites.is_empty() {
+            hosts::check_editable()?;
+        }
+
+        let state = self.resolve(profile_name, profile, duration, now)?;
+        if self.options.dry_run {
+            return self.run_steps(Direction::Block, false, |step| {
+                if let Some(description) = describe_step(step, &state) {
+                    println!("Would {}", description);
+                }
+                Ok(())
+            });
+        }
+
+        self.unblock_all()?; // cleaning up any previous state
+        fs::create_dir_all(&self.state_dir)?; // Creating state directory
+        at_rest::apply(self.options.history.encrypt)?;
+        self.run_steps(Direction::Block, false, |step| {
+            self.apply_step(step, &state)
+        })?;
+
+        let unblock_time = UNIX_EPOCH + Duration::from_secs(state.unblock_time_secs);
+        history::record(&Session {
+            schema_version: HISTORY_SCHEMA_VERSION,
+            profile_name: profile_name.to_string(),
+            start: now,
+            end: unblock_time.into(),
+            ended_early: false,
+            continues: 0,
+            reinstalled: Vec::new(),
+            tampered: Vec::new(),
+            abort_reason: None,
+            abort_screenshot: None,
+        })?;
+        if let Some(keep) = self.options.history.keep
+            && let Err(err) = history::purge(Some(keep)).and_then(|_| events::purge(Some(keep)))
+        {
+            self.progress.message(&format!(
+                "Warning: couldn't drop the old history: {:#}",
+                err
+            ));
+        }
+        if self.options.events {
+            events::emit(EventKind::BlockStarted {
+                profile_name: profile_name.to_string(),
+                unblock_time: unblock_time.into(),
+                websites: profile.websites.len(),
+                apps: state.resolved.apps.len(),
+            });
+        }
+        state.push(
+            PushEvent::Start,
+            "Block started",
+            &format!(
+                "`{}` until {}",
+                profile_name,
+                DateTime::<Local>::from(unblock_time).format("%H:%M")
+            ),
+        );
+
+        Ok(())
+    }
+
+    /// Works out everything the block will hold (domains, apps, the unblock time), without
+    /// applying any of it.
+    fn resolve(
+        &self,
+        profile_name: &str,
+        profile: &Profile,
+        duration: Duration,
+        now: DateTime<Local>,
+    ) -> Result<BlockState> {
+        let groups = self.resolve_app_groups(&profile.apps)?;
+        // scanning is slow, so it's only done when a group needs it
+        let grouped = if groups.is_empty() {
+            BTreeMap::new()
+        } else {
+            app_groups::members(&groups, &discover::installed_apps())
+        };
+        let listed = profile
+            .apps
+            .iter()
+            .filter(|app| app_groups::group_name(app).is_none())
+            .cloned()
+            .collect::<Vec<_>>();
+        discover::remember_bundle_ids(&listed)?;
+        let mut moved = BTreeMap::new();
+        let mut apps = Vec::new();
+        for app in listed {
+            if app.exists() {
+                apps.push(app);
+                continue;
+            }
+            let Some(found) = discover::locate_moved(&app) else {
+                anyhow::bail!(
+                    "{} is gone and its bundle ID wasn't found anywhere else",
+                    app.display()
+                );
+            };
+            self.progress.message(&format!(
+                "Warning: {} is gone, blocking it at {} instead",
+                app.display(),
+                found.display()
+            ));
+            apps.push(found.clone());
+            moved.insert(app, found);
+        }
+        for app in grouped.keys() {
+            if !apps.contains(app) {
+                apps.push(app.clone());
+            }
+        }
+        if !grouped.is_empty() {
+            self.progress.message(&format!(
+                "Found {} installed apps in app groups",
+                grouped.len()
+            ));
+        }
+        if !profile.app_limits.is_empty() {
+            // allowances used up earlier today stay used up
+            let usage = AppUsage::load(self.uid)?;
+            apps.extend(
+                profile
+                    .app_limits
+                    .iter()
+                    .filter(|limit| usage.used(&limit.app) >= limit.allowance)
+                    .map(|limit| limit.app.clone()),
+            );
+        }
+        let site_apps = site_apps::matching(&profile.urls());
+        if !site_apps.is_empty() {
+            self.progress.message(&format!(
+                "Found {} installed site apps for blocked websites",
+                site_apps.len()
+            ));
+            apps.extend(site_apps.into_iter().map(|app| app.path));
+        }
+
+        // anchored before the unblock time is set, so the NTP lookup can't eat into the block
+        let anchor = profile.strict.then(ClockAnchor::now);
+        let unblock_time = SystemTime::now() + duration;
+
+        Ok(BlockState {
+            schema_version: STATE_SCHEMA_VERSION,
+            profile_name: profile_name.to_string(),
+            profile: profile.clone(),
+            unblock_time_secs: unblock_time.duration_since(UNIX_EPOCH)?.as_secs(),
+            cooling_down: false,
+            config_source: self.options.config_source.clone(),
+            clock_guard: anchor.map(|anchor| ClockGuard {
+                anchor,
+                duration_secs: duration.as_secs(),
+            }),
+            resolved: ResolvedBlocklist {
+                domains: profile.websites.iter().flat_map(website_hosts).collect(),
+                apps,
+                grouped,
+                groups,
+                auto_blocked: Vec::new(),
+                removed: Vec::new(),
+                moved,
+            },
+            exceptions: Vec::new(),
+            app_exceptions: Vec::new(),
+            app_allowance_used_secs: 0,
+            websites_backend: self.options.websites_backend,
+            block_doh: self.options.block_doh,
+            utc_offset_secs: Some(utc_offset_secs()),
+            events: self.options.events,
+            names: self.options.history.names,
+            started_secs: Some(now.timestamp().max(0) as u64),
+            remaining_file: self
+                .options
+                .remaining_file_interval
+                .and_then(|_| env::home_dir())
+                .map(|home| home.join(REMAINING_FILE)),
+            remaining_interval_secs: self
+                .options
+                .remaining_file_interval
+                .map(|interval| interval.as_secs()),
+            time_format: self.options.time_format,
+            only: self.options.only,
+            items: self.options.items.clone(),
+            break_block: self
+                .options
+                .break_block
+                .clone()
+                .map(|(profile_name, profile)| BreakBlock {
+                    profile_name,
+                    profile: Profile {
+                        break_profile: None,
+                        ..profile
+                    },
+                }),
+            together: self.options.together.clone(),
+            config_backup: self
+                .options
+                .config_source
+                .as_ref()
+                .filter(|_| profile.strict)
+                .and_then(ConfigSource::read_unchanged),
+            headless: self.options.headless,
+            until: self.options.until,
+            accountability_webhook: self.options.accountability_webhook.clone(),
+            push: self.options.push.clone(),
+        })
+    }
+
+    /// Runs `steps` in order through `run`, recording how each one went in the state
+    /// directory. With `resume`, steps an unfinished run in the same direction already
+    /// got done are skipped.
+    fn run_steps(
+        &self,
+        direction: Direction,
+        resume: bool,
+        mut run: impl FnMut(Step) -> Result<()>,
+    ) -> Result<()> {
+        let steps = match direction {
+            Direction::Block => Step::BLOCK,
+            Direction::Unblock => Step::UNBLOCK,
+        };
+        let mut record = match StepRecord::load(&self.state_dir)? {
+            Some(record) if resume && record.direction == direction => record,
+            _ => StepRecord::new(direction),
+        };
+
+        for &step in steps {
+            if record.is_done(step) {
+                continue;
+            }
+
+            let result = run(step);
+            if self.options.dry_run {
+                result?;
+                continue;
+            }
+
+            record.record(step, result.as_ref().err().map(|err| format!("{:#}", err)));
+            record.save(&self.state_dir)?;
+            result.with_context(|| format!("The `{}` step of the {} failed", step, direction))?;
+        }
+
+        Ok(())
+    }
+
+    /// Applies one step of the block in `state`. Also used to repair an active block, so
+    /// parts the cool-down already lifted are left alone.
+    fn apply_step(&self, step: Step, state: &BlockState) -> Result<()> {
+        let profile = &state.profile;
+        match step {
+            Step::Hosts => {
+                if !profile.websites.is_empty() && !state.websites_lifted() {
+                    self.block_websites(
+                        &enforced_websites(profile, Local::now().time()),
+                        &state.allowed_domains(),
+                        state.websites_backend,
+                        profile.friction.is_some(),
+                        state.block_doh,
+                    )?;
+                    if profile.strict && state.websites_backend == WebsitesBackend::Hosts {
+                        HostsSection::new(&self.state_dir, self.uid).lock()?;
+                    }
+                }
+            }
+            Step::Pf => {
+                #[cfg(all(feature = "pf", target_os = "macos"))]
+                if let Some(kill_switch) = &profile.vpn_kill_switch {
+                    vpn::engage(kill_switch, &PacketFilter::new(&self.state_dir, self.uid))?;
+                    self.progress.message("VPN kill switch engaged");
+                }
+                // pf is only there on macOS, Linux keeps DNS over TLS
+                #[cfg(all(feature = "pf", target_os = "macos"))]
+                if state.block_doh && !profile.websites.is_empty() && !state.websites_lifted() {
+                    PacketFilter::new(&self.state_dir, self.uid)
+                        .load_anchor(PF_DOT_ANCHOR, DOT_RULES)?;
+                    self.progress.message("Blocked DNS over TLS using pf");
+                }
+            }
+            Step::DnsFlush => {
+                if !profile.websites.is_empty() {
+                    hosts::flush_dns_cache()?;
+                }
+            }
+            Step::Session => {
+                if self.options.headless {
+                    self.progress
+                        .message("Headless, leaving notifications, hooks and tabs alone");
+                } else {
+                    self.start_session(profile)?;
+                }
+            }
+            Step::Apps => {
+                if !state.resolved.apps.is_empty() && !state.apps_lifted() {
+                    self.block_apps(&state.resolved.apps)?;
+                }
+            }
+            Step::Daemon => self.schedule_jobs(state)?,
+            Step::State => {
+                self.write_block_state(state)?;
+                if self.options.partner
+                    && let Some(secret) = partner::saved_secret()?
+                {
+                    partner::write_secret(&self.state_dir.join(partner::SECRET_FILE), &secret)?;
+                }
+                if state.remaining_file.is_some() {
+                    self.write_remaining_file()?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Silences notifications, runs the start hooks, saves the work context and closes
+    /// tabs on blocked websites, whichever the profile asks for.
+    fn start_session(&self, profile: &Profile) -> Result<()> {
+        if let Some(silence) = &profile.silence_notifications {
+            focus::silence(silence, &self.state_dir)?;
+            self.progress.message(&format!(
+                "Silenced notifications from {} apps",
+                silence.apps.len()
+            ));
+        }
+
+        if let Some(hooks) = &profile.hooks {
+            hooks::start(hooks, &self.state_dir)?;
+        }
+
+        // before the tab audit, so the snapshot still sees every tab
+        if let Some(save) = &profile.save_context
+            && let Err(err) = self.save_context(save, &profile.urls())
+        {
+            self.progress.message(&format!(
+                "Warning: couldn't save the work context: {:#}",
+                err
+            ));
+        }
+
+        if let Some(mode) = profile.close_tabs {
+            // browsers may not allow automation, which shouldn't stop the block
+            let urls = enforced_websites(profile, Local::now().time())
+                .into_iter()
+                .map(|website| website.url)
+                .collect::<Vec<_>>();
+            let closed = tabs::audit(&urls, mode).unwrap_or_else(|err| {
+                self.progress
+                    .message(&format!("Warning: couldn't close browser tabs: {:#}", err));
+                0
+            });
+            if closed > 0 {
+                self.progress
+                    .message(&format!("Closed {} tabs on blocked websites", closed));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Schedules the unblock of `state` along with every job the block needs, replacing
+    /// the ones already there. Removing them takes the saved state too, so the `state`
+    /// step has to follow.
+    fn schedule_jobs(&self, state: &BlockState) -> Result<()> {
+        EnoughDaemon::remove(&self.state_dir)?;
+
+        let profile = &state.profile;
+        let apps = &state.resolved.apps;
+        let unblock_time = UNIX_EPOCH + Duration::from_secs(state.unblock_time_secs);
+        if profile.strict && EnoughDaemon::HANDS_OVER_TOKEN {
+            let token_file = self.state_dir.join(UNBLOCK_TOKEN_FILE);
+            fs::write(&token_file, Uuid::new_v4().to_string())?;
+            fs::set_permissions(&token_file, Permissions::from_mode(0o600))?;
+        }
+        self.schedule_unblock(unblock_time.into())?;
+
+        let boundaries = profile
+            .websites
+            .iter()
+            .filter_map(|website| website.allow)
+            .flat_map(|window| [window.start, window.end])
+            .collect::<Vec<_>>();
+        if !boundaries.is_empty() {
+            EnoughDaemon::schedule_windows(&self.state_dir, &boundaries)?;
+        }
+        if !profile.app_limits.is_empty() {
+            EnoughDaemon::track_usage(&self.state_dir, usage::TRACK_INTERVAL)?;
+        }
+        if profile.friction.is_some() {
+            EnoughDaemon::serve_friction(&self.state_dir)?;
+        }
+        if state.websites_backend == WebsitesBackend::DnsProxy && !profile.websites.is_empty() {
+            EnoughDaemon::serve_dns(&self.state_dir)?;
+        }
+        if state.websites_backend == WebsitesBackend::Hosts && !profile.websites.is_empty() {
+            // launchd doesn't follow `/etc` to `/private/etc`
+            let hosts = hosts::hosts_file();
+            EnoughDaemon::guard_hosts(&self.state_dir, &fs::canonicalize(&hosts).unwrap_or(hosts))?;
+        }
+        if !state.resolved.groups.is_empty() {
+            EnoughDaemon::watch_installs(&self.state_dir, &discover::app_dirs())?;
+        }
+        if !apps.is_empty() || !profile.app_limits.is_empty() || !state.resolved.groups.is_empty() {
+            EnoughDaemon::watch_relaunches(&self.state_dir)?;
+        }
+        if !apps.is_empty() {
+            let app_dirs = apps
+                .iter()
+                .filter_map(|app| app.parent())
+                .map(Path::to_path_buf)
+                .collect::<Vec<_>>();
+
+            let mut dirs = app_dirs.clone();
+            dirs.extend(REINSTALL_DIRS.iter().map(sandbox::path));
+            dirs.sort();
+            dirs.dedup();
+            EnoughDaemon::watch_reinstalls(&self.state_dir, &dirs)?;
+
+            if profile.strict {
+                let mut dirs = app_dirs;
+                dirs.extend(env::home_dir().map(|home| home.join(TRASH_DIR)));
+                dirs.sort();
+                dirs.dedup();
+                EnoughDaemon::guard_apps(&self.state_dir, &dirs)?;
+            }
+        }
+        if let Some(secs) = state.remaining_interval_secs {
+            EnoughDaemon::update_remaining(&self.state_dir, Duration::from_secs(secs))?;
+        }
+        if state.config_backup.is_some()
+            && let Some(source) = &state.config_source
+        {
+            EnoughDaemon::guard_config(&self.state_dir, &source.path)?;
+        }
+        EnoughDaemon::check_at_boot(&self.state_dir)?;
+        // last, the jobs registered before it would only set it off
+        EnoughDaemon::guard_jobs(&self.state_dir)?;
+
+        Ok(())
+    }
+
+    /// The definitions of the groups named by the `group:<name>` entries of `apps`.
+    fn resolve_app_groups(&self, apps: &[PathBuf]) -> Result<BTreeMap<String, AppGroup>> {
+        let mut groups = BTreeMap::new();
+        for name in apps.iter().filter_map(|app| app_groups::group_name(app)) {
+            let group = app_groups::find(name, &self.options.app_groups)
+                .with_context(|| format!("Unknown app group `{}`", name))?;
+            groups.insert(name.to_string(), group);
+        }
+
+        Ok(groups)
+    }
+
+    /// Takes the invoking user's block lock, failing with [`BlockSkipped::InProgress`]
+    /// while another invocation holds it. It's released when the file is dropped.
+    fn lock(&self) -> Result<File> {
+        let state_root = state_root();
+        fs::create_dir_all(&state_root)?;
+        let lock_path = state_root.join(format!("{}.lock", self.uid));
+        let file = File::create(&lock_path)
+            .with_context(|| format!("Failed to open {}", lock_path.display()))?;
+
+        if let Err(err) = file.try_lock() {
+            return match err {
+                TryLockError::WouldBlock => Err(BlockSkipped::InProgress.into()),
+                TryLockError::Error(err) => Err(err.into()),
+            };
+        }
+        Ok(file)
+    }
+
+    /// Finishes the block if it ended while the machine was off, or puts back what a
+    /// restart undoes otherwise: pf rules, the DNS cache and possibly the hosts section.
+    /// Run by the boot check.
+    pub fn check_after_boot(&self) -> Result<()> {
+        // a block or unblock going on right now will get there on its own
+        if self.unfinished_run()?.is_some() {
+            return Ok(());
+        }
+        let Some(state) = self.read_block_state()? else {
+            return Ok(());
+        };
+
+        if UNIX_EPOCH + Duration::from_secs(state.unblock_time_secs) <= SystemTime::now() {
+            eprintln!("The block ended while the machine was off, finishing it");
+            return self.finish_scheduled();
+        }
+        for step in [Step::Hosts, Step::Pf, Step::DnsFlush] {
+            self.apply_step(step, &state)?;
+        }
+        eprintln!("Re-applied block `{}` after a restart", state.profile_name);
+        Ok(())
+    }
+
+    /// The scheduled end of the block. If anything fails, or the block is still in place
+    /// afterwards, the unblock is re-armed with a growing delay instead of giving up.
+    pub fn finish_scheduled(&self) -> Result<()> {
+        let attempts_path = self.state_dir.join(UNBLOCK_ATTEMPTS_FILE);
+        let Err(err) = self.finish().and_then(|_| self.verify_finished()) else {
+            if attempts_path.exists() {
+                fs::remove_file(&attempts_path)?;
+            }
+            return Ok(());
+        };
+
+        let attempt = fs::read_to_string(&attempts_path)
+            .ok()
+            .and_then(|attempts| attempts.trim().parse::<u32>().ok())
+            .unwrap_or_default()
+            + 1;
+        let delay = UNBLOCK_RETRY_DELAY
+            .saturating_mul(2u32.saturating_pow(attempt - 1))
+            .min(UNBLOCK_RETRY_MAX_DELAY);
+
+        eprintln!(
+            "[{}] Unblock attempt {} failed, retrying in {}: {:#}",
+            Local::now().format("%Y-%m-%d %H:%M:%S"),
+            attempt,
+            format_duration(delay),
+            err
+        );
+
+        if let Some(state) = self.read_block_state().ok().flatten()
+            && state.events
+        {
+            events::emit(EventKind::UnblockFailed {
+                profile_name: Some(state.profile_name),
+                attempt,
+                error: format!("{:#}", err),
+            });
+        }
+
+        fs::create_dir_all(&self.state_dir)?;
+        fs::write(&attempts_path, attempt.to_string())?;
+        EnoughDaemon::reschedule(&self.state_dir, (SystemTime::now() + delay).into(), || {
+            Ok(())
+        })?;
+
+        Err(err)
+    }
+
+    /// Checks that nothing is left blocked, unless a cool-down keeps part of it on purpose
+    /// or the unblock was rescheduled for later.
+    fn verify_finished(&self) -> Result<()> {
+        if self.read_block_state()?.is_some_and(|state| {
+            state.cooling_down
+                || UNIX_EPOCH + Duration::from_secs(state.unblock_time_secs) > SystemTime::now()
+        }) {
+            return Ok(());
+        }
+
+        if HostsSection::new(&self.state_dir, self.uid).is_present()? {
+            anyhow::bail!("The hosts section is still in place");
+        }
+        if self.state_dir.join(APP_PERMS_BACKUP_FILE).exists() {
+            anyhow::bail!("Some apps still have their permissions removed");
+        }
+
+        Ok(())
+    }
+
+    /// Ends the block when its time is up. With a cool-down configured, only part of it
+    /// is lifted at first and the rest gets its own scheduled unblock.
+    pub fn finish(&self) -> Result<()> {
+        let Some(mut state) = self.read_block_state()? else {
+            return self.unblock_all();
+        };
+
+        // the wall clock may have been set forward to get here early
+        if !state.cooling_down
+            && let Some(guard) = &state.clock_guard
+            && let Some(elapsed) = guard.anchor.elapsed()
+        {
+            let duration = Duration::from_secs(guard.duration_secs);
+            if elapsed + CLOCK_TOLERANCE < duration {
+                let remaining = duration - elapsed;
+                state.tampered("clock", None);
+                self.reschedule_unblock(&mut state, SystemTime::now() + remaining)?;
+
+                eprintln!(
+                    "The clock was changed during the block, {} of it is still left",
+                    state.time_format.format(remaining)
+                );
+                return state.alert(
+                    "Clock change caught",
+                    &format!(
+                        "{} of the block is still left",
+                        state.time_format.format(remaining)
+                    ),
+                );
+            }
+        }
+
+        // launchd fired by local time, which a time zone change can bring forward
+        let unblock_time = UNIX_EPOCH + Duration::from_secs(state.unblock_time_secs);
+        let now = SystemTime::now();
+        log_fire_time(now, unblock_time);
+        if now + CLOCK_TOLERANCE < unblock_time {
+            self.reschedule_unblock(&mut state, unblock_time)?;
+            eprintln!("The unblock ran early, likely after a time zone change. Rescheduled it");
+            return Ok(());
+        }
+        // the job fires at the start of the minute, the seconds left are waited out here
+        thread::sleep(unblock_time.duration_since(now).unwrap_or_default());
+
+        let cool_down = match &state.profile.cool_down {
+            Some(cool_down) if !state.cooling_down => cool_down.clone(),
+            _ => return self.end_block(state),
+        };
+
+        match cool_down.keep {
+            CoolDownKeep::Websites => self.unblock_apps()?,
+            CoolDownKeep::Apps => self.unblock_websites()?,
+        }
+
+        state.cooling_down = true;
+        self.reschedule_unblock(&mut state, SystemTime::now() + cool_down.duration)?;
+
+        eprintln!(
+            "Cooling down, {} stay blocked for another {}",
+            cool_down.keep,
+            state.time_format.format(cool_down.duration)
+        );
+
+        Ok(())
+    }
+
+    /// Lifts the block, putting its break profile in place of it if it has one. The
+    /// daemons run without the config, so the break goes by the settings kept in the state.
+    fn end_block(&self, state: BlockState) -> Result<()> {
+        let Some(break_block) = state.break_block else {
+            return self.unblock_all();
+        };
+
+        eprintln!("Starting the break, profile `{}`", break_block.profile_name);
+        let manager = Self {
+            state_dir: self.state_dir.clone(),
+            uid: self.uid,
+            progress: Progress::new(ProgressFormat::Quiet),
+            options: BlockOptions {
+                config_source: state.config_source,
+                websites_backend: state.websites_backend,
+                block_doh: state.block_doh,
+                events: state.events,
+                history: HistoryPrivacy {
+                    keep: None,
+                    names: state.names,
+                    encrypt: at_rest::enabled(),
+                },
+                remaining_file_interval: state.remaining_interval_secs.map(Duration::from_secs),
+                app_groups: state.resolved.groups.into_iter().collect(),
+                time_format: state.time_format,
+                headless: state.headless,
+                accountability_webhook: state.accountability_webhook,
+                partner: self.state_dir.join(partner::SECRET_FILE).exists(),
+                push: state.push,
+                ..BlockOptions::default()
+            },
+            unblock_token: None,
+            partner_code_taken: false,
+        };
+        manager.block_items(
+            &break_block.profile_name,
+            &break_block.profile,
+            break_block.profile.duration,
+        )
+    }
+
+    /// Moves the scheduled unblock to `unblock_time`, saving it in the state along with
+    /// the time zone it was scheduled in.
+    fn reschedule_unblock(&self, state: &mut BlockState, unblock_time: SystemTime) -> Result<()> {
+        state.unblock_time_secs = unblock_time.duration_since(UNIX_EPOCH)?.as_secs();
+        state.utc_offset_secs = Some(utc_offset_secs());
+        // saved once the new unblock is loaded, and before the old one goes
+        EnoughDaemon::reschedule(&self.state_dir, unblock_time.into(), || {
+            self.write_block_state(state)
+        })
+    }
+
+    /// Pushes the active block's unblock back by `by`, returning when it ends now.
+    pub fn extend(&self, by: Duration) -> Result<DateTime<Local>> {
+        let Some(mut state) = self.read_block_state()? else {
+            anyhow::bail!("No active block is running");
+        };
+
+        let unblock_time = UNIX_EPOCH + Duration::from_secs(state.unblock_time_secs) + by;
+        // the clock guard holds the block to its duration, which got longer
+        if let Some(guard) = &mut state.clock_guard {
+            guard.duration_secs += by.as_secs();
+        }
+        self.reschedule_unblock(&mut state, unblock_time)?;
+        history::extend(unblock_time.into())?;
+
+        Ok(unblock_time.into())
+    }
+
+    /// Lets websites through during their allow windows and blocks them again after,
+    /// run by the daemon at every window boundary.
+    pub fn refresh_windows(&self) -> Result<()> {
+        let Some(state) = self.read_block_state()? else {
+            return Ok(());
+        };
+
+        if state.websites_lifted() {
+            return Ok(());
+        }
+
+        self.block_websites(
+            &enforced_websites(&state.profile, Local::now().time()),
+            &state.allowed_domains(),
+            state.websites_backend,
+            state.profile.friction.is_some(),
+            state.block_doh,
+        )
+    }
+
+    /// Lets `domain` through the active block for `duration`, re-blocking it afterwards
+    /// through a short-lived job. Returns when the exception ends.
+    pub fn allow_temp(&self, domain: &str, duration: Duration) -> Result<DateTime<Local>> {
+        let Some(mut state) = self.read_block_state()? else {
+            anyhow::bail!("No block is active");
+        };
+        if state.profile.strict {
+            anyhow::bail!(
+                "Profile `{}` is strict, its websites can't be let through",
+                state.profile_name
+            );
+        }
+
+        let domain = domain
+            .split_once("://")
+            .map_or(domain, |(_, rest)| rest)
+            .split('/')
+            .next()
+            .unwrap_or_default()
+            .trim_start_matches("www.")
+            .to_ascii_lowercase();
+        if !state
+            .resolved
+            .domains
+            .iter()
+            .any(|blocked| blocked.trim_start_matches("www.") == domain)
+        {
+            anyhow::bail!(
+                "{} isn't blocked by profile `{}`",
+                domain,
+                state.profile_name
+            );
+        }
+
+        let until = SystemTime::now() + duration;
+        state
+            .exceptions
+            .retain(|exception| exception.domain != domain);
+        state.exceptions.push(TempException {
+            domain,
+            until_secs: until.duration_since(UNIX_EPOCH)?.as_secs(),
+        });
+        self.apply_exceptions(state)?;
+
+        Ok(until.into())
+    }
+
+    /// Lets the blocked app called `name` (or at that path) through the active block for
+    /// `duration`, out of the profile's `app-allowance`, re-blocking it afterwards through
+    /// the same job as [`allow_temp`](Self::allow_temp). Returns the app and when the
+    /// exception ends.
+    pub fn allow_app(&self, name: &str, duration: Duration) -> Result<(PathBuf, DateTime<Local>)> {
+        let Some(mut state) = self.read_block_state()? else {
+            anyhow::bail!("No block is active");
+        };
+        if state.profile.strict {
+            anyhow::bail!(
+                "Profile `{}` is strict, its apps can't be let through",
+                state.profile_name
+            );
+        }
+        let Some(allowance) = state.profile.app_allowance else {
+            anyhow::bail!(
+                "Profile `{}` has no `app-allowance` to let apps through with",
+                state.profile_name
+            );
+        };
+
+        let backup = self.read_app_perms_backup()?;
+        let Some((app, perms)) = backup.iter().find(|(app, _)| {
+            app.as_path() == Path::new(name)
+                || app
+                    .file_stem()
+                    .is_some_and(|stem| stem.to_string_lossy().eq_ignore_ascii_case(name))
+        }) else {
+            anyhow::bail!("{} isn't blocked by profile `{}`", name, state.profile_name);
+        };
+        if state.apps_lifted() {
+            anyhow::bail!("The apps aren't blocked anymore");
+        }
+
+        let left = allowance.saturating_sub(Duration::from_secs(state.app_allowance_used_secs));
+        if duration > left {
+            anyhow::bail!(
+                "Only {} of the app allowance is left",
+                state.time_format.format(left)
+            );
+        }
+
+        change_app_perms(app, perms)?;
+        let mut executables = self.read_perms_backup(EXECUTABLES_PERMS_BACKUP_FILE)?;
+        executables.retain(|path, _| path.starts_with(app));
+        restore_perms(&executables)?;
+
+        let until = SystemTime::now() + duration;
+        state.app_allowance_used_secs += duration.as_secs();
+        state
+            .app_exceptions
+            .retain(|exception| exception.app != *app);
+        state.app_exceptions.push(AppException {
+            app: app.clone(),
+            until_secs: until.duration_since(UNIX_EPOCH)?.as_secs(),
+        });
+        let app = app.clone();
+        self.apply_exceptions(state)?;
+
+        Ok((app, until.into()))
+    }
+
+    /// Blocks the domains and apps whose temporary exception ran out, run by the daemon.
+    pub fn reblock_exceptions(&self) -> Result<()> {
+        let Some(state) = self.read_block_state()? else {
+            return Ok(());
+        };
+
+        self.apply_exceptions(state)
+    }
+
+    /// Drops the exceptions that ran out, blocking their apps again, updates the hosts
+    /// section for the rest and schedules the next re-block.
+    fn apply_exceptions(&self, mut state: BlockState) -> Result<()> {
+        let now_secs = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        state
+            .exceptions
+            .retain(|exception| exception.until_secs > now_secs);
+        let (app_exceptions, over) = state
+            .app_exceptions
+            .drain(..)
+            .partition::<Vec<_>, _>(|exception| exception.until_secs > now_secs);
+        state.app_exceptions = app_exceptions;
+        self.write_block_state(&state)?;
+
+        if !state.apps_lifted() {
+            for exception in over.iter().filter(|exception| exception.app.exists()) {
+                self.block_apps(std::slice::from_ref(&exception.app))?;
+                usage::quit(&exception.app)?;
+                eprintln!("{} is blocked again", exception.app.display());
+            }
+        }
+
+        if !state.websites_lifted() {
+            self.block_websites(
+                &enforced_websites(&state.profile, Local::now().time()),
+                &state.allowed_domains(),
+                state.websites_backend,
+                state.profile.friction.is_some(),
+                state.block_doh,
+            )?;
+        }
+
+        let next_reblock = state
+            .exceptions
+            .iter()
+            .map(|exception| exception.until_secs)
+            .chain(
+                state
+                    .app_exceptions
+                    .iter()
+                    .map(|exception| exception.until_secs),
+            )
+            .min()
+            .map(|secs| (UNIX_EPOCH + Duration::from_secs(secs)).into());
+        EnoughDaemon::schedule_reblock(&self.state_dir, next_reblock)
+    }
+
+    /// Counts another tracking interval for the foreground app, and blocks apps with a
+    /// usage limit once their allowance is used up. Run by the daemon.
+    pub fn track_usage(&self) -> Result<()> {
+        let Some(state) = self.read_block_state()? else {
+            return Ok(());
+        };
+
+        if state.apps_lifted() {
+            return Ok(());
+        }
+
+        let limits = &state.profile.app_limits;
+        let mut usage = AppUsage::load(self.uid)?;
+        if let Some(app) = usage::frontmost_app()
+            && limits.iter().any(|limit| limit.app == app)
+        {
+            usage.add(&app, usage::TRACK_INTERVAL);
+            usage.save(self.uid)?;
+        }
+
+        let blocked = self.read_app_perms_backup()?;
+        let exhausted = limits
+            .iter()
+            .filter(|limit| usage.used(&limit.app) >= limit.allowance)
+            .map(|limit| limit.app.clone())
+            .filter(|app| !blocked.contains_key(app))
+            .collect::<Vec<_>>();
+        if exhausted.is_empty() {
+            return Ok(());
+        }
+
+        self.block_apps(&exhausted)?;
+        for app in &exhausted {
+            usage::quit(app)?;
+            eprintln!("Allowance of {} used up, blocked it", app.display());
+        }
+
+        Ok(())
+    }
+
+    /// Counts another tracking interval for the quotas the foreground app or browser tab
+    /// is in, and blocks what's in the used up ones until their period ends. Run by the
+    /// quota watcher, blocks or not.
+    pub fn enforce_quotas(&self, quotas: &HashMap<String, Quota>) -> Result<()> {
+        let now = Local::now();
+        let mut usage = QuotaUsage::load(self.uid)?;
+        if let Some(app) = usage::frontmost_app() {
+            let url = tabs::front_tab_url(&app);
+            for (name, quota) in quotas {
+                if quota.apps.contains(&app)
+                    || url
+                        .as_ref()
+                        .is_some_and(|url| tabs::on_blocked_website(url, &quota.websites))
+                {
+                    usage.add(
+                        name,
+                        quota.period.start(now.date_naive()),
+                        usage::TRACK_INTERVAL,
+                    );
+                }
+            }
+            usage.save(self.uid)?;
+        }
+
+        for (name, quota) in quotas {
+            if usage.used(name, quota.period.start(now.date_naive())) >= quota.allowance {
+                self.enforce_quota(name, quota, now)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Blocks what's in a used up quota: in a block of its own lasting until the period
+    /// ends, or on top of the active block, whose end is left alone. What's left once it
+    /// ends is blocked again the next time the watcher runs.
+    fn enforce_quota(&self, name: &str, quota: &Quota, now: DateTime<Local>) -> Result<()> {
+        let until = quota.period.end(now);
+        let apps = quota
+            .apps
+            .iter()
+            .filter(|app| app.exists())
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let Some(mut state) = self.read_block_state()? else {
+            let profile = Profile {
+                websites: quota.websites.iter().cloned().map(Into::into).collect(),
+                apps,
+                ..Default::default()
+            };
+            let duration = (until - now).to_std().unwrap_or_default();
+            if let Err(err) = self.block_items(&format!("quota-{}", name), &profile, duration) {
+                if err.downcast_ref::<BlockSkipped>().is_some() {
+                    return Ok(());
+                }
+                let _ = self.recover();
+                return Err(err);
+            }
+            eprintln!(
+                "Quota `{}` used up, blocked it until {}",
+                name,
+                until.format("%a %H:%M")
+            );
+            return alert(
+                "Quota used up",
+                &format!("`{}` is blocked until {}", name, until.format("%a %H:%M")),
+            );
+        };
+
+        let urls = state.profile.urls();
+        let websites = quota
+            .websites
+            .iter()
+            .filter(|url| !state.websites_lifted() && !urls.contains(url))
+            .cloned()
+            .collect::<Vec<_>>();
+        let blocked = self.read_app_perms_backup()?;
+        let apps = apps
+            .into_iter()
+            .filter(|app| !state.apps_lifted() && !blocked.contains_key(app))
+            .collect::<Vec<_>>();
+        if websites.is_empty() && apps.is_empty() {
+            return Ok(());
+        }
+
+        let websites = websites.into_iter().map(Website::from).collect::<Vec<_>>();
+        state
+            .resolved
+            .domains
+            .extend(websites.iter().flat_map(website_hosts));
+        state.profile.websites.extend(websites.iter().cloned());
+        state.resolved.apps.extend(apps.iter().cloned());
+        self.write_block_state(&state)?;
+
+        if !websites.is_empty() {
+            self.block_websites(
+                &enforced_websites(&state.profile, now.time()),
+                &state.allowed_domains(),
+                state.websites_backend,
+                state.profile.friction.is_some(),
+                state.block_doh,
+            )?;
+        }
+        if !apps.is_empty() {
+            self.block_apps(&apps)?;
+            for app in &apps {
+                usage::quit(app)?;
+            }
+        }
+
+        eprintln!(
+            "Quota `{}` used up, blocked it along with `{}`",
+            name, state.profile_name
+        );
+        state.alert(
+            "Quota used up",
+            &format!("`{}` is blocked for the rest of the block", name),
+        )
+    }
+
+    /// Starts the first profile, by name, whose `schedule` is due and wasn't started for
+    /// this occurrence yet, lasting until the occurrence ends. Nothing is started over an
+    /// active block or a lockout, and a block lifted early stays lifted until the next
+    /// occurrence. Occurrences starting on one of `holidays` are skipped. Posts the
+    /// warm-up reminders of the ones starting soon too. Run by the scheduler, blocks or
+    /// not.
+    pub fn follow_schedules(
+        &self,
+        profiles: &HashMap<String, Profile>,
+        holidays: &scheduler::Holidays,
+    ) -> Result<()> {
+        if self.read_block_state()?.is_some() || audit::lockout_until()?.is_some() {
+            return Ok(());
+        }
+
+        let now = Local::now();
+        let mut state = scheduler::SchedulerState::load(self.uid)?;
+        if !self.options.headless {
+            self.remind(profiles, holidays, &mut state, now)?;
+        }
+        let mut due = profiles
+            .iter()
+            .filter_map(|(name, profile)| {
+                let end = profile
+                    .schedule
+                    .as_ref()?
+                    .current(now, profile.duration)
+                    .filter(|occurrence| !holidays.contains(occurrence.start.date_naive()))?
+                    .end;
+                let left = (end - now).to_std().ok()?;
+                let deferred = state
+                    .deferred_until(name, end)
+                    .is_some_and(|until| now < until);
+                // a bit of an occurrence isn't worth a block, it'd be lifted right away
+                (left >= scheduler::SCHEDULER_INTERVAL
+                    && !state.was_started(name, end)
+                    && !state.is_snoozed(name, end)
+                    && !deferred)
+                    .then_some((name, profile, end, left))
+            })
+            .collect::<Vec<_>>();
+        due.sort_by_key(|(name, ..)| *name);
+        let Some((name, profile, end, left)) = due.into_iter().next() else {
+            return Ok(());
+        };
+
+        if let Err(err) = self.block_items(name, profile, left)
+            && err.downcast_ref::<BlockSkipped>().is_none()
+        {
+            let _ = self.recover();
+            return Err(err);
+        }
+        state.mark_started(name, end);
+        state.save(self.uid)?;
+
+        eprintln!(
+            "Scheduled block `{}` started, until {}",
+            name,
+            end.format("%a %H:%M")
+        );
+        alert(
+            "Scheduled block started",
+            &format!("`{}` is blocked until {}", name, end.format("%a %H:%M")),
+        )
+    }
+
+    /// Posts a reminder 10 and 2 minutes before each scheduled block, offering to put its
+    /// start off once unless the profile is strict.
+    fn remind(
+        &self,
+        profiles: &HashMap<String, Profile>,
+        holidays: &scheduler::Holidays,
+        state: &mut scheduler::SchedulerState,
+        now: DateTime<Local>,
+    ) -> Result<()> {
+        let delta = |duration: Duration| TimeDelta::seconds(duration.as_secs() as i64);
+        let ahead = delta(scheduler::REMINDERS[0] + scheduler::SCHEDULER_INTERVAL);
+        let mut profiles = profiles.iter().collect::<Vec<_>>();
+        profiles.sort_by_key(|(name, _)| *name);
+        for (name, profile) in profiles {
+            let Some(schedule) = &profile.schedule else {
+                continue;
+            };
+            // one put off is already going on, and starts later than it says
+            let from = now - delta(scheduler::DEFER);
+            for occurrence in schedule.upcoming(from, now + ahead, profile.duration) {
+                if state.is_snoozed(name, occurrence.end)
+                    || holidays.contains(occurrence.start.date_naive())
+                {
+                    continue;
+                }
+                let deferred = state.deferred_until(name, occurrence.end);
+                let start = deferred.unwrap_or(occurrence.start);
+                let Some(lead) = scheduler::REMINDERS.into_iter().find(|lead| {
+                    let at = start - delta(*lead);
+                    at <= now && now < at + delta(scheduler::SCHEDULER_INTERVAL)
+                }) else {
+                    continue;
+                };
+
+                let message = format!(
+                    "`{}` starts at {}, save your work and close your tabs",
+                    name,
+                    start.format("%H:%M")
+                );
+                let subtitle = format!("Lock-in in {}", format_duration(lead));
+                if profile.strict || deferred.is_some() {
+                    alert(&subtitle, &message)?;
+                    continue;
+                }
+                let defer = format!("Defer {}", format_duration(scheduler::DEFER));
+                if ask(&subtitle, &message, &[&defer, "OK"])?.as_deref() == Some(defer.as_str()) {
+                    state.defer(name, occurrence);
+                    state.save(self.uid)?;
+                    eprintln!("Scheduled block `{}` put off by the reminder", name);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Terminates blocked apps running anyway, like a copy of the bundle or one on a disk
+    /// image, recognized by their bundle identifier. Polls until the block is over. Run by
+    /// the relaunch watcher.
+    pub fn watch_relaunches(&self) -> Result<()> {
+        let mut bundle_ids = HashMap::new();
+        let mut bundle_id = |app: &Path| {
+            bundle_ids
+                .entry(app.to_path_buf())
+                .or_insert_with(|| discover::bundle_id(app))
+                .clone()
+        };
+        let mut terminated = Vec::new();
+
+        while let Some(state) = self.read_block_state()? {
+            if state.apps_lifted() {
+                return Ok(());
+            }
+
+            let allowed = state.allowed_apps();
+            let blocked = self
+                .read_app_perms_backup()?
+                .into_keys()
+                .filter(|app| !allowed.contains(app))
+                .collect::<Vec<_>>();
+            let blocked_ids = blocked
+                .iter()
+                .filter_map(|app| bundle_id(app))
+                .collect::<Vec<_>>();
+            for (pid, app) in usage::running_apps() {
+                if !blocked.contains(&app)
+                    && !bundle_id(&app).is_some_and(|id| blocked_ids.contains(&id))
+                {
+                    continue;
+                }
+
+                usage::terminate(pid)?;
+                if !terminated.contains(&app) {
+                    eprintln!("{} is blocked, closed it", app.display());
+                    state.alert(
+                        "Blocked app closed",
+                        &format!("{} stays blocked until the block ends", app.display()),
+                    )?;
+                    terminated.push(app);
+                }
+            }
+
+            thread::sleep(RELAUNCH_POLL_INTERVAL);
+        }
+
+        Ok(())
+    }
+
+    /// Puts blocked apps moved to the Trash back in place, blocked again. Apps deleted for
+    /// good (or in a Trash this can't read without Full Disk Access) extend the block by
+    /// [`APP_REMOVAL_PENALTY`] instead, once per app. Run by the apps guard in strict mode.
+    pub fn guard_apps(&self) -> Result<()> {
+        let Some(mut state) = self.read_block_state()? else {
+            return Ok(());
+        };
+        if !state.profile.strict || state.apps_lifted() {
+            return Ok(());
+        }
+
+        let trash = env::home_dir().map(|home| home.join(TRASH_DIR));
+        let mut penalty = Duration::ZERO;
+        for app in self.read_app_perms_backup()?.into_keys() {
+            if app.exists() || state.resolved.removed.contains(&app) {
+                continue;
+            }
+
+            state.tampered("app", Some(&app));
+
+            let name = app.display();
+            if let Some(trashed) = trash.as_deref().and_then(|trash| trashed_copy(trash, &app))
+                && let Err(err) = put_back(&trashed, &app)
+            {
+                eprintln!("Couldn't put {} back: {:#}", name, err);
+            }
+            if app.exists() {
+                self.block_apps(std::slice::from_ref(&app))?;
+                eprintln!("{} was moved to the Trash, put it back", name);
+                state.alert(
+                    "Blocked app put back",
+                    &format!("{} stays blocked until the block ends", name),
+                )?;
+                continue;
+            }
+
+            eprintln!(
+                "{} was removed, extending the block by {}",
+                name,
+                state.time_format.format(APP_REMOVAL_PENALTY)
+            );
+            state.alert(
+                "Blocked app removed",
+                &format!(
+                    "The block was extended by {} for removing {}",
+                    state.time_format.format(APP_REMOVAL_PENALTY),
+                    name
+                ),
+            )?;
+            state.resolved.removed.push(app.clone());
+            penalty += APP_REMOVAL_PENALTY;
+        }
+
+        if penalty.is_zero() {
+            return self.write_block_state(&state);
+        }
+        let unblock_time = UNIX_EPOCH + Duration::from_secs(state.unblock_time_secs) + penalty;
+        self.reschedule_unblock(&mut state, unblock_time)
+    }
+
+    /// Blocks apps again that were reinstalled (by Homebrew, the App Store or by hand)
+    /// and came back with their permissions, noting it in the history. Run by the
+    /// reinstall watcher.
+    pub fn reblock_reinstalls(&self) -> Result<()> {
+        let Some(state) = self.read_block_state()? else {
+            return Ok(());
+        };
+        if state.apps_lifted() {
+            return Ok(());
+        }
+
+        let allowed = state.allowed_apps();
+        for app in self
+            .read_app_perms_backup()?
+            .into_keys()
+            .filter(|app| !allowed.contains(app))
+        {
+            let is_blocked = |path: &Path| {
+                fs::symlink_metadata(path)
+                    .is_ok_and(|metadata| metadata.permissions().mode() & 0o7777 == 0)
+            };
+            if !app.exists()
+                || is_blocked(&app) && bundle_executables(&app).iter().all(|path| is_blocked(path))
+            {
+                continue;
+            }
+
+            self.block_apps(std::slice::from_ref(&app))?;
+            usage::quit(&app)?;
+            eprintln!(
+                "{} was reinstalled{}, blocked it again",
+                app.display(),
+                install_source(&app)
+                    .map(|source| format!(" through {}", source))
+                    .unwrap_or_default()
+            );
+
+            history::record_reinstall(&state.names.path(&app).unwrap_or_default())?;
+            state.tampered("app_reinstall", Some(&app));
+            state.alert(
+                "Reinstalled app blocked",
+                &format!("{} stays blocked until the block ends", app.display()),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Blocks apps installed since the block started that belong to one of its app
+    /// groups, quitting them if they were already opened. Run by the install watcher.
+    pub fn block_new_installs(&self) -> Result<()> {
+        let Some(mut state) = self.read_block_state()? else {
+            return Ok(());
+        };
+        if state.resolved.groups.is_empty() || state.apps_lifted() {
+            return Ok(());
+        }
+
+        let mut installed = discover::installed_apps();
+        // a copy still in progress has no Info.plist yet, so there'd be nothing to match
+        for _ in 0..INSTALL_SETTLE_ATTEMPTS {
+            let pending = installed
+                .iter()
+                .any(|app| app.bundle_id.is_none() && !state.resolved.apps.contains(&app.path));
+            if !pending {
+                break;
+            }
+            thread::sleep(INSTALL_SETTLE_DELAY);
+            installed = discover::installed_apps();
+        }
+
+        let new = app_groups::members(&state.resolved.groups, &installed)
+            .into_iter()
+            .filter(|(app, _)| !state.resolved.apps.contains(app))
+            .collect::<Vec<_>>();
+        if new.is_empty() {
+            return Ok(());
+        }
+
+        let apps = new.iter().map(|(app, _)| app.clone()).collect::<Vec<_>>();
+        self.block_apps(&apps)?;
+        for (app, group) in new {
+            usage::quit(&app)?;
+            eprintln!(
+                "{} was installed during the block, blocked it with the `{}` app group",
+                app.display(),
+                group
+            );
+            state.resolved.apps.push(app.clone());
+            state.resolved.auto_blocked.push(app.clone());
+            state.resolved.grouped.insert(app, group);
+        }
+
+        self.write_block_state(&state)
+    }
+
+    fn save_context(&self, save: &SaveContext, websites: &[Url]) -> Result<()> {
+        let context = WorkContext::snapshot(save, websites)?;
+        if context.is_empty() {
+            return Ok(());
+        }
+
+        context.save(self.uid)?;
+        self.progress.message(&format!(
+            "Saved {} apps and {} tabs for `enough restore-context`",
+            context.apps.len(),
+            context.tabs.len()
+        ));
+        Ok(())
+    }
+
+    /// Blocks the hosts of `websites`, except for the `allowed` domains (given without
+    /// `www.`), which also let through everything blocked along with them. `soft`
+    /// points them at the friction server instead of nowhere, `doh` adds the
+    /// [`DOH_ENDPOINTS`].
+    fn block_websites(
+        &self,
+        websites: &[Website],
+        allowed: &[String],
+        backend: WebsitesBackend,
+        soft: bool,
+        doh: bool,
+    ) -> Result<()> {
+        let mut blocked_hosts = Vec::new();
+        for (i, website) in websites.iter().enumerate() {
+            let hosts = website_hosts(website);
+            let is_allowed = hosts
+                .first()
+                .is_some_and(|host| allowed.contains(&host.trim_start_matches("www.").to_string()));
+            if !is_allowed {
+                blocked_hosts.extend(hosts);
+            }
+            self.progress.update("domains", i + 1, websites.len());
+        }
+        if doh {
+            blocked_hosts.extend(DOH_ENDPOINTS.iter().map(|host| host.to_string()));
+        }
+        blocked_hosts.retain(|host| {
+            !allowed
+                .iter()
+                .any(|domain| host.trim_start_matches("www.") == domain)
+        });
+
+        if backend == WebsitesBackend::Resolver {
+            if ResolverFiles::new(&self.state_dir, self.uid).apply(&blocked_hosts)? {
+                hosts::flush_dns_cache()?;
+            }
+            self.progress.message(&format!(
+                "Blocked {} websites using resolver files",
+                websites.len()
+            ));
+            return Ok(());
+        }
+        if backend == WebsitesBackend::DnsProxy {
+            if DnsProxy::new(&self.state_dir).apply(&blocked_hosts)? {
+                hosts::flush_dns_cache()?;
+            }
+            self.progress.message(&format!(
+                "Blocked {} websites using the DNS proxy",
+                websites.len()
+            ));
+            return Ok(());
+        }
+        if backend == WebsitesBackend::Pf {
+            self.block_through_pf(&blocked_hosts)?;
+            self.progress
+                .message(&format!("Blocked {} domains using pf", blocked_hosts.len()));
+            return Ok(());
+        }
+
+        // large hosts files slow down every lookup, so the excess goes through pf
+        let limit = self.options.hosts_limit;
+        let spilled = if blocked_hosts.len() > limit && self.options.spill_to_pf {
+            blocked_hosts.split_off(limit)
+        } else {
+            if blocked_hosts.len() > limit {
+                eprintln!(
+                    "Warning: blocking {} domains through the hosts file (limit is {}), \
+                    name resolution may slow down{}",
+                    blocked_hosts.len(),
+                    limit,
+                    if cfg!(all(feature = "pf", target_os = "macos")) {
+                        ". Enable `spill-to-pf` to avoid this"
+                    } else {
+                        ""
+                    }
+                );
+            }
+            Vec::new()
+        };
+
+        let entries = if soft {
+            hosts::loopback_entries_for(&blocked_hosts)
+        } else {
+            hosts::entries_for(&blocked_hosts)
+        };
+        if HostsSection::new(&self.state_dir, self.uid).apply(&entries)? {
+            hosts::flush_dns_cache()?;
+        }
+
+        self.progress.message(&format!(
+            "Blocked {} domains using hosts file",
+            blocked_hosts.len()
+        ));
+
+        if !spilled.is_empty() {
+            self.block_through_pf(&spilled)?;
+            self.progress
+                .message(&format!("Blocked {} more domains using pf", spilled.len()));
+        }
+
+        Ok(())
+    }
+
+    #[cfg(all(feature = "pf", target_os = "macos"))]
+    fn block_through_pf(&self, hosts: &[String]) -> Result<()> {
+        let rules = pf::rules_blocking(hosts);
+        PacketFilter::new(&self.state_dir, self.uid).load_anchor(PF_SITES_ANCHOR, &rules)
+    }
+
+    /// Never reached, `spill_to_pf` is always off and the pf backend refused without pf.
+    #[cfg(not(all(feature = "pf", target_os = "macos")))]
+    fn block_through_pf(&self, _hosts: &[String]) -> Result<()> {
+        anyhow::bail!("This build doesn't include pf")
+    }
+
+    fn block_apps(&self, apps: &[PathBuf]) -> Result<()> {
+        // backing up each app's permissions first, so unblocking restores them exactly
+        let mut backup = self.read_app_perms_backup()?;
+        let mut executables_backup = self.read_perms_backup(EXECUTABLES_PERMS_BACKUP_FILE)?;
+        for (i, app) in apps.iter().enumerate() {
+            if !backup.contains_key(app) {
+                let mode = fs::metadata(app)
+                    .with_context(|| format!("Failed to read permissions of {}", app.display()))?
+                    .permissions()
+                    .mode();
+                backup.insert(app.clone(), format!("{:o}", mode & 0o7777));
+                let backup_file = self.state_dir.join(APP_PERMS_BACKUP_FILE);
+                fs::write(backup_file, at_rest::seal(&serde_yml::to_string(&backup)?)?)?;
+            }
+
+            // the inner executables could still be started directly if their own
+            // permissions let them
+            let result = block_executables(app, &mut executables_backup);
+            let backup_file = self.state_dir.join(EXECUTABLES_PERMS_BACKUP_FILE);
+            fs::write(
+                backup_file,
+                at_rest::seal(&serde_yml::to_string(&executables_backup)?)?,
+            )?;
+            result?;
+
+            change_app_perms(app, BLOCKED_APP_PERMS)?;
+            self.progress.update("apps", i + 1, apps.len());
+        }
+
+        Ok(())
+    }
+
+    fn read_app_perms_backup(&self) -> Result<BTreeMap<PathBuf, String>> {
+        self.read_perms_backup(APP_PERMS_BACKUP_FILE)
+    }
+
+    fn read_perms_backup(&self, file: &str) -> Result<BTreeMap<PathBuf, String>> {
+        let backup_file = self.state_dir.join(file);
+        if !backup_file.exists() {
+            return Ok(BTreeMap::new());
+        }
+
+        let content = at_rest::open(fs::read_to_string(backup_file)?)?;
+        Ok(serde_yml::from_str(&content)?)
+    }
+
+    pub fn unblock_all(&self) -> Result<()> {
+        self.check_liftable()?;
+        self.lift(false)
+    }
+
+    /// Lifts the block right away for `enough emergency`, strict or not and without the
+    /// partner's code, which leaves its own audit record and lockout behind.
+    pub fn unblock_in_emergency(&self) -> Result<()> {
+        self.lift(false)
+    }
+
+    /// The secret shared with the accountability partner as the block started, whose
+    /// code lifting it early takes. Kept apart from the state, only readable by root.
+    pub fn partner_secret(&self) -> Result<Option<String>> {
+        let path = self.state_dir.join(partner::SECRET_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(fs::read_to_string(path)?.trim().to_string()))
+    }
+
+    /// Whether lifting the block takes the accountability partner's code, not taken yet.
+    /// A strict block doesn't give way to it either.
+    pub fn needs_partner_code(&self) -> Result<bool> {
+        let Some(state) = self.read_block_state()? else {
+            return Ok(false);
+        };
+        Ok(!state.profile.strict
+            && !state.is_over()
+            && !self.partner_code_taken
+            && self.partner_secret()?.is_some())
+    }
+
+    /// Refuses to lift a block before its time without the accountability partner's
+    /// code if it was started with one, and a strict one at all, unless it's the
+    /// scheduled unblock handing over the token the block was scheduled with. A missing
+    /// token file lets nothing through, which is all there is on macOS.
+    pub fn check_liftable(&self) -> Result<()> {
+        let Some(state) = self.read_block_state()? else {
+            return Ok(());
+        };
+        if state.is_over() {
+            return Ok(());
+        }
+        if !state.profile.strict {
+            if self.needs_partner_code()? {
+                anyhow::bail!(
+                    "Lifting `{}` early takes a code from your accountability partner",
+                    state.profile_name
+                );
+            }
+            return Ok(());
+        }
+
+        let expected = fs::read_to_string(self.state_dir.join(UNBLOCK_TOKEN_FILE)).ok();
+        if expected.is_none_or(|expected| Some(expected.trim()) != self.unblock_token.as_deref()) {
+            let unblock_time = UNIX_EPOCH + Duration::from_secs(state.unblock_time_secs);
+            anyhow::bail!(
+                "`{}` is strict, only its scheduled unblock at {} can lift it",
+                state.profile_name,
+                DateTime::<Local>::from(unblock_time).format("%H:%M")
+            );
+        }
+        Ok(())
+    }
+
+    /// Runs the unblock steps, resuming an unblock that stopped halfway with `resume`.
+    fn lift(&self, resume: bool) -> Result<()> {
+        // read first, the daemon step takes the state with it
+        let state = self.read_block_state().ok().flatten();
+        let remaining_file = state
+            .as_ref()
+            .and_then(|state| state.remaining_file.clone());
+        let queued = self.read_queued_config().ok().flatten();
+        self.run_steps(Direction::Unblock, resume, |step| {
+            self.lift_step(step, remaining_file.as_deref())
+        })?;
+
+        if let Some(queued) = queued {
+            fs::write(&queued.path, queued.content)?;
+            eprintln!(
+                "Applied the config changes made during the block to `{}`",
+                queued.path.display()
+            );
+        }
+
+        if let Some(state) = &state {
+            state.push(
+                PushEvent::End,
+                "Block ended",
+                &format!("`{}` is over", state.profile_name),
+            );
+        }
+        if let Some(together) = state.and_then(|state| state.together)
+            && let Err(e) = together.leave()
+        {
+            eprintln!(
+                "Warning: couldn't leave the room `{}`: {:#}",
+                together.room, e
+            );
+        }
+        Ok(())
+    }
+
+    fn lift_step(&self, step: Step, remaining_file: Option<&Path>) -> Result<()> {
+        match step {
+            Step::Hosts => {
+                HostsSection::new(&self.state_dir, self.uid).clear()?;
+                ResolverFiles::new(&self.state_dir, self.uid).clear()?;
+                DnsProxy::new(&self.state_dir).clear()?;
+                // the websites' share of pf, as a cool-down keeping the apps lifts only that
+                #[cfg(all(feature = "pf", target_os = "macos"))]
+                for anchor in [PF_SITES_ANCHOR, PF_DOT_ANCHOR] {
+                    PacketFilter::new(&self.state_dir, self.uid).flush_anchor(anchor)?;
+                }
+            }
+            Step::DnsFlush => hosts::flush_dns_cache()?,
+            Step::Apps => self.unblock_apps()?,
+            Step::Session => {
+                focus::restore(&self.state_dir)?;
+                hooks::end(&self.state_dir)?;
+            }
+            Step::Pf => {
+                #[cfg(all(feature = "pf", target_os = "macos"))]
+                PacketFilter::new(&self.state_dir, self.uid).clear()?;
+            }
+            // jobs whose state directory is gone are only found by their name
+            Step::Daemon => {
+                EnoughDaemon::remove(&self.state_dir)?;
+                EnoughDaemon::remove_all(&persistent_jobs(self.uid))?;
+            }
+            Step::State => {
+                // there's nothing left to count down, an empty file could be misread as 0
+                if let Some(path) = remaining_file
+                    && path.exists()
+                {
+                    fs::remove_file(path)?;
+                }
+                if self.state_dir.exists() {
+                    fs::remove_dir_all(&self.state_dir)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Finishes a block or unblock that stopped halfway (a failed step, a crash, a power
+    /// cut): a half-applied block is rolled back, a half-done unblock is resumed. Returns
+    /// the record of the run it finished, if there was one.
+    pub fn recover(&self) -> Result<Option<StepRecord>> {
+        let Some(record) = StepRecord::load(&self.state_dir)? else {
+            return Ok(None);
+        };
+        if record.is_complete() {
+            return Ok(None);
+        }
+
+        match record.direction {
+            Direction::Block => self.lift(false)?,
+            Direction::Unblock => self.lift(true)?,
+        }
+        Ok(Some(record))
+    }
+
+    /// The record of the latest block or unblock, if it didn't get through every step.
+    pub fn unfinished_run(&self) -> Result<Option<StepRecord>> {
+        Ok(StepRecord::load(&self.state_dir)?.filter(|record| !record.is_complete()))
+    }
+
+    /// Runs `steps` again: the block's own during a block, the unblock's otherwise.
+    pub fn repair(&self, steps: &[Step]) -> Result<()> {
+        let state = self.read_block_state()?;
+        for &step in steps {
+            match &state {
+                Some(state) => self.apply_step(step, state)?,
+                None => self.lift_step(step, None)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn unblock_websites(&self) -> Result<()> {
+        self.lift_step(Step::Hosts, None)?;
+        self.lift_step(Step::DnsFlush, None)
+    }
+
+    fn unblock_apps(&self) -> Result<()> {
+        for (app, perms) in self.read_app_perms_backup()? {
+            // the app may have been deleted during the block
+            if app.exists() {
+                change_app_perms(&app, &perms)?;
+            }
+        }
+        restore_perms(&self.read_perms_backup(EXECUTABLES_PERMS_BACKUP_FILE)?)?;
+
+        for file in [APP_PERMS_BACKUP_FILE, EXECUTABLES_PERMS_BACKUP_FILE] {
+            let backup_file = self.state_dir.join(file);
+            if backup_file.exists() {
+                fs::remove_file(backup_file)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn schedule_unblock(&self, unblock_time: DateTime<Local>) -> Result<()> {
+        EnoughDaemon::schedule(&self.state_dir, unblock_time)?;
+        EnoughDaemon::watch_network(&self.state_dir)?;
+        Ok(())
+    }
+
+    /// Puts the hosts section back if it went missing and flushes the DNS cache again,
+    /// resolving the websites again for the pf backend. Run by the network watcher, since
+    /// network changes can override our entries or change the addresses behind them.
+    pub fn reapply(&self) -> Result<()> {
+        if !self.get_status()?.is_blocked() {
+            return Ok(());
+        }
+
+        self.restore_hosts_section()?;
+        hosts::flush_dns_cache()?;
+
+        if let Some(state) = self.read_block_state()?
+            && state.websites_backend == WebsitesBackend::Pf
+            && !state.profile.websites.is_empty()
+            && !state.websites_lifted()
+        {
+            self.block_websites(
+                &enforced_websites(&state.profile, Local::now().time()),
+                &state.allowed_domains(),
+                WebsitesBackend::Pf,
+                false,
+                state.block_doh,
+            )?;
+        }
+
+        self.follow_time_zone()
+    }
+
+    /// Puts the hosts section back as soon as an edit takes it out, flushing the DNS
+    /// cache so the edit doesn't linger there. Run by the hosts guard.
+    pub fn guard_hosts(&self) -> Result<()> {
+        if !self.get_status()?.is_blocked() {
+            return Ok(());
+        }
+
+        if self.restore_hosts_section()? {
+            hosts::flush_dns_cache()?;
+        }
+        Ok(())
+    }
+
+    /// Re-applies the hosts section, reporting the tampering if it was missing. Returns
+    /// whether it was.
+    fn restore_hosts_section(&self) -> Result<bool> {
+        if !HostsSection::new(&self.state_dir, self.uid).reapply()? {
+            return Ok(false);
+        }
+
+        eprintln!("Hosts section was missing, re-applied it");
+        if let Some(state) = self.read_block_state()? {
+            state.tampered("hosts_section", None);
+            state.alert(
+                "Hosts file put back",
+                "The blocked websites stay blocked until the block ends",
+            )?;
+        }
+        Ok(true)
+    }
+
+    /// Reschedules the jobs going by local time when the time zone changed, so they still
+    /// run at the same instant.
+    fn follow_time_zone(&self) -> Result<()> {
+        let Some(mut state) = self.read_block_state()? else {
+            return Ok(());
+        };
+        if state
+            .utc_offset_secs
+            .is_none_or(|offset| offset == utc_offset_secs())
+        {
+            return Ok(());
+        }
+
+        let unblock_time = UNIX_EPOCH + Duration::from_secs(state.unblock_time_secs);
+        self.reschedule_unblock(&mut state, unblock_time)?;
+        if !state.exceptions.is_empty() {
+            self.apply_exceptions(state)?;
+        }
+
+        eprintln!(
+            "Time zone changed, the unblock is now at {} local time",
+            DateTime::<Local>::from(unblock_time).format("%H:%M")
+        );
+        Ok(())
     }
 
-    pub fn block_items(
-        &self,
-        profile_name: &str,
-        profile: &Profile,
-        duration: Duration,
-    ) -> Result<()> {
-        fs::create_dir_all(&self.state_dir)?; // Creating state directory
-        self.unblock_all()?; // cleaning up any previous state
+    /// The profile of the active block, as it was when the block started.
+    pub fn active_profile(&self) -> Result<Option<(String, Profile)>> {
+        Ok(self
+            .read_block_state()?
+            .map(|state| (state.profile_name, state.profile)))
+    }
 
-        if !profile.websites.is_empty() {
-            Self::block_websites(&profile.websites)?;
+    /// The content of the config at `path`, with the edits queued for it by
+    /// [`Self::save_config`].
+    pub fn read_config(&self, path: &Path) -> Result<String> {
+        if let Some(queued) = self.read_queued_config()?
+            && fs::canonicalize(path).is_ok_and(|path| path == queued.path)
+        {
+            return Ok(queued.content);
         }
 
-        // if !profile.apps.is_empty() {
-        //     Self::block_apps(&profile.apps)?;
-        // }
+        Ok(fs::read_to_string(path)?)
+    }
 
-        let unblock_time = SystemTime::now() + duration;
-        self.schedule_unblock(unblock_time.into())?;
-        self.save_block_state(profile_name, profile, unblock_time)?;
+    /// Writes an edited config to `path`, unless a strict block was started from it: then
+    /// the edit waits for the block to end, so the block can't be weakened through the
+    /// config. Returns whether it was queued.
+    pub fn save_config(&self, path: &Path, content: &str) -> Result<bool> {
+        let Some(source) = self
+            .read_block_state()?
+            .filter(|state| state.config_backup.is_some())
+            .and_then(|state| state.config_source)
+            .filter(|source| source.is_at(path))
+        else {
+            fs::write(path, content)?;
+            return Ok(false);
+        };
 
-        Ok(())
+        self.queue_config(QueuedConfig {
+            path: source.path,
+            content: content.to_string(),
+        })?;
+        Ok(true)
     }
 
-    fn block_websites(websites: &[Url]) -> Result<()> {
-        let hosts_file_contents = fs::read_to_string(HOSTS_FILE)?;
-        let cleaned_content = Self::remove_existing_blocks(&hosts_file_contents);
-
-        let mut new_content = cleaned_content;
-        new_content.push_str(&format!("\n\n{}\n", ENOUGH_MARKER_START));
-        for url in websites {
-            if let Some(host) = url.host_str() {
-                new_content.push_str(&format!("0.0.0.0 {}\n", host));
-                new_content.push_str(&format!("::1 {}\n", host));
+    /// Puts the strict block's config back when it was edited to take blocked websites or
+    /// apps out of the profile, queueing the edit for when the block ends. Run by the
+    /// config watcher.
+    pub fn guard_config(&self) -> Result<()> {
+        let Some(state) = self.read_block_state()? else {
+            return Ok(());
+        };
+        let (Some(source), Some(backup)) = (&state.config_source, &state.config_backup) else {
+            return Ok(());
+        };
+        if !source.has_changed() {
+            return Ok(());
+        }
 
-                if !host.contains("www.") {
-                    new_content.push_str(&format!("0.0.0.0 www.{}\n", host));
-                    new_content.push_str(&format!("::1 www.{}\n", host));
-                } else {
-                    // If host contains www., also block the non-www variant
-                    let non_www = host.trim_start_matches("www.");
-                    new_content.push_str(&format!("0.0.0.0 {}\n", non_www));
-                    new_content.push_str(&format!("::1 {}\n", non_www));
+        let content = fs::read_to_string(&source.path).ok();
+        let weakened = match content.as_deref().map(EnoughConfig::parse) {
+            None => true,
+            // a config that doesn't load can't be used to weaken anything
+            Some(Err(_)) => false,
+            Some(config) => {
+                let config = config?;
+                match config.profiles.get(&state.profile_name) {
+                    Some(profile) => {
+                        state
+                            .profile
+                            .websites
+                            .iter()
+                            .any(|website| !profile.websites.contains(website))
+                            || state
+                                .profile
+                                .apps
+                                .iter()
+                                .any(|app| !profile.apps.contains(app))
+                    }
+                    None => !config.templates.contains_key(&state.profile_name),
                 }
             }
+        };
+        if !weakened {
+            return Ok(());
+        }
+
+        if let Some(content) = content {
+            if self.read_queued_config()?.is_some() {
+                eprintln!("Warning: this edit replaces the config changes queued before it");
+            }
+            self.queue_config(QueuedConfig {
+                path: source.path.clone(),
+                content,
+            })?;
+        }
+        fs::write(&source.path, backup)?;
+        state.tampered("config", Some(&source.path));
+        eprintln!(
+            "{} was edited to weaken the block, put it back",
+            source.path.display()
+        );
+        state.alert(
+            "Config put back",
+            "Edits taking blocked items out of the profile apply once the block ends",
+        )
+    }
+
+    /// Reports the block's jobs whose plist or unit was deleted from under it. They
+    /// aren't put back from here, reinstalling would take this guard down with them. Run
+    /// by the jobs guard.
+    pub fn guard_jobs(&self) -> Result<()> {
+        let Some(state) = self.read_block_state()? else {
+            return Ok(());
+        };
+        if EnoughDaemon::missing_jobs(&self.state_dir).is_empty() {
+            return Ok(());
         }
-        new_content.push_str(&format!("{}\n\n", ENOUGH_MARKER_END));
 
-        fs::write(HOSTS_FILE, new_content)?;
+        // jobs are briefly missing while they're replaced, and all of them while lifting
+        thread::sleep(JOBS_SETTLE_DELAY);
+        let missing = EnoughDaemon::missing_jobs(&self.state_dir);
+        if missing.is_empty() || self.unfinished_run()?.is_some() {
+            return Ok(());
+        }
 
-        let output = Command::new("sudo")
-            .args(&["dscacheutil", "-flushcache"])
-            .output()?;
+        eprintln!("Jobs of the block were removed: {}", missing.join(", "));
+        state.tampered("job", None);
+        state.alert(
+            "Block jobs removed",
+            "Removing them doesn't end the block, run `sudo enough doctor --fix` to repair it",
+        )
+    }
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            anyhow::bail!("Failed to flush DNS cache: {}", stderr);
+    fn read_queued_config(&self) -> Result<Option<QueuedConfig>> {
+        let path = self.state_dir.join(QUEUED_CONFIG_FILE);
+        if !path.exists() {
+            return Ok(None);
         }
 
-        eprintln!("Blocked {} websites using hosts file", websites.len());
+        let content = at_rest::open(fs::read_to_string(path)?)?;
+        Ok(Some(serde_yml::from_str(&content)?))
+    }
 
+    fn queue_config(&self, queued: QueuedConfig) -> Result<()> {
+        fs::write(
+            self.state_dir.join(QUEUED_CONFIG_FILE),
+            at_rest::seal(&serde_yml::to_string(&queued)?)?,
+        )?;
         Ok(())
     }
 
-    fn block_apps(apps: &[PathBuf]) -> Result<()> {
-        for app in apps {
-            change_app_perms(app, BLOCKED_APP_PERMS)?;
+    fn read_block_state(&self) -> Result<Option<BlockState>> {
+        let state_file = self.state_dir.join("current_block.yaml");
+        if !state_file.exists() {
+            return Ok(None);
         }
 
-        todo!("App blocking not implemented yet");
+        let state_content = at_rest::open(fs::read_to_string(&state_file)?)?;
+        let mut state = serde_yml::from_str::<BlockState>(&state_content)?;
+        if state.schema_version > STATE_SCHEMA_VERSION {
+            anyhow::bail!(
+                "The block was started by a newer version of enough, which has to end it"
+            );
+        }
+        // older states only lack fields, which the defaults fill in, so they're the
+        // current version once read
+        state.schema_version = STATE_SCHEMA_VERSION;
+        Ok(Some(state))
     }
 
-    pub fn unblock_all(&self) -> Result<()> {
-        Self::unblock_websites()?;
-        // Self::unblock_apps()?;
+    fn write_block_state(&self, state: &BlockState) -> Result<()> {
+        let state_yml = at_rest::seal(&serde_yml::to_string(state)?)?;
+        let state_file = self.state_dir.join("current_block.yaml");
+        // renamed into place, so the daemons never read half of it
+        let staged = state_file.with_extension("yaml.tmp");
+        fs::write(&staged, state_yml)?;
+        fs::rename(staged, state_file)?;
+
+        // tiny flat copy of what prompts need, so they don't parse the whole state
+        let sidecar = format!("{} {}\n", state.unblock_time_secs, state.profile_name);
+        fs::write(self.state_dir.join(STATUS_SIDECAR_FILE), sidecar)?;
+
+        Ok(())
+    }
 
-        // Removing launchd daemon
-        EnoughDaemon::remove()?;
+    /// Writes `<profile> <seconds left>` to the remaining-time file, if the block keeps
+    /// one. Run by its job every interval.
+    pub fn write_remaining_file(&self) -> Result<()> {
+        let Some(state) = self.read_block_state()? else {
+            return Ok(());
+        };
+        let Some(path) = &state.remaining_file else {
+            return Ok(());
+        };
 
-        // Cleaning up state
-        fs::remove_dir_all(&self.state_dir)?;
+        let remaining = state
+            .unblock_time_secs
+            .saturating_sub(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs());
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // written aside and renamed, so readers never see a half-written file
+        let partial = path.with_extension("partial");
+        fs::write(&partial, format!("{} {}\n", state.profile_name, remaining))?;
+        unix_fs::chown(&partial, Some(self.uid), None)?;
+        fs::rename(&partial, path)?;
 
         Ok(())
     }
 
-    fn unblock_websites() -> Result<()> {
-        let hosts_file_contents = fs::read_to_string(HOSTS_FILE)?;
-        let cleaned_content = Self::remove_existing_blocks(&hosts_file_contents);
-        fs::write(HOSTS_FILE, cleaned_content)?;
+    /// Cheap status lookup meant for shell prompts and status bars. Reads the sidecar
+    /// file only, falling back to the full state when it's missing or unreadable.
+    pub fn get_quick_status(&self) -> Result<Status> {
+        match self.read_sidecar() {
+            Some(status) => Ok(status),
+            None => self.get_status(),
+        }
+    }
 
-        let output = Command::new("sudo")
-            .args(&["dscacheutil", "-flushcache"])
-            .output()
-            .with_context(|| "Failed to get output for DNS flushing command")?;
+    fn read_sidecar(&self) -> Option<Status> {
+        let sidecar = fs::read_to_string(self.state_dir.join(STATUS_SIDECAR_FILE)).ok()?;
+        let (secs, profile_name) = sidecar.trim_end().split_once(' ')?;
+        let unblock_time_secs = secs.parse::<u64>().ok()?;
+        Some(Status::Blocked {
+            profile_name: profile_name.to_string(),
+            unblock_time: (UNIX_EPOCH + Duration::from_secs(unblock_time_secs)).into(),
+        })
+    }
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            anyhow::bail!("Failed to flush DNS cache: {}", stderr);
+    pub fn get_status(&self) -> Result<Status> {
+        // an encrypted state only opens as root, the sidecar has all a status needs
+        if !at_rest::can_open()
+            && at_rest::is_sealed(&self.state_dir.join("current_block.yaml"))
+            && let Some(status) = self.read_sidecar()
+        {
+            return Ok(status);
         }
 
-        Ok(())
+        Ok(match self.read_status_view()? {
+            Some(view) => view.into_status(),
+            None => Status::Unblocked,
+        })
     }
 
-    fn unblock_apps() -> Result<()> {
-        todo!(
-            "Need to backup each app's permissions before blocking in a file and restore from there"
-        );
-        // for app in apps {
-        //     change_app_perms(app, UNBLOCKED_APP_PERMS)?;
-        // }
+    /// How the active block's websites are blocked, `None` without an active block.
+    pub fn websites_backend(&self) -> Result<Option<WebsitesBackend>> {
+        Ok(self.read_block_state()?.map(|state| state.websites_backend))
     }
 
-    fn remove_existing_blocks(content: &str) -> String {
-        let lines = content.lines().collect::<Vec<_>>();
-        let mut result = Vec::new();
-        let mut in_block = false;
+    /// The active block's soft-block settings, `None` without one or for a hard block.
+    pub fn friction(&self) -> Result<Option<Friction>> {
+        Ok(self
+            .read_block_state()?
+            .and_then(|state| state.profile.friction))
+    }
 
-        for line in lines {
-            if line.contains(ENOUGH_MARKER_START) {
-                in_block = true;
-                continue;
+    /// Traces why a website (URL or bare domain) or app path is blocked by the active block.
+    pub fn explain(&self, target: &str) -> Result<BlockReason> {
+        let Some(state) = self.read_block_state()? else {
+            return Ok(BlockReason::NoActiveBlock);
+        };
+        let profile_name = state.profile_name.clone();
+
+        let target = target.trim();
+        let looks_like_path = target.starts_with('/') || target.ends_with(".app");
+        if looks_like_path {
+            let path = PathBuf::from(target.trim_end_matches('/'));
+            if state.profile.apps.contains(&path)
+                || state.resolved.moved.values().any(|found| *found == path)
+            {
+                return Ok(BlockReason::App { profile_name, path });
             }
 
-            if line.contains(ENOUGH_MARKER_END) {
-                in_block = false;
+            if let Some(limit) = state
+                .profile
+                .app_limits
+                .iter()
+                .find(|limit| limit.app == path)
+            {
+                return Ok(BlockReason::UsageLimit {
+                    profile_name,
+                    path,
+                    used: AppUsage::load(self.uid)?.used(&limit.app),
+                    allowance: limit.allowance,
+                });
+            }
+
+            if let Some(group) = state.resolved.grouped.get(&path) {
+                return Ok(BlockReason::AppGroup {
+                    profile_name,
+                    path,
+                    group: group.clone(),
+                });
+            }
+
+            // apps blocked without being listed were picked up as site apps
+            if state.resolved.apps.contains(&path) {
+                return Ok(BlockReason::SiteApp { profile_name, path });
+            }
+
+            return Ok(BlockReason::NotBlocked { profile_name });
+        }
+
+        let url = Url::parse(target).or_else(|_| Url::parse(&format!("https://{}", target)))?;
+        let host = url
+            .host_str()
+            .with_context(|| format!("`{}` doesn't have a host", target))?;
+
+        for website in &state.profile.websites {
+            let Some(blocked) = website.url.host_str() else {
                 continue;
+            };
+
+            if host == blocked {
+                return Ok(BlockReason::Website {
+                    profile_name,
+                    entry: website.url.clone(),
+                    variant: false,
+                });
             }
 
-            if !in_block {
-                result.push(line);
+            if host.trim_start_matches("www.") == blocked.trim_start_matches("www.") {
+                return Ok(BlockReason::Website {
+                    profile_name,
+                    entry: website.url.clone(),
+                    variant: true,
+                });
             }
         }
+        // a host listed as a website of its own is reported as that
+        if let Some(website) = state
+            .profile
+            .websites
+            .iter()
+            .find(|website| website_hosts(website).iter().any(|blocked| blocked == host))
+        {
+            return Ok(BlockReason::Subdomain {
+                profile_name,
+                entry: website.url.clone(),
+            });
+        }
+        if state.block_doh && !state.profile.websites.is_empty() && DOH_ENDPOINTS.contains(&host) {
+            return Ok(BlockReason::DohEndpoint { profile_name });
+        }
 
-        result.join("\n")
+        Ok(BlockReason::NotBlocked { profile_name })
     }
 
-    fn schedule_unblock(&self, unblock_time: DateTime<Local>) -> Result<()> {
-        EnoughDaemon::schedule(unblock_time)?;
-        Ok(())
+    /// Details about the active block for human readable output, if one is running.
+    pub fn get_summary(&self) -> Result<Option<BlockSummary>> {
+        let Some(view) = self.read_status_view()? else {
+            return Ok(None);
+        };
+
+        let unblock_time = UNIX_EPOCH + Duration::from_secs(view.unblock_time_secs);
+        let remaining = unblock_time
+            .duration_since(SystemTime::now())
+            .unwrap_or_default();
+
+        if remaining.is_zero() {
+            return Ok(None);
+        }
+
+        Ok(Some(BlockSummary {
+            profile_name: view.profile_name,
+            apps: view.resolved.apps.len(),
+            auto_blocked: view.resolved.auto_blocked,
+            websites: view.profile.websites.len(),
+            domains: view.resolved.domains.len(),
+            remaining,
+            cooling_down: view.cooling_down,
+            config_changed: view
+                .config_source
+                .is_some_and(|source| source.has_changed()),
+            allowed: view
+                .exceptions
+                .into_iter()
+                .map(|exception| {
+                    let until = UNIX_EPOCH + Duration::from_secs(exception.until_secs);
+                    (exception.domain, until)
+                })
+                .filter(|(_, until)| *until > SystemTime::now())
+                .map(|(domain, until)| (domain, until.into()))
+                .collect(),
+            only: view.only,
+            items: view.items,
+            together: view.together,
+            until: view.until,
+        }))
     }
 
-    fn save_block_state(
-        &self,
-        profile_name: &str,
-        profile: &Profile,
-        unblock_time: SystemTime,
-    ) -> Result<()> {
-        let state = BlockState {
-            profile_name: profile_name.to_string(),
-            profile: profile.clone(),
-            unblock_time_secs: unblock_time.duration_since(UNIX_EPOCH)?.as_secs(),
+    /// Everything about the active block in one serializable struct, see
+    /// [`BlockSnapshot`].
+    pub fn get_snapshot(&self) -> Result<Option<BlockSnapshot>> {
+        let Some(state) = self.read_block_state()? else {
+            return Ok(None);
         };
 
-        let state_yml = serde_yml::to_string(&state)?;
-        let state_file = self.state_dir.join("current_block.yaml");
-        fs::write(state_file, state_yml)?;
+        let unblock_time = UNIX_EPOCH + Duration::from_secs(state.unblock_time_secs);
+        let remaining = unblock_time
+            .duration_since(SystemTime::now())
+            .unwrap_or_default();
+        if remaining.is_zero() {
+            return Ok(None);
+        }
 
-        Ok(())
+        let mut recent_sessions = history::sessions()?;
+        let skipped = recent_sessions.len().saturating_sub(SNAPSHOT_SESSIONS);
+        recent_sessions.drain(..skipped);
+
+        Ok(Some(BlockSnapshot {
+            allowed: state.allowed_domains(),
+            profile_name: state.profile_name,
+            unblock_time: unblock_time.into(),
+            remaining: Duration::from_secs(remaining.as_secs()),
+            cooling_down: state.cooling_down,
+            websites: state.profile.urls(),
+            apps: state.resolved.apps,
+            recent_sessions,
+        }))
     }
 
-    // i don't fw this function mixing printing with business logic
-    pub fn get_status(&self, print: bool) -> Result<Status> {
+    fn read_status_view(&self) -> Result<Option<StatusView>> {
         let state_file = self.state_dir.join("current_block.yaml");
 
         if !state_file.exists() {
-            if print {
-                eprintln!("No active block is running");
-            }
-            return Ok(Status::Unblocked);
+            return Ok(None);
         }
 
-        let state_content = fs::read_to_string(&state_file)?;
-        let state = serde_yml::from_str::<BlockState>(&state_content)?;
+        let state_content = at_rest::open(fs::read_to_string(&state_file)?)?;
+        let view = serde_yml::from_str::<StatusView>(&state_content)?;
+        Ok(Some(view))
+    }
+}
 
-        let unblock_time = UNIX_EPOCH + Duration::from_secs(state.unblock_time_secs);
-        let now = SystemTime::now();
+/// The subset of [`BlockState`] that status queries need. Profile entries are only
+/// counted, so reading it never parses URLs or paths.
+#[derive(Deserialize)]
+struct StatusView {
+    profile_name: String,
+    profile: ProfileCounts,
+    unblock_time_secs: u64,
+    #[serde(default)]
+    cooling_down: bool,
+    #[serde(default)]
+    config_source: Option<ConfigSource>,
+    #[serde(default)]
+    resolved: ResolvedCounts,
+    #[serde(default)]
+    exceptions: Vec<TempException>,
+    #[serde(default)]
+    only: Option<BlockScope>,
+    #[serde(default)]
+    items: ItemFilter,
+    #[serde(default)]
+    together: Option<Membership>,
+    #[serde(default)]
+    until: bool,
+}
+
+#[derive(Deserialize, Default)]
+struct ResolvedCounts {
+    #[serde(default)]
+    domains: Vec<IgnoredAny>,
+    #[serde(default)]
+    apps: Vec<IgnoredAny>,
+    #[serde(default)]
+    auto_blocked: Vec<PathBuf>,
+}
 
-        if now < unblock_time && print {
-            let remaining = unblock_time.duration_since(now)?;
+#[derive(Deserialize)]
+struct ProfileCounts {
+    #[serde(default)]
+    websites: Vec<IgnoredAny>,
+}
 
-            println!("Active block (profile: {})", state.profile_name);
-            println!("• {} apps blocked", state.profile.apps.len());
-            println!("• {} websites blocked", state.profile.websites.len());
-            println!("• Time remaining: {}", format_duration(remaining));
+impl StatusView {
+    fn into_status(self) -> Status {
+        Status::Blocked {
+            profile_name: self.profile_name,
+            unblock_time: (UNIX_EPOCH + Duration::from_secs(self.unblock_time_secs)).into(),
         }
+    }
+}
 
-        Ok(Status::Blocked {
-            profile_name: state.profile_name,
-            unblock_time: unblock_time.into(),
-        })
+/// The active block as a whole, for tools embedding its status (a prompt module, a
+/// status bar plugin) by linking the crate.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BlockSnapshot {
+    pub profile_name: String,
+    pub unblock_time: DateTime<Local>,
+    #[serde(with = "humantime_serde")]
+    pub remaining: Duration,
+    /// Whether the main block is over and only the cool-down part is left
+    pub cooling_down: bool,
+    pub websites: Vec<Url>,
+    /// Including site apps and used up app limits
+    pub apps: Vec<PathBuf>,
+    /// Domains let through for now by `allow-temp`
+    pub allowed: Vec<String>,
+    /// The latest sessions, this one included, oldest first
+    pub recent_sessions: Vec<Session>,
+}
+
+#[derive(Debug)]
+pub struct BlockSummary {
+    pub profile_name: String,
+    /// Including site apps, group members and used up app limits
+    pub apps: usize,
+    /// Group members blocked as they were installed during the block
+    pub auto_blocked: Vec<PathBuf>,
+    pub websites: usize,
+    /// Domains the websites expanded to
+    pub domains: usize,
+    pub remaining: Duration,
+    pub cooling_down: bool,
+    /// The config file was edited (or removed) after the block started
+    pub config_changed: bool,
+    /// Domains let through for now by `allow-temp`, with when they get blocked again
+    pub allowed: Vec<(String, DateTime<Local>)>,
+    /// Set when only the websites, or only the apps, of the profile are blocked
+    pub only: Option<BlockScope>,
+    /// Set when starting the block left items out, or kept to some
+    pub items: ItemFilter,
+    /// The room of the group session, if the block is part of one
+    pub together: Option<Membership>,
+    /// Started `--until` a time of day
+    pub until: bool,
+}
+
+#[derive(Debug)]
+pub enum BlockReason {
+    NoActiveBlock,
+    NotBlocked {
+        profile_name: String,
+    },
+    Website {
+        profile_name: String,
+        entry: Url,
+        /// Blocked as the `www.`/non-`www.` counterpart of the entry
+        variant: bool,
+    },
+    /// One of the subdomains or related hosts blocked along with a website
+    Subdomain {
+        profile_name: String,
+        entry: Url,
+    },
+    /// One of the [`DOH_ENDPOINTS`], blocked with `block-doh`
+    DohEndpoint {
+        profile_name: String,
+    },
+    App {
+        profile_name: String,
+        path: PathBuf,
+    },
+    SiteApp {
+        profile_name: String,
+        path: PathBuf,
+    },
+    AppGroup {
+        profile_name: String,
+        path: PathBuf,
+        group: String,
+    },
+    /// An app with a usage limit, blocked once `used` is past `allowance`
+    UsageLimit {
+        profile_name: String,
+        path: PathBuf,
+        used: Duration,
+        allowance: Duration,
+    },
+}
+
+impl Display for BlockReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoActiveBlock => write!(f, "Not blocked, no active block is running"),
+            Self::NotBlocked { profile_name } => {
+                write!(f, "Not blocked by the active profile `{}`", profile_name)
+            }
+            Self::Website {
+                profile_name,
+                entry,
+                variant: false,
+            } => write!(
+                f,
+                "Blocked by profile `{}`, listed as {}",
+                profile_name, entry
+            ),
+            Self::Website {
+                profile_name,
+                entry,
+                variant: true,
+            } => write!(
+                f,
+                "Blocked by profile `{}`, as the www counterpart of {}",
+                profile_name, entry
+            ),
+            Self::Subdomain {
+                profile_name,
+                entry,
+            } => write!(
+                f,
+                "Blocked by profile `{}`, along with {}",
+                profile_name, entry
+            ),
+            Self::DohEndpoint { profile_name } => write!(
+                f,
+                "Blocked by profile `{}`, as a DNS over HTTPS endpoint (`block-doh`)",
+                profile_name
+            ),
+            Self::App { profile_name, path } => write!(
+                f,
+                "Blocked by profile `{}`, listed as {}",
+                profile_name,
+                path.display()
+            ),
+            Self::SiteApp { profile_name, path } => write!(
+                f,
+                "Blocked by profile `{}`, {} is a site app opening one of its websites",
+                profile_name,
+                path.display()
+            ),
+            Self::AppGroup {
+                profile_name,
+                path,
+                group,
+            } => write!(
+                f,
+                "Blocked by profile `{}`, {} is in the `{}` app group",
+                profile_name,
+                path.display(),
+                group
+            ),
+            Self::UsageLimit {
+                profile_name,
+                path,
+                used,
+                allowance,
+            } if used >= allowance => write!(
+                f,
+                "Blocked by profile `{}`, the daily allowance of {} for {} is used up",
+                profile_name,
+                format_duration(*allowance),
+                path.display()
+            ),
+            Self::UsageLimit {
+                profile_name,
+                path,
+                used,
+                allowance,
+            } => write!(
+                f,
+                "Not blocked yet, profile `{}` allows {} of {} per day and {} is used",
+                profile_name,
+                format_duration(*allowance),
+                path.display(),
+                format_duration(*used)
+            ),
+        }
     }
 }
 
@@ -251,17 +3487,632 @@ impl Status {
     pub fn is_blocked(&self) -> bool {
         matches!(self, Self::Blocked { .. })
     }
+
+    /// The status as printed for other tools, a JSON object with the
+    /// [`STATUS_SCHEMA_VERSION`] and the profile and unblock time while blocked.
+    pub fn to_json(&self) -> Result<String> {
+        let mut json = serde_json::json!({ "schema_version": STATUS_SCHEMA_VERSION });
+        if let serde_json::Value::Object(status) = serde_json::to_value(self)? {
+            json.as_object_mut()
+                .context("The status isn't a JSON object")?
+                .extend(status);
+        }
+        Ok(json.to_string())
+    }
+}
+
+/// Logs when the scheduled unblock actually ran, against when it was planned for.
+fn log_fire_time(fired: SystemTime, planned: SystemTime) {
+    let offset = match fired.duration_since(planned) {
+        Err(early) => -(early.duration().as_secs_f64()),
+        late => late.unwrap_or_default().as_secs_f64(),
+    };
+    eprintln!(
+        "[{}] Scheduled unblock fired, planned for {} ({:+.1}s)",
+        DateTime::<Local>::from(fired).format("%Y-%m-%d %H:%M:%S"),
+        DateTime::<Local>::from(planned).format("%H:%M:%S"),
+        offset
+    );
+}
+
+/// Where [`ENOUGH_DATA_DIR`] is, moved under the sandbox in one.
+pub fn data_dir() -> PathBuf {
+    sandbox::path(ENOUGH_DATA_DIR)
+}
+
+/// Where [`ENOUGH_STATE_DIR`] is, holding a state directory and a lock per user.
+pub fn state_root() -> PathBuf {
+    sandbox::path(ENOUGH_STATE_DIR)
+}
+
+/// IDs of the jobs of user `uid` running blocks or not, the quota watcher and the
+/// scheduler.
+pub fn persistent_jobs(uid: u32) -> Vec<String> {
+    [
+        EnoughDaemon::quota_watcher(&usage::quota_watcher_dir(uid)),
+        EnoughDaemon::scheduler(&scheduler::scheduler_dir(uid)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+fn utc_offset_secs() -> i32 {
+    Local::now().offset().local_minus_utc()
+}
+
+/// The websites that aren't inside their allow window at `time`.
+fn enforced_websites(profile: &Profile, time: NaiveTime) -> Vec<Website> {
+    profile
+        .websites
+        .iter()
+        .filter(|website| !website.is_allowed_at(time))
+        .cloned()
+        .collect()
+}
+
+/// The hosts blocking `website` covers: its own with the `www.` counterpart, then its
+/// `subdomains` when given, the [`KNOWN_SUBDOMAINS`] of it otherwise.
+fn website_hosts(website: &Website) -> Vec<String> {
+    let mut hosts = hosts::hosts_for(&website.url);
+    let Some(domain) = hosts
+        .first()
+        .map(|host| host.trim_start_matches("www.").to_string())
+    else {
+        return hosts;
+    };
+    let expanded = match &website.subdomains {
+        Some(subdomains) => subdomains
+            .iter()
+            .map(|subdomain| format!("{}.{}", subdomain, domain))
+            .collect(),
+        None => KNOWN_SUBDOMAINS
+            .iter()
+            .find(|(known, _)| *known == domain)
+            .map(|(_, related)| related.iter().map(|host| host.to_string()).collect())
+            .unwrap_or_else(Vec::new),
+    };
+    for host in expanded {
+        if !hosts.contains(&host) {
+            hosts.push(host);
+        }
+    }
+    hosts
+}
+
+/// What the `step` of blocking `state` does, `None` when there's nothing to do.
+fn describe_step(step: Step, state: &BlockState) -> Option<String> {
+    let profile = &state.profile;
+    match step {
+        Step::Hosts => (!profile.websites.is_empty()).then(|| {
+            let through = match state.websites_backend {
+                WebsitesBackend::Hosts if profile.friction.is_some() => {
+                    "the hosts file, showing the countdown page"
+                }
+                WebsitesBackend::Hosts => "the hosts file",
+                WebsitesBackend::Resolver => "resolver files",
+                WebsitesBackend::DnsProxy => "the local DNS proxy",
+                WebsitesBackend::Pf => "pf rules",
+            };
+            format!(
+                "block {} domains of {} websites through {}",
+                state.resolved.domains.len(),
+                profile.websites.len(),
+                through
+            )
+        }),
+        Step::Pf => {
+            let mut parts = Vec::new();
+            if profile.vpn_kill_switch.is_some() {
+                parts.push("engage the VPN kill switch");
+            }
+            if state.block_doh
+                && cfg!(all(feature = "pf", target_os = "macos"))
+                && !profile.websites.is_empty()
+            {
+                parts.push("block DNS over TLS");
+            }
+            (!parts.is_empty()).then(|| parts.join(" and "))
+        }
+        Step::DnsFlush => (!profile.websites.is_empty()).then(|| "flush the DNS cache".to_string()),
+        Step::Session => {
+            let mut parts = Vec::new();
+            if let Some(silence) = &profile.silence_notifications {
+                parts.push(format!(
+                    "silence notifications from {} apps",
+                    silence.apps.len()
+                ));
+            }
+            if let Some(hooks) = &profile.hooks
+                && !hooks.on_start.is_empty()
+            {
+                parts.push(format!("run {} start hooks", hooks.on_start.len()));
+            }
+            if profile.save_context.is_some() {
+                parts.push("save the work context".to_string());
+            }
+            if profile.close_tabs.is_some() {
+                parts.push("close tabs on blocked websites".to_string());
+            }
+            (!parts.is_empty()).then(|| parts.join(", "))
+        }
+        Step::Apps => (!state.resolved.apps.is_empty()).then(|| {
+            let apps = state
+                .resolved
+                .apps
+                .iter()
+                .map(|app| app.display().to_string())
+                .collect::<Vec<_>>();
+            format!("block {} apps: {}", apps.len(), apps.join(", "))
+        }),
+        Step::Daemon => {
+            let unblock_time: DateTime<Local> =
+                (UNIX_EPOCH + Duration::from_secs(state.unblock_time_secs)).into();
+            Some(format!(
+                "schedule the unblock for {}",
+                unblock_time.format("%Y-%m-%d %H:%M:%S")
+            ))
+        }
+        Step::State => Some("save the block state".to_string()),
+    }
+}
+
+/// What installed `app`, if it can be told.
+fn install_source(app: &Path) -> Option<&'static str> {
+    if app.join("Contents/_MASReceipt").exists() {
+        return Some("the App Store");
+    }
+
+    // casks are moved out of the Caskroom, which only keeps their metadata
+    let recently_changed = |dir: &&str| {
+        fs::metadata(sandbox::path(dir))
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|elapsed| elapsed < RECENT_INSTALL)
+    };
+    REINSTALL_DIRS
+        .iter()
+        .filter(|dir| dir.ends_with("Caskroom"))
+        .any(recently_changed)
+        .then_some("Homebrew")
+}
+
+/// Posts a notification in the invoking user's session.
+fn alert(subtitle: &str, message: &str) -> Result<()> {
+    let script = format!(
+        "display notification \"{}\" with title \"enough\" subtitle \"{}\"",
+        message.replace('"', "\\\""),
+        subtitle
+    );
+    as_invoking_user("osascript")
+        .args(["-e", &script])
+        .output()?;
+    Ok(())
 }
 
-fn change_app_perms(app: &PathBuf, perms: &str) -> Result<()> {
-    let output = Command::new("sudo")
-        .args(&["chmod", perms, app.to_str().unwrap()])
+/// Shows an alert with `buttons`, the last one the default, returning the one clicked.
+/// `None` when no one clicked before it went away, so the scheduler isn't held up.
+fn ask(subtitle: &str, message: &str, buttons: &[&str]) -> Result<Option<String>> {
+    let quoted = buttons
+        .iter()
+        .map(|button| format!("\"{}\"", button))
+        .collect::<Vec<_>>();
+    let script = format!(
+        "display alert \"{}\" message \"{}\" buttons {{{}}} default button {} giving up after {}",
+        subtitle,
+        message.replace('"', "\\\""),
+        quoted.join(", "),
+        quoted.last().map(String::as_str).unwrap_or_default(),
+        ALERT_TIMEOUT.as_secs()
+    );
+    let output = as_invoking_user("osascript")
+        .args(["-e", &script])
         .output()?;
+    // e.g. `button returned:OK, gave up:false`
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout
+        .trim()
+        .strip_prefix("button returned:")
+        .and_then(|rest| rest.split(", gave up:").next())
+        .filter(|button| !button.is_empty())
+        .map(str::to_string))
+}
+
+/// The copy of `app` in `trash`, which Finder renames (`Steam 10.21.07.app`) when the
+/// name is taken. The latest one if there are several.
+fn trashed_copy(trash: &Path, app: &Path) -> Option<PathBuf> {
+    let exact = trash.join(app.file_name()?);
+    if exact.exists() {
+        return Some(exact);
+    }
+
+    let stem = app.file_stem()?.to_str()?;
+    fs::read_dir(trash)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry.file_name().to_str().is_some_and(|name| {
+                name.strip_prefix(stem)
+                    .is_some_and(|rest| rest.starts_with(' ') && rest.ends_with(".app"))
+            })
+        })
+        .max_by_key(|entry| entry.metadata().and_then(|meta| meta.modified()).ok())
+        .map(|entry| entry.path())
+}
+
+/// Moves `trashed` back to `app`, copying across volumes where a rename can't.
+fn put_back(trashed: &Path, app: &Path) -> Result<()> {
+    if fs::rename(trashed, app).is_ok() {
+        return Ok(());
+    }
 
+    let output = Command::new("mv")
+        .arg(trashed)
+        .arg(app)
+        .output()
+        .with_context(|| format!("Failed to move {}", trashed.display()))?;
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("Failed to block app {:?}: {}", app, stderr);
+        anyhow::bail!("mv failed: {}", stderr.trim());
+    }
+
+    Ok(())
+}
+
+/// The executables `app` can be started through: the files in its `Contents/MacOS`, and
+/// in that of helper apps nested in it (login items, crash reporters, ...).
+fn bundle_executables(app: &Path) -> Vec<PathBuf> {
+    let mut bundles = vec![app.to_path_buf()];
+    collect_helper_apps(&app.join("Contents"), HELPER_APPS_DEPTH, &mut bundles);
+
+    let mut executables = bundles
+        .iter()
+        .filter_map(|bundle| fs::read_dir(bundle.join("Contents/MacOS")).ok())
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_ok_and(|file_type| file_type.is_file()))
+        .map(|entry| entry.path())
+        .collect::<Vec<_>>();
+    executables.sort();
+    executables
+}
+
+fn collect_helper_apps(dir: &Path, depth: usize, bundles: &mut Vec<PathBuf>) {
+    let Some(entries) = fs::read_dir(dir).ok() else {
+        return;
+    };
+
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        // symlinks inside frameworks point back into the same bundle
+        if !entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+            continue;
+        }
+
+        let path = entry.path();
+        if path.extension().is_some_and(|ext| ext == "app") {
+            collect_helper_apps(&path.join("Contents"), depth.saturating_sub(1), bundles);
+            bundles.push(path);
+        } else if depth > 1 && entry.file_name() != "Resources" {
+            collect_helper_apps(&path, depth - 1, bundles);
+        }
+    }
+}
+
+/// Removes every permission from the executables inside `app`, backing up the modes of
+/// the ones `backup` doesn't have yet.
+fn block_executables(app: &Path, backup: &mut BTreeMap<PathBuf, String>) -> Result<()> {
+    for executable in bundle_executables(app) {
+        let mode = fs::symlink_metadata(&executable)
+            .with_context(|| format!("Failed to read permissions of {}", executable.display()))?
+            .permissions()
+            .mode();
+        backup
+            .entry(executable.clone())
+            .or_insert_with(|| format!("{:o}", mode & 0o7777));
+        fs::set_permissions(&executable, Permissions::from_mode(0o000))
+            .with_context(|| format!("Failed to change permissions of {}", executable.display()))?;
     }
 
     Ok(())
 }
+
+/// Puts back the modes in a backup from [`block_executables`], skipping files that are
+/// gone.
+fn restore_perms(backup: &BTreeMap<PathBuf, String>) -> Result<()> {
+    for (path, mode) in backup {
+        if fs::symlink_metadata(path).is_err() {
+            continue;
+        }
+
+        let mode = u32::from_str_radix(mode, 8)
+            .with_context(|| format!("Invalid mode `{}` backed up for {}", mode, path.display()))?;
+        fs::set_permissions(path, Permissions::from_mode(mode))
+            .with_context(|| format!("Failed to restore permissions of {}", path.display()))?;
+    }
+
+    Ok(())
+}
+
+fn change_app_perms(app: &Path, perms: &str) -> Result<()> {
+    let mode = u32::from_str_radix(perms, 8)
+        .with_context(|| format!("Invalid mode `{}` for app {:?}", perms, app))?;
+    fs::set_permissions(app, Permissions::from_mode(mode))
+        .with_context(|| format!("Failed to change permissions of app {:?}", app))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A manager with a block of `profile` in a state directory of its own, ending
+    /// `ends_in` seconds from now (before now if negative).
+    fn block_of(profile: &str, ends_in: i64) -> BlockManager {
+        let state_dir = env::temp_dir().join(format!("enough-test-{}", Uuid::new_v4()));
+        fs::create_dir_all(&state_dir).unwrap();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        fs::write(
+            state_dir.join("current_block.yaml"),
+            format!(
+                "profile_name: focus\nprofile: {}\nunblock_time_secs: {}\n",
+                profile,
+                now + ends_in
+            ),
+        )
+        .unwrap();
+        BlockManager {
+            state_dir,
+            ..BlockManager::new()
+        }
+    }
+
+    #[test]
+    fn strict_blocks_are_only_lifted_with_the_token_or_once_over() {
+        let strict = block_of("{duration: 1h, strict: true}", 3600);
+        assert!(strict.check_liftable().is_err());
+        fs::write(strict.state_dir.join(UNBLOCK_TOKEN_FILE), "token\n").unwrap();
+        assert!(strict.check_liftable().is_err());
+        let wrong = BlockManager {
+            unblock_token: Some("other".to_string()),
+            ..block_of("{duration: 1h, strict: true}", 3600)
+        };
+        fs::write(wrong.state_dir.join(UNBLOCK_TOKEN_FILE), "token\n").unwrap();
+        assert!(wrong.check_liftable().is_err());
+        let scheduled = BlockManager {
+            state_dir: strict.state_dir.clone(),
+            ..BlockManager::new().with_unblock_token(Some("token".to_string()))
+        };
+        assert!(scheduled.check_liftable().is_ok());
+
+        // a missing token file lets nothing through, whatever is handed over
+        fs::remove_file(strict.state_dir.join(UNBLOCK_TOKEN_FILE)).unwrap();
+        assert!(scheduled.check_liftable().is_err());
+
+        let over = block_of("{duration: 1h, strict: true}", -60);
+        assert!(over.check_liftable().is_ok());
+        let casual = block_of("{duration: 1h}", 3600);
+        assert!(casual.check_liftable().is_ok());
+
+        for manager in [strict, wrong, over, casual] {
+            fs::remove_dir_all(&manager.state_dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn blocks_started_with_a_partner_take_their_code_early_only() {
+        // redeeming a code keeps it in the data directory
+        sandbox::for_tests();
+        let secret = "partner secret";
+        let partnered = |ends_in| {
+            let manager = block_of("{duration: 1h}", ends_in);
+            fs::write(manager.state_dir.join(partner::SECRET_FILE), secret).unwrap();
+            manager
+        };
+
+        let early = partnered(3600);
+        assert!(early.needs_partner_code().unwrap());
+        assert!(early.check_liftable().is_err());
+        let state_dir = early.state_dir.clone();
+        assert!(early.with_partner_code("000000x").is_err());
+        let early = BlockManager {
+            state_dir,
+            ..BlockManager::new()
+        }
+        .with_partner_code(&partner::code(secret, SystemTime::now()))
+        .unwrap();
+        assert!(!early.needs_partner_code().unwrap());
+        assert!(early.check_liftable().is_ok());
+
+        let over = partnered(-60);
+        assert!(!over.needs_partner_code().unwrap());
+        assert!(over.check_liftable().is_ok());
+        // a strict one doesn't give way to the code either
+        let strict = block_of("{duration: 1h, strict: true}", 3600);
+        fs::write(strict.state_dir.join(partner::SECRET_FILE), secret).unwrap();
+        assert!(!strict.needs_partner_code().unwrap());
+        assert!(strict.check_liftable().is_err());
+
+        for manager in [early, over, strict] {
+            fs::remove_dir_all(&manager.state_dir).unwrap();
+        }
+    }
+
+    /// The sandbox of the tests, with a hosts file.
+    fn sandbox_with_hosts() -> &'static Path {
+        let root = sandbox::for_tests();
+        fs::create_dir_all(root.join("etc")).unwrap();
+        if !root.join("etc/hosts").exists() {
+            fs::write(root.join("etc/hosts"), "127.0.0.1 localhost\n").unwrap();
+        }
+        root
+    }
+
+    #[test]
+    fn websites_let_through_are_blocked_again_once_their_time_is_up() {
+        let root = sandbox_with_hosts();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let manager = block_of(
+            &format!(
+                "{{duration: 1h, websites: [https://reddit.com, https://news.ycombinator.com]}}\nexceptions: [{{domain: reddit.com, until_secs: {}}}, {{domain: news.ycombinator.com, until_secs: {}}}]",
+                now - 1,
+                now + 600
+            ),
+            3600,
+        );
+        let state = manager.read_block_state().unwrap().unwrap();
+        assert_eq!(state.allowed_domains(), ["news.ycombinator.com"]);
+
+        manager.reblock_exceptions().unwrap();
+        let state = manager.read_block_state().unwrap().unwrap();
+        assert_eq!(state.exceptions.len(), 1);
+        let hosts = fs::read_to_string(root.join("etc/hosts")).unwrap();
+        assert!(hosts.contains("0.0.0.0 reddit.com"));
+        assert!(!hosts.contains("news.ycombinator.com"));
+
+        HostsSection::new(&manager.state_dir, manager.uid)
+            .clear()
+            .unwrap();
+        fs::remove_dir_all(&manager.state_dir).unwrap();
+    }
+
+    #[test]
+    fn apps_let_through_are_blocked_again_once_their_time_is_up() {
+        sandbox_with_hosts();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let manager = block_of("{duration: 1h}", 3600);
+        let app = |name: &str| {
+            let app = manager.state_dir.join(format!("{}.app", name));
+            fs::create_dir_all(app.join("Contents/MacOS")).unwrap();
+            fs::write(app.join("Contents/MacOS").join(name), "").unwrap();
+            fs::set_permissions(
+                app.join("Contents/MacOS").join(name),
+                Permissions::from_mode(0o755),
+            )
+            .unwrap();
+            app
+        };
+        let (over, allowed) = (app("Game"), app("Notes"));
+        let state = manager.state_dir.join("current_block.yaml");
+        let mut content = fs::read_to_string(&state).unwrap();
+        content.push_str(&format!(
+            "app_exceptions: [{{app: {}, until_secs: {}}}, {{app: {}, until_secs: {}}}]\n",
+            over.display(),
+            now - 1,
+            allowed.display(),
+            now + 600
+        ));
+        fs::write(&state, content).unwrap();
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o7777;
+        let allowed_mode = mode(&allowed);
+
+        let state = manager.read_block_state().unwrap().unwrap();
+        assert_eq!(state.allowed_apps(), std::slice::from_ref(&allowed));
+        manager.reblock_exceptions().unwrap();
+        let state = manager.read_block_state().unwrap().unwrap();
+        assert_eq!(state.app_exceptions.len(), 1);
+        assert_eq!(mode(&over), 0);
+        assert_eq!(mode(&over.join("Contents/MacOS/Game")), 0);
+        assert_eq!(mode(&allowed), allowed_mode);
+
+        HostsSection::new(&manager.state_dir, manager.uid)
+            .clear()
+            .unwrap();
+        restore_perms(&manager.read_app_perms_backup().unwrap()).unwrap();
+        fs::remove_dir_all(&manager.state_dir).unwrap();
+    }
+
+    #[test]
+    fn nested_executables_get_their_modes_back() {
+        let app = env::temp_dir()
+            .join(format!("enough-test-{}", Uuid::new_v4()))
+            .join("Steam.app");
+        let main = app.join("Contents/MacOS/steam_osx");
+        let helper = app.join("Contents/Frameworks/Steam Helper.app/Contents/MacOS/Steam Helper");
+        let resource = app.join("Contents/Resources/icon.icns");
+        for (path, mode) in [(&main, 0o755), (&helper, 0o711), (&resource, 0o644)] {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+            fs::set_permissions(path, Permissions::from_mode(mode)).unwrap();
+        }
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o7777;
+
+        let mut backup = BTreeMap::new();
+        block_executables(&app, &mut backup).unwrap();
+        assert_eq!(backup.len(), 2);
+        assert_eq!(mode(&main), 0);
+        assert_eq!(mode(&helper), 0);
+        assert_eq!(mode(&resource), 0o644);
+
+        // blocking again, after a reinstall, keeps the modes from before the block
+        block_executables(&app, &mut backup).unwrap();
+        restore_perms(&backup).unwrap();
+        assert_eq!(mode(&main), 0o755);
+        assert_eq!(mode(&helper), 0o711);
+
+        fs::remove_dir_all(app.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn filters_take_items_by_url_host_path_or_name() {
+        let profile = Profile {
+            websites: ["https://www.reddit.com", "https://news.ycombinator.com"]
+                .map(|url| Url::parse(url).unwrap().into())
+                .into(),
+            apps: vec![
+                PathBuf::from("/Applications/Steam.app"),
+                PathBuf::from("group:games"),
+            ],
+            ..Default::default()
+        };
+        let filter = |only: &[&str], except: &[&str]| ItemFilter {
+            only: only.iter().map(|item| item.to_string()).collect(),
+            except: except.iter().map(|item| item.to_string()).collect(),
+        };
+
+        let without = filter(&[], &["https://reddit.com", "steam"])
+            .narrow(&profile)
+            .unwrap();
+        assert_eq!(without.websites.len(), 1);
+        assert_eq!(without.apps, [PathBuf::from("group:games")]);
+
+        let only = filter(&["reddit.com", "Steam.app"], &[])
+            .narrow(&profile)
+            .unwrap();
+        assert_eq!(only.websites[0].url.host_str(), Some("www.reddit.com"));
+        assert_eq!(only.apps, [PathBuf::from("/Applications/Steam.app")]);
+
+        assert!(filter(&[], &["twitter.com"]).narrow(&profile).is_err());
+    }
+
+    #[test]
+    fn websites_bring_their_known_or_configured_subdomains() {
+        let website = |url: &str| Website::from(Url::parse(url).unwrap());
+
+        let youtube = website_hosts(&website("https://www.youtube.com/feed"));
+        assert_eq!(youtube[..2], ["www.youtube.com", "youtube.com"]);
+        assert!(youtube.contains(&"m.youtube.com".to_string()));
+        assert!(youtube.contains(&"googlevideo.com".to_string()));
+
+        let configured = website_hosts(&Website {
+            subdomains: Some(vec!["music".to_string()]),
+            ..website("https://youtube.com")
+        });
+        assert_eq!(
+            configured,
+            ["youtube.com", "www.youtube.com", "music.youtube.com"]
+        );
+
+        assert_eq!(website_hosts(&website("https://example.org")).len(), 2);
+    }
+}
@@ -0,0 +1,462 @@
+use anyhow::{Context, Ok, Result};
+use serde_json::{Value, json};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+#[cfg(target_os = "linux")]
+use crate::user::as_invoking_user;
+use crate::{block::data_dir, sandbox};
+
+/// The browsers `--harden` wrote policies for, so uninstalling can take them back
+const HARDENED_FILE: &str = "hardened_browsers.txt";
+/// Our policy file in the managed policies directory of Chromium browsers on Linux
+const POLICY_FILE_NAME: &str = "enough.json";
+
+/// A browser whose DNS settings are known, by where they're kept.
+struct Browser {
+    name: &'static str,
+    /// Also the domain of its managed preferences on macOS
+    bundle_id: &'static str,
+    /// How `xdg-settings` names it on Linux
+    desktop_entry: &'static str,
+    engine: Engine,
+    /// Its profile data under the home directory, on macOS and on Linux
+    data: (&'static str, &'static str),
+    /// Where machine-wide policies go on Linux, and on macOS for Firefox
+    policies: (&'static str, &'static str),
+}
+
+#[derive(PartialEq, Eq)]
+enum Engine {
+    Chromium,
+    Firefox,
+    /// Resolves through the system, which always reads the hosts file
+    System,
+}
+
+const BROWSERS: &[Browser] = &[
+    Browser {
+        name: "Google Chrome",
+        bundle_id: "com.google.Chrome",
+        desktop_entry: "google-chrome.desktop",
+        engine: Engine::Chromium,
+        data: (
+            "Library/Application Support/Google/Chrome",
+            ".config/google-chrome",
+        ),
+        policies: ("", "/etc/opt/chrome/policies/managed"),
+    },
+    Browser {
+        name: "Brave",
+        bundle_id: "com.brave.Browser",
+        desktop_entry: "brave-browser.desktop",
+        engine: Engine::Chromium,
+        data: (
+            "Library/Application Support/BraveSoftware/Brave-Browser",
+            ".config/BraveSoftware/Brave-Browser",
+        ),
+        policies: ("", "/etc/brave/policies/managed"),
+    },
+    Browser {
+        name: "Microsoft Edge",
+        bundle_id: "com.microsoft.edgemac",
+        desktop_entry: "microsoft-edge.desktop",
+        engine: Engine::Chromium,
+        data: (
+            "Library/Application Support/Microsoft Edge",
+            ".config/microsoft-edge",
+        ),
+        policies: ("", "/etc/opt/edge/policies/managed"),
+    },
+    Browser {
+        name: "Chromium",
+        bundle_id: "org.chromium.Chromium",
+        desktop_entry: "chromium.desktop",
+        engine: Engine::Chromium,
+        data: ("Library/Application Support/Chromium", ".config/chromium"),
+        policies: ("", "/etc/chromium/policies/managed"),
+    },
+    Browser {
+        name: "Firefox",
+        bundle_id: "org.mozilla.firefox",
+        desktop_entry: "firefox.desktop",
+        engine: Engine::Firefox,
+        data: (
+            "Library/Application Support/Firefox/Profiles",
+            ".mozilla/firefox",
+        ),
+        policies: (
+            "/Applications/Firefox.app/Contents/Resources/distribution",
+            "/etc/firefox/policies",
+        ),
+    },
+    Browser {
+        name: "Safari",
+        bundle_id: "com.apple.Safari",
+        desktop_entry: "",
+        engine: Engine::System,
+        data: ("Library/Safari", ""),
+        policies: ("", ""),
+    },
+];
+
+impl Browser {
+    /// How the system names it as the default browser.
+    fn system_name(&self) -> &'static str {
+        platform((self.bundle_id, self.desktop_entry))
+    }
+}
+
+/// Something that lets a browser get around the hosts file.
+pub struct Finding {
+    pub browser: &'static str,
+    pub problem: &'static str,
+    /// What to do about it
+    pub hint: &'static str,
+}
+
+/// What the browsers in use would get around the hosts file with: DNS over HTTPS, which
+/// skips the system resolver, and proxies, which resolve names on their side. The
+/// default browser is named too, if it's known.
+pub fn check() -> (Option<&'static str>, Vec<Finding>) {
+    let mut findings = Vec::new();
+    for browser in installed() {
+        let data = data_path(browser);
+        match browser.engine {
+            Engine::Chromium if chromium_uses_doh(&data) && !has_policy(browser) => {
+                findings.push(Finding {
+                    browser: browser.name,
+                    problem: "uses secure DNS",
+                    hint: "turn off Settings > Privacy and security > Security > Use secure DNS, or block with `--harden`",
+                })
+            }
+            Engine::Firefox => {
+                let prefs = firefox_prefs(&data);
+                let pref = |name: &str| {
+                    prefs
+                        .iter()
+                        .rev()
+                        .find_map(|(key, value)| (key == name).then_some(value.as_str()))
+                };
+                if matches!(pref("network.trr.mode"), Some("2" | "3")) && !has_policy(browser) {
+                    findings.push(Finding {
+                        browser: browser.name,
+                        problem: "uses DNS over HTTPS",
+                        hint: "turn off Settings > Privacy & Security > DNS over HTTPS, or block with `--harden`",
+                    });
+                }
+                if matches!(pref("network.proxy.type"), Some("1" | "2")) {
+                    findings.push(Finding {
+                        browser: browser.name,
+                        problem: "goes through a proxy",
+                        hint: "set Settings > Network Settings to No proxy while blocking",
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    if system_proxy() {
+        findings.push(Finding {
+            browser: "The system",
+            problem: "goes through a proxy",
+            hint: if cfg!(target_os = "macos") {
+                "turn the proxies off in System Settings > Network > Details > Proxies while blocking"
+            } else {
+                "set the network proxy to None in the system settings while blocking"
+            },
+        });
+    }
+
+    (default_browser().map(|browser| browser.name), findings)
+}
+
+/// Turns DNS over HTTPS off in every installed browser that has it, through the managed
+/// policies browsers read at launch. Returns the browsers it was turned off in.
+pub fn harden() -> Result<Vec<&'static str>> {
+    let mut hardened = Vec::new();
+    for browser in installed() {
+        match browser.engine {
+            Engine::Chromium if cfg!(target_os = "macos") => {
+                run_defaults(&[
+                    "write",
+                    &preferences(browser),
+                    "DnsOverHttpsMode",
+                    "-string",
+                    "off",
+                ])?;
+            }
+            Engine::Chromium => {
+                let dir = sandbox::path(platform(browser.policies));
+                fs::create_dir_all(&dir)?;
+                fs::write(
+                    dir.join(POLICY_FILE_NAME),
+                    json!({ "DnsOverHttpsMode": "off" }).to_string(),
+                )?;
+            }
+            Engine::Firefox => {
+                let path = sandbox::path(platform(browser.policies)).join("policies.json");
+                let mut policies = read_json(&path).unwrap_or_else(|| json!({}));
+                policies["policies"]["DNSOverHTTPS"] = json!({ "Enabled": false, "Locked": true });
+                fs::create_dir_all(path.parent().unwrap_or(Path::new("/")))?;
+                fs::write(&path, serde_json::to_string_pretty(&policies)?)
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+            }
+            Engine::System => continue,
+        }
+        hardened.push(browser);
+    }
+
+    let mut recorded = hardened_browsers();
+    for browser in &hardened {
+        if !recorded.contains(&browser.bundle_id.to_string()) {
+            recorded.push(browser.bundle_id.to_string());
+        }
+    }
+    fs::create_dir_all(data_dir())?;
+    fs::write(data_dir().join(HARDENED_FILE), recorded.join("\n"))?;
+
+    Ok(hardened.iter().map(|browser| browser.name).collect())
+}
+
+/// Takes back the policies written by [`harden`].
+pub fn unharden() -> Result<()> {
+    for bundle_id in hardened_browsers() {
+        let Some(browser) = BROWSERS
+            .iter()
+            .find(|browser| browser.bundle_id == bundle_id)
+        else {
+            continue;
+        };
+        match browser.engine {
+            Engine::Chromium if cfg!(target_os = "macos") => {
+                // fails when the key is gone already, which is fine
+                run_defaults(&["delete", &preferences(browser), "DnsOverHttpsMode"]).ok();
+            }
+            Engine::Chromium => {
+                let path = sandbox::path(platform(browser.policies)).join(POLICY_FILE_NAME);
+                if path.exists() {
+                    fs::remove_file(path)?;
+                }
+            }
+            Engine::Firefox => {
+                let path = sandbox::path(platform(browser.policies)).join("policies.json");
+                let Some(mut policies) = read_json(&path) else {
+                    continue;
+                };
+                if let Some(entries) = policies["policies"].as_object_mut() {
+                    entries.remove("DNSOverHTTPS");
+                }
+                if policies["policies"]
+                    .as_object()
+                    .is_none_or(|entries| entries.is_empty())
+                {
+                    fs::remove_file(&path)?;
+                } else {
+                    fs::write(&path, serde_json::to_string_pretty(&policies)?)?;
+                }
+            }
+            Engine::System => {}
+        }
+    }
+
+    let path = data_dir().join(HARDENED_FILE);
+    if path.exists() {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+fn hardened_browsers() -> Vec<String> {
+    fs::read_to_string(data_dir().join(HARDENED_FILE))
+        .unwrap_or_default()
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
+
+/// The macOS or the Linux half of a pair.
+fn platform(pair: (&'static str, &'static str)) -> &'static str {
+    if cfg!(target_os = "macos") {
+        pair.0
+    } else {
+        pair.1
+    }
+}
+
+/// The known browsers the invoking user has used, by their profile data.
+fn installed() -> impl Iterator<Item = &'static Browser> {
+    BROWSERS
+        .iter()
+        .filter(|browser| !platform(browser.data).is_empty() && data_path(browser).exists())
+}
+
+fn data_path(browser: &Browser) -> PathBuf {
+    env::home_dir()
+        .unwrap_or_default()
+        .join(platform(browser.data))
+}
+
+/// The machine-wide preferences Chromium browsers on macOS read their policies from.
+fn preferences(browser: &Browser) -> String {
+    sandbox::path(format!("/Library/Preferences/{}", browser.bundle_id))
+        .display()
+        .to_string()
+}
+
+fn has_policy(browser: &Browser) -> bool {
+    hardened_browsers().iter().any(|id| id == browser.bundle_id)
+}
+
+fn chromium_uses_doh(data: &Path) -> bool {
+    read_json(&data.join("Local State"))
+        .is_some_and(|state| state["dns_over_https"]["mode"] == "secure")
+}
+
+/// Every `user_pref` of every Firefox profile under `data`, in the order they're set.
+fn firefox_prefs(data: &Path) -> Vec<(String, String)> {
+    let Some(profiles) = fs::read_dir(data).ok() else {
+        return Vec::new();
+    };
+    profiles
+        .filter_map(|profile| profile.ok())
+        .flat_map(|profile| ["prefs.js", "user.js"].map(|file| profile.path().join(file)))
+        .filter_map(|file| fs::read_to_string(file).ok())
+        .flat_map(|prefs| {
+            prefs
+                .lines()
+                .filter_map(|line| {
+                    let args = line.trim().strip_prefix("user_pref(")?.strip_suffix(");")?;
+                    let (key, value) = args.split_once(',')?;
+                    Some((
+                        key.trim().trim_matches('"').to_string(),
+                        value.trim().trim_matches('"').to_string(),
+                    ))
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+fn read_json(path: &Path) -> Option<Value> {
+    serde_json::from_str(&fs::read_to_string(path).ok()?).ok()
+}
+
+fn run_defaults(args: &[&str]) -> Result<()> {
+    let output = sandbox::command("defaults").args(args).output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("defaults {} failed: {}", args[0], stderr.trim());
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn default_browser() -> Option<&'static Browser> {
+    let plist = env::home_dir()?
+        .join("Library/Preferences/com.apple.LaunchServices/com.apple.launchservices.secure.plist");
+    let output = std::process::Command::new("plutil")
+        .args(["-convert", "json", "-o", "-"])
+        .arg(plist)
+        .output()
+        .ok()?;
+    let handlers = serde_json::from_slice::<Value>(&output.stdout).ok()?;
+    let bundle_id = handlers["LSHandlers"]
+        .as_array()?
+        .iter()
+        .find(|handler| handler["LSHandlerURLScheme"] == "https")
+        .and_then(|handler| handler["LSHandlerRoleAll"].as_str())
+        // nothing set is Safari
+        .unwrap_or("com.apple.Safari");
+
+    BROWSERS
+        .iter()
+        .find(|browser| browser.system_name().eq_ignore_ascii_case(bundle_id))
+}
+
+#[cfg(target_os = "linux")]
+fn default_browser() -> Option<&'static Browser> {
+    let output = as_invoking_user("xdg-settings")
+        .args(["get", "default-web-browser"])
+        .output()
+        .ok()?;
+    let entry = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    BROWSERS
+        .iter()
+        .find(|browser| !browser.system_name().is_empty() && browser.system_name() == entry)
+}
+
+/// Whether the system sends web traffic through a proxy, or a PAC file picking one.
+#[cfg(target_os = "macos")]
+fn system_proxy() -> bool {
+    let Some(output) = std::process::Command::new("scutil")
+        .arg("--proxy")
+        .output()
+        .ok()
+    else {
+        return false;
+    };
+    String::from_utf8_lossy(&output.stdout).lines().any(|line| {
+        let line = line.split_whitespace().collect::<Vec<_>>();
+        matches!(
+            line[..],
+            [
+                "HTTPEnable" | "HTTPSEnable" | "ProxyAutoConfigEnable",
+                ":",
+                "1"
+            ]
+        )
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn system_proxy() -> bool {
+    as_invoking_user("gsettings")
+        .args(["get", "org.gnome.system.proxy", "mode"])
+        .output()
+        .is_ok_and(|output| {
+            let mode = String::from_utf8_lossy(&output.stdout);
+            matches!(mode.trim(), "'manual'" | "'auto'")
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn firefox_prefs_are_read_from_every_profile_in_order() {
+        let data = env::temp_dir().join(format!("enough-firefox-{}", Uuid::new_v4()));
+        let profile = data.join("abc123.default-release");
+        fs::create_dir_all(&profile).unwrap();
+        fs::write(
+            profile.join("prefs.js"),
+            "// Mozilla User Preferences\n\
+             user_pref(\"network.trr.mode\", 2);\n\
+             user_pref(\"network.trr.uri\", \"https://dns.example/dns-query\");\n",
+        )
+        .unwrap();
+        fs::write(
+            profile.join("user.js"),
+            "user_pref(\"network.trr.mode\", 5);\n",
+        )
+        .unwrap();
+
+        let prefs = firefox_prefs(&data);
+        let mode = prefs
+            .iter()
+            .rev()
+            .find(|(key, _)| key == "network.trr.mode")
+            .map(|(_, value)| value.as_str());
+        assert_eq!(mode, Some("5"));
+        assert!(prefs.contains(&(
+            "network.trr.uri".to_string(),
+            "https://dns.example/dns-query".to_string()
+        )));
+
+        fs::remove_dir_all(data).unwrap();
+    }
+}
@@ -0,0 +1,90 @@
+use serde::Serialize;
+
+use crate::{
+    block::{STATE_SCHEMA_VERSION, STATUS_SCHEMA_VERSION},
+    config::CONFIG_SCHEMA_VERSION,
+    events,
+    history::HISTORY_SCHEMA_VERSION,
+    progress::PROGRESS_SCHEMA_VERSION,
+};
+
+/// What this build can do, for frontends to gate their features on. Fields are only ever
+/// added, so integrations can rely on the existing ones.
+#[derive(Debug, Serialize)]
+pub struct Capabilities {
+    pub name: &'static str,
+    pub version: &'static str,
+    pub platform: &'static str,
+    /// Cargo features the binary was built with
+    pub features: Vec<&'static str>,
+    pub backends: Backends,
+    pub config_schema_version: u32,
+    pub schemas: Schemas,
+}
+
+/// Versions of what enough writes for other tools to read. Each is bumped when a field
+/// is renamed, removed or changes meaning, not when one is added.
+#[derive(Debug, Serialize)]
+pub struct Schemas {
+    pub config: u32,
+    /// `current_block.yaml` in the state directory
+    pub state: u32,
+    /// The lines of the history file
+    pub history: u32,
+    /// The lines of the event log
+    pub events: u32,
+    /// `enough status --json` and `enough block --headless`
+    pub status: u32,
+    /// `enough block --progress json`
+    pub progress: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Backends {
+    /// Blocking websites through the hosts file
+    pub hosts: bool,
+    /// Blocking through the packet filter, for hosts over the limit and VPN kill switches
+    pub pf: bool,
+    /// Answering for blocked domains from a local DNS server, the `dns-proxy` backend
+    pub dns_sinkhole: bool,
+    /// What schedules the unblock
+    pub scheduler: &'static str,
+}
+
+impl Capabilities {
+    pub fn current() -> Self {
+        let mut features = Vec::new();
+        if cfg!(feature = "pf") {
+            features.push("pf");
+        }
+        if cfg!(feature = "webhooks") {
+            features.push("webhooks");
+        }
+
+        Self {
+            name: env!("CARGO_PKG_NAME"),
+            version: env!("CARGO_PKG_VERSION"),
+            platform: std::env::consts::OS,
+            features,
+            backends: Backends {
+                hosts: true,
+                pf: cfg!(all(feature = "pf", target_os = "macos")),
+                dns_sinkhole: true,
+                scheduler: if cfg!(target_os = "linux") {
+                    "systemd"
+                } else {
+                    "launchd"
+                },
+            },
+            config_schema_version: CONFIG_SCHEMA_VERSION,
+            schemas: Schemas {
+                config: CONFIG_SCHEMA_VERSION,
+                state: STATE_SCHEMA_VERSION,
+                history: HISTORY_SCHEMA_VERSION,
+                events: events::SCHEMA_VERSION,
+                status: STATUS_SCHEMA_VERSION,
+                progress: PROGRESS_SCHEMA_VERSION,
+            },
+        }
+    }
+}
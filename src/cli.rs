@@ -1,17 +1,56 @@
 use anyhow::{Context, Ok, Result};
-use chrono::Utc;
+use chrono::{DateTime, Local, NaiveTime, TimeDelta, Utc};
 use clap::{Command, CommandFactory as _, Parser, Subcommand};
-use humantime_serde::re::humantime::{format_duration, parse_duration};
+use serde::Serialize;
 use std::{
+    collections::{HashMap, hash_map::Entry},
     env,
     fmt::Debug,
-    io::{self, Write as _},
+    io::{self, IsTerminal as _, Read as _, Write as _},
+    iter,
     path::PathBuf,
-    time::Duration,
+    time::{Duration, SystemTime},
 };
+use url::Url;
 
-use crate::block::{BlockManager, Status};
-use crate::config::EnoughConfig;
+use crate::audit::{self, AuditRecord};
+use crate::backup;
+use crate::block::{BlockManager, BlockOptions, BlockScope, BlockSkipped, ItemFilter, Status};
+use crate::browsers;
+use crate::capabilities::Capabilities;
+use crate::config::{
+    AbortJournal, DEFAULT_UNBLOCK_EARLY_DELAY, EnoughConfig, QuotaPeriod, StatusTime,
+    WebsitesBackend,
+};
+use crate::context::WorkContext;
+use crate::contract::{self, Contract};
+use crate::discover;
+use crate::dns_proxy;
+use crate::doctor;
+use crate::email;
+use crate::events;
+use crate::friction;
+use crate::history;
+use crate::household::Household;
+use crate::lint::{self, Severity};
+#[cfg(feature = "webhooks")]
+use crate::notify;
+use crate::output;
+use crate::partner;
+use crate::progress::{Progress, ProgressFormat};
+use crate::report::Report;
+use crate::sandbox;
+use crate::scheduler;
+use crate::share::{self, SharedProfile};
+use crate::shell_init::{self, InitShell};
+use crate::stats::{self, Stats};
+use crate::suggest;
+use crate::third_party;
+use crate::time_format::{self, TimeFormat};
+use crate::together;
+use crate::uninstall;
+use crate::usage::{self, QuotaUsage};
+use crate::user::invoking_uid;
 
 /// Enough overstimulation, take back control over your focus
 #[derive(Debug, Parser)]
@@ -21,15 +60,53 @@ use crate::config::EnoughConfig;
     author,
     long_about=None,
     after_help="You REALLY can't access these websites and apps for the specified duration, so make sure you won't need them",
+    disable_version_flag = true,
+    arg_required_else_help = true,
 )]
 pub struct EnoughCLI {
     #[clap(subcommand)]
-    command: EnoughOptions,
+    command: Option<EnoughOptions>,
+    /// Print version
+    #[clap(short = 'V', long)]
+    version: bool,
+    /// With `--version`, print the version and capabilities of this build as JSON
+    #[clap(long, requires = "version")]
+    json: bool,
+    /// How to print durations, overriding the config's `time-format`
+    #[clap(long, global = true, value_enum)]
+    time_format: Option<TimeFormat>,
+    /// Print without emojis, symbols or lines updated in place, for screen readers
+    #[clap(long, global = true)]
+    plain: bool,
 }
 
 impl EnoughCLI {
     pub fn run(self) -> Result<()> {
-        self.command.parse()
+        output::set_plain(self.plain);
+        if self.version {
+            if self.json {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&Capabilities::current())?
+                );
+            } else {
+                println!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+            }
+            return Ok(());
+        }
+
+        // in parental mode the blocks are the child's, whoever starts them
+        if let Some(household) = Household::load()? {
+            household.act_for_child()?;
+        }
+
+        match self.command {
+            Some(command) => command.parse(self.time_format),
+            None => {
+                EnoughCLI::command().print_help()?;
+                Ok(())
+            }
+        }
     }
 }
 
@@ -53,8 +130,75 @@ enum EnoughOptions {
         #[clap(short, long)]
         profile: Option<String>,
         /// Override the duration set in the profile
-        #[clap(short, long, value_parser = parse_duration)]
+        #[clap(short, long, value_parser = time_format::parse)]
         duration: Option<Duration>,
+        /// Block until this time of day instead, e.g. `17:30`, tomorrow if it's past
+        #[clap(short, long, value_parser = time_format::parse_time, conflicts_with = "duration")]
+        until: Option<NaiveTime>,
+        /// Fill in a variable of a template, e.g. `--set hours=3`. Variables that aren't
+        /// set are asked for
+        #[clap(long = "set", value_name = "NAME=VALUE", value_parser = parse_variable)]
+        vars: Vec<(String, String)>,
+        /// How to report progress while the block is applied
+        #[clap(long, value_enum, default_value_t = ProgressFormat::Human)]
+        progress: ProgressFormat,
+        /// Don't print any progress (same as `--progress=quiet`)
+        #[clap(short, long, default_value = "false", conflicts_with = "progress")]
+        quiet: bool,
+        /// Wait for blocks of other tools (SelfControl, Focus, Cold Turkey) to end before
+        /// starting, instead of fighting them over the hosts file
+        #[clap(long, default_value = "false")]
+        wait_for_others: bool,
+        /// Print the steps blocking would go through, without applying any of them
+        #[clap(long, default_value = "false")]
+        dry_run: bool,
+        /// Only block the profile's websites, leaving its apps alone
+        #[clap(long, default_value = "false", conflicts_with = "apps_only")]
+        websites_only: bool,
+        /// Only block the profile's apps, leaving its websites alone
+        #[clap(long, default_value = "false")]
+        apps_only: bool,
+        /// Only block this website or app of the profile, e.g. `reddit.com` or `Steam`,
+        /// can be given more than once
+        #[clap(long, value_name = "ITEM")]
+        only: Vec<String>,
+        /// Leave this website or app of the profile out, e.g. `https://reddit.com` or
+        /// `/Applications/Steam.app`, can be given more than once
+        #[clap(long, value_name = "ITEM")]
+        except: Vec<String>,
+        /// Turn DNS over HTTPS off in the installed browsers through their managed
+        /// policies, so they can't resolve blocked websites around the hosts file
+        #[clap(long, default_value = "false")]
+        harden: bool,
+        /// Answer yes to confirmations, like starting a block over the daily limit
+        #[clap(short, long, default_value = "false")]
+        yes: bool,
+        /// Never prompt, leave out everything needing a GUI session (notifications,
+        /// hooks, tabs) and print the started block as JSON on stdout, for SSH and
+        /// automations
+        #[clap(long, default_value = "false")]
+        headless: bool,
+        /// Set by `enough together`
+        #[clap(long, hide = true)]
+        room: Option<String>,
+    },
+    /// Lift the active block after typing a random phrase and waiting out a countdown
+    #[clap(name = "unblock-early")]
+    UnblockEarly {
+        /// Path to the config file with the countdown's length and the accountability
+        /// webhook
+        #[clap(short, long)]
+        config: Option<PathBuf>,
+    },
+    /// Lift the active block immediately, leaving an audit record behind.
+    /// No new blocks can be started for the rest of the day afterwards
+    Emergency {
+        /// Why the block has to be lifted, recorded in the audit log
+        #[clap(short, long)]
+        reason: String,
+        /// Path to the config file holding the accountability webhook
+        #[clap(short, long)]
+        config: Option<PathBuf>,
     },
     /// (INTERNAL, DO NOT RUN MANUALLY) CLEANUP COMMAND.
     /// Rollback changes of the latest run in case of errors
@@ -62,6 +206,89 @@ enum EnoughOptions {
     Unblock {
         #[clap(long, default_value = "false", hide = true)]
         fix: bool,
+        /// Set by the scheduled daemon, so cool-downs kick in instead of a full unblock
+        #[clap(long, default_value = "false", hide = true)]
+        scheduled: bool,
+    },
+    /// (INTERNAL, DO NOT RUN MANUALLY) Re-validates the active block after a network change
+    #[clap(hide = true, name = "___zzzreapply")]
+    Reapply,
+    /// (INTERNAL, DO NOT RUN MANUALLY) Opens or closes the allow windows of websites
+    #[clap(hide = true, name = "___zzzwindows")]
+    Windows,
+    /// (INTERNAL, DO NOT RUN MANUALLY) Tracks the usage of apps with a limit
+    #[clap(hide = true, name = "___zzztrack")]
+    Track,
+    /// (INTERNAL, DO NOT RUN MANUALLY) Blocks apps of the block's app groups installed
+    /// during it
+    #[clap(hide = true, name = "___zzzinstalls")]
+    Installs,
+    /// (INTERNAL, DO NOT RUN MANUALLY) Puts blocked apps moved to the Trash back
+    #[clap(hide = true, name = "___zzzguard")]
+    Guard,
+    /// (INTERNAL, DO NOT RUN MANUALLY) Closes blocked apps started anyway
+    #[clap(hide = true, name = "___zzzrelaunch")]
+    Relaunch,
+    /// (INTERNAL, DO NOT RUN MANUALLY) Blocks reinstalled apps again
+    #[clap(hide = true, name = "___zzzreinstalls")]
+    Reinstalls,
+    /// (INTERNAL, DO NOT RUN MANUALLY) Puts a strict block's config back when it's
+    /// weakened
+    #[clap(hide = true, name = "___zzzconfig")]
+    ConfigGuard,
+    /// (INTERNAL, DO NOT RUN MANUALLY) Puts back the hosts section edited out during a
+    /// block
+    #[clap(hide = true, name = "___zzzhosts")]
+    HostsGuard,
+    /// (INTERNAL, DO NOT RUN MANUALLY) Reports the block's jobs that were removed
+    #[clap(hide = true, name = "___zzzjobs")]
+    JobsGuard,
+    /// Lengthen the active block by a duration, e.g. `30m`
+    Extend {
+        /// How much longer to block
+        #[clap(value_parser = time_format::parse)]
+        duration: Duration,
+    },
+    /// Let one domain through the active block for a short while, blocking it again
+    /// automatically afterwards
+    AllowTemp {
+        /// The domain to let through, e.g. `docs.google.com`
+        domain: String,
+        /// How long to let it through
+        #[clap(value_parser = time_format::parse)]
+        duration: Duration,
+    },
+    /// Let one blocked app through the active block for a short while, out of the
+    /// profile's `app-allowance`, blocking it again automatically afterwards
+    AllowApp {
+        /// The app to let through, e.g. `Spotify` or `/Applications/Spotify.app`
+        app: String,
+        /// How long to let it through
+        #[clap(value_parser = time_format::parse)]
+        duration: Duration,
+    },
+    /// (INTERNAL, DO NOT RUN MANUALLY) Blocks domains and apps again after their
+    /// `allow-temp` and `allow-app`
+    #[clap(hide = true, name = "___zzzreblock")]
+    Reblock,
+    /// (INTERNAL, DO NOT RUN MANUALLY) Re-applies or finishes the block after a restart
+    #[clap(hide = true, name = "___zzzboot")]
+    Boot,
+    /// (INTERNAL, DO NOT RUN MANUALLY) Updates the remaining-time file
+    #[clap(hide = true, name = "___zzzremaining")]
+    Remaining,
+    /// (INTERNAL, DO NOT RUN MANUALLY) Serves the countdown page of soft-blocked websites
+    #[clap(hide = true, name = "___zzzfriction")]
+    Friction,
+    /// (INTERNAL, DO NOT RUN MANUALLY) Answers lookups for the `dns-proxy` backend
+    #[clap(hide = true, name = "___zzzdns")]
+    DnsProxy,
+    /// Check that the hosts section, resolver files and scheduled jobs match the
+    /// block state, with nothing left behind after a block
+    Doctor {
+        /// Repair what's wrong by running the block's (or unblock's) steps again
+        #[clap(long, default_value = "false")]
+        fix: bool,
     },
     /// Show current status
     Status {
@@ -71,12 +298,235 @@ enum EnoughOptions {
         /// Output in a single line (for status bars)
         #[clap(long, default_value = "false", conflicts_with = "json")]
         line: bool,
+        /// Only say until when you're focusing, without profile names or what's blocked
+        #[clap(long, default_value = "false", conflicts_with_all = ["json", "line"])]
+        public: bool,
+        /// Show until when the block runs, on top of the time left with `--remaining`
+        #[clap(long, default_value = "false")]
+        at: bool,
+        /// Show the time left, on top of until when with `--at`
+        #[clap(long, default_value = "false")]
+        remaining: bool,
+        /// Print nothing, only succeed if this profile is the one blocking right now, for
+        /// scripts and jobs depending on it
+        #[clap(long, value_name = "PROFILE", conflicts_with_all = ["json", "line", "public"])]
+        check: Option<String>,
+    },
+    /// Show time spent in blocks, streaks and achievements
+    Stats,
+    /// Suggest the block you likely want now, going by the time of day, the profiles you
+    /// started around this time before and the calendar events going on
+    Suggest {
+        /// Path to the config file to use
+        #[clap(short, long)]
+        config: Option<PathBuf>,
+        /// Start the suggested block
+        #[clap(long, default_value = "false")]
+        run: bool,
+        /// Don't look at the calendar
+        #[clap(long, default_value = "false")]
+        no_calendar: bool,
+    },
+    /// Focus along with others: join a room on the config's relay and block until the
+    /// session going on in it ends, or start one for whoever joins next
+    Together {
+        /// The room to join, e.g. `study-hall`
+        #[clap(short, long)]
+        room: String,
+        /// Path to the config file to use
+        #[clap(short, long)]
+        config: Option<PathBuf>,
+        /// Name of the profile to run
+        #[clap(short, long)]
+        profile: Option<String>,
+        /// How long a session started in the empty room lasts, instead of the profile's
+        /// duration
+        #[clap(short, long, value_parser = time_format::parse)]
+        duration: Option<Duration>,
+        /// Don't print any progress
+        #[clap(short, long, default_value = "false")]
+        quiet: bool,
+    },
+    /// Run a relay for `enough together`, keeping the rooms in memory
+    Relay {
+        /// Address to listen on
+        #[clap(long, default_value = "0.0.0.0:7070")]
+        listen: String,
+    },
+    /// Summarize the blocks as a report to read, or to email weekly from cron
+    Report {
+        /// Only cover the last 7 days
+        #[clap(long)]
+        week: bool,
+        /// Send the report to this address instead of printing it, set up under `email`
+        /// in the config
+        #[clap(long)]
+        email: Option<String>,
+        /// Print the HTML version of the report
+        #[clap(long, conflicts_with = "email")]
+        html: bool,
+        #[clap(short, long)]
+        config: Option<PathBuf>,
+    },
+    /// Follow the local event log, with `events: true` in the config
+    Events {
+        #[clap(subcommand)]
+        action: EventsOptions,
+    },
+    /// Manage the block history kept for the stats and reports
+    History {
+        #[clap(subcommand)]
+        action: HistoryOptions,
+    },
+    /// Save the config and your history, streaks and other data to an archive, to move
+    /// to another machine
+    Backup {
+        /// Where to write the archive
+        #[clap(short, long, default_value = "enough-backup.tar.gz")]
+        output: PathBuf,
+        /// Path to the config file to back up
+        #[clap(short, long)]
+        config: Option<PathBuf>,
+    },
+    /// Restore an archive made with `enough backup`
+    Restore {
+        archive: PathBuf,
+        /// Where to write the config, `~/.config/enough/enough.yaml` by default
+        #[clap(short, long)]
+        config: Option<PathBuf>,
+        /// Replace the config and data files that already exist
+        #[clap(long, default_value = "false")]
+        force: bool,
+    },
+    /// Reopen the apps and tabs saved when the last block started
+    RestoreContext,
+    /// Explain why a website or app is blocked
+    Why {
+        /// A URL, domain or app path
+        target: String,
+    },
+    /// Find installed apps to block
+    Apps {
+        #[clap(subcommand)]
+        action: AppsOptions,
+    },
+    /// Inspect the config file
+    Config {
+        #[clap(subcommand)]
+        action: ConfigOptions,
+    },
+    /// Print the active block's profile (or another one) as a share string, for a friend
+    /// to run the same block with `enough import --share`
+    Share {
+        #[clap(short, long)]
+        config: Option<PathBuf>,
+        /// Profile to share instead of the active block's
+        #[clap(short, long)]
+        profile: Option<String>,
+        /// Print it as a QR code too, through qrencode
+        #[clap(long, default_value = "false")]
+        qr: bool,
+    },
+    /// Add a profile shared with `enough share` to the config
+    Import {
+        /// The share string
+        #[clap(long)]
+        share: String,
+        #[clap(short, long)]
+        config: Option<PathBuf>,
+        /// Name to add the profile as instead of the one it was shared with
+        #[clap(long)]
+        name: Option<String>,
     },
     /// List available profiles
     Profiles {
         #[clap(short, long)]
         config: Option<PathBuf>,
     },
+    /// Print the code an accountability partner reads out to let a block be lifted early,
+    /// or a new secret to share with them
+    Code {
+        /// Print the current code, on the partner's side
+        #[clap(long, conflicts_with = "new_secret")]
+        for_partner: bool,
+        /// Make a new secret to share, kept where only root reads it, for `partner: true`
+        /// under `accountability`
+        #[clap(long)]
+        new_secret: bool,
+        /// The secret shared with you, on the partner's side
+        #[clap(long, requires = "for_partner")]
+        secret: Option<String>,
+    },
+    /// Commit to a profile for a number of days in front of a witness
+    Contract {
+        #[clap(subcommand)]
+        action: ContractOptions,
+    },
+    /// Measure the daily or weekly allowances under `quotas` in the config all the time,
+    /// blocking what's in one once it's used up until the day or week is over
+    Quota {
+        #[clap(subcommand)]
+        action: QuotaOptions,
+    },
+    /// (INTERNAL, DO NOT RUN MANUALLY) Measures the quotas and blocks the used up ones
+    #[clap(hide = true, name = "___zzzquotas")]
+    Quotas {
+        #[clap(long)]
+        config: PathBuf,
+    },
+    /// Start the profiles with a `schedule` in the config on their own, blocks or not
+    Scheduler {
+        #[clap(subcommand)]
+        action: SchedulerOptions,
+    },
+    /// Skip the next scheduled block of a profile, or the one due now if it didn't start
+    SnoozeSchedule {
+        /// The profile whose next scheduled block is skipped
+        profile_name: String,
+        /// Path to the config file with the schedules
+        #[clap(short, long)]
+        config: Option<PathBuf>,
+    },
+    /// (INTERNAL, DO NOT RUN MANUALLY) Starts the profiles whose schedule is due
+    #[clap(hide = true, name = "___zzzscheduler")]
+    Schedules {
+        #[clap(long)]
+        config: PathBuf,
+    },
+    /// Manage parental mode, where an admin passphrase guards the blocks
+    Household {
+        #[clap(subcommand)]
+        action: HouseholdOptions,
+    },
+    /// Print shell functions for the common flows (`focus`, `focus-<profile>`, `focuson`)
+    /// and a prompt hook, generated from the config's profiles. Add
+    /// `eval "$(enough shell-init zsh)"` to your shell's rc file
+    ShellInit {
+        /// The shell to generate the functions for
+        shell: InitShell,
+        /// Path to the config file to generate them from
+        #[clap(short, long)]
+        config: Option<PathBuf>,
+        /// Also wrap `sudo`, `launchctl` and `systemctl`, to ask first when they're used
+        /// to get around a strict block
+        #[clap(long, default_value = "false")]
+        guard: bool,
+    },
+    /// (INTERNAL, DO NOT RUN MANUALLY) Checks a command of the shell guard against the
+    /// active block
+    #[clap(hide = true, name = "___zzzbypass")]
+    Bypass {
+        #[clap(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+    /// Remove every trace of enough for you: jobs, hosts entries, pf anchors, state,
+    /// history, and the shared pieces once no other user has any. Your own config file,
+    /// the policy and parental mode are kept
+    Uninstall {
+        /// Keep the block history and the audit log
+        #[clap(long, default_value = "false")]
+        keep_history: bool,
+    },
     /// Generate shell completions
     Completions {
         /// The shell to generate the completions for
@@ -84,8 +534,140 @@ enum EnoughOptions {
     },
 }
 
+#[derive(Debug, Subcommand)]
+enum EventsOptions {
+    /// Print the latest events as JSON lines, then stream new ones
+    Tail {
+        /// How many past events to print first
+        #[clap(short = 'n', long, default_value = "10")]
+        lines: usize,
+        /// Stop after the past events instead of waiting for new ones
+        #[clap(long, default_value = "false")]
+        no_follow: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum HistoryOptions {
+    /// Remove the history and the event log, or only what's older than `--older-than`
+    Purge {
+        /// Only remove sessions and events older than this, e.g. `90d`
+        #[clap(long, value_parser = time_format::parse)]
+        older_than: Option<Duration>,
+        /// Don't ask before removing everything
+        #[clap(short, long, default_value = "false")]
+        yes: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum AppsOptions {
+    /// List installed apps with their bundle IDs and architectures, optionally
+    /// picking some to add to a profile
+    Discover {
+        /// Profile to add the picked apps to
+        #[clap(short, long)]
+        profile: Option<String>,
+        /// Path to the config file to edit
+        #[clap(short, long)]
+        config: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum ConfigOptions {
+    /// Flag duplicate entries and profiles that never run
+    Lint {
+        /// Path to the config file to check
+        #[clap(short, long)]
+        config: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum ContractOptions {
+    /// Sign a commitment to a profile and send it to a witness. Config edits weakening
+    /// the profile are refused until the contract expires
+    Create {
+        /// How many days the contract holds
+        #[clap(long)]
+        days: u32,
+        /// Profile to commit to
+        #[clap(short, long)]
+        profile: String,
+        /// Email address of the witness receiving the signed contract
+        #[clap(long)]
+        witness: String,
+        /// Also refuse emergency unblocks of the profile
+        #[clap(long, default_value = "false")]
+        strict: bool,
+        /// Path to the config file to use
+        #[clap(short, long)]
+        config: Option<PathBuf>,
+    },
+    /// List the contracts in force
+    List,
+}
+
+#[derive(Debug, Subcommand)]
+enum QuotaOptions {
+    /// Start measuring the quotas of the config
+    Enable {
+        /// Path to the config file with the quotas
+        #[clap(short, long)]
+        config: Option<PathBuf>,
+    },
+    /// Stop measuring the quotas, what's blocked already stays blocked
+    Disable,
+    /// Show how much of each quota is used up
+    Status {
+        /// Path to the config file with the quotas
+        #[clap(short, long)]
+        config: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum SchedulerOptions {
+    /// Start following the schedules of the config
+    Install {
+        /// Path to the config file with the schedules
+        #[clap(short, long)]
+        config: Option<PathBuf>,
+    },
+    /// Stop following the schedules, the active block stays until it ends
+    Uninstall,
+    /// Show each profile's schedule and whether it's due now
+    Status {
+        /// Path to the config file with the schedules
+        #[clap(short, long)]
+        config: Option<PathBuf>,
+    },
+    /// List the scheduled blocks coming up, with the skipped and put off ones
+    Preview {
+        /// Path to the config file with the schedules
+        #[clap(short, long)]
+        config: Option<PathBuf>,
+        /// How many days ahead to look
+        #[clap(long, default_value_t = 7)]
+        days: u32,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum HouseholdOptions {
+    /// Turn on parental mode for a child account, asking for a new admin passphrase
+    Setup {
+        /// The account the blocks are meant for
+        #[clap(long)]
+        child: String,
+    },
+    /// Turn off parental mode
+    Disable,
+}
+
 impl EnoughOptions {
-    fn parse(self) -> Result<()> {
+    fn parse(self, time_format: Option<TimeFormat>) -> Result<()> {
         match self {
             Self::Init { output } => {
                 EnoughConfig::generate_sample(output.clone())
@@ -95,50 +677,533 @@ impl EnoughOptions {
                 config,
                 profile,
                 duration,
+                until,
+                vars,
+                progress,
+                quiet,
+                wait_for_others,
+                dry_run,
+                websites_only,
+                apps_only,
+                only,
+                except,
+                harden,
+                yes,
+                headless,
+                room,
             } => {
                 is_sudo()?;
 
                 let block_manager = BlockManager::new();
-                if block_manager.get_status(false)?.is_blocked() {
+                if block_manager.get_status()?.is_blocked() {
                     anyhow::bail!("A block is already active, please wait until it expires");
                 }
 
-                let conf = EnoughConfig::load(config)?;
+                if let Some(until) = audit::lockout_until()? {
+                    anyhow::bail!(
+                        "An emergency unblock was used today, new blocks are locked out until {}",
+                        until.format("%H:%M")
+                    );
+                }
+
+                let others = third_party::active_tools();
+                for tool in &others {
+                    eprintln!(
+                        "Warning: {} may have a block active, both editing the hosts file can conflict",
+                        tool
+                    );
+                }
+                if wait_for_others && !others.is_empty() {
+                    eprintln!("Waiting for their blocks to end...");
+                    third_party::wait_until_inactive();
+                }
+
+                let conf = load_config(config)?;
                 let profile_name = profile
                     .or_else(|| conf.default_profile.clone())
                     .with_context(
                         || "No profile specified and no default profile set in the config file",
                     )?;
-                let profile = conf
-                    .profiles
-                    .get(&profile_name)
-                    .with_context(|| format!("Profile `{}` not found", profile_name))?;
-                let duration = duration.unwrap_or(profile.duration);
+                let mut vars = vars.into_iter().collect::<HashMap<_, _>>();
+                for var in conf.template_variables(&profile_name).unwrap_or_default() {
+                    if let Entry::Vacant(entry) = vars.entry(var) {
+                        if headless {
+                            anyhow::bail!("Missing `--set {}=<value>`", entry.key());
+                        }
+                        let value = prompt(&format!("Value for `{}`:", entry.key()))?;
+                        entry.insert(value);
+                    }
+                }
+                let profile = &conf.profile(&profile_name, &vars)?;
+                let duration = match until {
+                    Some(until) => time_format::until(until, Local::now()),
+                    None => duration.unwrap_or(profile.duration),
+                };
+                let time_format = time_format.unwrap_or(conf.time_format);
+                let break_block = match &profile.break_profile {
+                    Some(break_profile) => Some((
+                        break_profile.clone(),
+                        conf.profile(break_profile, &HashMap::new())?,
+                    )),
+                    None => None,
+                };
+                for contract in contract::active()? {
+                    contract.check(&conf)?;
+                    if contract.terms.profile_name == profile_name
+                        && duration < contract.terms.duration
+                    {
+                        anyhow::bail!(
+                            "Contract {} holds `{}` blocks to at least {}",
+                            contract.terms.id,
+                            profile_name,
+                            time_format.format(contract.terms.duration)
+                        );
+                    }
+                }
+                if conf.policy_profiles.contains(&profile_name) && duration < profile.duration {
+                    anyhow::bail!(
+                        "Profile `{}` is mandated by policy and can't be shorter than {}",
+                        profile_name,
+                        time_format.format(profile.duration)
+                    );
+                }
+
+                if let Some(limits) = &conf.limits {
+                    let blocked_today = Duration::from_secs(history::blocked_today()?.as_secs());
+                    if blocked_today >= limits.max_daily_block_time {
+                        let message = format!(
+                            "You've already been in blocks for {} today (limit is {})",
+                            time_format.format(blocked_today),
+                            time_format.format(limits.max_daily_block_time)
+                        );
+                        if limits.strict {
+                            anyhow::bail!("{}, no more blocks today", message);
+                        }
+                        if headless && !yes {
+                            anyhow::bail!("{}, pass `--yes` to start another one", message);
+                        }
+                        if !yes && !confirm(&format!("{}. Start another one?", message))? {
+                            return Ok(());
+                        }
+                    }
+                }
+
+                let (duration, together) = match room {
+                    Some(room) => {
+                        let relay = conf
+                            .relay
+                            .as_ref()
+                            .with_context(|| "Group sessions need a `relay` in the config file")?;
+                        let (membership, joined) = together::join(relay, &room, duration)?;
+                        let until = Local::now() + joined.remaining();
+                        match joined.peers {
+                            0 | 1 => eprintln!(
+                                "Started a session in `{}`, whoever joins focuses until {} too",
+                                room,
+                                until.format("%H:%M")
+                            ),
+                            peers => eprintln!(
+                                "Joined `{}`, {} in the room, focusing until {}",
+                                room,
+                                peers,
+                                until.format("%H:%M")
+                            ),
+                        }
+                        (joined.remaining(), Some(membership))
+                    }
+                    None => (duration, None),
+                };
+
+                let progress = if quiet {
+                    ProgressFormat::Quiet
+                } else {
+                    progress
+                };
+                // other backends don't go through the resolver, so browsers can't skip them
+                let has_websites =
+                    !profile.websites.is_empty() && conf.websites_backend == WebsitesBackend::Hosts;
+                let block_manager = BlockManager::new()
+                    .with_progress(Progress::new(progress))
+                    .with_options(BlockOptions {
+                        dry_run,
+                        time_format,
+                        break_block,
+                        together: together.clone(),
+                        headless,
+                        until: until.is_some(),
+                        only: match (websites_only, apps_only) {
+                            (true, _) => Some(BlockScope::Websites),
+                            (_, true) => Some(BlockScope::Apps),
+                            _ => None,
+                        },
+                        items: ItemFilter { only, except },
+                        ..BlockOptions::from(&conf)
+                    });
+                if let Err(err) = block_manager.block_items(&profile_name, profile, duration) {
+                    let Some(skipped) = err.downcast_ref::<BlockSkipped>() else {
+                        // steps already applied would otherwise stay until the next block
+                        match block_manager.recover() {
+                            Err(rollback_err) => eprintln!(
+                                "Warning: couldn't roll back the partly applied block: {:#}",
+                                rollback_err
+                            ),
+                            recovered => {
+                                if recovered.is_ok_and(|record| record.is_some()) {
+                                    eprintln!("Rolled back the partly applied block");
+                                }
+                            }
+                        }
+                        if let Some(together) = &together {
+                            let _ = together.leave();
+                        }
+                        return Err(err);
+                    };
+                    eprintln!("{}, leaving it be", skipped);
+                    return Ok(());
+                }
+                if dry_run {
+                    return Ok(());
+                }
+                if harden && has_websites {
+                    let hardened = browsers::harden()?;
+                    if !hardened.is_empty() && !headless {
+                        eprintln!(
+                            "Turned DNS over HTTPS off in {}, restart them for it to apply",
+                            hardened.join(", ")
+                        );
+                    }
+                }
+                if headless {
+                    println!("{}", block_manager.get_quick_status()?.to_json()?);
+                    return Ok(());
+                }
+
+                if has_websites {
+                    let (default_browser, findings) = browsers::check();
+                    for finding in &findings {
+                        let default = if default_browser == Some(finding.browser) {
+                            " (your default browser)"
+                        } else {
+                            ""
+                        };
+                        eprintln!(
+                            "Warning: {}{} {}, blocked websites may still load: {}",
+                            finding.browser, default, finding.problem, finding.hint
+                        );
+                    }
+                }
+                if conf.achievement_notifications
+                    && let Err(e) = stats::announce_new_achievements()
+                {
+                    eprintln!("{:#}", e);
+                }
+            }
+            Self::UnblockEarly { config } => {
+                is_sudo()?;
+                if let Some(household) = Household::load()? {
+                    household.require_admin("lift the block")?;
+                }
+
+                let block_manager = BlockManager::new();
+                let Some((profile_name, profile)) = block_manager.active_profile()? else {
+                    anyhow::bail!("No active block is running");
+                };
+                let block_manager = take_partner_code(block_manager)?;
+                block_manager.check_liftable()?;
+
+                // a config that doesn't load shouldn't make the wait any shorter
+                let conf = EnoughConfig::load(config).ok();
+                let delay = conf
+                    .as_ref()
+                    .map_or(DEFAULT_UNBLOCK_EARLY_DELAY, |conf| conf.unblock_early_delay);
+                let time_format = conf
+                    .as_ref()
+                    .map(|conf| conf.time_format)
+                    .unwrap_or_default();
+
+                if let Some(note) = profile
+                    .note
+                    .or(profile.friction.and_then(|friction| friction.note))
+                {
+                    eprintln!("You started `{}` for: {}", profile_name, note);
+                }
+                let abort = conf
+                    .as_ref()
+                    .and_then(|conf| conf.abort_journal.as_ref())
+                    .map(|journal| journal_abort(journal, None))
+                    .transpose()?;
+                let phrase = friction::phrase();
+                eprintln!(
+                    "To lift the block early, type this phrase:\n\n    {}\n",
+                    phrase
+                );
+                if prompt(">")? != phrase {
+                    anyhow::bail!("That's not the phrase, the block stays");
+                }
+
+                let still_blocked = || {
+                    Ok(matches!(
+                        block_manager.get_quick_status()?,
+                        Status::Blocked { profile_name: active, .. } if active == profile_name
+                    ))
+                };
+                if !friction::count_down(delay, time_format, still_blocked)? {
+                    eprintln!("The block ended in the meantime");
+                    return Ok(());
+                }
+
+                let reason = abort
+                    .as_ref()
+                    .map_or("typed the phrase", |(reason, _)| reason.as_str());
+                let record = AuditRecord::new("unblock_early", &profile_name, reason);
+                audit::append(&record).with_context(|| "Failed to write the audit record")?;
+                block_manager.unblock_all()?;
+                history::end_early(Local::now())?;
+                if let Some((reason, screenshot)) = abort {
+                    history::record_abort(&reason, screenshot)?;
+                }
+                eprintln!("Block `{}` lifted early", profile_name);
+
+                notify_accountability(conf.and_then(|conf| conf.accountability?.webhook), &record);
+            }
+            Self::Emergency { reason, config } => {
+                is_sudo()?;
+
+                if reason.trim().is_empty() {
+                    anyhow::bail!("An emergency unblock needs a reason");
+                }
+
+                if let Some(household) = Household::load()? {
+                    household.require_admin("lift the block")?;
+                }
 
                 let block_manager = BlockManager::new();
-                block_manager.block_items(&profile_name, profile, duration)?;
+                let Status::Blocked { profile_name, .. } = block_manager.get_status()? else {
+                    anyhow::bail!("No active block is running");
+                };
+                // the partner isn't waited on in an emergency, only told
+                let partner = block_manager.partner_secret()?.is_some();
+
+                if let Some(contract) = contract::active()?.into_iter().find(|contract| {
+                    contract.terms.strict && contract.terms.profile_name == profile_name
+                }) {
+                    anyhow::bail!(
+                        "Contract {} doesn't allow emergency unblocks of `{}` until {}",
+                        contract.terms.id,
+                        profile_name,
+                        contract.terms.expires.format("%Y-%m-%d %H:%M")
+                    );
+                }
+
+                let conf = EnoughConfig::load(config).ok();
+                let abort = conf
+                    .as_ref()
+                    .and_then(|conf| conf.abort_journal.as_ref())
+                    .map(|journal| journal_abort(journal, Some(&reason)))
+                    .transpose()?;
+
+                // recording before unblocking, so a failed write can't leave an unaudited escape
+                let record = AuditRecord::new("emergency_unblock", &profile_name, &reason);
+                audit::append(&record).with_context(|| "Failed to write the audit record")?;
+
+                block_manager.unblock_in_emergency()?;
+                history::end_early(Local::now())?;
+                if let Some((reason, screenshot)) = abort {
+                    history::record_abort(&reason, screenshot)?;
+                }
+                let until = audit::lock_out_for_today()?;
+                eprintln!(
+                    "Block `{}` lifted, new blocks are locked out until {}",
+                    profile_name,
+                    until.format("%H:%M")
+                );
+
+                let webhook = conf.and_then(|conf| conf.accountability?.webhook);
+                if partner && webhook.is_none() {
+                    eprintln!(
+                        "Your accountability partner wasn't told, `accountability` has no `webhook`"
+                    );
+                }
+                notify_accountability(webhook, &record);
             }
-            Self::Unblock { fix } => {
+            Self::Unblock { fix, scheduled } => {
                 is_sudo()?;
+                // the scheduled unblock of a strict block gets its token on stdin
+                let mut token = String::new();
+                if !io::stdin().is_terminal() {
+                    io::stdin().read_to_string(&mut token)?;
+                }
+                let block_manager = BlockManager::new().with_unblock_token(
+                    Some(token.trim().to_string()).filter(|token| !token.is_empty()),
+                );
 
-                if fix {
-                    let block_manager = BlockManager::new();
-                    block_manager.unblock_all()?;
+                if fix && scheduled {
+                    block_manager.finish_scheduled()?;
+                } else if fix {
+                    take_partner_code(block_manager)?.unblock_all()?;
                     eprintln!("All items unblocked");
                 } else {
                     eprintln!("This command is for internal use only, do NOT run it manually");
                 }
             }
-            Self::Status { json, line } => {
+            Self::Reapply => {
+                is_sudo()?;
+                BlockManager::new().reapply()?;
+            }
+            Self::Windows => {
+                is_sudo()?;
+                BlockManager::new().refresh_windows()?;
+            }
+            Self::Track => {
+                is_sudo()?;
+                BlockManager::new().track_usage()?;
+            }
+            Self::Installs => {
+                is_sudo()?;
+                BlockManager::new().block_new_installs()?;
+            }
+            Self::Guard => {
+                is_sudo()?;
+                BlockManager::new().guard_apps()?;
+            }
+            Self::Reinstalls => {
+                is_sudo()?;
+                BlockManager::new().reblock_reinstalls()?;
+            }
+            Self::Relaunch => {
+                is_sudo()?;
+                BlockManager::new().watch_relaunches()?;
+            }
+            Self::ConfigGuard => {
+                is_sudo()?;
+                BlockManager::new().guard_config()?;
+            }
+            Self::HostsGuard => {
+                is_sudo()?;
+                BlockManager::new().guard_hosts()?;
+            }
+            Self::JobsGuard => {
+                is_sudo()?;
+                BlockManager::new().guard_jobs()?;
+            }
+            Self::Extend { duration } => {
+                is_sudo()?;
+
                 let block_manager = BlockManager::new();
-                if json {
-                    let status = block_manager.get_status(false)?;
+                let Status::Blocked { profile_name, .. } = block_manager.get_status()? else {
+                    anyhow::bail!("No active block is running");
+                };
+                let until = block_manager.extend(duration)?;
+                println!(
+                    "Block `{}` extended, it ends at {}",
+                    profile_name,
+                    until.format("%H:%M")
+                );
+            }
+            Self::AllowTemp { domain, duration } => {
+                is_sudo()?;
+                if let Some(household) = Household::load()? {
+                    household.require_admin("let a website through")?;
+                }
+
+                let until = BlockManager::new().allow_temp(&domain, duration)?;
+                println!("{} is let through until {}", domain, until.format("%H:%M"));
+            }
+            Self::AllowApp { app, duration } => {
+                is_sudo()?;
+                if let Some(household) = Household::load()? {
+                    household.require_admin("let an app through")?;
+                }
+
+                let (app, until) = BlockManager::new().allow_app(&app, duration)?;
+                println!(
+                    "{} is let through until {}",
+                    app.display(),
+                    until.format("%H:%M")
+                );
+            }
+            Self::Reblock => {
+                is_sudo()?;
+                BlockManager::new().reblock_exceptions()?;
+            }
+            Self::Boot => {
+                is_sudo()?;
+                BlockManager::new().check_after_boot()?;
+            }
+            Self::Remaining => {
+                is_sudo()?;
+                BlockManager::new().write_remaining_file()?;
+            }
+            Self::Friction => {
+                is_sudo()?;
+                friction::serve()?;
+            }
+            Self::DnsProxy => {
+                is_sudo()?;
+                dns_proxy::serve()?;
+            }
+            Self::Doctor { fix } => {
+                let mut checks = doctor::checks()?;
+                for check in &checks {
+                    println!("{}", check);
+                }
+
+                if fix && checks.iter().any(|check| check.problem.is_some()) {
+                    is_sudo()?;
+                    for name in doctor::fix(&checks)? {
+                        println!("Repaired the {}", name);
+                    }
+                    checks = doctor::checks()?;
+                }
+
+                let problems = checks
+                    .iter()
+                    .filter(|check| check.problem.is_some())
+                    .count();
+                if problems > 0 {
+                    anyhow::bail!("Found {} problems", problems);
+                }
+            }
+            Self::Status {
+                json,
+                line,
+                public,
+                at,
+                remaining,
+                check,
+            } => {
+                if let Some(profile) = check {
+                    return match BlockManager::new().get_quick_status()? {
+                        Status::Blocked { profile_name, .. } if profile_name == profile => Ok(()),
+                        _ => anyhow::bail!("`{}` isn't blocking right now", profile),
+                    };
+                }
+
+                let conf = EnoughConfig::load(None).ok();
+                let time_format = time_format
+                    .or(conf.as_ref().map(|conf| conf.time_format))
+                    .unwrap_or_default();
+                let status_time = match (at, remaining) {
+                    (true, true) => StatusTime::Both,
+                    (true, false) => StatusTime::At,
+                    (false, true) => StatusTime::Remaining,
+                    (false, false) => conf.map(|conf| conf.status_time).unwrap_or_default(),
+                };
+                let block_manager = BlockManager::new();
+                if public {
+                    match block_manager.get_quick_status()? {
+                        Status::Blocked { unblock_time, .. } => {
+                            println!("Focusing until {}", unblock_time.format("%H:%M"))
+                        }
+                        Status::Unblocked => println!("Not focusing right now"),
+                    }
+                } else if json {
+                    let status = block_manager.get_quick_status()?;
                     if status.is_blocked() {
-                        let json = serde_json::to_string(&status)?;
-                        println!("{:#}", json);
+                        println!("{}", status.to_json()?);
                     }
                 } else if line {
-                    let status = block_manager.get_status(false)?;
+                    let status = block_manager.get_quick_status()?;
                     match status {
                         Status::Blocked {
                             profile_name,
@@ -149,27 +1214,577 @@ impl EnoughOptions {
                                 .signed_duration_since(now)
                                 .to_std()
                                 .unwrap_or_default();
-                            let remaining_secs = Duration::from_secs(remaining.as_secs());
-                            print!("🔴 {} ({})", profile_name, format_duration(remaining_secs));
+                            print!(
+                                "{} {} ({})",
+                                if output::is_plain() {
+                                    "Blocked:"
+                                } else {
+                                    "🔴"
+                                },
+                                profile_name,
+                                time_left(status_time, time_format, remaining, unblock_time)
+                            );
                         }
+                        Status::Unblocked if output::is_plain() => print!("Not blocked"),
                         Status::Unblocked => print!("🟢 Unblocked"),
                     }
                     io::stdout().flush()?;
                 } else {
-                    block_manager.get_status(true)?;
+                    match block_manager.get_summary()? {
+                        Some(summary) => {
+                            println!("Active block (profile: {})", summary.profile_name);
+                            println!("{} {} apps blocked", output::bullet(), summary.apps);
+                            for app in &summary.auto_blocked {
+                                println!(
+                                    "{} {} was blocked when it got installed",
+                                    output::bullet(),
+                                    app.display()
+                                );
+                            }
+                            println!(
+                                "{} {} websites blocked ({} domains)",
+                                output::bullet(),
+                                summary.websites,
+                                summary.domains
+                            );
+                            let unblock_time = Local::now() + summary.remaining;
+                            // a block started `--until` a time is told by that time too
+                            let status_time = match status_time {
+                                StatusTime::Remaining if summary.until && !remaining => {
+                                    StatusTime::Both
+                                }
+                                status_time => status_time,
+                            };
+                            match status_time {
+                                StatusTime::At => {
+                                    println!(
+                                        "{} Blocked until {}",
+                                        output::bullet(),
+                                        unblock_time.format("%H:%M")
+                                    )
+                                }
+                                _ => println!(
+                                    "{} Time remaining: {}",
+                                    output::bullet(),
+                                    time_left(
+                                        status_time,
+                                        time_format,
+                                        summary.remaining,
+                                        unblock_time
+                                    )
+                                ),
+                            }
+                            if let Some(only) = summary.only {
+                                println!(
+                                    "{} Only the {} of the profile are blocked",
+                                    output::bullet(),
+                                    only
+                                );
+                            }
+                            if !summary.items.is_empty() {
+                                println!("{} {}", output::bullet(), summary.items);
+                            }
+                            if let Some(together) = &summary.together {
+                                match together.room() {
+                                    Err(e) => println!(
+                                        "{} Focusing together in `{}` (relay unreachable: {:#})",
+                                        output::bullet(),
+                                        together.room,
+                                        e
+                                    ),
+                                    room => println!(
+                                        "{} Focusing together in `{}`, {} in the room right now",
+                                        output::bullet(),
+                                        together.room,
+                                        room.map(|room| room.peers).unwrap_or_default()
+                                    ),
+                                }
+                            }
+                            if summary.cooling_down {
+                                println!(
+                                    "{} Cooling down, part of the block was already lifted",
+                                    output::bullet()
+                                );
+                            }
+                            for (domain, until) in &summary.allowed {
+                                println!(
+                                    "{} {} is let through until {}",
+                                    output::bullet(),
+                                    domain,
+                                    until.format("%H:%M")
+                                );
+                            }
+                            if summary.config_changed {
+                                println!(
+                                    "{} The config changed since this block started, edits apply to the next one",
+                                    output::bullet()
+                                );
+                            }
+                        }
+                        None => {
+                            eprintln!("No active block is running");
+                            if WorkContext::load(invoking_uid()).ok().flatten().is_some() {
+                                eprintln!("Run `sudo enough restore-context` to reopen your work");
+                            }
+                        }
+                    }
+
+                    if Household::load()?.is_some() {
+                        println!("Blocks are managed by the household admin");
+                    }
+                }
+            }
+            Self::Suggest {
+                config,
+                run,
+                no_calendar,
+            } => {
+                let conf = EnoughConfig::load(config.clone())?;
+                let time_format = time_format.unwrap_or(conf.time_format);
+                let mut profile_names = conf.profiles.keys().cloned().collect::<Vec<_>>();
+                profile_names.sort();
+                let events = if no_calendar {
+                    Vec::new()
+                } else {
+                    suggest::current_events()
+                };
+
+                let suggestions =
+                    suggest::rank(&profile_names, &history::sessions()?, &events, Local::now());
+                let Some(suggestion) = suggestions.into_iter().next() else {
+                    anyhow::bail!(
+                        "Nothing to suggest, no blocks were started around this time before and no calendar event names a profile"
+                    );
+                };
+                let duration = suggestion
+                    .duration
+                    .unwrap_or(conf.profiles[&suggestion.profile_name].duration);
+                println!(
+                    "Suggested: `{}` for {} ({})",
+                    suggestion.profile_name,
+                    time_format.format(duration),
+                    suggestion.reasons.join(", ")
+                );
+
+                if !run {
+                    eprintln!("Pass `--run` to start it");
+                    return Ok(());
+                }
+                return Self::Block {
+                    config,
+                    profile: Some(suggestion.profile_name),
+                    duration: Some(duration),
+                    until: None,
+                    vars: Vec::new(),
+                    progress: ProgressFormat::Human,
+                    quiet: false,
+                    wait_for_others: false,
+                    dry_run: false,
+                    websites_only: false,
+                    apps_only: false,
+                    only: Vec::new(),
+                    except: Vec::new(),
+                    harden: false,
+                    yes: false,
+                    headless: false,
+                    room: None,
+                }
+                .parse(Some(time_format));
+            }
+            Self::Together {
+                room,
+                config,
+                profile,
+                duration,
+                quiet,
+            } => {
+                return Self::Block {
+                    config,
+                    profile,
+                    duration,
+                    until: None,
+                    vars: Vec::new(),
+                    progress: ProgressFormat::Human,
+                    quiet,
+                    wait_for_others: false,
+                    dry_run: false,
+                    websites_only: false,
+                    apps_only: false,
+                    only: Vec::new(),
+                    except: Vec::new(),
+                    harden: false,
+                    yes: false,
+                    headless: false,
+                    room: Some(room),
+                }
+                .parse(time_format);
+            }
+            Self::Relay { listen } => together::serve(&listen)?,
+            Self::Stats => {
+                let conf = EnoughConfig::load(None).ok();
+                let time_format = time_format
+                    .or(conf.as_ref().map(|conf| conf.time_format))
+                    .unwrap_or_default();
+                let weights = conf.map(|conf| conf.focus_score).unwrap_or_default();
+                let sessions = history::sessions()?;
+                let now = Local::now();
+                let stats = Stats::from_sessions(&sessions, now);
+                println!("{} {} blocks", output::bullet(), stats.sessions);
+                println!(
+                    "{} {} blocked in total",
+                    output::bullet(),
+                    time_format.format(stats.total)
+                );
+                println!(
+                    "{} Longest session: {}",
+                    output::bullet(),
+                    time_format.format(stats.longest_session)
+                );
+                println!(
+                    "{} Streak: {} days (longest {})",
+                    output::bullet(),
+                    stats.current_streak,
+                    stats.longest_streak
+                );
+                if stats.continues > 0 {
+                    println!(
+                        "{} Continued past soft blocks {} times",
+                        output::bullet(),
+                        stats.continues
+                    );
+                }
+                if stats.reinstalls > 0 {
+                    println!(
+                        "{} Reinstalled blocked apps {} times, they were blocked again",
+                        output::bullet(),
+                        stats.reinstalls
+                    );
+                }
+                if stats.tampers > 0 {
+                    println!(
+                        "{} Caught tampering with blocks {} times",
+                        output::bullet(),
+                        stats.tampers
+                    );
+                }
+                let today = now.date_naive();
+                println!(
+                    "{} Focus score: {} today, {} yesterday",
+                    output::bullet(),
+                    stats::focus_score(&sessions, today, now, &weights),
+                    today
+                        .pred_opt()
+                        .map(|yesterday| stats::focus_score(&sessions, yesterday, now, &weights))
+                        .unwrap_or_default()
+                );
+
+                let achievements = stats.achievements();
+                if !achievements.is_empty() {
+                    println!("\nAchievements");
+                    for achievement in achievements {
+                        println!("{} {}", output::bullet(), achievement);
+                    }
+                }
+            }
+            Self::Report {
+                week,
+                email,
+                html,
+                config,
+            } => {
+                let time_format = time_format.unwrap_or_else(configured_time_format);
+                let report = Report::new(&history::sessions()?, Local::now(), week, time_format);
+                match email {
+                    Some(to) => {
+                        let email_config = EnoughConfig::load(config)?.email.unwrap_or_default();
+                        email::send(
+                            &email_config,
+                            &to,
+                            &report.subject(),
+                            &report.text(),
+                            &report.html(),
+                        )?;
+                        eprintln!("Sent the report to {}", to);
+                    }
+                    None if html => print!("{}", report.html()),
+                    None => print!("{}", report.text()),
+                }
+            }
+            Self::Events { action } => match action {
+                EventsOptions::Tail { lines, no_follow } => events::tail(lines, !no_follow)?,
+            },
+            Self::History { action } => match action {
+                HistoryOptions::Purge { older_than, yes } => {
+                    is_sudo()?;
+                    // the active session is never old enough for `--older-than`
+                    if older_than.is_none() && BlockManager::new().get_status()?.is_blocked() {
+                        anyhow::bail!("A block is active, its session can't be removed yet");
+                    }
+                    if older_than.is_none()
+                        && !yes
+                        && !confirm("Remove the whole history, streaks and stats included?")?
+                    {
+                        return Ok(());
+                    }
+                    let sessions = history::purge(older_than)?;
+                    let events = events::purge(older_than)?;
+                    eprintln!("Removed {} sessions and {} events", sessions, events);
+                }
+            },
+            Self::Backup { output, config } => backup::backup(config, &output)?,
+            Self::Restore {
+                archive,
+                config,
+                force,
+            } => {
+                is_sudo()?;
+                backup::restore(&archive, config, force)?;
+            }
+            Self::RestoreContext => {
+                is_sudo()?;
+
+                if BlockManager::new().get_status()?.is_blocked() {
+                    anyhow::bail!("A block is still active, restore the context once it ends");
+                }
+
+                let uid = invoking_uid();
+                let context = WorkContext::load(uid)?.with_context(
+                    || "No work context saved, set `save-context` in the profile to keep one",
+                )?;
+                context.restore(uid)?;
+                eprintln!(
+                    "Reopened {} apps and {} tabs",
+                    context.apps.len(),
+                    context.tabs.len()
+                );
+            }
+            Self::Why { target } => {
+                let reason = BlockManager::new().explain(&target)?;
+                println!("{}", reason);
+            }
+            Self::Apps { action } => action.parse()?,
+            Self::Config { action } => action.parse()?,
+            Self::Share {
+                config,
+                profile,
+                qr,
+            } => {
+                let (profile_name, profile) = match profile {
+                    Some(profile_name) => {
+                        let profile =
+                            load_config(config)?.profile(&profile_name, &HashMap::new())?;
+                        (profile_name, profile)
+                    }
+                    None => BlockManager::new().active_profile()?.with_context(
+                        || "No active block is running, pass `--profile` to share one",
+                    )?,
+                };
+
+                let share = SharedProfile::new(&profile_name, &profile).encode()?;
+                println!("{}", share);
+                if qr {
+                    share::print_qr(&share)?;
+                }
+            }
+            Self::Import {
+                share,
+                config,
+                name,
+            } => {
+                if let Some(household) = Household::load()? {
+                    household.require_admin("import a profile")?;
+                }
+
+                let shared = SharedProfile::decode(&share)?;
+                let profile_name = name.unwrap_or(shared.profile_name);
+                let config_path = EnoughConfig::resolve_path(config)?;
+                let block_manager = BlockManager::new();
+                let content = EnoughConfig::add_profile(
+                    &block_manager.read_config(&config_path)?,
+                    &profile_name,
+                    &shared.profile,
+                )?;
+                EnoughConfig::parse(&content)
+                    .context("The shared profile doesn't fit the config, left it as it was")?;
+
+                if block_manager.save_config(&config_path, &content)? {
+                    eprintln!(
+                        "A strict block is running, `{}` is added to the config once it ends",
+                        profile_name
+                    );
+                    return Ok(());
                 }
+                eprintln!(
+                    "Added `{}` to `{}`, start it with `sudo enough block -p {}`",
+                    profile_name,
+                    config_path.display(),
+                    profile_name
+                );
             }
             Self::Profiles { config } => {
-                let conf = EnoughConfig::load(config)?;
+                let conf = load_config(config)?;
                 println!("{}", conf);
             }
+            Self::Code {
+                for_partner,
+                new_secret,
+                secret,
+            } => {
+                if new_secret {
+                    is_sudo()?;
+                    println!("{}", partner::new_secret()?);
+                    eprintln!(
+                        "Share it with your partner, it isn't shown again. Blocks started with `partner: true` under `accountability` take their code"
+                    );
+                    return Ok(());
+                }
+                // the secret isn't kept on this side where the user could read it
+                let (true, Some(secret)) = (for_partner, secret) else {
+                    anyhow::bail!(
+                        "Pass `--for-partner --secret <secret>` to print the current code on the partner's side, or `--new-secret` for a secret to share"
+                    );
+                };
+                println!("{}", partner::code(&secret, SystemTime::now()));
+            }
+            Self::Contract { action } => {
+                is_sudo()?;
+                action.parse()?;
+            }
+            Self::Quota { action } => action.parse(time_format)?,
+            Self::Quotas { config } => {
+                is_sudo()?;
+                let conf = EnoughConfig::load(Some(config))?;
+                BlockManager::new()
+                    .with_options(BlockOptions::from(&conf))
+                    .enforce_quotas(&conf.quotas)?;
+            }
+            Self::Scheduler { action } => action.parse(time_format)?,
+            Self::SnoozeSchedule {
+                profile_name,
+                config,
+            } => {
+                is_sudo()?;
+                if let Some(household) = Household::load()? {
+                    household.require_admin("skip a scheduled block")?;
+                }
+
+                let conf = load_config(config)?;
+                let profile = conf
+                    .profiles
+                    .get(&profile_name)
+                    .with_context(|| format!("Profile `{}` not found", profile_name))?;
+                let schedule = profile
+                    .schedule
+                    .as_ref()
+                    .with_context(|| format!("Profile `{}` has no `schedule`", profile_name))?;
+                if profile.strict {
+                    anyhow::bail!(
+                        "`{}` is strict, its scheduled blocks can't be skipped",
+                        profile_name
+                    );
+                }
+
+                let mut state = scheduler::SchedulerState::load(invoking_uid())?;
+                let holidays = scheduler::Holidays::load(&conf);
+                let now = Local::now();
+                // the block of the one going on started already, lifting it is up to
+                // `unblock-early`, and the ones on holidays are skipped anyway
+                let next = iter::successors(schedule.next(now, profile.duration), |occurrence| {
+                    schedule.next(occurrence.end, profile.duration)
+                })
+                .take_while(|occurrence| occurrence.start < now + TimeDelta::days(366))
+                .find(|occurrence| {
+                    !state.was_started(&profile_name, occurrence.end)
+                        && !holidays.contains(occurrence.start.date_naive())
+                })
+                .with_context(|| {
+                    format!("`{}` isn't scheduled in the coming year", profile_name)
+                })?;
+                let when = format!(
+                    "{} until {}",
+                    next.start.format("%a %d %b %H:%M"),
+                    next.end.format("%a %H:%M")
+                );
+                if state.is_snoozed(&profile_name, next.end) {
+                    anyhow::bail!(
+                        "The `{}` block from {} is skipped already",
+                        profile_name,
+                        when
+                    );
+                }
+                state.snooze(&profile_name, next);
+                state.save(invoking_uid())?;
+                eprintln!("Skipping the `{}` block from {}", profile_name, when);
+            }
+            Self::Schedules { config } => {
+                is_sudo()?;
+                let conf = EnoughConfig::load(Some(config))?;
+                BlockManager::new()
+                    .with_options(BlockOptions::from(&conf))
+                    .follow_schedules(&conf.profiles, &scheduler::Holidays::load(&conf))?;
+            }
+            Self::Household { action } => {
+                is_sudo()?;
+                action.parse()?;
+            }
+            Self::ShellInit {
+                shell,
+                config,
+                guard,
+            } => {
+                let conf = load_config(config.clone())?;
+                let exe =
+                    env::current_exe().with_context(|| "Failed to find enough's executable")?;
+                print!(
+                    "{}",
+                    shell_init::script(shell, &conf, &exe, config.as_deref(), guard)
+                );
+            }
+            Self::Bypass { args } => {
+                let block_manager = BlockManager::new();
+                let Some((profile_name, profile)) = block_manager.active_profile()? else {
+                    return Ok(());
+                };
+                let Some(target) = shell_init::bypass_target(&args).filter(|_| profile.strict)
+                else {
+                    return Ok(());
+                };
+
+                eprintln!(
+                    "That looks like {}, in the middle of a strict `{}` block.",
+                    target, profile_name
+                );
+                if let Status::Blocked { unblock_time, .. } = block_manager.get_quick_status()? {
+                    eprintln!("It ends on its own at {}.", unblock_time.format("%H:%M"));
+                }
+                let note = profile
+                    .note
+                    .or(profile.friction.and_then(|friction| friction.note));
+                if let Some(note) = note {
+                    eprintln!("You started it for: {}", note);
+                }
+                if !confirm("Go ahead anyway?")? {
+                    anyhow::bail!("Not running it, back to focusing");
+                }
+            }
+            Self::Uninstall { keep_history } => {
+                is_sudo()?;
+                if let Some(household) = Household::load()? {
+                    household.require_admin("uninstall enough")?;
+                }
+
+                uninstall::uninstall(keep_history)?;
+                println!(
+                    "Removed every trace of enough, run `cargo uninstall enough` to remove the binary too"
+                );
+            }
             Self::Completions { shell } => {
                 let cmd = EnoughCLI::command();
                 let name = cmd.get_name().to_string();
 
                 let mut filtered_cmd = Command::new(env!("CARGO_PKG_NAME"));
                 for sub in cmd.get_subcommands() {
-                    if sub.get_name() != "___zzzunblock" {
+                    if !sub.get_name().starts_with("___zzz") {
                         filtered_cmd = filtered_cmd.subcommand(sub);
                     }
                 }
@@ -182,7 +1797,491 @@ impl EnoughOptions {
     }
 }
 
+impl AppsOptions {
+    fn parse(self) -> Result<()> {
+        match self {
+            Self::Discover { profile, config } => {
+                let apps = discover::installed_apps();
+                for (i, app) in apps.iter().enumerate() {
+                    println!(
+                        "{:>3}. {} ({}) [{}]",
+                        i + 1,
+                        app.path.display(),
+                        app.bundle_id.as_deref().unwrap_or("no bundle ID"),
+                        app.archs.as_deref().unwrap_or("unknown arch"),
+                    );
+                }
+
+                let Some(profile) = profile else {
+                    return Ok(());
+                };
+
+                // resolving before prompting, so a bad path doesn't waste the selection
+                let config_path = EnoughConfig::resolve_path(config)?;
+                eprint!(
+                    "Apps to add to `{}` (e.g. 1,3,5-7, empty to cancel): ",
+                    profile
+                );
+                io::stderr().flush()?;
+                let mut input = String::new();
+                io::stdin().read_line(&mut input)?;
+
+                let picked = discover::parse_selection(&input, apps.len())?
+                    .into_iter()
+                    .map(|i| apps[i].path.clone())
+                    .collect::<Vec<_>>();
+                if picked.is_empty() {
+                    return Ok(());
+                }
+
+                let block_manager = BlockManager::new();
+                let content = EnoughConfig::append_apps(
+                    &block_manager.read_config(&config_path)?,
+                    &profile,
+                    &picked,
+                )?;
+                if block_manager.save_config(&config_path, &content)? {
+                    eprintln!(
+                        "A strict block is running, the {} apps are added to `{}` once it ends",
+                        picked.len(),
+                        profile
+                    );
+                    return Ok(());
+                }
+                eprintln!(
+                    "Added {} apps to `{}` in `{}`",
+                    picked.len(),
+                    profile,
+                    config_path.display()
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ConfigOptions {
+    fn parse(self) -> Result<()> {
+        match self {
+            Self::Lint { config } => {
+                let conf = load_config(config)?;
+                let findings = lint::lint(&conf);
+                for finding in &findings {
+                    println!("{}", finding);
+                }
+
+                let warnings = findings
+                    .iter()
+                    .filter(|finding| finding.severity == Severity::Warning)
+                    .count();
+                if warnings > 0 {
+                    anyhow::bail!("Found {} problems in the config", warnings);
+                }
+                eprintln!("No problems found");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ContractOptions {
+    fn parse(self) -> Result<()> {
+        match self {
+            Self::Create {
+                days,
+                profile,
+                witness,
+                strict,
+                config,
+            } => {
+                if days == 0 {
+                    anyhow::bail!("A contract has to last at least a day");
+                }
+
+                let conf = load_config(config)?;
+                let contract = Contract::create(&conf, &profile, days, &witness, strict)?;
+                eprintln!(
+                    "Contract {} signed, `{}` can't be weakened until {}",
+                    contract.terms.id,
+                    profile,
+                    contract.terms.expires.format("%Y-%m-%d %H:%M")
+                );
+
+                if let Err(e) = contract.send_to_witness() {
+                    eprintln!("{:#}\nSend this to {} yourself:\n", e, witness);
+                    println!("{}", contract.document());
+                }
+
+                notify_accountability(conf.accountability.and_then(|a| a.webhook), &contract);
+            }
+            Self::List => {
+                let contracts = contract::active()?;
+                if contracts.is_empty() {
+                    eprintln!("No contracts in force");
+                }
+                for contract in contracts {
+                    println!(
+                        "{}  {}  until {}{}  (witness: {})",
+                        contract.terms.id,
+                        contract.terms.profile_name,
+                        contract.terms.expires.format("%Y-%m-%d %H:%M"),
+                        if contract.terms.strict {
+                            ", strict"
+                        } else {
+                            ""
+                        },
+                        contract.terms.witness
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl QuotaOptions {
+    fn parse(self, time_format: Option<TimeFormat>) -> Result<()> {
+        match self {
+            Self::Enable { config } => {
+                is_sudo()?;
+                let conf = load_config(config)?;
+                if conf.quotas.is_empty() {
+                    anyhow::bail!("The config has no `quotas` to measure");
+                }
+                let source = conf
+                    .source
+                    .as_ref()
+                    .with_context(|| "Couldn't tell which file the config was read from")?;
+                usage::watch_quotas(&source.path)?;
+                eprintln!(
+                    "Measuring {} quotas from {}, every {}",
+                    conf.quotas.len(),
+                    source.path.display(),
+                    TimeFormat::Human.format(usage::TRACK_INTERVAL)
+                );
+            }
+            Self::Disable => {
+                is_sudo()?;
+                if !usage::unwatch_quotas()? {
+                    anyhow::bail!("Quotas aren't being measured");
+                }
+                eprintln!("Stopped measuring the quotas");
+            }
+            Self::Status { config } => {
+                let conf = load_config(config)?;
+                let time_format = time_format.unwrap_or(conf.time_format);
+                let usage = QuotaUsage::load(invoking_uid())?;
+                let today = Local::now().date_naive();
+                let mut quotas = conf.quotas.iter().collect::<Vec<_>>();
+                quotas.sort_by_key(|(name, _)| *name);
+                for (name, quota) in quotas {
+                    let used = usage.used(name, quota.period.start(today));
+                    println!(
+                        "{} {}: {} of {} {}",
+                        if used >= quota.allowance {
+                            output::flagged("Used up:")
+                        } else {
+                            output::bullet()
+                        },
+                        name,
+                        time_format.format(used.min(quota.allowance)),
+                        time_format.format(quota.allowance),
+                        match quota.period {
+                            QuotaPeriod::Day => "today",
+                            QuotaPeriod::Week => "this week",
+                        }
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl SchedulerOptions {
+    fn parse(self, time_format: Option<TimeFormat>) -> Result<()> {
+        match self {
+            Self::Install { config } => {
+                is_sudo()?;
+                let conf = load_config(config)?;
+                let scheduled = conf
+                    .profiles
+                    .values()
+                    .filter(|profile| profile.schedule.is_some())
+                    .count();
+                if scheduled == 0 {
+                    anyhow::bail!("No profile of the config has a `schedule` to follow");
+                }
+                let source = conf
+                    .source
+                    .as_ref()
+                    .with_context(|| "Couldn't tell which file the config was read from")?;
+                scheduler::install(&source.path)?;
+                eprintln!(
+                    "Following the schedules of {} profiles from {}",
+                    scheduled,
+                    source.path.display()
+                );
+            }
+            Self::Uninstall => {
+                is_sudo()?;
+                if !scheduler::uninstall()? {
+                    anyhow::bail!("The schedules aren't being followed");
+                }
+                eprintln!("Stopped following the schedules");
+            }
+            Self::Status { config } => {
+                let conf = load_config(config)?;
+                let time_format = time_format.unwrap_or(conf.time_format);
+                let holidays = scheduler::Holidays::load(&conf);
+                let now = Local::now();
+                let mut profiles = conf
+                    .profiles
+                    .iter()
+                    .filter_map(|(name, profile)| Some((name, profile, profile.schedule.as_ref()?)))
+                    .collect::<Vec<_>>();
+                profiles.sort_by_key(|(name, ..)| *name);
+                for (name, profile, schedule) in profiles {
+                    match schedule
+                        .current(now, profile.duration)
+                        .filter(|occurrence| !holidays.contains(occurrence.start.date_naive()))
+                    {
+                        Some(scheduler::Occurrence { end, .. }) => println!(
+                            "{} {}: {}, due for another {}",
+                            output::flagged("Due:"),
+                            name,
+                            schedule,
+                            time_format.format((end - now).to_std().unwrap_or_default())
+                        ),
+                        None => println!("{} {}: {}", output::bullet(), name, schedule),
+                    }
+                }
+                if !scheduler::is_installed() {
+                    eprintln!(
+                        "The schedules aren't being followed, see `enough scheduler install`"
+                    );
+                }
+            }
+            Self::Preview { config, days } => {
+                let conf = load_config(config)?;
+                let state = scheduler::SchedulerState::load(invoking_uid())?;
+                let holidays = scheduler::Holidays::load(&conf);
+                let now = Local::now();
+                let until = now + TimeDelta::days(days as i64);
+                let mut occurrences = conf
+                    .profiles
+                    .iter()
+                    .filter_map(|(name, profile)| Some((name, profile, profile.schedule.as_ref()?)))
+                    .flat_map(|(name, profile, schedule)| {
+                        schedule
+                            .current(now, profile.duration)
+                            .into_iter()
+                            .chain(schedule.upcoming(now, until, profile.duration))
+                            .map(move |occurrence| (occurrence.start, name, occurrence))
+                    })
+                    .collect::<Vec<_>>();
+                occurrences.sort_by_key(|(start, name, _)| (*start, *name));
+                occurrences.dedup();
+                if occurrences.is_empty() {
+                    eprintln!("Nothing is scheduled in the next {} days", days);
+                }
+                for (_, name, occurrence) in occurrences {
+                    let note = if holidays.contains(occurrence.start.date_naive()) {
+                        ", skipped for the holiday".to_string()
+                    } else if state.is_snoozed(name, occurrence.end) {
+                        ", skipped".to_string()
+                    } else if let Some(until) = state.deferred_until(name, occurrence.end) {
+                        format!(", put off until {}", until.format("%H:%M"))
+                    } else {
+                        String::new()
+                    };
+                    println!(
+                        "{} {} until {}: {}{}",
+                        output::bullet(),
+                        occurrence.start.format("%a %d %b %H:%M"),
+                        occurrence.end.format("%a %H:%M"),
+                        name,
+                        note
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl HouseholdOptions {
+    fn parse(self) -> Result<()> {
+        match self {
+            Self::Setup { child } => {
+                if let Some(household) = Household::load()? {
+                    household.require_admin("replace the current setup")?;
+                }
+
+                let passphrase = rpassword::prompt_password("New admin passphrase: ")?;
+                if passphrase.is_empty() {
+                    anyhow::bail!("The admin passphrase can't be empty");
+                }
+                if rpassword::prompt_password("Repeat the admin passphrase: ")? != passphrase {
+                    anyhow::bail!("Passphrases don't match");
+                }
+
+                Household::setup(&child, &passphrase)?;
+                eprintln!(
+                    "Parental mode enabled for `{}`, blocks now go on their account with the profiles from `{}`",
+                    child,
+                    Household::config_path().display()
+                );
+            }
+            Self::Disable => {
+                let household = Household::load()?.with_context(|| "Parental mode is not on")?;
+                household.require_admin("turn off parental mode")?;
+                Household::disable()?;
+                eprintln!("Parental mode disabled");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Loads the config, which in parental mode is the admin's system-wide one.
+fn load_config(config: Option<PathBuf>) -> Result<EnoughConfig> {
+    let Some(household) = Household::load()? else {
+        return EnoughConfig::load(config);
+    };
+
+    if config.is_some() {
+        household.require_admin("use a different config file")?;
+        return EnoughConfig::load(config);
+    }
+
+    EnoughConfig::load(Some(Household::config_path()))
+}
+
+/// Posts `payload` to the accountability webhook, if there is one. Failing to reach it
+/// is only reported, it never stops the command.
+#[cfg(feature = "webhooks")]
+fn notify_accountability(webhook: Option<Url>, payload: &impl Serialize) {
+    if let Some(webhook) = webhook
+        && let Err(e) = notify::post_webhook(&webhook, payload)
+    {
+        eprintln!("{:#}", e);
+    }
+}
+
+#[cfg(not(feature = "webhooks"))]
+fn notify_accountability(webhook: Option<Url>, _payload: &impl Serialize) {
+    if webhook.is_some() {
+        eprintln!(
+            "This build doesn't include webhooks, the accountability webhook wasn't notified"
+        );
+    }
+}
+
+/// What goes in the history for the config's `abort-journal`: the `reason`, asked for
+/// when it isn't given, and the screenshot if one is wanted. A screenshot that can't be
+/// taken is only reported.
+fn journal_abort(
+    journal: &AbortJournal,
+    reason: Option<&str>,
+) -> Result<(String, Option<PathBuf>)> {
+    let reason = match reason {
+        Some(reason) => reason.to_string(),
+        None => prompt("What are you lifting the block for?")?,
+    };
+    if reason.trim().is_empty() {
+        anyhow::bail!("The abort journal needs a reason to lift the block early");
+    }
+
+    let screenshot = match journal.screenshot.then(history::take_screenshot) {
+        Some(Err(err)) => {
+            eprintln!("Couldn't take the screenshot: {:#}", err);
+            None
+        }
+        screenshot => screenshot.and_then(|screenshot| screenshot.ok()),
+    };
+    Ok((reason, screenshot))
+}
+
+/// Asks for a line of input, for values that weren't passed as arguments.
+fn prompt(question: &str) -> Result<String> {
+    if !io::stdin().is_terminal() {
+        anyhow::bail!("{} Can't ask without a terminal", question);
+    }
+
+    eprint!("{} ", question);
+    io::stderr().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+
+    Ok(input.trim().to_string())
+}
+
+/// The end of a block as `status_time` has it: `1h 5m`, `until 17:42` or both.
+fn time_left(
+    status_time: StatusTime,
+    time_format: TimeFormat,
+    remaining: Duration,
+    unblock_time: DateTime<Local>,
+) -> String {
+    let until = format!("until {}", unblock_time.format("%H:%M"));
+    match status_time {
+        StatusTime::Remaining => time_format.format(remaining),
+        StatusTime::At => until,
+        StatusTime::Both => format!("{}, {}", time_format.format(remaining), until),
+    }
+}
+
+/// The config's `time-format`, the default one without a config.
+fn configured_time_format() -> TimeFormat {
+    EnoughConfig::load(None)
+        .map(|config| config.time_format)
+        .unwrap_or_default()
+}
+
+/// Parses a template variable given as `name=value`.
+fn parse_variable(arg: &str) -> Result<(String, String)> {
+    let (name, value) = arg
+        .split_once('=')
+        .with_context(|| format!("Expected `NAME=VALUE`, found `{}`", arg))?;
+    Ok((name.trim().to_string(), value.to_string()))
+}
+
+/// Asks a yes/no question on the terminal, anything but `y` counts as no.
+fn confirm(question: &str) -> Result<bool> {
+    if !io::stdin().is_terminal() {
+        anyhow::bail!("{} Can't ask for confirmation without a terminal", question);
+    }
+
+    eprint!("{} [y/N] ", question);
+    io::stderr().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+
+    Ok(input.trim().eq_ignore_ascii_case("y"))
+}
+
+/// Asks for the accountability partner's code, if lifting the block takes one.
+fn take_partner_code(block_manager: BlockManager) -> Result<BlockManager> {
+    if !block_manager.needs_partner_code()? {
+        return Ok(block_manager);
+    }
+    eprintln!(
+        "Lifting this block early takes a code from your accountability partner, from `enough code --for-partner`"
+    );
+    block_manager.with_partner_code(&prompt("Code:")?)
+}
+
 fn is_sudo() -> Result<()> {
+    if sandbox::root().is_some() {
+        return Ok(());
+    }
     env::var("SUDO_USER").with_context(|| "This command must be run with sudo")?;
     Ok(())
 }
@@ -0,0 +1,82 @@
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::{process::Command, time::Duration};
+
+use crate::sandbox;
+
+/// Server asked for the real time, the same one macOS syncs against
+const NTP_SERVER: &str = "time.apple.com";
+
+/// Readings taken at block start from clocks the user can't simply set, to tell how much
+/// time really passed when the wall clock says the block is over.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClockAnchor {
+    wall_secs: i64,
+    /// Since boot, only comparable while `boot_session` stays the same
+    monotonic_secs: u64,
+    boot_session: Option<String>,
+    /// How far the wall clock was off from NTP, if the server could be reached
+    ntp_offset_secs: Option<f64>,
+}
+
+impl ClockAnchor {
+    pub fn now() -> Self {
+        Self {
+            wall_secs: Local::now().timestamp(),
+            monotonic_secs: monotonic().as_secs(),
+            boot_session: boot_session(),
+            ntp_offset_secs: ntp_offset(),
+        }
+    }
+
+    /// Time that really passed since the anchor: from the monotonic clock while still on
+    /// the same boot, from NTP after a reboot. `None` when neither can tell.
+    pub fn elapsed(&self) -> Option<Duration> {
+        if self.boot_session.is_some() && boot_session() == self.boot_session {
+            return monotonic().checked_sub(Duration::from_secs(self.monotonic_secs));
+        }
+
+        let offset = ntp_offset()?;
+        let now = Local::now().timestamp() as f64 + offset;
+        let start = self.wall_secs as f64 + self.ntp_offset_secs.unwrap_or_default();
+        Duration::try_from_secs_f64(now - start).ok()
+    }
+}
+
+fn monotonic() -> Duration {
+    let mut time = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // keeps counting through sleep, and setting the date doesn't touch it
+    unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut time) };
+    Duration::new(time.tv_sec as u64, time.tv_nsec as u32)
+}
+
+/// Changes on every boot, unlike `kern.boottime`, which moves along with the wall clock.
+fn boot_session() -> Option<String> {
+    let output = Command::new("sysctl")
+        .args(["-n", "kern.bootsessionuuid"])
+        .output()
+        .ok()?;
+
+    let session = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !session.is_empty()).then_some(session)
+}
+
+/// Offset of the wall clock from NTP time in seconds, printed by `sntp` as e.g.
+/// `+0.003256 +/- 0.016708 time.apple.com 17.253.4.125`.
+fn ntp_offset() -> Option<f64> {
+    let output = sandbox::command("sntp")
+        .args(["-t", "5", NTP_SERVER])
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.split_whitespace().next()?.parse().ok())
+}
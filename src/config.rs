@@ -1,9 +1,35 @@
 use anyhow::{Context, Ok, Result};
-use config::{Config, File};
+use chrono::{DateTime, Datelike as _, Days, Local, NaiveDate, NaiveTime};
+use config::{Config, File, FileFormat};
 use humantime_serde::re::humantime::format_duration;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, env, fmt::Display, fs, path::PathBuf, time::Duration};
-use url::Url;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    env,
+    fmt::Display,
+    fs,
+    ops::Range,
+    os::unix::fs::MetadataExt as _,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use url::{Host, Url};
+
+use crate::{
+    app_groups,
+    block::data_dir,
+    discover, output,
+    scheduler::{DateRange, Schedule},
+    time_format::TimeFormat,
+};
+
+/// Bumped whenever the config format changes in a way older versions can't read
+pub const CONFIG_SCHEMA_VERSION: u32 = 1;
+/// How long `enough unblock-early` counts down without `unblock-early-delay`
+pub const DEFAULT_UNBLOCK_EARLY_DELAY: Duration = Duration::from_secs(5 * 60);
+/// Read-only system config merged over the user's, for teams and parents
+const POLICY_FILE: &str = "policy.yaml";
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
@@ -11,20 +37,965 @@ pub struct EnoughConfig {
     /// The default profile to use if none is specified
     pub default_profile: Option<String>,
     pub profiles: HashMap<String, Profile>,
+    /// Profiles with `{{variable}}` placeholders in their values, filled in with
+    /// `enough block --set variable=value`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub templates: HashMap<String, serde_yml::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accountability: Option<Accountability>,
+    /// Notifications on the phone and watch, through ntfy or Pushover
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub push: Option<Push>,
+    /// Maximum number of domains written to the hosts file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hosts_limit: Option<usize>,
+    /// Block domains over the hosts limit through pf, instead of writing them anyway
+    #[serde(default = "default_true")]
+    pub spill_to_pf: bool,
+    /// Where blocked websites are written to
+    #[serde(default, alias = "blocking-backend")]
+    pub websites_backend: WebsitesBackend,
+    /// Block the known DNS over HTTPS endpoints along with websites, and DNS over TLS
+    /// through pf on macOS, so browsers' secure DNS can't skip the block
+    #[serde(default)]
+    pub block_doh: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limits: Option<Limits>,
+    /// Post a notification when an achievement from `enough stats` is unlocked
+    #[serde(default)]
+    pub achievement_notifications: bool,
+    /// Groups of apps to block as `group:<name>` in `apps`, on top of the built-in
+    /// `games` and `browsers`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub app_groups: HashMap<String, AppGroup>,
+    /// Log block events to a local JSONL file, for `enough events tail` and other tools
+    #[serde(default)]
+    pub events: bool,
+    /// Keep a plain-text file with the active profile and the seconds left, for
+    /// consumers that can only `cat`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remaining_file: Option<RemainingFile>,
+    /// How `enough report --email` sends mail
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<EmailConfig>,
+    /// How durations are printed, `--time-format` overrides it
+    #[serde(default)]
+    pub time_format: TimeFormat,
+    /// Whether `enough status` shows the time left, the unblock time or both
+    #[serde(default)]
+    pub status_time: StatusTime,
+    /// Relay coordinating group sessions, see `enough together` and `enough relay`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relay: Option<Url>,
+    /// Daily or weekly allowances of websites and apps, measured outside of blocks too
+    /// once `enough quota enable` is run
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub quotas: HashMap<String, Quota>,
+    /// Days the scheduled blocks don't start on, like public holidays and vacations:
+    /// days like `2025-12-25` and ranges like `2025-12-24..2026-01-02`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub holidays: Vec<DateRange>,
+    /// An ICS calendar file or feed URL whose events are holidays too, a feed is fetched
+    /// again once a day
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub holiday_calendar: Option<String>,
+    /// How long `enough unblock-early` counts down once the phrase is typed
+    #[serde(
+        default = "default_unblock_early_delay",
+        with = "crate::time_format::duration"
+    )]
+    pub unblock_early_delay: Duration,
+    /// Ask why when a block is lifted early, keeping the answer in the history for the
+    /// reports
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub abort_journal: Option<AbortJournal>,
+    /// Points of the daily focus score in `enough stats`
+    #[serde(default)]
+    pub focus_score: FocusScoreWeights,
+    /// How long the history and the event log are kept, and what of them is readable
+    #[serde(default)]
+    pub history: HistoryPrivacy,
+    /// Profiles mandated by the system policy, which can't be weakened
+    #[serde(skip)]
+    pub policy_profiles: HashSet<String>,
+    /// The file this config was read from
+    #[serde(skip)]
+    pub source: Option<ConfigSource>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_unblock_early_delay() -> Duration {
+    DEFAULT_UNBLOCK_EARLY_DELAY
+}
+
+/// How `enough status` tells when the block ends, `--at` and `--remaining` override it.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum StatusTime {
+    /// The time left, e.g. `1h 5m`
+    #[default]
+    Remaining,
+    /// The wall-clock time of the unblock, e.g. `until 17:42`
+    At,
+    Both,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum WebsitesBackend {
+    /// Entries in our section of `/etc/hosts`
+    #[default]
+    Hosts,
+    /// A file per domain under `/etc/resolver`, for when `/etc/hosts` can't be edited,
+    /// like on nix-darwin. Covers subdomains too
+    Resolver,
+    /// A local DNS server sinking the domains and every subdomain of them, set as the
+    /// system's resolver for the block
+    DnsProxy,
+    /// pf rules dropping traffic to the addresses the domains resolve to when the
+    /// block starts or the network changes, which DNS over HTTPS doesn't get around
+    Pf,
+}
+
+/// Where a config was read from, to tell later whether the file has changed since.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigSource {
+    pub path: PathBuf,
+    pub sha256: String,
+}
+
+impl ConfigSource {
+    /// Whether the file no longer matches what was read, which includes it being gone.
+    pub fn has_changed(&self) -> bool {
+        !fs::read(&self.path).is_ok_and(|content| sha256_hex(&content) == self.sha256)
+    }
+
+    /// The file's content, as long as it's still what was read.
+    pub fn read_unchanged(&self) -> Option<String> {
+        fs::read_to_string(&self.path)
+            .ok()
+            .filter(|content| sha256_hex(content.as_bytes()) == self.sha256)
+    }
+
+    /// Whether this is the config at `path`.
+    pub fn is_at(&self, path: &Path) -> bool {
+        fs::canonicalize(path).is_ok_and(|path| path == self.path)
+    }
+}
+
+/// The `{{variable}}` names used anywhere in `value`, which must be made of letters,
+/// digits, `-` and `_`.
+fn template_placeholders(value: &serde_yml::Value) -> Result<BTreeSet<String>> {
+    let mut placeholders = BTreeSet::new();
+    match value {
+        serde_yml::Value::String(text) => {
+            for (_, name) in placeholder_spans(text)? {
+                placeholders.insert(name.to_string());
+            }
+        }
+        serde_yml::Value::Sequence(values) => {
+            for value in values {
+                placeholders.extend(template_placeholders(value)?);
+            }
+        }
+        serde_yml::Value::Mapping(mapping) => {
+            for value in mapping.values() {
+                placeholders.extend(template_placeholders(value)?);
+            }
+        }
+        _ => {}
+    }
+    Ok(placeholders)
+}
+
+/// Where each `{{variable}}` of `text` is, with its name trimmed of the spaces around it.
+fn placeholder_spans(text: &str) -> Result<Vec<(Range<usize>, &str)>> {
+    let mut spans = Vec::new();
+    let mut offset = 0;
+    while let Some(start) = text[offset..].find("{{").map(|start| offset + start) {
+        let end = text[start + 2..]
+            .find("}}")
+            .map(|end| start + 2 + end)
+            .with_context(|| format!("Unclosed `{{{{` in `{}`", text))?;
+        let name = text[start + 2..end].trim();
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            anyhow::bail!("Invalid variable name `{}` in `{}`", name, text);
+        }
+        spans.push((start..end + 2, name));
+        offset = end + 2;
+    }
+    Ok(spans)
+}
+
+/// Replaces every `{{variable}}` in the strings of `value`, substituting values rather
+/// than text so they can't change the structure of the profile. The placeholders were
+/// checked by [`template_placeholders`], it fails on the same strings.
+fn fill_placeholders(value: &mut serde_yml::Value, vars: &HashMap<String, String>) -> Result<()> {
+    match value {
+        serde_yml::Value::String(text) => {
+            let mut filled = String::with_capacity(text.len());
+            let mut copied = 0;
+            for (span, name) in placeholder_spans(text)? {
+                filled.push_str(&text[copied..span.start]);
+                match vars.get(name) {
+                    Some(var) => filled.push_str(var),
+                    None => filled.push_str(&text[span.clone()]),
+                }
+                copied = span.end;
+            }
+            filled.push_str(&text[copied..]);
+            *text = filled;
+        }
+        serde_yml::Value::Sequence(values) => {
+            for value in values {
+                fill_placeholders(value, vars)?;
+            }
+        }
+        serde_yml::Value::Mapping(mapping) => {
+            for value in mapping.values_mut() {
+                fill_placeholders(value, vars)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
 }
 
+pub fn sha256_hex(content: &[u8]) -> String {
+    let digest = Sha256::digest(content);
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Wellness guard against spending the whole day in blocks
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct Limits {
+    /// Time spent in blocks today after which starting another one has to be confirmed
+    #[serde(with = "crate::time_format::duration")]
+    pub max_daily_block_time: Duration,
+    /// Refuse to start another block instead of asking for confirmation
+    #[serde(default)]
+    pub strict: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct Push {
+    /// An ntfy topic, e.g. `https://ntfy.sh/my-focus-topic`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ntfy: Option<Url>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pushover: Option<Pushover>,
+    /// What gets pushed, everything by default
+    #[serde(default = "default_push_events")]
+    pub events: Vec<PushEvent>,
+}
+
+fn default_push_events() -> Vec<PushEvent> {
+    vec![PushEvent::Start, PushEvent::End, PushEvent::Tamper]
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Pushover {
+    /// The application's API token
+    pub token: String,
+    /// The user (or group) key to notify
+    pub user: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum PushEvent {
+    /// A block started
+    Start,
+    /// A block ended, on time or not
+    End,
+    /// Tampering with a block was caught
+    Tamper,
+}
+
+/// Someone who gets told when a block is weakened
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct Accountability {
+    /// Webhook receiving a JSON payload on emergency unblocks
+    pub webhook: Option<Url>,
+    /// Lifting a block early takes the code the partner reads out, from the secret
+    /// `enough code --new-secret` made
+    #[serde(default)]
+    pub partner: bool,
+    /// Where the secret used to be kept, readable by anyone who can read the config.
+    /// Refused, so the partner isn't silently dropped
+    #[serde(default, skip_serializing)]
+    pub partner_secret: Option<String>,
+}
+
+/// Apps picked up from the installed ones when the block starts, so new installs are
+/// covered too
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct AppGroup {
+    /// Bundle ID prefixes, e.g. `com.valvesoftware.`
+    #[serde(default)]
+    pub bundle_ids: Vec<String>,
+    /// `LSApplicationCategoryType` values, e.g. `public.app-category.games`
+    #[serde(default)]
+    pub categories: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct AbortJournal {
+    /// Also keep a screenshot of what was on screen, next to the history
+    #[serde(default)]
+    pub screenshot: bool,
+}
+
+/// What the history and the event log keep, for the stats without a readable log of
+/// what tempted you.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct HistoryPrivacy {
+    /// Sessions and events older than this are dropped whenever a block starts
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        with = "crate::time_format::duration::option"
+    )]
+    pub keep: Option<Duration>,
+    /// How the apps caught in a block are written down
+    pub names: StoredNames,
+    /// Encrypts the history and the block state with a key kept in the Keychain (a
+    /// root-only file on Linux), so a copy of the data directory doesn't give them away
+    pub encrypt: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum StoredNames {
+    #[default]
+    Plain,
+    /// A hash of the name, the same for the same app
+    Hash,
+    /// Nothing, only that something was caught
+    Omit,
+}
+
+impl StoredNames {
+    /// `path` as it's written down, `None` when it's left out.
+    pub fn path(self, path: &Path) -> Option<PathBuf> {
+        match self {
+            Self::Plain => Some(path.to_path_buf()),
+            Self::Hash => {
+                let hash = sha256_hex(path.as_os_str().as_encoded_bytes());
+                Some(PathBuf::from(format!("sha256:{}", &hash[..16])))
+            }
+            Self::Omit => None,
+        }
+    }
+}
+
+/// A day's focus score is the hours blocked that day times `per-hour`, less the weight
+/// of every block lifted early, tampering caught and soft block continued past, kept
+/// between 0 and 100.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct FocusScoreWeights {
+    pub per_hour: u32,
+    pub abort: u32,
+    pub tamper: u32,
+    pub continues: u32,
+}
+
+impl Default for FocusScoreWeights {
+    fn default() -> Self {
+        Self {
+            per_hour: 25,
+            abort: 20,
+            tamper: 15,
+            continues: 5,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RemainingFile {
+    /// How often the file is updated during a block
+    #[serde(
+        default = "default_remaining_interval",
+        with = "crate::time_format::duration"
+    )]
+    pub interval: Duration,
+}
+
+fn default_remaining_interval() -> Duration {
+    Duration::from_secs(30)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct EmailConfig {
+    /// Sender address, the recipient's by default
+    pub from: Option<String>,
+    /// SMTP server, like `smtps://smtp.fastmail.com:465`. Mail goes through the local
+    /// `sendmail` without one
+    pub smtp: Option<Url>,
+    pub username: Option<String>,
+    /// Command printing the SMTP password, like
+    /// `security find-generic-password -s enough-smtp -w`
+    pub password_command: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "kebab-case")]
 pub struct Profile {
-    #[serde(with = "humantime_serde")]
+    #[serde(with = "crate::time_format::duration")]
     pub duration: Duration,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub websites: Vec<Website>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub apps: Vec<PathBuf>,
+    /// Apps only blocked once they've been used for a daily allowance
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub app_limits: Vec<AppLimit>,
+    /// Time the blocked apps can be let through with `enough allow-app`, over the whole
+    /// block
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "crate::time_format::duration::option"
+    )]
+    pub app_allowance: Option<Duration>,
+    /// Cut off VPN tunnels for the duration of the block, since they can bypass the hosts file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vpn_kill_switch: Option<VpnKillSwitch>,
+    /// Silence notifications from chat apps without blocking the apps themselves
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub silence_notifications: Option<SilenceNotifications>,
+    /// Keep part of the block going for a while after it ends, to ease back in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cool_down: Option<CoolDown>,
+    /// Close browser tabs already open on blocked websites when the block starts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub close_tabs: Option<CloseTabs>,
+    /// Remember the work left open when the block starts, for `enough restore-context`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub save_context: Option<SaveContext>,
+    /// Harden the block against workarounds, like setting the clock forward
+    #[serde(default)]
+    pub strict: bool,
+    /// Time of day the profile can be started in, e.g. `07:00-20:00`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_start_window: Option<StartWindow>,
+    /// When `enough scheduler` blocks the profile on its own, e.g. `weekdays 09:00-17:00`
+    /// or a cron expression like `30 8 * * 1-5` starting a block of `duration`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<Schedule>,
+    /// Show a countdown page on blocked websites instead of failing to load them, with a
+    /// way to continue anyway that's counted in `enough stats`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub friction: Option<Friction>,
+    /// Actions run when the block starts and ends, like starting focus music
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hooks: Option<Hooks>,
+    /// Profile blocked for its duration right after this one ends, so breaks are breaks:
+    /// the work apps and websites blocked in turn. Its own `break-profile` is ignored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub break_profile: Option<String>,
+    /// What the session is for, shown when the shell guard catches a bypass attempt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct Hooks {
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        with = "serde_yml::with::singleton_map_recursive"
+    )]
+    pub on_start: Vec<HookAction>,
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        with = "serde_yml::with::singleton_map_recursive"
+    )]
+    pub on_end: Vec<HookAction>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum HookAction {
+    /// Opens an app, file or URL, like `open` does
+    Open(String),
+    /// Runs an AppleScript
+    Applescript(String),
+    /// Plays an Apple Music playlist, pausing Music again at unblock when used on start
+    AppleMusicPlaylist(String),
+    /// Turns the screen grayscale, which takes until the next login on some versions
+    Grayscale,
+    HideDock,
+    /// Hides the content of notifications, leaving only the app and title
+    HideNotificationPreviews,
+}
+
+impl HookAction {
+    /// Whether the action tweaks a setting, which is put back automatically at unblock
+    pub fn is_tweak(&self) -> bool {
+        matches!(
+            self,
+            Self::Grayscale | Self::HideDock | Self::HideNotificationPreviews
+        )
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct Friction {
+    /// How long the countdown runs before continuing is offered
+    #[serde(
+        default = "default_friction_delay",
+        with = "crate::time_format::duration"
+    )]
+    pub delay: Duration,
+    /// How long a website stays reachable after continuing
+    #[serde(
+        default = "default_friction_allowance",
+        with = "crate::time_format::duration"
+    )]
+    pub allowance: Duration,
+    /// Shown on the countdown page, e.g. what the session is for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+fn default_friction_delay() -> Duration {
+    Duration::from_secs(30)
+}
+
+fn default_friction_allowance() -> Duration {
+    Duration::from_secs(10 * 60)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CoolDown {
+    #[serde(with = "crate::time_format::duration")]
+    pub duration: Duration,
+    /// What stays blocked during the cool-down, the rest is unblocked on time
+    #[serde(default)]
+    pub keep: CoolDownKeep,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum CoolDownKeep {
+    #[default]
+    Websites,
+    Apps,
+}
+
+impl Display for CoolDownKeep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Websites => write!(f, "websites"),
+            Self::Apps => write!(f, "apps"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum CloseTabs {
+    Close,
+    /// Save the tabs to Safari's Reading List and a read-later file before closing them
+    ReadLater,
+}
+
+/// A blocked website, either a plain URL or `{ url, allow, subdomains }` to let it
+/// through during a daily window or choose the subdomains blocked along with it
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(from = "WebsiteEntry", into = "WebsiteEntry")]
+pub struct Website {
+    pub url: Url,
+    pub allow: Option<StartWindow>,
+    /// Subdomains blocked along with the website, e.g. `m` for `m.youtube.com`, instead
+    /// of the known ones
+    pub subdomains: Option<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum WebsiteEntry {
+    Url(Url),
+    Scheduled {
+        url: Url,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        allow: Option<StartWindow>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        subdomains: Option<Vec<String>>,
+    },
+}
+
+impl From<WebsiteEntry> for Website {
+    fn from(entry: WebsiteEntry) -> Self {
+        match entry {
+            WebsiteEntry::Url(url) => url.into(),
+            WebsiteEntry::Scheduled {
+                url,
+                allow,
+                subdomains,
+            } => Self {
+                url,
+                allow,
+                subdomains,
+            },
+        }
+    }
+}
+
+impl From<Website> for WebsiteEntry {
+    fn from(website: Website) -> Self {
+        match website {
+            Website {
+                url,
+                allow: None,
+                subdomains: None,
+            } => Self::Url(url),
+            Website {
+                url,
+                allow,
+                subdomains,
+            } => Self::Scheduled {
+                url,
+                allow,
+                subdomains,
+            },
+        }
+    }
+}
+
+impl Website {
+    /// The path, query or fragment of the URL, which blocking ignores since it works on
+    /// whole domains. `None` for a bare domain.
+    pub fn ignored_part(&self) -> Option<String> {
+        let url = &self.url;
+        let mut ignored = url.path().trim_start_matches('/').to_string();
+        if let Some(query) = url.query() {
+            ignored.push('?');
+            ignored.push_str(query);
+        }
+        if let Some(fragment) = url.fragment() {
+            ignored.push('#');
+            ignored.push_str(fragment);
+        }
+        (!ignored.is_empty()).then(|| format!("/{}", ignored))
+    }
+}
+
+impl From<Url> for Website {
+    fn from(url: Url) -> Self {
+        Self {
+            url,
+            allow: None,
+            subdomains: None,
+        }
+    }
+}
+
+impl Website {
+    /// Whether the website is let through at `time`.
+    pub fn is_allowed_at(&self, time: NaiveTime) -> bool {
+        self.allow.is_some_and(|window| window.contains(time))
+    }
+}
+
+impl Display for Website {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.allow {
+            Some(window) => write!(f, "{} (allowed {})", self.url, window),
+            None => write!(f, "{}", self.url),
+        }
+    }
+}
+
+impl Profile {
+    /// URLs of all the websites, whether or not they're currently allowed.
+    pub fn urls(&self) -> Vec<Url> {
+        self.websites
+            .iter()
+            .map(|website| website.url.clone())
+            .collect()
+    }
+}
+
+/// A daily window, which wraps around midnight when it ends before it starts
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub struct StartWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl StartWindow {
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+impl TryFrom<String> for StartWindow {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        let (start, end) = value
+            .split_once('-')
+            .with_context(|| format!("Expected a window like `07:00-20:00`, found `{}`", value))?;
+        let parse = |time: &str| {
+            NaiveTime::parse_from_str(time.trim(), "%H:%M")
+                .with_context(|| format!("Invalid time `{}` in window `{}`", time.trim(), value))
+        };
+
+        Ok(Self {
+            start: parse(start)?,
+            end: parse(end)?,
+        })
+    }
+}
+
+impl From<StartWindow> for String {
+    fn from(window: StartWindow) -> Self {
+        window.to_string()
+    }
+}
+
+impl Display for StartWindow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}-{}",
+            self.start.format("%H:%M"),
+            self.end.format("%H:%M")
+        )
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AppLimit {
+    pub app: PathBuf,
+    /// Time the app may spend in the foreground per day before it gets blocked
+    #[serde(with = "crate::time_format::duration")]
+    pub allowance: Duration,
+}
+
+/// Foreground time allowed per day or week, after which its websites and apps are
+/// blocked until the period ends.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct Quota {
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub websites: Vec<Url>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub apps: Vec<PathBuf>,
+    #[serde(with = "crate::time_format::duration")]
+    pub allowance: Duration,
+    #[serde(default)]
+    pub period: QuotaPeriod,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum QuotaPeriod {
+    #[default]
+    Day,
+    /// From Monday to Sunday
+    Week,
+}
+
+impl QuotaPeriod {
+    /// The first day of the period `date` is in.
+    pub fn start(self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Self::Day => date,
+            Self::Week => date - Days::new(date.weekday().num_days_from_monday().into()),
+        }
+    }
+
+    /// Midnight after the last day of the period `now` is in.
+    pub fn end(self, now: DateTime<Local>) -> DateTime<Local> {
+        let days = match self {
+            Self::Day => 1,
+            Self::Week => 7,
+        };
+        (self.start(now.date_naive()) + Days::new(days))
+            .and_time(NaiveTime::MIN)
+            .and_local_timezone(Local)
+            .earliest()
+            .unwrap_or(now)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SaveContext {
+    /// Apps to reopen after the block
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub apps: Vec<PathBuf>,
+    /// Also remember open browser tabs that aren't on blocked websites
+    #[serde(default = "default_true")]
+    pub tabs: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct VpnKillSwitch {
+    /// Names of VPN processes to kill when the block starts
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub processes: Vec<String>,
+}
+
+/// macOS doesn't let other apps configure Focus filters, so this goes through two
+/// shortcuts the user sets up in the Shortcuts app
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct SilenceNotifications {
+    /// Apps to silence, passed as input to the "on" shortcut
+    pub apps: Vec<String>,
+    /// Shortcut turning on a Focus that filters out the given apps
+    #[serde(default = "SilenceNotifications::default_on_shortcut")]
+    pub on_shortcut: String,
+    /// Shortcut turning that Focus off again at unblock
+    #[serde(default = "SilenceNotifications::default_off_shortcut")]
+    pub off_shortcut: String,
+}
+
+impl SilenceNotifications {
+    fn default_on_shortcut() -> String {
+        "Enough Focus On".to_string()
+    }
+
+    fn default_off_shortcut() -> String {
+        "Enough Focus Off".to_string()
+    }
 }
 
 impl EnoughConfig {
     pub fn load(path: Option<PathBuf>) -> Result<Self> {
+        let policy = Self::load_policy()?;
+
+        // a policy alone is enough to run its profiles
+        if path.is_none()
+            && Self::find_config_file().is_none()
+            && let Some(policy) = policy
+        {
+            policy.validate()?;
+            return Ok(policy);
+        }
+
+        let mut config = Self::read(Self::resolve_path(path)?)?;
+        if let Some(policy) = policy {
+            config.merge_policy(policy);
+        }
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Reads the file once into memory, so what gets hashed is exactly what got parsed.
+    fn read(path: PathBuf) -> Result<Self> {
+        let content = fs::read_to_string(&path)?;
+        let mut config = Self::deserialize(&content)?;
+
+        config.source = Some(ConfigSource {
+            path: fs::canonicalize(&path).unwrap_or(path),
+            sha256: sha256_hex(content.as_bytes()),
+        });
+        Ok(config)
+    }
+
+    /// A config from its YAML `content`, checked like a loaded one but without the policy.
+    pub fn parse(content: &str) -> Result<Self> {
+        let config = Self::deserialize(content)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn deserialize(content: &str) -> Result<Self> {
+        Ok(Config::builder()
+            .add_source(File::from_str(content, FileFormat::Yaml))
+            .build()?
+            .try_deserialize::<Self>()?)
+    }
+
+    /// Where the admin installs the system policy.
+    pub fn policy_path() -> PathBuf {
+        data_dir().join(POLICY_FILE)
+    }
+
+    /// Loads the system policy, if there is one. It's only trusted when owned by root and
+    /// not writable by anyone else, since users must not be able to weaken it.
+    fn load_policy() -> Result<Option<Self>> {
+        let path = Self::policy_path();
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let metadata = fs::metadata(&path)?;
+        if metadata.uid() != 0 || metadata.mode() & 0o022 != 0 {
+            anyhow::bail!(
+                "Policy `{}` must be owned by root and only writable by root",
+                path.display()
+            );
+        }
+
+        let mut policy = Self::read(path.clone())
+            .with_context(|| format!("Failed to load policy `{}`", path.display()))?;
+        policy.policy_profiles = policy.profiles.keys().cloned().collect();
+        Ok(Some(policy))
+    }
+
+    /// Merges the policy over this config. Profiles the policy mandates keep its settings,
+    /// websites and apps of the user's profile with the same name are added on top.
+    fn merge_policy(&mut self, policy: Self) {
+        for (profile_name, mut mandated) in policy.profiles {
+            if let Some(own) = self.profiles.remove(&profile_name) {
+                for website in own.websites {
+                    if !mandated.websites.contains(&website) {
+                        mandated.websites.push(website);
+                    }
+                }
+                for app in own.apps {
+                    if !mandated.apps.contains(&app) {
+                        mandated.apps.push(app);
+                    }
+                }
+            }
+            self.profiles.insert(profile_name, mandated);
+        }
+        self.policy_profiles = policy.policy_profiles;
+
+        if policy.default_profile.is_some() {
+            self.default_profile = policy.default_profile;
+        }
+        if policy.accountability.is_some() {
+            self.accountability = policy.accountability;
+        }
+        if policy.hosts_limit.is_some() {
+            self.hosts_limit = policy.hosts_limit;
+        }
+        if policy.limits.is_some() {
+            self.limits = policy.limits;
+        }
+    }
+
+    /// Returns the given config path, or the first config file found, making sure it exists.
+    pub fn resolve_path(path: Option<PathBuf>) -> Result<PathBuf> {
         let config_path = match path {
             Some(p) => p,
             None => Self::find_config_file().with_context(|| "No config file found")?,
@@ -37,57 +1008,267 @@ impl EnoughConfig {
             );
         }
 
-        let config = Config::builder()
-            .add_source(File::from(config_path))
-            .build()?
-            .try_deserialize::<Self>()?;
+        Ok(config_path)
+    }
 
-        config.validate()?;
-        Ok(config)
+    /// Adds a profile to the config `content`, like [`Self::append_apps`] editing the YAML
+    /// document directly.
+    pub fn add_profile(content: &str, profile_name: &str, profile: &Profile) -> Result<String> {
+        let mut document = serde_yml::from_str::<serde_yml::Value>(content)?;
+
+        let profiles = document
+            .as_mapping_mut()
+            .context("The config is not a mapping")?
+            .entry("profiles".into())
+            .or_insert_with(|| serde_yml::Value::Mapping(Default::default()))
+            .as_mapping_mut()
+            .context("`profiles` is not a mapping")?;
+        if profiles.contains_key(profile_name) {
+            anyhow::bail!("Profile `{}` already exists", profile_name);
+        }
+        profiles.insert(profile_name.into(), serde_yml::to_value(profile)?);
+
+        Ok(serde_yml::to_string(&document)?)
+    }
+
+    /// Appends apps to a profile in the config `content`. Edits the YAML document
+    /// directly, so keys enough doesn't know about are kept (comments are lost though).
+    pub fn append_apps(content: &str, profile_name: &str, apps: &[PathBuf]) -> Result<String> {
+        let mut document = serde_yml::from_str::<serde_yml::Value>(content)?;
+
+        let profile = document
+            .get_mut("profiles")
+            .and_then(|profiles| profiles.get_mut(profile_name))
+            .with_context(|| format!("Profile `{}` not found", profile_name))?;
+        let profile = profile
+            .as_mapping_mut()
+            .with_context(|| format!("Profile `{}` is not a mapping", profile_name))?;
+
+        let existing = profile
+            .entry("apps".into())
+            .or_insert_with(|| serde_yml::Value::Sequence(Vec::new()));
+        let existing = existing
+            .as_sequence_mut()
+            .with_context(|| format!("`apps` of profile `{}` is not a list", profile_name))?;
+
+        for app in apps {
+            let app = serde_yml::Value::String(app.to_string_lossy().into_owned());
+            if !existing.contains(&app) {
+                existing.push(app);
+            }
+        }
+
+        Ok(serde_yml::to_string(&document)?)
     }
 
     fn validate(&self) -> Result<()> {
+        if self
+            .accountability
+            .as_ref()
+            .is_some_and(|accountability| accountability.partner_secret.is_some())
+        {
+            anyhow::bail!(
+                "`partner-secret` under `accountability` is readable from the config, share a new one from `sudo enough code --new-secret` and set `partner: true` instead"
+            );
+        }
+
         // validating that default_profile exists in the list of profiles
-        if let Some(default_profile) = &self.default_profile {
-            if !self.profiles.contains_key(default_profile) {
+        if let Some(default_profile) = &self.default_profile
+            && !self.profiles.contains_key(default_profile)
+            && !self.templates.contains_key(default_profile)
+        {
+            anyhow::bail!(
+                "Default profile `{}` not found in profiles",
+                default_profile
+            );
+        }
+
+        for (profile_name, profile) in &self.profiles {
+            self.validate_profile(profile_name, profile)?;
+            if let Some(break_profile) = &profile.break_profile
+                && (break_profile == profile_name || !self.profiles.contains_key(break_profile))
+            {
                 anyhow::bail!(
-                    "Default profile `{}` not found in profiles",
-                    default_profile
+                    "Break profile `{}` of profile `{}` must be another profile",
+                    break_profile,
+                    profile_name
                 );
             }
         }
 
-        for (profile_name, profile) in &self.profiles {
-            for website in &profile.websites {
-                Self::validate_website(website).with_context(|| {
-                    format!(
-                        "Invalid website URL `{}` in profile `{}`",
-                        website, profile_name
-                    )
-                })?;
+        for (template_name, template) in &self.templates {
+            if self.profiles.contains_key(template_name) {
+                anyhow::bail!(
+                    "`{}` is defined both as a profile and as a template",
+                    template_name
+                );
+            }
+            if !template.is_mapping() {
+                anyhow::bail!("Template `{}` must be a profile", template_name);
             }
+            template_placeholders(template)
+                .with_context(|| format!("Invalid template `{}`", template_name))?;
+        }
+
+        for (quota_name, quota) in &self.quotas {
+            Self::validate_quota(quota_name, quota)?;
+        }
+
+        if let Some(push) = &self.push
+            && push.ntfy.is_none()
+            && push.pushover.is_none()
+        {
+            anyhow::bail!("`push` needs an `ntfy` topic or `pushover` credentials");
+        }
+
+        Ok(())
+    }
+
+    fn validate_quota(quota_name: &str, quota: &Quota) -> Result<()> {
+        if quota.websites.is_empty() && quota.apps.is_empty() {
+            anyhow::bail!("Quota `{}` has no websites or apps", quota_name);
+        }
+        if quota.allowance.is_zero() {
+            anyhow::bail!("Quota `{}` needs an allowance", quota_name);
+        }
+        for url in &quota.websites {
+            Self::validate_website(url).with_context(|| {
+                format!("Invalid website URL `{}` in quota `{}`", url, quota_name)
+            })?;
+        }
+        // the watcher checks every minute, too often to look through the app groups
+        if let Some(app) = quota
+            .apps
+            .iter()
+            .find(|app| app_groups::group_name(app).is_some())
+        {
+            anyhow::bail!(
+                "Quota `{}` can't hold the app group `{}`, list its apps instead",
+                quota_name,
+                app.display()
+            );
+        }
+
+        Ok(())
+    }
 
-            for app in &profile.apps {
-                if !app.exists() {
+    fn validate_profile(&self, profile_name: &str, profile: &Profile) -> Result<()> {
+        for website in &profile.websites {
+            Self::validate_website(&website.url).with_context(|| {
+                format!(
+                    "Invalid website URL `{}` in profile `{}`",
+                    website.url, profile_name
+                )
+            })?;
+            for subdomain in website.subdomains.iter().flatten() {
+                let is_label = |label: &str| {
+                    !label.is_empty()
+                        && label
+                            .chars()
+                            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+                };
+                if !subdomain.split('.').all(is_label) {
+                    anyhow::bail!(
+                        "Invalid subdomain `{}` of `{}` in profile `{}`, expected a name like `m` or `music`",
+                        subdomain,
+                        website.url,
+                        profile_name
+                    );
+                }
+            }
+        }
+
+        for app in &profile.apps {
+            if let Some(group) = app_groups::group_name(app) {
+                if app_groups::find(group, &self.app_groups).is_none() {
                     anyhow::bail!(
-                        "App path `{}` specified in profile `{}` does not exist",
-                        app.display(),
+                        "App group `{}` in profile `{}` isn't built in or under `app-groups`",
+                        group,
                         profile_name
                     );
                 }
+                continue;
+            }
+            // moved apps are looked up by their bundle ID at block start
+            if !app.exists() && !discover::has_remembered_bundle_id(app) {
+                anyhow::bail!(
+                    "App path `{}` specified in profile `{}` does not exist",
+                    app.display(),
+                    profile_name
+                );
             }
         }
 
         Ok(())
     }
 
+    /// Variables the template called `name` needs, `None` if there's no such template.
+    pub fn template_variables(&self, name: &str) -> Option<Vec<String>> {
+        let placeholders = template_placeholders(self.templates.get(name)?).ok()?;
+        Some(placeholders.into_iter().collect())
+    }
+
+    /// The profile called `name`, filled in from the template of that name (with `vars`)
+    /// if there's no such profile.
+    pub fn profile(&self, name: &str, vars: &HashMap<String, String>) -> Result<Profile> {
+        if let Some(profile) = self.profiles.get(name) {
+            if !vars.is_empty() {
+                anyhow::bail!("Profile `{}` isn't a template, it takes no `--set`", name);
+            }
+            return Ok(profile.clone());
+        }
+
+        let template = self
+            .templates
+            .get(name)
+            .with_context(|| format!("Profile `{}` not found", name))?;
+        let placeholders = template_placeholders(template)?;
+        if let Some(missing) = placeholders.iter().find(|var| !vars.contains_key(*var)) {
+            anyhow::bail!(
+                "Template `{}` needs a value for `{}`, pass it with `--set {}=...`",
+                name,
+                missing,
+                missing
+            );
+        }
+        if let Some(unused) = vars.keys().find(|var| !placeholders.contains(*var)) {
+            anyhow::bail!("Template `{}` has no `{{{{{}}}}}` to set", name, unused);
+        }
+
+        let mut value = template.clone();
+        fill_placeholders(&mut value, vars)?;
+        let profile = serde_yml::from_value::<Profile>(value)
+            .with_context(|| format!("Template `{}` isn't a valid profile once filled in", name))?;
+        self.validate_profile(name, &profile)?;
+
+        Ok(profile)
+    }
+
     fn validate_website(url: &Url) -> Result<()> {
         if !matches!(url.scheme(), "http" | "https") {
             anyhow::bail!("URL scheme must be http or https, found `{}`", url.scheme());
         }
 
-        if url.host().is_none() {
+        let Some(host) = url.host() else {
             anyhow::bail!("URL must have a valid host, found `{}`", url);
+        };
+        // blocking these would cut off local services, dev servers included
+        let is_loopback = match host {
+            Host::Domain(domain) => {
+                let domain = domain.trim_end_matches('.');
+                domain == "localhost" || domain.ends_with(".localhost")
+            }
+            Host::Ipv4(ip) => ip.is_loopback() || ip.is_unspecified(),
+            Host::Ipv6(ip) => ip.is_loopback() || ip.is_unspecified(),
+        };
+        if is_loopback {
+            anyhow::bail!("`{}` is this machine, it can't be blocked", host);
+        }
+        if !matches!(host, Host::Domain(_)) {
+            anyhow::bail!(
+                "Websites are blocked by domain, an IP address like `{}` can't be",
+                host
+            );
         }
 
         Ok(())
@@ -104,21 +1285,15 @@ impl EnoughConfig {
             PathBuf::from(&home).join(".config/enough.yml"),
         ];
 
-        for path in possible_paths {
-            if path.exists() {
-                return Some(path);
-            }
-        }
-
-        None
+        possible_paths.into_iter().find(|path| path.exists())
     }
 
-    fn default_config_path() -> PathBuf {
+    pub fn default_config_path() -> PathBuf {
         let home = env::home_dir()
             .with_context(|| "$HOME environment variable not set")
             .unwrap();
 
-        PathBuf::from(home).join(".config/enough/enough.yaml")
+        home.join(".config/enough/enough.yaml")
     }
 
     pub fn generate_sample(output_path: Option<PathBuf>) -> Result<String> {
@@ -139,8 +1314,8 @@ impl EnoughConfig {
                     Profile {
                         duration: Duration::from_secs(125),
                         websites: vec![
-                            Url::parse("https://www.youtube.com")?,
-                            Url::parse("https://reddit.com")?,
+                            Url::parse("https://www.youtube.com")?.into(),
+                            Url::parse("https://reddit.com")?.into(),
                         ],
                         apps: vec![
                             PathBuf::from("/Applications/CrossOver.app"),
@@ -149,6 +1324,7 @@ impl EnoughConfig {
                                 "/nix/store/d2ap3myk8zyzgfi9c2p87in3mvljvbw4-spotify-1.2.64.408/Applications/Spotify.app",
                             ),
                         ],
+                        ..Default::default()
                     },
                 ),
                 (
@@ -156,14 +1332,40 @@ impl EnoughConfig {
                     Profile {
                         duration: Duration::from_secs(30),
                         websites: vec![
-                            Url::parse("https://www.youtube.com")?,
-                            Url::parse("https://www.reddit.com")?,
-                            Url::parse("https://www.github.com")?,
+                            Url::parse("https://www.youtube.com")?.into(),
+                            Url::parse("https://www.reddit.com")?.into(),
+                            Url::parse("https://www.github.com")?.into(),
                         ],
                         apps: vec![],
+                        ..Default::default()
                     },
                 ),
             ]),
+            accountability: None,
+            push: None,
+            hosts_limit: None,
+            spill_to_pf: true,
+            websites_backend: WebsitesBackend::default(),
+            block_doh: false,
+            limits: None,
+            achievement_notifications: false,
+            app_groups: HashMap::new(),
+            events: false,
+            remaining_file: None,
+            email: None,
+            time_format: TimeFormat::default(),
+            status_time: StatusTime::default(),
+            relay: None,
+            quotas: HashMap::new(),
+            holidays: Vec::new(),
+            holiday_calendar: None,
+            unblock_early_delay: default_unblock_early_delay(),
+            abort_journal: None,
+            focus_score: FocusScoreWeights::default(),
+            history: HistoryPrivacy::default(),
+            templates: HashMap::new(),
+            policy_profiles: HashSet::new(),
+            source: None,
         };
 
         let yaml_content = serde_yml::to_string(&sample_config)?;
@@ -180,6 +1382,35 @@ impl EnoughConfig {
 
 impl Display for EnoughConfig {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let marker = |name: &String| {
+            let is_default = self.default_profile.as_ref() == Some(name);
+            match (is_default, self.policy_profiles.contains(name)) {
+                (true, true) => " (default, policy)",
+                (true, false) => " (default)",
+                (false, true) => " (policy)",
+                (false, false) => "",
+            }
+        };
+
+        // a sentence per profile, tables don't read out well
+        if output::is_plain() {
+            for (i, (name, profile)) in self.profiles.iter().enumerate() {
+                if i > 0 {
+                    writeln!(f)?;
+                }
+                write!(
+                    f,
+                    "{}: {}, {} websites, {} apps{}",
+                    name,
+                    format_duration(profile.duration),
+                    profile.websites.len(),
+                    profile.apps.len(),
+                    marker(name)
+                )?;
+            }
+            return std::fmt::Result::Ok(());
+        }
+
         let w_name = 20;
         let w_dur = 12;
         let w_web = 8;
@@ -187,12 +1418,11 @@ impl Display for EnoughConfig {
 
         writeln!(
             f,
-            "  {:<w_name$} {:<w_dur$} {:<w_web$} {:<w_app$} {}",
+            "  {:<w_name$} {:<w_dur$} {:<w_web$} {:<w_app$}",
             "Name",
             "Duration",
             "Websites",
             "Apps",
-            "",
             w_name = w_name,
             w_dur = w_dur,
             w_web = w_web,
@@ -200,8 +1430,7 @@ impl Display for EnoughConfig {
         )?;
         write!(
             f,
-            "  {:-<w_name$} {:-<w_dur$} {:-<w_web$} {:-<w_app$} {}",
-            "",
+            "  {:-<w_name$} {:-<w_dur$} {:-<w_web$} {:-<w_app$}",
             "",
             "",
             "",
@@ -212,12 +1441,6 @@ impl Display for EnoughConfig {
             w_app = w_app,
         )?;
         for (name, profile) in &self.profiles {
-            let is_default = if let Some(default_profile) = &self.default_profile {
-                name == default_profile
-            } else {
-                false
-            };
-            let marker = if is_default { " (default)" } else { "" };
             write!(
                 f,
                 "\n• {:<w_name$} {:<w_dur$} {:<w_web$} {:<w_app$} {}",
@@ -229,7 +1452,7 @@ impl Display for EnoughConfig {
                 ),
                 format!("{:<w_web$}", profile.websites.len(), w_web = w_web),
                 format!("{:<w_app$}", profile.apps.len(), w_app = w_app),
-                marker,
+                marker(name),
                 w_name = w_name,
                 w_dur = w_dur,
                 w_web = w_web,
@@ -257,4 +1480,102 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn parses_hook_actions() {
+        let hooks = serde_yml::from_str::<Hooks>(
+            "
+on-start:
+  - apple-music-playlist: Deep Focus
+  - open: /Applications/Noizio.app
+  - hide-dock
+on-end:
+  - applescript: tell application \"Noizio\" to quit
+",
+        )
+        .unwrap();
+
+        assert_eq!(
+            hooks.on_start,
+            [
+                HookAction::AppleMusicPlaylist("Deep Focus".to_string()),
+                HookAction::Open("/Applications/Noizio.app".to_string()),
+                HookAction::HideDock,
+            ]
+        );
+        assert_eq!(
+            hooks.on_end,
+            [HookAction::Applescript(
+                "tell application \"Noizio\" to quit".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn start_window_wraps_around_midnight() {
+        let window = StartWindow::try_from("22:00-06:00".to_string()).unwrap();
+        let at = |time| NaiveTime::parse_from_str(time, "%H:%M").unwrap();
+
+        assert!(window.contains(at("23:30")));
+        assert!(window.contains(at("05:59")));
+        assert!(!window.contains(at("06:00")));
+        assert!(!window.contains(at("12:00")));
+    }
+
+    #[test]
+    fn quota_periods_end_at_midnight() {
+        use chrono::TimeZone as _;
+
+        // a Wednesday
+        let now = Local.with_ymd_and_hms(2025, 3, 5, 15, 30, 0).unwrap();
+        let midnight = |day| Local.with_ymd_and_hms(2025, 3, day, 0, 0, 0).unwrap();
+
+        assert_eq!(QuotaPeriod::Day.start(now.date_naive()), now.date_naive());
+        assert_eq!(QuotaPeriod::Day.end(now), midnight(6));
+        assert_eq!(
+            QuotaPeriod::Week.start(now.date_naive()),
+            midnight(3).date_naive()
+        );
+        assert_eq!(QuotaPeriod::Week.end(now), midnight(10));
+    }
+
+    #[test]
+    fn fills_in_templates() {
+        let config = serde_yml::from_str::<EnoughConfig>(
+            "
+profiles: {}
+templates:
+  client-work:
+    duration: '{{hours}}h'
+    websites:
+      - https://{{ site  }}/r/{{site}}
+",
+        )
+        .unwrap();
+
+        let vars = HashMap::from([
+            ("hours".to_string(), "3".to_string()),
+            ("site".to_string(), "reddit.com".to_string()),
+        ]);
+        let profile = config.profile("client-work", &vars).unwrap();
+        assert_eq!(profile.duration, Duration::from_secs(3 * 60 * 60));
+        assert_eq!(
+            profile.websites[0].url.as_str(),
+            "https://reddit.com/r/reddit.com"
+        );
+
+        assert!(config.profile("client-work", &HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn history_names_are_hashed_the_same_or_left_out() {
+        let history = serde_yml::from_str::<HistoryPrivacy>("keep: 90d\nnames: hash").unwrap();
+        assert_eq!(history.keep, Some(Duration::from_secs(90 * 86400)));
+
+        let steam = Path::new("/Applications/Steam.app");
+        let hashed = history.names.path(steam).unwrap();
+        assert!(hashed.to_string_lossy().starts_with("sha256:"));
+        assert_eq!(history.names.path(steam), Some(hashed));
+        assert_eq!(StoredNames::Omit.path(steam), None);
+    }
 }
@@ -0,0 +1,104 @@
+use anyhow::{Context, Ok, Result};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::PathBuf};
+use url::Url;
+
+use crate::{block::data_dir, config::SaveContext, tabs, user::as_invoking_user};
+
+/// Work left open when a block started, reopened with `enough restore-context`.
+/// Kept in the data dir, since the state dir is removed at unblock.
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct WorkContext {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub apps: Vec<PathBuf>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tabs: Vec<SavedTab>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SavedTab {
+    pub browser: String,
+    pub url: Url,
+}
+
+impl WorkContext {
+    /// Records the configured apps and the open tabs that aren't on blocked `websites`.
+    pub fn snapshot(save: &SaveContext, websites: &[Url]) -> Result<Self> {
+        let tabs = if save.tabs {
+            tabs::all_open_tabs()?
+                .into_iter()
+                .filter(|tab| !tabs::on_blocked_website(&tab.url, websites))
+                .map(|tab| SavedTab {
+                    browser: tab.browser.to_string(),
+                    url: tab.url,
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self {
+            apps: save.apps.clone(),
+            tabs,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.apps.is_empty() && self.tabs.is_empty()
+    }
+
+    pub fn save(&self, uid: u32) -> Result<()> {
+        fs::create_dir_all(data_dir())?;
+        fs::write(Self::path(uid), serde_yml::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn load(uid: u32) -> Result<Option<Self>> {
+        let path = Self::path(uid);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(&path)?;
+        let context = serde_yml::from_str(&content)
+            .with_context(|| format!("Failed to parse `{}`", path.display()))?;
+        Ok(Some(context))
+    }
+
+    /// Reopens the apps and tabs, then forgets the snapshot.
+    pub fn restore(&self, uid: u32) -> Result<()> {
+        for app in &self.apps {
+            open(|command| {
+                command.arg(app);
+            })
+            .with_context(|| format!("Failed to open `{}`", app.display()))?;
+        }
+
+        for tab in &self.tabs {
+            open(|command| {
+                command.args(["-a", &tab.browser, tab.url.as_str()]);
+            })
+            .with_context(|| format!("Failed to open {} in {}", tab.url, tab.browser))?;
+        }
+
+        fs::remove_file(Self::path(uid))?;
+        Ok(())
+    }
+
+    fn path(uid: u32) -> PathBuf {
+        data_dir().join(format!("context.{}.yaml", uid))
+    }
+}
+
+fn open(args: impl FnOnce(&mut std::process::Command)) -> Result<()> {
+    let mut command = as_invoking_user("open");
+    args(&mut command);
+
+    let output = command.output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("{}", stderr.trim());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,304 @@
+use anyhow::{Context, Ok, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    fs,
+    io::Write as _,
+    os::unix::fs::PermissionsExt as _,
+    path::PathBuf,
+    process::{Command, Stdio},
+    time::Duration,
+};
+use url::Url;
+use uuid::Uuid;
+
+use crate::{block::data_dir, config::EnoughConfig, user::invoking_uid};
+
+/// Secret only root can read, so contracts can't be re-signed after editing them
+const SIGNING_KEY_FILE: &str = "contract.key";
+const CONTRACTS_FILE: &str = "contracts";
+
+/// What the user commits to, shared with a witness and signed so edits are detectable.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct Terms {
+    pub id: String,
+    pub user: Option<String>,
+    pub profile_name: String,
+    pub websites: Vec<Url>,
+    pub apps: Vec<PathBuf>,
+    /// Shortest duration blocks of the profile may run for
+    #[serde(with = "humantime_serde")]
+    pub duration: Duration,
+    /// Emergency unblocks are refused for the profile while the contract holds
+    pub strict: bool,
+    pub witness: String,
+    pub created: DateTime<Local>,
+    pub expires: DateTime<Local>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Contract {
+    #[serde(flatten)]
+    pub terms: Terms,
+    /// Keyed sha256 of the terms
+    pub signature: String,
+}
+
+impl Contract {
+    /// Commits to the profile as it is in `config` right now, for `days` days.
+    pub fn create(
+        config: &EnoughConfig,
+        profile_name: &str,
+        days: u32,
+        witness: &str,
+        strict: bool,
+    ) -> Result<Self> {
+        let profile = config
+            .profiles
+            .get(profile_name)
+            .with_context(|| format!("Profile `{}` not found", profile_name))?;
+
+        let created = Local::now();
+        let terms = Terms {
+            id: Uuid::new_v4().to_string(),
+            user: std::env::var("SUDO_USER").ok(),
+            profile_name: profile_name.to_string(),
+            websites: profile.urls(),
+            apps: profile.apps.clone(),
+            duration: profile.duration,
+            strict,
+            witness: witness.to_string(),
+            created,
+            expires: created + chrono::Duration::days(days.into()),
+        };
+        let signature = sign(&terms)?;
+
+        let contract = Self { terms, signature };
+        let mut contracts = active()?;
+        contracts.push(contract.clone());
+        save(&contracts)?;
+
+        Ok(contract)
+    }
+
+    /// Plain-text commitment document sent to the witness.
+    pub fn document(&self) -> String {
+        let terms = &self.terms;
+        let mut document = format!(
+            "{} commits to the enough profile `{}` until {}.\n\n",
+            terms.user.as_deref().unwrap_or("I"),
+            terms.profile_name,
+            terms.expires.format("%Y-%m-%d %H:%M")
+        );
+        document.push_str(&format!(
+            "Blocks last at least {}\n",
+            humantime_serde::re::humantime::format_duration(terms.duration)
+        ));
+        for website in &terms.websites {
+            document.push_str(&format!("Blocked website: {}\n", website));
+        }
+        for app in &terms.apps {
+            document.push_str(&format!("Blocked app: {}\n", app.display()));
+        }
+        document.push_str(if terms.strict {
+            "Emergency unblocks are not allowed\n"
+        } else {
+            "Emergency unblocks are allowed, and audited\n"
+        });
+        document.push_str(&format!(
+            "\nContract {} signed {}\nSignature: {}\n",
+            terms.id,
+            terms.created.format("%Y-%m-%d %H:%M"),
+            self.signature
+        ));
+        document
+    }
+
+    /// Emails the document to the witness through the system `mail` command.
+    pub fn send_to_witness(&self) -> Result<()> {
+        let mut child = Command::new("mail")
+            .args(["-s", "enough block contract", &self.terms.witness])
+            .stdin(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| "Failed to run mail")?;
+        child
+            .stdin
+            .take()
+            .with_context(|| "Failed to open mail's stdin")?
+            .write_all(self.document().as_bytes())?;
+        let output = child.wait_with_output()?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!(
+                "Failed to email `{}`: {}",
+                self.terms.witness,
+                stderr.trim()
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Checks that `config` doesn't weaken the contract's profile.
+    pub fn check(&self, config: &EnoughConfig) -> Result<()> {
+        let terms = &self.terms;
+        let why = match config.profiles.get(&terms.profile_name) {
+            None => "removes the profile".to_string(),
+            Some(profile) if profile.duration < terms.duration => {
+                "shortens its duration".to_string()
+            }
+            Some(profile) => {
+                let urls = profile.urls();
+                let website = terms
+                    .websites
+                    .iter()
+                    .find(|website| !urls.contains(website));
+                let app = terms.apps.iter().find(|app| !profile.apps.contains(app));
+                match (website, app) {
+                    (Some(website), _) => format!("no longer blocks {}", website),
+                    (_, Some(app)) => format!("no longer blocks {}", app.display()),
+                    (None, None) => return Ok(()),
+                }
+            }
+        };
+
+        anyhow::bail!(
+            "The config {} `{}`, which contract {} holds you to until {}",
+            why,
+            terms.profile_name,
+            terms.id,
+            terms.expires.format("%Y-%m-%d %H:%M")
+        )
+    }
+}
+
+/// The invoking user's contracts that haven't expired. Contracts whose signature doesn't
+/// match are refused, rather than dropped, so tampering doesn't end them.
+pub fn active() -> Result<Vec<Contract>> {
+    let path = contracts_path();
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(&path)?;
+    let contracts = serde_json::from_str::<Vec<Contract>>(&content)
+        .with_context(|| format!("Corrupted contracts file `{}`", path.display()))?;
+
+    let now = Local::now();
+    let mut active = Vec::new();
+    for contract in contracts {
+        if contract.terms.expires <= now {
+            continue;
+        }
+        if sign(&contract.terms)? != contract.signature {
+            anyhow::bail!("Contract {} has been tampered with", contract.terms.id);
+        }
+        active.push(contract);
+    }
+    Ok(active)
+}
+
+fn contracts_path() -> PathBuf {
+    data_dir().join(format!("{}.{}.json", CONTRACTS_FILE, invoking_uid()))
+}
+
+fn save(contracts: &[Contract]) -> Result<()> {
+    fs::create_dir_all(data_dir())?;
+    fs::write(contracts_path(), serde_json::to_string_pretty(contracts)?)?;
+    Ok(())
+}
+
+fn sign(terms: &Terms) -> Result<String> {
+    let digest = Sha256::new()
+        .chain_update(signing_key()?.as_bytes())
+        .chain_update(serde_json::to_string(terms)?.as_bytes())
+        .finalize();
+
+    Ok(digest.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+fn signing_key() -> Result<String> {
+    let path = data_dir().join(SIGNING_KEY_FILE);
+    if path.exists() {
+        return Ok(fs::read_to_string(&path)?);
+    }
+
+    let key = Uuid::new_v4().to_string();
+    fs::create_dir_all(data_dir())?;
+    fs::write(&path, &key)?;
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sandbox;
+
+    fn config(duration: &str, websites: &str) -> EnoughConfig {
+        serde_yml::from_str::<EnoughConfig>(&format!(
+            "
+profiles:
+  focus:
+    duration: {}
+    websites: {}
+",
+            duration, websites
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn contracts_hold_the_profile_and_refuse_tampering() {
+        sandbox::for_tests();
+        let signed = config("2h", "[https://reddit.com, https://youtube.com]");
+        let contract = Contract::create(&signed, "focus", 7, "friend@example.com", true).unwrap();
+        assert!(
+            contract
+                .document()
+                .contains("Emergency unblocks are not allowed")
+        );
+        assert!(
+            active()
+                .unwrap()
+                .iter()
+                .any(|active| active.terms.id == contract.terms.id)
+        );
+
+        assert!(contract.check(&signed).is_ok());
+        assert!(
+            contract
+                .check(&config(
+                    "3h",
+                    "[https://reddit.com, https://youtube.com, https://x.com]"
+                ))
+                .is_ok()
+        );
+        assert!(
+            contract
+                .check(&config("1h", "[https://reddit.com, https://youtube.com]"))
+                .is_err()
+        );
+        assert!(
+            contract
+                .check(&config("2h", "[https://reddit.com]"))
+                .is_err()
+        );
+        assert!(
+            contract
+                .check(&serde_yml::from_str::<EnoughConfig>("profiles: {}").unwrap())
+                .is_err()
+        );
+
+        let tampered = fs::read_to_string(contracts_path())
+            .unwrap()
+            .replace("\"strict\": true", "\"strict\": false");
+        fs::write(contracts_path(), tampered).unwrap();
+        assert!(active().is_err());
+        fs::remove_file(contracts_path()).unwrap();
+    }
+}
@@ -0,0 +1,709 @@
+use anyhow::{Context as _, Ok, Result};
+use chrono::{DateTime, Local, NaiveTime};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use uuid::Uuid;
+
+use crate::{
+    daemon::{UNBLOCK_ARGS, UNBLOCK_TOKEN_FILE, UnblockingDaemon},
+    sandbox,
+    user::invoking_uid,
+};
+
+const DAEMON_ID_FILE: &str = "daemon_id";
+const STATE_BACKUP_FILE: &str = "current_block.yaml";
+const NETWORK_WATCHER_ID_FILE: &str = "network_watcher_id";
+const WINDOWS_JOB_ID_FILE: &str = "windows_job_id";
+const USAGE_JOB_ID_FILE: &str = "usage_job_id";
+const REBLOCK_JOB_ID_FILE: &str = "reblock_job_id";
+const FRICTION_JOB_ID_FILE: &str = "friction_job_id";
+const REMAINING_JOB_ID_FILE: &str = "remaining_job_id";
+const INSTALLS_WATCHER_ID_FILE: &str = "installs_watcher_id";
+const APPS_GUARD_ID_FILE: &str = "apps_guard_id";
+const REINSTALLS_WATCHER_ID_FILE: &str = "reinstalls_watcher_id";
+const CONFIG_GUARD_ID_FILE: &str = "config_guard_id";
+const QUOTA_WATCHER_ID_FILE: &str = "quota_watcher_id";
+const SCHEDULER_ID_FILE: &str = "scheduler_id";
+const RELAUNCH_WATCHER_ID_FILE: &str = "relaunch_watcher_id";
+const JOBS_GUARD_ID_FILE: &str = "jobs_guard_id";
+const BOOT_CHECK_ID_FILE: &str = "boot_check_id";
+const HOSTS_GUARD_ID_FILE: &str = "hosts_guard_id";
+const DNS_PROXY_JOB_ID_FILE: &str = "dns_proxy_job_id";
+/// Jobs of a block other than the unblock itself, the guard first so removing the rest
+/// doesn't set it off
+const JOB_ID_FILES: [&str; 15] = [
+    JOBS_GUARD_ID_FILE,
+    NETWORK_WATCHER_ID_FILE,
+    WINDOWS_JOB_ID_FILE,
+    USAGE_JOB_ID_FILE,
+    REBLOCK_JOB_ID_FILE,
+    FRICTION_JOB_ID_FILE,
+    REMAINING_JOB_ID_FILE,
+    INSTALLS_WATCHER_ID_FILE,
+    APPS_GUARD_ID_FILE,
+    REINSTALLS_WATCHER_ID_FILE,
+    CONFIG_GUARD_ID_FILE,
+    RELAUNCH_WATCHER_ID_FILE,
+    BOOT_CHECK_ID_FILE,
+    HOSTS_GUARD_ID_FILE,
+    DNS_PROXY_JOB_ID_FILE,
+];
+/// System units, so the jobs run as root without a user session or sudo
+const UNITS_DIR: &str = "/etc/systemd/system";
+/// Rewritten by NetworkManager or systemd-resolved whenever the DNS servers change
+const RESOLV_CONF: &str = "/etc/resolv.conf";
+/// Replaced when the time zone changes, which moves the local-time jobs
+const TIME_ZONE_FILE: &str = "/etc/localtime";
+/// What starts a job's service: a timer, a path watch, or nothing for ones kept running
+const TRIGGER_EXTENSIONS: [&str; 2] = ["timer", "path"];
+
+pub struct SystemdDaemon;
+
+/// What starts a job's service.
+enum Trigger {
+    /// `[Timer]` settings, e.g. `OnCalendar=2025-03-04 17:42:00`
+    Timer(Vec<String>),
+    /// Paths whose changes start it
+    Paths(Vec<PathBuf>),
+    /// The service is started right away and restarted whenever it exits
+    KeepAlive,
+}
+
+impl UnblockingDaemon for SystemdDaemon {
+    const HANDS_OVER_TOKEN: bool = true;
+
+    fn schedule(state_dir: &Path, unblock_time: DateTime<Local>) -> Result<()> {
+        let token_file = state_dir.join(UNBLOCK_TOKEN_FILE);
+        let daemon_id = Self::install_units(
+            state_dir,
+            "unblock",
+            &UNBLOCK_ARGS,
+            token_file.exists().then_some(token_file.as_path()),
+            &Trigger::Timer(vec![
+                Self::on_calendar(unblock_time),
+                "Persistent=true".into(),
+            ]),
+        )?;
+
+        eprintln!("Scheduled unblock for {}", unblock_time.format("%H:%M:%S"));
+
+        // saving daemon info for cleanup
+        fs::create_dir_all(state_dir)?;
+        fs::write(state_dir.join(DAEMON_ID_FILE), &daemon_id)?;
+
+        Ok(())
+    }
+
+    fn watch_network(state_dir: &Path) -> Result<()> {
+        Self::install_job(
+            state_dir,
+            NETWORK_WATCHER_ID_FILE,
+            "network",
+            &["___zzzreapply"],
+            &Trigger::Paths(vec![RESOLV_CONF.into(), TIME_ZONE_FILE.into()]),
+        )
+    }
+
+    fn schedule_windows(state_dir: &Path, boundaries: &[NaiveTime]) -> Result<()> {
+        let timers = boundaries
+            .iter()
+            .map(|time| format!("OnCalendar=*-*-* {}", time.format("%H:%M:00")))
+            .collect();
+
+        Self::install_job(
+            state_dir,
+            WINDOWS_JOB_ID_FILE,
+            "windows",
+            &["___zzzwindows"],
+            &Trigger::Timer(timers),
+        )
+    }
+
+    fn track_usage(state_dir: &Path, interval: Duration) -> Result<()> {
+        Self::install_job(
+            state_dir,
+            USAGE_JOB_ID_FILE,
+            "usage",
+            &["___zzztrack"],
+            &Self::every(interval),
+        )
+    }
+
+    fn schedule_reblock(state_dir: &Path, at: Option<DateTime<Local>>) -> Result<()> {
+        Self::remove_job(state_dir, REBLOCK_JOB_ID_FILE)?;
+        let Some(at) = at else {
+            return Ok(());
+        };
+
+        Self::install_job(
+            state_dir,
+            REBLOCK_JOB_ID_FILE,
+            "reblock",
+            &["___zzzreblock"],
+            &Trigger::Timer(vec![Self::on_calendar(at), "Persistent=true".into()]),
+        )
+    }
+
+    fn watch_installs(state_dir: &Path, dirs: &[PathBuf]) -> Result<()> {
+        Self::install_job(
+            state_dir,
+            INSTALLS_WATCHER_ID_FILE,
+            "installs",
+            &["___zzzinstalls"],
+            &Trigger::Paths(dirs.to_vec()),
+        )
+    }
+
+    fn guard_apps(state_dir: &Path, dirs: &[PathBuf]) -> Result<()> {
+        Self::install_job(
+            state_dir,
+            APPS_GUARD_ID_FILE,
+            "guard",
+            &["___zzzguard"],
+            &Trigger::Paths(dirs.to_vec()),
+        )
+    }
+
+    fn watch_reinstalls(state_dir: &Path, dirs: &[PathBuf]) -> Result<()> {
+        Self::install_job(
+            state_dir,
+            REINSTALLS_WATCHER_ID_FILE,
+            "reinstalls",
+            &["___zzzreinstalls"],
+            &Trigger::Paths(dirs.to_vec()),
+        )
+    }
+
+    fn guard_config(state_dir: &Path, config: &Path) -> Result<()> {
+        Self::install_job(
+            state_dir,
+            CONFIG_GUARD_ID_FILE,
+            "config",
+            &["___zzzconfig"],
+            &Trigger::Paths(vec![config.to_path_buf()]),
+        )
+    }
+
+    fn guard_hosts(state_dir: &Path, hosts: &Path) -> Result<()> {
+        Self::install_job(
+            state_dir,
+            HOSTS_GUARD_ID_FILE,
+            "hosts",
+            &["___zzzhosts"],
+            &Trigger::Paths(vec![hosts.to_path_buf()]),
+        )
+    }
+
+    fn check_at_boot(state_dir: &Path) -> Result<()> {
+        Self::install_job(
+            state_dir,
+            BOOT_CHECK_ID_FILE,
+            "boot",
+            &["___zzzboot"],
+            &Trigger::Timer(vec!["OnBootSec=1min".into()]),
+        )
+    }
+
+    fn guard_jobs(state_dir: &Path) -> Result<()> {
+        Self::install_job(
+            state_dir,
+            JOBS_GUARD_ID_FILE,
+            "jobs",
+            &["___zzzjobs"],
+            &Trigger::Paths(vec![sandbox::path(UNITS_DIR)]),
+        )
+    }
+
+    fn missing_jobs(state_dir: &Path) -> Vec<String> {
+        let units_dir = sandbox::path(UNITS_DIR);
+        JOB_ID_FILES
+            .iter()
+            .chain([&DAEMON_ID_FILE])
+            .filter_map(|id_file| fs::read_to_string(state_dir.join(id_file)).ok())
+            .map(|job_id| job_id.trim().to_string())
+            .filter(|job_id| !units_dir.join(format!("{}.service", job_id)).exists())
+            .collect()
+    }
+
+    fn watch_quotas(dir: &Path, config: &Path, interval: Duration) -> Result<()> {
+        Self::unwatch_quotas(dir)?;
+        Self::install_job(
+            dir,
+            QUOTA_WATCHER_ID_FILE,
+            "quotas",
+            &["___zzzquotas", "--config", &config.to_string_lossy()],
+            &Self::every(interval),
+        )
+    }
+
+    fn unwatch_quotas(dir: &Path) -> Result<()> {
+        Self::remove_job(dir, QUOTA_WATCHER_ID_FILE)
+    }
+
+    fn quota_watcher(dir: &Path) -> Option<String> {
+        let job_id = fs::read_to_string(dir.join(QUOTA_WATCHER_ID_FILE)).ok()?;
+        Some(job_id.trim().to_string())
+    }
+
+    fn watch_schedules(dir: &Path, config: &Path, interval: Duration) -> Result<()> {
+        Self::unwatch_schedules(dir)?;
+        Self::install_job(
+            dir,
+            SCHEDULER_ID_FILE,
+            "scheduler",
+            &["___zzzscheduler", "--config", &config.to_string_lossy()],
+            &Self::every(interval),
+        )
+    }
+
+    fn unwatch_schedules(dir: &Path) -> Result<()> {
+        Self::remove_job(dir, SCHEDULER_ID_FILE)
+    }
+
+    fn scheduler(dir: &Path) -> Option<String> {
+        let job_id = fs::read_to_string(dir.join(SCHEDULER_ID_FILE)).ok()?;
+        Some(job_id.trim().to_string())
+    }
+
+    fn watch_relaunches(state_dir: &Path) -> Result<()> {
+        Self::install_job(
+            state_dir,
+            RELAUNCH_WATCHER_ID_FILE,
+            "relaunch",
+            &["___zzzrelaunch"],
+            &Trigger::KeepAlive,
+        )
+    }
+
+    fn update_remaining(state_dir: &Path, interval: Duration) -> Result<()> {
+        Self::install_job(
+            state_dir,
+            REMAINING_JOB_ID_FILE,
+            "remaining",
+            &["___zzzremaining"],
+            &Self::every(interval),
+        )
+    }
+
+    fn serve_friction(state_dir: &Path) -> Result<()> {
+        Self::install_job(
+            state_dir,
+            FRICTION_JOB_ID_FILE,
+            "friction",
+            &["___zzzfriction"],
+            &Trigger::KeepAlive,
+        )
+    }
+
+    fn serve_dns(state_dir: &Path) -> Result<()> {
+        Self::install_job(
+            state_dir,
+            DNS_PROXY_JOB_ID_FILE,
+            "dns",
+            &["___zzzdns"],
+            &Trigger::KeepAlive,
+        )
+    }
+
+    fn reschedule(
+        state_dir: &Path,
+        unblock_time: DateTime<Local>,
+        then: impl FnOnce() -> Result<()>,
+    ) -> Result<()> {
+        let daemon_id_path = state_dir.join(DAEMON_ID_FILE);
+        let old_id = match daemon_id_path.exists() {
+            true => Some(fs::read_to_string(&daemon_id_path)?.trim().to_string()),
+            false => None,
+        };
+        Self::schedule(state_dir, unblock_time)?;
+        if let Err(err) = then() {
+            // back to the old job, the state it goes with wasn't saved
+            let new_id = fs::read_to_string(&daemon_id_path)?;
+            Self::remove_units(new_id.trim())?;
+            if let Some(old_id) = &old_id {
+                fs::write(&daemon_id_path, old_id)?;
+            }
+            return Err(err);
+        }
+
+        match old_id {
+            Some(old_id) => Self::remove_units(&old_id),
+            None => Ok(()),
+        }
+    }
+
+    fn remove(state_dir: &Path) -> Result<()> {
+        for id_file in JOB_ID_FILES {
+            Self::remove_job(state_dir, id_file)?;
+        }
+        Self::remove_unblock_daemon(state_dir)
+    }
+
+    fn installed_jobs() -> Result<Vec<String>> {
+        let Some(entries) = fs::read_dir(sandbox::path(UNITS_DIR)).ok() else {
+            return Ok(Vec::new());
+        };
+
+        // every job is an `enough-<kind>-<uid>-<uuid>.service`, with its trigger next to it
+        let uid = invoking_uid().to_string();
+        let jobs = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let name = entry
+                    .file_name()
+                    .to_str()?
+                    .strip_suffix(".service")?
+                    .to_string();
+                let mut parts = name.splitn(4, '-');
+                let owned = parts.next() == Some("enough") && parts.nth(1) == Some(uid.as_str());
+                owned.then_some(name)
+            })
+            .collect();
+
+        Ok(jobs)
+    }
+
+    fn remove_all(keep: &[String]) -> Result<()> {
+        for job_id in Self::installed_jobs()?
+            .into_iter()
+            .filter(|job_id| !keep.contains(job_id))
+        {
+            Self::remove_units(&job_id)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl SystemdDaemon {
+    fn remove_unblock_daemon(state_dir: &Path) -> Result<()> {
+        let daemon_id_path = state_dir.join(DAEMON_ID_FILE);
+
+        if daemon_id_path.exists() {
+            let daemon_id = fs::read_to_string(&daemon_id_path)?;
+
+            fs::remove_file(&daemon_id_path)?;
+            fs::remove_file(state_dir.join(STATE_BACKUP_FILE))?;
+
+            eprintln!("Removing daemon with ID: {}", daemon_id.trim());
+            Self::remove_units(daemon_id.trim())?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes and starts a job's units, keeping its ID in `id_file` for removal.
+    fn install_job(
+        state_dir: &Path,
+        id_file: &str,
+        kind: &str,
+        args: &[&str],
+        trigger: &Trigger,
+    ) -> Result<()> {
+        let job_id = Self::install_units(state_dir, kind, args, None, trigger)?;
+
+        fs::create_dir_all(state_dir)?;
+        fs::write(state_dir.join(id_file), job_id)?;
+
+        Ok(())
+    }
+
+    /// Stops and deletes the job whose ID is kept in `id_file`, if there is one.
+    fn remove_job(state_dir: &Path, id_file: &str) -> Result<()> {
+        let job_id_path = state_dir.join(id_file);
+        if !job_id_path.exists() {
+            return Ok(());
+        }
+
+        let job_id = fs::read_to_string(&job_id_path)?;
+        Self::remove_units(job_id.trim())?;
+        fs::remove_file(&job_id_path)?;
+
+        Ok(())
+    }
+
+    /// Writes the service running `args` and the unit triggering it, then enables the
+    /// trigger so it survives a reboot. Returns the job's ID,
+    /// `enough-<kind>-<uid>-<uuid>`.
+    fn install_units(
+        state_dir: &Path,
+        kind: &str,
+        args: &[&str],
+        stdin: Option<&Path>,
+        trigger: &Trigger,
+    ) -> Result<String> {
+        let job_id = format!("enough-{}-{}-{}", kind, invoking_uid(), Uuid::new_v4());
+        let current_exe = env::current_exe().context("Failed to get current executable path")?;
+        let units_dir = sandbox::path(UNITS_DIR);
+        fs::create_dir_all(&units_dir)?;
+
+        let (unit, content) = match trigger {
+            Trigger::Timer(settings) => ("timer", Some(Self::generate_timer(settings))),
+            Trigger::Paths(paths) => ("path", Some(Self::generate_path(paths))),
+            Trigger::KeepAlive => ("service", None),
+        };
+        let service = Self::generate_service(&current_exe, state_dir, args, kind, stdin, trigger);
+        let mut units = vec![(format!("{}.service", job_id), service)];
+        if let Some(content) = content {
+            units.push((format!("{}.{}", job_id, unit), content));
+        }
+        for (name, content) in units {
+            let path = units_dir.join(name);
+            fs::write(&path, content)
+                .with_context(|| format!("Failed to write unit file to {}", path.display()))?;
+        }
+
+        Self::systemctl(&["daemon-reload"])?;
+        Self::systemctl(&["enable", "--now", &format!("{}.{}", job_id, unit)])?;
+
+        Ok(job_id)
+    }
+
+    /// Stops, disables and deletes every unit of the job.
+    fn remove_units(job_id: &str) -> Result<()> {
+        let units_dir = sandbox::path(UNITS_DIR);
+        let units = TRIGGER_EXTENSIONS
+            .iter()
+            .chain(&["service"])
+            .map(|extension| format!("{}.{}", job_id, extension))
+            .filter(|unit| units_dir.join(unit).exists())
+            .collect::<Vec<_>>();
+        if units.is_empty() {
+            return Ok(());
+        }
+
+        // the units may not be loaded anymore, the files go either way. The service is
+        // stopped last, it may be the one running this
+        let service = format!("{}.service", job_id);
+        let mut args = vec!["disable"];
+        args.extend(units.iter().map(String::as_str));
+        let _ = Self::systemctl(&args);
+        let triggers = units
+            .iter()
+            .map(String::as_str)
+            .filter(|unit| *unit != service)
+            .collect::<Vec<_>>();
+        if !triggers.is_empty() {
+            let mut args = vec!["stop"];
+            args.extend(triggers);
+            let _ = Self::systemctl(&args);
+        }
+        for unit in &units {
+            fs::remove_file(units_dir.join(unit))?;
+        }
+
+        Self::systemctl(&["daemon-reload"])?;
+        let _ = Self::systemctl(&["stop", &service]);
+        Ok(())
+    }
+
+    fn systemctl(args: &[&str]) -> Result<()> {
+        let output = sandbox::command("systemctl")
+            .args(args)
+            .output()
+            .context("Failed to execute systemctl")?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!("systemctl {} failed: {}", args.join(" "), stderr.trim());
+        }
+
+        Ok(())
+    }
+
+    /// The service running the executable with `args` as root, for the invoking user
+    /// like `sudo` would, logging to `<log_name>.out` and `<log_name>.err`. Services
+    /// without a trigger are kept running.
+    fn generate_service(
+        executable_path: &Path,
+        state_dir: &Path,
+        args: &[&str],
+        log_name: &str,
+        stdin: Option<&Path>,
+        trigger: &Trigger,
+    ) -> String {
+        let environment = format!(
+            "\"SUDO_UID={}\"{}",
+            invoking_uid(),
+            env::var("SUDO_USER")
+                .map(|user| format!(" \"SUDO_USER={}\"", user))
+                .unwrap_or_default()
+        );
+        let (kind, install) = match trigger {
+            Trigger::KeepAlive => (
+                "simple\nRestart=always",
+                "\n[Install]\nWantedBy=multi-user.target\n",
+            ),
+            _ => ("oneshot", ""),
+        };
+
+        format!(
+            r#"[Unit]
+Description=enough {}
+
+[Service]
+Type={}
+ExecStart="{}" {}
+Environment={}
+StandardOutput=append:{}/{}.out
+StandardError=append:{}/{}.err
+{}{}"#,
+            log_name,
+            kind,
+            executable_path.display(),
+            args.join(" "),
+            environment,
+            state_dir.display(),
+            log_name,
+            state_dir.display(),
+            log_name,
+            // systemd opens it as root, the token never leaves files only root reads
+            stdin
+                .map(|path| format!("StandardInput=file:{}\n", path.display()))
+                .unwrap_or_default(),
+            install,
+        )
+    }
+
+    /// Unlike launchd, timers go down to the second, so the unblock fires right on time.
+    fn generate_timer(settings: &[String]) -> String {
+        format!(
+            "[Timer]
+{}
+AccuracySec=1s
+
+[Install]
+WantedBy=timers.target
+",
+            settings.join("\n")
+        )
+    }
+
+    fn generate_path(paths: &[PathBuf]) -> String {
+        let watch_paths = paths
+            .iter()
+            .map(|path| format!("PathChanged={}", path.display()))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        format!(
+            "[Path]
+{}
+
+[Install]
+WantedBy=paths.target
+",
+            watch_paths
+        )
+    }
+
+    fn on_calendar(time: DateTime<Local>) -> String {
+        format!("OnCalendar={}", time.format("%Y-%m-%d %H:%M:%S"))
+    }
+
+    /// A timer firing every `interval` from now on.
+    fn every(interval: Duration) -> Trigger {
+        Trigger::Timer(vec![
+            format!("OnActiveSec={}s", interval.as_secs()),
+            format!("OnUnitActiveSec={}s", interval.as_secs()),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone as _;
+
+    #[test]
+    fn units_run_the_job_as_root_with_its_trigger() {
+        let state_dir = Path::new("/var/lib/enough/state/501");
+        let token = state_dir.join(UNBLOCK_TOKEN_FILE);
+        let unblock = SystemdDaemon::generate_service(
+            Path::new("/usr/local/bin/enough"),
+            state_dir,
+            &UNBLOCK_ARGS,
+            "unblock",
+            Some(&token),
+            &Trigger::Timer(Vec::new()),
+        );
+        assert!(unblock.contains("Type=oneshot\n"));
+        assert!(
+            unblock
+                .contains("ExecStart=\"/usr/local/bin/enough\" ___zzzunblock --fix --scheduled\n")
+        );
+        assert!(unblock.contains(&format!("Environment=\"SUDO_UID={}\"", invoking_uid())));
+        assert!(unblock.contains("StandardError=append:/var/lib/enough/state/501/unblock.err\n"));
+        assert!(unblock.contains("StandardInput=file:/var/lib/enough/state/501/unblock_token\n"));
+        assert!(!unblock.contains("[Install]"));
+
+        let dns = SystemdDaemon::generate_service(
+            Path::new("/usr/local/bin/enough"),
+            state_dir,
+            &["___zzzdns"],
+            "dns",
+            None,
+            &Trigger::KeepAlive,
+        );
+        assert!(dns.contains("Type=simple\nRestart=always\n"));
+        assert!(dns.ends_with("[Install]\nWantedBy=multi-user.target\n"));
+        assert!(!dns.contains("StandardInput"));
+
+        let at = Local.with_ymd_and_hms(2025, 3, 4, 17, 42, 5).unwrap();
+        let timer = SystemdDaemon::generate_timer(&[
+            SystemdDaemon::on_calendar(at),
+            "Persistent=true".into(),
+        ]);
+        assert_eq!(
+            timer,
+            "[Timer]\nOnCalendar=2025-03-04 17:42:05\nPersistent=true\nAccuracySec=1s\n\n[Install]\nWantedBy=timers.target\n"
+        );
+        let Trigger::Timer(every) = SystemdDaemon::every(Duration::from_secs(60)) else {
+            panic!("`every` is a timer");
+        };
+        assert_eq!(every, ["OnActiveSec=60s", "OnUnitActiveSec=60s"]);
+
+        let path = SystemdDaemon::generate_path(&[PathBuf::from("/etc/hosts")]);
+        assert!(path.starts_with("[Path]\nPathChanged=/etc/hosts\n"));
+        assert!(path.ends_with("WantedBy=paths.target\n"));
+    }
+
+    #[test]
+    fn a_reschedule_whose_state_isnt_saved_keeps_the_old_job() {
+        let root = sandbox::for_tests();
+        let state_dir = root.join(format!("state-{}", Uuid::new_v4()));
+        let units = sandbox::path(UNITS_DIR);
+        let job_id = || fs::read_to_string(state_dir.join(DAEMON_ID_FILE)).unwrap();
+        let at = |day| Local.with_ymd_and_hms(2099, 1, day, 12, 0, 0).unwrap();
+
+        SystemdDaemon::schedule(&state_dir, at(1)).unwrap();
+        let old_id = job_id();
+        let failed = SystemdDaemon::reschedule(&state_dir, at(2), || {
+            anyhow::bail!("The state wasn't saved")
+        });
+        assert!(failed.is_err());
+        assert_eq!(job_id(), old_id);
+        assert!(units.join(format!("{}.timer", old_id)).exists());
+        // the job loaded for the new time is gone again
+        let left = fs::read_dir(&units)
+            .unwrap()
+            .filter_map(|entry| fs::read_to_string(entry.unwrap().path()).ok())
+            .filter(|unit| unit.contains("OnCalendar=2099-01-02 12:00:00"))
+            .count();
+        assert_eq!(left, 0);
+
+        SystemdDaemon::reschedule(&state_dir, at(3), || Ok(())).unwrap();
+        let new_id = job_id();
+        assert_ne!(new_id, old_id);
+        assert!(!units.join(format!("{}.timer", old_id)).exists());
+        assert!(!units.join(format!("{}.service", old_id)).exists());
+        let timer = fs::read_to_string(units.join(format!("{}.timer", new_id))).unwrap();
+        assert!(timer.contains("OnCalendar=2099-01-03 12:00:00\n"));
+
+        SystemdDaemon::remove_units(&new_id).unwrap();
+        fs::remove_dir_all(&state_dir).unwrap();
+    }
+}
@@ -1,72 +1,485 @@
 use anyhow::{Context as _, Ok, Result};
-use chrono::{DateTime, Local, Timelike as _};
-use std::io::Write;
+use chrono::{DateTime, Local, NaiveTime, Timelike as _};
 use std::{
     env, fs,
     os::unix::ffi::OsStrExt as _,
     path::{Path, PathBuf},
-    process::Command,
+    time::Duration,
 };
 use uuid::Uuid;
 
-use crate::daemon::UnblockingDaemon;
+use crate::{
+    daemon::{UNBLOCK_ARGS, UnblockingDaemon},
+    sandbox,
+    user::invoking_uid,
+};
 
-const DAEMON_ID_PATH: &str = "/tmp/enough/daemon_id";
-const STATE_BACKUP_PATH: &str = "/tmp/enough/current_block.yaml";
-const HOME_DIR_BACKUP_PATH: &str = "/tmp/enough/home_dir";
+const DAEMON_ID_FILE: &str = "daemon_id";
+const STATE_BACKUP_FILE: &str = "current_block.yaml";
+const HOME_DIR_BACKUP_FILE: &str = "home_dir";
+const NETWORK_WATCHER_ID_FILE: &str = "network_watcher_id";
+const WINDOWS_JOB_ID_FILE: &str = "windows_job_id";
+const USAGE_JOB_ID_FILE: &str = "usage_job_id";
+const REBLOCK_JOB_ID_FILE: &str = "reblock_job_id";
+const FRICTION_JOB_ID_FILE: &str = "friction_job_id";
+const REMAINING_JOB_ID_FILE: &str = "remaining_job_id";
+const INSTALLS_WATCHER_ID_FILE: &str = "installs_watcher_id";
+const APPS_GUARD_ID_FILE: &str = "apps_guard_id";
+const REINSTALLS_WATCHER_ID_FILE: &str = "reinstalls_watcher_id";
+const CONFIG_GUARD_ID_FILE: &str = "config_guard_id";
+const QUOTA_WATCHER_ID_FILE: &str = "quota_watcher_id";
+const SCHEDULER_ID_FILE: &str = "scheduler_id";
+const RELAUNCH_WATCHER_ID_FILE: &str = "relaunch_watcher_id";
+const JOBS_GUARD_ID_FILE: &str = "jobs_guard_id";
+const BOOT_CHECK_ID_FILE: &str = "boot_check_id";
+const HOSTS_GUARD_ID_FILE: &str = "hosts_guard_id";
+const DNS_PROXY_JOB_ID_FILE: &str = "dns_proxy_job_id";
+/// Jobs of a block other than the unblock itself, the guard first so removing the rest
+/// doesn't set it off
+const JOB_ID_FILES: [&str; 15] = [
+    JOBS_GUARD_ID_FILE,
+    NETWORK_WATCHER_ID_FILE,
+    WINDOWS_JOB_ID_FILE,
+    USAGE_JOB_ID_FILE,
+    REBLOCK_JOB_ID_FILE,
+    FRICTION_JOB_ID_FILE,
+    REMAINING_JOB_ID_FILE,
+    INSTALLS_WATCHER_ID_FILE,
+    APPS_GUARD_ID_FILE,
+    REINSTALLS_WATCHER_ID_FILE,
+    CONFIG_GUARD_ID_FILE,
+    RELAUNCH_WATCHER_ID_FILE,
+    BOOT_CHECK_ID_FILE,
+    HOSTS_GUARD_ID_FILE,
+    DNS_PROXY_JOB_ID_FILE,
+];
+/// Touched by configd whenever interfaces, DNS servers or VPNs change
+const NETWORK_CONFIG_DIR: &str = "/Library/Preferences/SystemConfiguration";
+/// Replaced when the time zone changes, which moves the local-time jobs
+const TIME_ZONE_FILE: &str = "/etc/localtime";
 
 pub struct LaunchDaemon;
 
 impl UnblockingDaemon for LaunchDaemon {
-    fn schedule(unblock_time: DateTime<Local>) -> Result<()> {
-        let daemon_id = format!("com.enough.unblock.{}", Uuid::new_v4());
+    // the jobs are the user's launch agents, which can read whatever they're handed
+    const HANDS_OVER_TOKEN: bool = false;
+
+    fn schedule(state_dir: &Path, unblock_time: DateTime<Local>) -> Result<()> {
+        let daemon_id = format!("com.enough.unblock.{}.{}", invoking_uid(), Uuid::new_v4());
         let plist_path = Self::get_plist_path(&daemon_id, None)?;
 
         let current_exe = env::current_exe().context("Failed to get current executable path")?;
-        let plist_content = Self::generate_plist(&daemon_id, &current_exe, unblock_time);
+        let plist_content = Self::generate_plist(&daemon_id, &current_exe, state_dir, unblock_time);
 
         fs::write(&plist_path, plist_content)
             .with_context(|| format!("Failed to write plist file to {}", plist_path.display()))?;
 
-        let output = Command::new("launchctl")
+        let output = sandbox::command("launchctl")
             .arg("load")
             .arg(&plist_path)
             .output()
             .context("Failed to execute launchctl load command")?;
 
         if !output.status.success() {
+            // not leaving a plist launchd would pick up at the next login
+            let _ = fs::remove_file(&plist_path);
             let stderr = String::from_utf8_lossy(&output.stderr);
             anyhow::bail!("launchctl load failed: {}", stderr);
         }
 
-        eprint!("Scheduled unblock for ");
-        println!("{}", unblock_time.format("%H:%M:%S"));
+        eprintln!("Scheduled unblock for {}", unblock_time.format("%H:%M:%S"));
 
         let home_dir = env::home_dir().with_context(|| "Couldn't find the home directory")?;
         eprintln!("backed up home dir: {}", home_dir.display());
         // saving daemon info for cleanup
-        fs::create_dir_all("/tmp/enough")?;
-        fs::write(DAEMON_ID_PATH, &daemon_id)?;
-        fs::write(HOME_DIR_BACKUP_PATH, home_dir.as_os_str().as_bytes())?;
+        fs::create_dir_all(state_dir)?;
+        fs::write(state_dir.join(DAEMON_ID_FILE), &daemon_id)?;
+        fs::write(
+            state_dir.join(HOME_DIR_BACKUP_FILE),
+            home_dir.as_os_str().as_bytes(),
+        )?;
 
         Ok(())
     }
 
-    fn remove() -> Result<()> {
-        if Path::new(DAEMON_ID_PATH).exists() {
-            let daemon_id = fs::read_to_string(DAEMON_ID_PATH)?;
-            let home_dir = fs::read_to_string(HOME_DIR_BACKUP_PATH)?;
+    fn watch_network(state_dir: &Path) -> Result<()> {
+        let watcher_id = format!("com.enough.network.{}.{}", invoking_uid(), Uuid::new_v4());
+        let current_exe = env::current_exe().context("Failed to get current executable path")?;
+        let plist_content = Self::generate_watcher_plist(&watcher_id, &current_exe, state_dir);
+
+        Self::install_job(
+            state_dir,
+            NETWORK_WATCHER_ID_FILE,
+            &watcher_id,
+            &plist_content,
+        )
+    }
+
+    fn schedule_windows(state_dir: &Path, boundaries: &[NaiveTime]) -> Result<()> {
+        let job_id = format!("com.enough.windows.{}.{}", invoking_uid(), Uuid::new_v4());
+        let current_exe = env::current_exe().context("Failed to get current executable path")?;
+        let plist_content =
+            Self::generate_windows_plist(&job_id, &current_exe, state_dir, boundaries);
+
+        Self::install_job(state_dir, WINDOWS_JOB_ID_FILE, &job_id, &plist_content)
+    }
+
+    fn track_usage(state_dir: &Path, interval: Duration) -> Result<()> {
+        let job_id = format!("com.enough.usage.{}.{}", invoking_uid(), Uuid::new_v4());
+        let current_exe = env::current_exe().context("Failed to get current executable path")?;
+        let plist_content = Self::generate_usage_plist(&job_id, &current_exe, state_dir, interval);
+
+        Self::install_job(state_dir, USAGE_JOB_ID_FILE, &job_id, &plist_content)
+    }
+
+    fn schedule_reblock(state_dir: &Path, at: Option<DateTime<Local>>) -> Result<()> {
+        Self::remove_job(state_dir, REBLOCK_JOB_ID_FILE)?;
+        let Some(at) = at else {
+            return Ok(());
+        };
+
+        let job_id = format!("com.enough.reblock.{}.{}", invoking_uid(), Uuid::new_v4());
+        let current_exe = env::current_exe().context("Failed to get current executable path")?;
+        let plist_content = Self::generate_reblock_plist(&job_id, &current_exe, state_dir, at);
+
+        Self::install_job(state_dir, REBLOCK_JOB_ID_FILE, &job_id, &plist_content)
+    }
+
+    fn watch_installs(state_dir: &Path, dirs: &[PathBuf]) -> Result<()> {
+        let watcher_id = format!("com.enough.installs.{}.{}", invoking_uid(), Uuid::new_v4());
+        let current_exe = env::current_exe().context("Failed to get current executable path")?;
+        let plist_content = Self::generate_watch_paths_plist(
+            &watcher_id,
+            &current_exe,
+            state_dir,
+            "___zzzinstalls",
+            "installs",
+            dirs,
+        );
+
+        Self::install_job(
+            state_dir,
+            INSTALLS_WATCHER_ID_FILE,
+            &watcher_id,
+            &plist_content,
+        )
+    }
+
+    fn guard_apps(state_dir: &Path, dirs: &[PathBuf]) -> Result<()> {
+        let job_id = format!("com.enough.guard.{}.{}", invoking_uid(), Uuid::new_v4());
+        let current_exe = env::current_exe().context("Failed to get current executable path")?;
+        let plist_content = Self::generate_watch_paths_plist(
+            &job_id,
+            &current_exe,
+            state_dir,
+            "___zzzguard",
+            "guard",
+            dirs,
+        );
+
+        Self::install_job(state_dir, APPS_GUARD_ID_FILE, &job_id, &plist_content)
+    }
+
+    fn watch_reinstalls(state_dir: &Path, dirs: &[PathBuf]) -> Result<()> {
+        let watcher_id = format!(
+            "com.enough.reinstalls.{}.{}",
+            invoking_uid(),
+            Uuid::new_v4()
+        );
+        let current_exe = env::current_exe().context("Failed to get current executable path")?;
+        let plist_content = Self::generate_watch_paths_plist(
+            &watcher_id,
+            &current_exe,
+            state_dir,
+            "___zzzreinstalls",
+            "reinstalls",
+            dirs,
+        );
+
+        Self::install_job(
+            state_dir,
+            REINSTALLS_WATCHER_ID_FILE,
+            &watcher_id,
+            &plist_content,
+        )
+    }
+
+    fn guard_config(state_dir: &Path, config: &Path) -> Result<()> {
+        let job_id = format!("com.enough.config.{}.{}", invoking_uid(), Uuid::new_v4());
+        let current_exe = env::current_exe().context("Failed to get current executable path")?;
+        let plist_content = Self::generate_watch_paths_plist(
+            &job_id,
+            &current_exe,
+            state_dir,
+            "___zzzconfig",
+            "config",
+            &[config.to_path_buf()],
+        );
+
+        Self::install_job(state_dir, CONFIG_GUARD_ID_FILE, &job_id, &plist_content)
+    }
+
+    fn guard_hosts(state_dir: &Path, hosts: &Path) -> Result<()> {
+        let job_id = format!("com.enough.hosts.{}.{}", invoking_uid(), Uuid::new_v4());
+        let current_exe = env::current_exe().context("Failed to get current executable path")?;
+        let plist_content = Self::generate_watch_paths_plist(
+            &job_id,
+            &current_exe,
+            state_dir,
+            "___zzzhosts",
+            "hosts",
+            &[hosts.to_path_buf()],
+        );
+
+        Self::install_job(state_dir, HOSTS_GUARD_ID_FILE, &job_id, &plist_content)
+    }
+
+    fn check_at_boot(state_dir: &Path) -> Result<()> {
+        let job_id = format!("com.enough.boot.{}.{}", invoking_uid(), Uuid::new_v4());
+        let current_exe = env::current_exe().context("Failed to get current executable path")?;
+        let plist_content = Self::generate_boot_plist(&job_id, &current_exe, state_dir);
+
+        Self::install_job(state_dir, BOOT_CHECK_ID_FILE, &job_id, &plist_content)
+    }
+
+    fn guard_jobs(state_dir: &Path) -> Result<()> {
+        let job_id = format!("com.enough.jobs.{}.{}", invoking_uid(), Uuid::new_v4());
+        let current_exe = env::current_exe().context("Failed to get current executable path")?;
+        let plist_content = Self::generate_watch_paths_plist(
+            &job_id,
+            &current_exe,
+            state_dir,
+            "___zzzjobs",
+            "jobs",
+            &[Self::launch_agents_dir()?],
+        );
+
+        Self::install_job(state_dir, JOBS_GUARD_ID_FILE, &job_id, &plist_content)
+    }
+
+    fn missing_jobs(state_dir: &Path) -> Vec<String> {
+        let home_dir = fs::read_to_string(state_dir.join(HOME_DIR_BACKUP_FILE)).ok();
+        JOB_ID_FILES
+            .iter()
+            .chain([&DAEMON_ID_FILE])
+            .filter_map(|id_file| fs::read_to_string(state_dir.join(id_file)).ok())
+            .map(|job_id| job_id.trim().to_string())
+            .filter(|job_id| {
+                Self::get_plist_path(job_id, home_dir.clone().map(PathBuf::from))
+                    .is_ok_and(|plist_path| !plist_path.exists())
+            })
+            .collect()
+    }
+
+    fn watch_quotas(dir: &Path, config: &Path, interval: Duration) -> Result<()> {
+        Self::unwatch_quotas(dir)?;
+        let job_id = format!("com.enough.quotas.{}.{}", invoking_uid(), Uuid::new_v4());
+        let current_exe = env::current_exe().context("Failed to get current executable path")?;
+        let plist_content =
+            Self::generate_config_job_plist(&job_id, &current_exe, "quotas", dir, config, interval);
+
+        Self::install_job(dir, QUOTA_WATCHER_ID_FILE, &job_id, &plist_content)
+    }
+
+    fn unwatch_quotas(dir: &Path) -> Result<()> {
+        Self::remove_job(dir, QUOTA_WATCHER_ID_FILE)
+    }
+
+    fn quota_watcher(dir: &Path) -> Option<String> {
+        let job_id = fs::read_to_string(dir.join(QUOTA_WATCHER_ID_FILE)).ok()?;
+        Some(job_id.trim().to_string())
+    }
+
+    fn watch_schedules(dir: &Path, config: &Path, interval: Duration) -> Result<()> {
+        Self::unwatch_schedules(dir)?;
+        let job_id = format!("com.enough.scheduler.{}.{}", invoking_uid(), Uuid::new_v4());
+        let current_exe = env::current_exe().context("Failed to get current executable path")?;
+        let plist_content = Self::generate_config_job_plist(
+            &job_id,
+            &current_exe,
+            "scheduler",
+            dir,
+            config,
+            interval,
+        );
+
+        Self::install_job(dir, SCHEDULER_ID_FILE, &job_id, &plist_content)
+    }
+
+    fn unwatch_schedules(dir: &Path) -> Result<()> {
+        Self::remove_job(dir, SCHEDULER_ID_FILE)
+    }
+
+    fn scheduler(dir: &Path) -> Option<String> {
+        let job_id = fs::read_to_string(dir.join(SCHEDULER_ID_FILE)).ok()?;
+        Some(job_id.trim().to_string())
+    }
+
+    fn watch_relaunches(state_dir: &Path) -> Result<()> {
+        let job_id = format!("com.enough.relaunch.{}.{}", invoking_uid(), Uuid::new_v4());
+        let current_exe = env::current_exe().context("Failed to get current executable path")?;
+        let plist_content = Self::generate_relaunch_plist(&job_id, &current_exe, state_dir);
+
+        Self::install_job(state_dir, RELAUNCH_WATCHER_ID_FILE, &job_id, &plist_content)
+    }
+
+    fn update_remaining(state_dir: &Path, interval: Duration) -> Result<()> {
+        let job_id = format!("com.enough.remaining.{}.{}", invoking_uid(), Uuid::new_v4());
+        let current_exe = env::current_exe().context("Failed to get current executable path")?;
+        let plist_content =
+            Self::generate_remaining_plist(&job_id, &current_exe, state_dir, interval);
+
+        Self::install_job(state_dir, REMAINING_JOB_ID_FILE, &job_id, &plist_content)
+    }
+
+    fn serve_friction(state_dir: &Path) -> Result<()> {
+        let job_id = format!("com.enough.friction.{}.{}", invoking_uid(), Uuid::new_v4());
+        let current_exe = env::current_exe().context("Failed to get current executable path")?;
+        let plist_content = Self::generate_friction_plist(&job_id, &current_exe, state_dir);
+
+        Self::install_job(state_dir, FRICTION_JOB_ID_FILE, &job_id, &plist_content)
+    }
+
+    fn serve_dns(state_dir: &Path) -> Result<()> {
+        let job_id = format!("com.enough.dns.{}.{}", invoking_uid(), Uuid::new_v4());
+        let current_exe = env::current_exe().context("Failed to get current executable path")?;
+        let plist_content = Self::generate_dns_plist(&job_id, &current_exe, state_dir);
+
+        Self::install_job(state_dir, DNS_PROXY_JOB_ID_FILE, &job_id, &plist_content)
+    }
+
+    fn reschedule(
+        state_dir: &Path,
+        unblock_time: DateTime<Local>,
+        then: impl FnOnce() -> Result<()>,
+    ) -> Result<()> {
+        let old = Self::unblock_job(state_dir)?;
+        Self::schedule(state_dir, unblock_time)?;
+        if let Err(err) = then() {
+            // back to the old job, the state it goes with wasn't saved
+            if let Some((new_id, new_plist)) = Self::unblock_job(state_dir)? {
+                Self::retire_job(&new_id, &new_plist)?;
+            }
+            if let Some((old_id, _)) = &old {
+                fs::write(state_dir.join(DAEMON_ID_FILE), old_id)?;
+            }
+            return Err(err);
+        }
+
+        match old {
+            Some((old_id, old_plist)) => Self::retire_job(&old_id, &old_plist),
+            None => Ok(()),
+        }
+    }
+
+    fn remove(state_dir: &Path) -> Result<()> {
+        for id_file in JOB_ID_FILES {
+            Self::remove_job(state_dir, id_file)?;
+        }
+        Self::remove_unblock_daemon(state_dir)
+    }
+
+    fn installed_jobs() -> Result<Vec<String>> {
+        let Some(entries) = fs::read_dir(Self::launch_agents_dir()?).ok() else {
+            return Ok(Vec::new());
+        };
+
+        // every job ID is `com.enough.<kind>.<uid>.<uuid>`
+        let uid = invoking_uid().to_string();
+        let jobs = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let name = entry
+                    .file_name()
+                    .to_str()?
+                    .strip_suffix(".plist")?
+                    .to_string();
+                let mut parts = name.split('.');
+                let owned = parts.next() == Some("com")
+                    && parts.next() == Some("enough")
+                    && parts.nth(1) == Some(uid.as_str());
+                owned.then_some(name)
+            })
+            .collect();
+
+        Ok(jobs)
+    }
+
+    fn remove_all(keep: &[String]) -> Result<()> {
+        let launch_agents_dir = Self::launch_agents_dir()?;
+        for job_id in Self::installed_jobs()?
+            .into_iter()
+            .filter(|job_id| !keep.contains(job_id))
+        {
+            let plist_path = launch_agents_dir.join(format!("{}.plist", job_id));
+            // the job may not be loaded anymore, the plist goes either way
+            sandbox::command("launchctl")
+                .arg("unload")
+                .arg(&plist_path)
+                .output()?;
+            fs::remove_file(&plist_path)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl LaunchDaemon {
+    /// ID and plist of the scheduled unblock, if there is one.
+    fn unblock_job(state_dir: &Path) -> Result<Option<(String, PathBuf)>> {
+        let daemon_id_path = state_dir.join(DAEMON_ID_FILE);
+        if !daemon_id_path.exists() {
+            return Ok(None);
+        }
+
+        let daemon_id = fs::read_to_string(&daemon_id_path)?.trim().to_string();
+        let home_dir = fs::read_to_string(state_dir.join(HOME_DIR_BACKUP_FILE))?;
+        let plist_path = Self::get_plist_path(&daemon_id, Some(home_dir.into()))?;
+        Ok(Some((daemon_id, plist_path)))
+    }
+
+    /// Deletes the plist of a job before removing it from launchd, which ends it if it's
+    /// the one running this, so nothing of it is left either way.
+    fn retire_job(job_id: &str, plist_path: &Path) -> Result<()> {
+        if plist_path.exists() {
+            fs::remove_file(plist_path)?;
+        }
+        let output = sandbox::command("launchctl")
+            .args(["remove", job_id])
+            .output()?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!("launchctl remove failed: {}", stderr);
+        }
+        Ok(())
+    }
+
+    fn remove_unblock_daemon(state_dir: &Path) -> Result<()> {
+        let daemon_id_path = state_dir.join(DAEMON_ID_FILE);
+        let state_backup_path = state_dir.join(STATE_BACKUP_FILE);
+        let home_dir_backup_path = state_dir.join(HOME_DIR_BACKUP_FILE);
+
+        if daemon_id_path.exists() {
+            let daemon_id = fs::read_to_string(&daemon_id_path)?;
+            let home_dir = fs::read_to_string(&home_dir_backup_path)?;
             eprintln!("restored home dir: {}", home_dir);
-            let plist_path = Self::get_plist_path(&daemon_id.trim(), Some(home_dir.into()))?;
+            let plist_path = Self::get_plist_path(daemon_id.trim(), Some(home_dir.into()))?;
             eprintln!("restored plist path: {}", plist_path.display());
 
-            fs::remove_file(DAEMON_ID_PATH)?;
-            fs::remove_file(STATE_BACKUP_PATH)?;
-            fs::remove_file(HOME_DIR_BACKUP_PATH)?;
+            fs::remove_file(&daemon_id_path)?;
+            fs::remove_file(&state_backup_path)?;
+            fs::remove_file(&home_dir_backup_path)?;
+
+            if !plist_path.exists() {
+                sandbox::command("launchctl")
+                    .args(["remove", daemon_id.trim()])
+                    .output()?;
+                return Ok(());
+            }
 
             // unloading the daemon
             eprintln!("Unloading daemon with ID: {}", daemon_id);
-            let output = Command::new("launchctl")
+            let output = sandbox::command("launchctl")
                 .arg("unload")
                 .arg(&plist_path)
                 .output()?;
@@ -77,26 +490,81 @@ impl UnblockingDaemon for LaunchDaemon {
                 anyhow::bail!("launchctl unload failed: {}", stderr);
             }
 
-            writeln!(
-                std::fs::File::create("~/Downloads/daemon_unloaded")?,
-                "Unloaded daemon with ID: {}",
-                daemon_id
-            )?;
-
             fs::remove_file(&plist_path)?;
         }
 
         Ok(())
     }
-}
 
-impl LaunchDaemon {
+    /// Writes and loads a job's plist, keeping its ID in `id_file` for removal.
+    fn install_job(state_dir: &Path, id_file: &str, job_id: &str, plist: &str) -> Result<()> {
+        let plist_path = Self::get_plist_path(job_id, None)?;
+        fs::write(&plist_path, plist)
+            .with_context(|| format!("Failed to write plist file to {}", plist_path.display()))?;
+
+        let output = sandbox::command("launchctl")
+            .arg("load")
+            .arg(&plist_path)
+            .output()
+            .context("Failed to execute launchctl load command")?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!("launchctl load failed: {}", stderr);
+        }
+
+        fs::create_dir_all(state_dir)?;
+        fs::write(state_dir.join(id_file), job_id)?;
+
+        Ok(())
+    }
+
+    /// Unloads and deletes the job whose ID is kept in `id_file`, if there is one.
+    fn remove_job(state_dir: &Path, id_file: &str) -> Result<()> {
+        let job_id_path = state_dir.join(id_file);
+        if !job_id_path.exists() {
+            return Ok(());
+        }
+
+        let job_id = fs::read_to_string(&job_id_path)?;
+        let home_dir = fs::read_to_string(state_dir.join(HOME_DIR_BACKUP_FILE)).ok();
+        let plist_path = Self::get_plist_path(job_id.trim(), home_dir.map(PathBuf::from))?;
+        if !plist_path.exists() {
+            // deleted from under the job, which launchd still has loaded
+            sandbox::command("launchctl")
+                .args(["remove", job_id.trim()])
+                .output()?;
+            fs::remove_file(&job_id_path)?;
+            return Ok(());
+        }
+
+        let output = sandbox::command("launchctl")
+            .arg("unload")
+            .arg(&plist_path)
+            .output()?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!("launchctl unload failed: {}", stderr);
+        }
+
+        fs::remove_file(&plist_path)?;
+        fs::remove_file(&job_id_path)?;
+
+        Ok(())
+    }
+
+    fn launch_agents_dir() -> Result<PathBuf> {
+        let home_dir = env::home_dir().context("Couldn't find the home directory")?;
+        Ok(home_dir.join("Library/LaunchAgents"))
+    }
+
     fn get_plist_path(daemon_id: &str, home_dir: Option<PathBuf>) -> Result<PathBuf> {
         let home_dir = match home_dir {
             Some(home) => home,
             None => {
                 let home_dir_bytes = env::home_dir().context("Couldn't find the home directory")?;
-                let home_dir = String::from_utf8_lossy(&home_dir_bytes.as_os_str().as_bytes());
+                let home_dir = String::from_utf8_lossy(home_dir_bytes.as_os_str().as_bytes());
                 home_dir.trim().to_string().into()
             }
         };
@@ -115,23 +583,71 @@ impl LaunchDaemon {
     fn generate_plist(
         daemon_id: &str,
         executable_path: &Path,
+        state_dir: &Path,
         unblock_time: DateTime<Local>,
     ) -> String {
-        let start_calendar_interval = format!(
+        let start_calendar_interval = Self::calendar_interval(unblock_time);
+
+        let plist = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>sudo</string>
+        <string>{}</string>
+{}
+    </array>
+    <key>StartCalendarInterval</key>
+{}
+    <key>RunAtLoad</key>
+    <false/>
+    <key>StandardOutPath</key>
+    <string>{}/unblock.out</string>
+    <key>StandardErrorPath</key>
+    <string>{}/unblock.err</string>
+</dict>
+</plist>"#,
+            daemon_id,
+            executable_path.display(),
+            UNBLOCK_ARGS
+                .iter()
+                .map(|arg| format!("        <string>{}</string>", arg))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            start_calendar_interval,
+            state_dir.display(),
+            state_dir.display(),
+        );
+
+        plist
+    }
+
+    /// launchd only goes down to the minute, so the job fires at the start of the minute
+    /// of `time` and the unblock waits out the seconds left itself.
+    fn calendar_interval(time: DateTime<Local>) -> String {
+        format!(
             "    <dict>
         <key>Hour</key>
         <integer>{}</integer>
         <key>Minute</key>
         <integer>{}</integer>
-        <key>Second</key>
-        <integer>{}</integer>
     </dict>",
-            unblock_time.hour(),
-            unblock_time.minute(),
-            unblock_time.second(),
-        );
+            time.hour(),
+            time.minute(),
+        )
+    }
 
-        let plist = format!(
+    fn generate_reblock_plist(
+        job_id: &str,
+        executable_path: &Path,
+        state_dir: &Path,
+        at: DateTime<Local>,
+    ) -> String {
+        format!(
             r#"<?xml version="1.0" encoding="UTF-8"?>
 <!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
 <plist version="1.0">
@@ -142,24 +658,415 @@ impl LaunchDaemon {
     <array>
         <string>sudo</string>
         <string>{}</string>
-        <string>___zzzunblock</string>
-        <string>--fix</string>
+        <string>___zzzreblock</string>
     </array>
     <key>StartCalendarInterval</key>
 {}
     <key>RunAtLoad</key>
     <false/>
     <key>StandardOutPath</key>
-    <string>/tmp/enough/unblock.out</string>
+    <string>{}/reblock.out</string>
     <key>StandardErrorPath</key>
-    <string>/tmp/enough/unblock.err</string>
+    <string>{}/reblock.err</string>
 </dict>
 </plist>"#,
-            daemon_id,
+            job_id,
             executable_path.display(),
-            start_calendar_interval
-        );
+            Self::calendar_interval(at),
+            state_dir.display(),
+            state_dir.display(),
+        )
+    }
 
-        plist
+    fn generate_friction_plist(job_id: &str, executable_path: &Path, state_dir: &Path) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>sudo</string>
+        <string>{}</string>
+        <string>___zzzfriction</string>
+    </array>
+    <key>KeepAlive</key>
+    <true/>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>StandardOutPath</key>
+    <string>{}/friction.out</string>
+    <key>StandardErrorPath</key>
+    <string>{}/friction.err</string>
+</dict>
+</plist>"#,
+            job_id,
+            executable_path.display(),
+            state_dir.display(),
+            state_dir.display(),
+        )
+    }
+
+    fn generate_dns_plist(job_id: &str, executable_path: &Path, state_dir: &Path) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>sudo</string>
+        <string>{}</string>
+        <string>___zzzdns</string>
+    </array>
+    <key>KeepAlive</key>
+    <true/>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>StandardOutPath</key>
+    <string>{}/dns.out</string>
+    <key>StandardErrorPath</key>
+    <string>{}/dns.err</string>
+</dict>
+</plist>"#,
+            job_id,
+            executable_path.display(),
+            state_dir.display(),
+            state_dir.display(),
+        )
+    }
+
+    /// Runs once whenever launchd loads it, at login after a restart too.
+    fn generate_boot_plist(job_id: &str, executable_path: &Path, state_dir: &Path) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>sudo</string>
+        <string>{}</string>
+        <string>___zzzboot</string>
+    </array>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>StandardOutPath</key>
+    <string>{}/boot.out</string>
+    <key>StandardErrorPath</key>
+    <string>{}/boot.err</string>
+</dict>
+</plist>"#,
+            job_id,
+            executable_path.display(),
+            state_dir.display(),
+            state_dir.display(),
+        )
+    }
+
+    fn generate_relaunch_plist(job_id: &str, executable_path: &Path, state_dir: &Path) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>sudo</string>
+        <string>{}</string>
+        <string>___zzzrelaunch</string>
+    </array>
+    <key>KeepAlive</key>
+    <true/>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>StandardOutPath</key>
+    <string>{}/relaunch.out</string>
+    <key>StandardErrorPath</key>
+    <string>{}/relaunch.err</string>
+</dict>
+</plist>"#,
+            job_id,
+            executable_path.display(),
+            state_dir.display(),
+            state_dir.display(),
+        )
+    }
+
+    fn generate_watcher_plist(
+        watcher_id: &str,
+        executable_path: &Path,
+        state_dir: &Path,
+    ) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>sudo</string>
+        <string>{}</string>
+        <string>___zzzreapply</string>
+    </array>
+    <key>WatchPaths</key>
+    <array>
+        <string>{}</string>
+        <string>{}</string>
+    </array>
+    <key>RunAtLoad</key>
+    <false/>
+    <key>StandardOutPath</key>
+    <string>{}/network.out</string>
+    <key>StandardErrorPath</key>
+    <string>{}/network.err</string>
+</dict>
+</plist>"#,
+            watcher_id,
+            executable_path.display(),
+            NETWORK_CONFIG_DIR,
+            TIME_ZONE_FILE,
+            state_dir.display(),
+            state_dir.display(),
+        )
+    }
+
+    /// A job running `command` whenever one of `dirs` changes, logging to `<log_name>.out`
+    /// and `<log_name>.err`.
+    fn generate_watch_paths_plist(
+        job_id: &str,
+        executable_path: &Path,
+        state_dir: &Path,
+        command: &str,
+        log_name: &str,
+        dirs: &[PathBuf],
+    ) -> String {
+        let watch_paths = dirs
+            .iter()
+            .map(|dir| format!("        <string>{}</string>", dir.display()))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>sudo</string>
+        <string>{}</string>
+        <string>{}</string>
+    </array>
+    <key>WatchPaths</key>
+    <array>
+{}
+    </array>
+    <key>RunAtLoad</key>
+    <false/>
+    <key>StandardOutPath</key>
+    <string>{}/{}.out</string>
+    <key>StandardErrorPath</key>
+    <string>{}/{}.err</string>
+</dict>
+</plist>"#,
+            job_id,
+            executable_path.display(),
+            command,
+            watch_paths,
+            state_dir.display(),
+            log_name,
+            state_dir.display(),
+            log_name,
+        )
+    }
+
+    fn generate_windows_plist(
+        job_id: &str,
+        executable_path: &Path,
+        state_dir: &Path,
+        boundaries: &[NaiveTime],
+    ) -> String {
+        let start_calendar_intervals = boundaries
+            .iter()
+            .map(|time| {
+                format!(
+                    "        <dict>
+            <key>Hour</key>
+            <integer>{}</integer>
+            <key>Minute</key>
+            <integer>{}</integer>
+        </dict>",
+                    time.hour(),
+                    time.minute(),
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>sudo</string>
+        <string>{}</string>
+        <string>___zzzwindows</string>
+    </array>
+    <key>StartCalendarInterval</key>
+    <array>
+{}
+    </array>
+    <key>RunAtLoad</key>
+    <false/>
+    <key>StandardOutPath</key>
+    <string>{}/windows.out</string>
+    <key>StandardErrorPath</key>
+    <string>{}/windows.err</string>
+</dict>
+</plist>"#,
+            job_id,
+            executable_path.display(),
+            start_calendar_intervals,
+            state_dir.display(),
+            state_dir.display(),
+        )
+    }
+
+    fn generate_usage_plist(
+        job_id: &str,
+        executable_path: &Path,
+        state_dir: &Path,
+        interval: Duration,
+    ) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>sudo</string>
+        <string>{}</string>
+        <string>___zzztrack</string>
+    </array>
+    <key>StartInterval</key>
+    <integer>{}</integer>
+    <key>RunAtLoad</key>
+    <false/>
+    <key>StandardOutPath</key>
+    <string>{}/usage.out</string>
+    <key>StandardErrorPath</key>
+    <string>{}/usage.err</string>
+</dict>
+</plist>"#,
+            job_id,
+            executable_path.display(),
+            interval.as_secs(),
+            state_dir.display(),
+            state_dir.display(),
+        )
+    }
+
+    /// A job running `___zzz<kind> --config <config>` every `interval`, blocks or not,
+    /// logging to `dir`.
+    fn generate_config_job_plist(
+        job_id: &str,
+        executable_path: &Path,
+        kind: &str,
+        dir: &Path,
+        config: &Path,
+        interval: Duration,
+    ) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>sudo</string>
+        <string>{}</string>
+        <string>___zzz{}</string>
+        <string>--config</string>
+        <string>{}</string>
+    </array>
+    <key>StartInterval</key>
+    <integer>{}</integer>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>StandardOutPath</key>
+    <string>{}/{}.out</string>
+    <key>StandardErrorPath</key>
+    <string>{}/{}.err</string>
+</dict>
+</plist>"#,
+            job_id,
+            executable_path.display(),
+            kind,
+            config.display(),
+            interval.as_secs(),
+            dir.display(),
+            kind,
+            dir.display(),
+            kind,
+        )
+    }
+
+    fn generate_remaining_plist(
+        job_id: &str,
+        executable_path: &Path,
+        state_dir: &Path,
+        interval: Duration,
+    ) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>sudo</string>
+        <string>{}</string>
+        <string>___zzzremaining</string>
+    </array>
+    <key>StartInterval</key>
+    <integer>{}</integer>
+    <key>RunAtLoad</key>
+    <false/>
+    <key>StandardOutPath</key>
+    <string>{}/remaining.out</string>
+    <key>StandardErrorPath</key>
+    <string>{}/remaining.err</string>
+</dict>
+</plist>"#,
+            job_id,
+            executable_path.display(),
+            interval.as_secs(),
+            state_dir.display(),
+            state_dir.display(),
+        )
     }
 }
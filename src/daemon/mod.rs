@@ -1,9 +1,17 @@
+#[cfg(target_os = "linux")]
+mod linux;
 #[cfg(target_os = "macos")]
 mod macos;
 
 use anyhow::Result;
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Local, NaiveTime};
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
+#[cfg(target_os = "linux")]
+pub use linux::SystemdDaemon as EnoughDaemon;
 #[cfg(target_os = "macos")]
 pub use macos::LaunchDaemon as EnoughDaemon;
 
@@ -11,9 +19,124 @@ pub use macos::LaunchDaemon as EnoughDaemon;
 /// This trait is implemented differently for macOS and Linux due to their distinct
 /// approaches to background services.
 pub trait UnblockingDaemon {
-    /// Schedules a daemon to unblock at the specified time.
-    fn schedule(unblock_time: DateTime<Local>) -> Result<()>;
+    /// Whether the scheduled unblock is handed the token of a strict block, which takes a
+    /// job started by root. Without it a strict block is only lifted once its time is up.
+    const HANDS_OVER_TOKEN: bool;
 
-    /// Removes the scheduled daemon.
-    fn remove() -> Result<()>;
+    /// Schedules a daemon to unblock at the specified time, keeping its bookkeeping
+    /// in the given (per-user) state directory.
+    fn schedule(state_dir: &Path, unblock_time: DateTime<Local>) -> Result<()>;
+
+    /// Registers a job that re-validates the block whenever the network configuration
+    /// changes, since switching networks or VPNs can flush or override resolvers. It also
+    /// runs when the time zone changes.
+    fn watch_network(state_dir: &Path) -> Result<()>;
+
+    /// Registers a job running every day at each of the given times, where websites
+    /// enter or leave their allow windows.
+    fn schedule_windows(state_dir: &Path, boundaries: &[NaiveTime]) -> Result<()>;
+
+    /// Registers a job running every `interval`, tracking how long apps with a usage
+    /// limit have been in the foreground.
+    fn track_usage(state_dir: &Path, interval: Duration) -> Result<()>;
+
+    /// Replaces the job re-blocking temporary exceptions with one running at `at`, or
+    /// just removes it when there's nothing left to re-block.
+    fn schedule_reblock(state_dir: &Path, at: Option<DateTime<Local>>) -> Result<()>;
+
+    /// Registers a job running whenever apps are added to or removed from one of `dirs`,
+    /// blocking new installs that belong to the block's app groups.
+    fn watch_installs(state_dir: &Path, dirs: &[PathBuf]) -> Result<()>;
+
+    /// Registers a job running whenever something changes in one of `dirs`, putting
+    /// blocked apps moved to the Trash back in strict mode.
+    fn guard_apps(state_dir: &Path, dirs: &[PathBuf]) -> Result<()>;
+
+    /// Registers a job running whenever something changes in one of `dirs`, blocking
+    /// blocked apps again after Homebrew or the App Store reinstalls them.
+    fn watch_reinstalls(state_dir: &Path, dirs: &[PathBuf]) -> Result<()>;
+
+    /// Registers a job running whenever the `config` a strict block was started from
+    /// changes, putting it back if the edit weakens the block.
+    fn guard_config(state_dir: &Path, config: &Path) -> Result<()>;
+
+    /// Registers a job running whenever the `hosts` file changes, putting the block's
+    /// section back if an edit took it out.
+    fn guard_hosts(state_dir: &Path, hosts: &Path) -> Result<()>;
+
+    /// Registers a job running after a restart, putting back what it undid or finishing
+    /// a block that ended in the meantime.
+    fn check_at_boot(state_dir: &Path) -> Result<()>;
+
+    /// Registers a job running whenever the jobs' plists or units change, reporting the
+    /// ones of the block that went missing.
+    fn guard_jobs(state_dir: &Path) -> Result<()>;
+
+    /// IDs of the block's jobs whose plist or unit is gone.
+    fn missing_jobs(state_dir: &Path) -> Vec<String>;
+
+    /// Replaces the job measuring the quotas of `config` with one running every
+    /// `interval`, blocks or not. Its bookkeeping is kept in `dir`, which outlives blocks.
+    fn watch_quotas(dir: &Path, config: &Path, interval: Duration) -> Result<()>;
+
+    /// Removes the job from [`watch_quotas`](Self::watch_quotas), if there is one.
+    fn unwatch_quotas(dir: &Path) -> Result<()>;
+
+    /// ID of the job from [`watch_quotas`](Self::watch_quotas), if there is one.
+    fn quota_watcher(dir: &Path) -> Option<String>;
+
+    /// Replaces the job starting and lifting the blocks scheduled in `config` with one
+    /// running every `interval`, blocks or not. Its bookkeeping is kept in `dir`.
+    fn watch_schedules(dir: &Path, config: &Path, interval: Duration) -> Result<()>;
+
+    /// Removes the job from [`watch_schedules`](Self::watch_schedules), if there is one.
+    fn unwatch_schedules(dir: &Path) -> Result<()>;
+
+    /// ID of the job from [`watch_schedules`](Self::watch_schedules), if there is one.
+    fn scheduler(dir: &Path) -> Option<String>;
+
+    /// Registers a job kept running for the whole block, closing blocked apps that are
+    /// started anyway.
+    fn watch_relaunches(state_dir: &Path) -> Result<()>;
+
+    /// Registers a job running every `interval`, updating the remaining-time file.
+    fn update_remaining(state_dir: &Path, interval: Duration) -> Result<()>;
+
+    /// Registers a job kept running for the whole block, serving the countdown page of
+    /// soft-blocked websites.
+    fn serve_friction(state_dir: &Path) -> Result<()>;
+
+    /// Registers a job kept running for the whole block, answering lookups for the
+    /// `dns-proxy` backend.
+    fn serve_dns(state_dir: &Path) -> Result<()>;
+
+    /// Moves the scheduled unblock to another time, leaving the other jobs in place. The
+    /// new job is loaded under a new ID before `then` saves what goes with it, and the
+    /// old one is removed last: failing to load leaves the block as it was, and an
+    /// unblock job rescheduling itself is only ended once everything is in place.
+    fn reschedule(
+        state_dir: &Path,
+        unblock_time: DateTime<Local>,
+        then: impl FnOnce() -> Result<()>,
+    ) -> Result<()>;
+
+    /// Removes the daemon scheduled from the given state directory, along with the
+    /// network watcher and the other jobs.
+    fn remove(state_dir: &Path) -> Result<()>;
+
+    /// IDs of every job registered for the invoking user, including ones left behind
+    /// by a state directory that's gone.
+    fn installed_jobs() -> Result<Vec<String>>;
+
+    /// Removes every job from [`installed_jobs`](Self::installed_jobs), but the ones in
+    /// `keep`.
+    fn remove_all(keep: &[String]) -> Result<()>;
 }
+
+/// Holds the token the scheduled unblock of a strict block hands over, in its state
+/// directory and only readable by root, so lifting it by hand can be told apart.
+pub const UNBLOCK_TOKEN_FILE: &str = "unblock_token";
+
+/// The arguments of the scheduled unblock. The token of a strict block goes on its
+/// stdin, never in the arguments or the job's files.
+const UNBLOCK_ARGS: [&str; 3] = ["___zzzunblock", "--fix", "--scheduled"];
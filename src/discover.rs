@@ -0,0 +1,215 @@
+use anyhow::{Context, Ok, Result};
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::{block::data_dir, sandbox, user::invoking_uid};
+
+/// Bundle identifiers of the apps seen at block start, by path, to find them once moved
+const BUNDLE_IDS_FILE: &str = "bundle_ids";
+
+/// Where apps get installed, relative to the home dir when not absolute
+const APP_DIRS: &[&str] = &[
+    "/Applications",
+    "/Applications/Setapp",
+    "/opt/homebrew/Caskroom",
+    "/usr/local/Caskroom",
+    "/run/current-system/Applications",
+    "Applications",
+    "Applications/Home Manager Apps",
+    ".nix-profile/Applications",
+];
+/// Caskroom keeps apps under `<cask>/<version>/`, so it needs a deeper scan
+const MAX_DEPTH: usize = 3;
+
+#[derive(Debug, Clone)]
+pub struct InstalledApp {
+    pub path: PathBuf,
+    pub bundle_id: Option<String>,
+    /// Architectures of the main executable, e.g. `arm64 x86_64`
+    pub archs: Option<String>,
+    /// App Store category, e.g. `public.app-category.games`
+    pub category: Option<String>,
+}
+
+/// The usual install locations, whether they exist or not.
+pub fn app_dirs() -> Vec<PathBuf> {
+    let home = env::home_dir().unwrap_or_default();
+    APP_DIRS
+        .iter()
+        .map(|dir| {
+            let dir = Path::new(dir);
+            if dir.is_absolute() {
+                sandbox::path(dir)
+            } else {
+                home.join(dir)
+            }
+        })
+        .collect()
+}
+
+/// Lists installed applications from the usual install locations, sorted by name.
+pub fn installed_apps() -> Vec<InstalledApp> {
+    let mut paths = Vec::new();
+    for dir in app_dirs() {
+        collect_apps(&dir, MAX_DEPTH, &mut paths);
+    }
+
+    // nix and Caskroom apps are often symlinked into the other locations
+    paths.sort_by_key(|path| fs::canonicalize(path).unwrap_or_else(|_| path.clone()));
+    paths.dedup_by_key(|path| fs::canonicalize(&*path).unwrap_or_else(|_| path.clone()));
+    paths.sort_by_key(|path| path.file_name().map(|name| name.to_ascii_lowercase()));
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let info_plist = path.join("Contents/Info.plist");
+            let bundle_id = bundle_id(&path);
+            let category = read_plist_key(&info_plist, "LSApplicationCategoryType");
+            let archs = read_plist_key(&info_plist, "CFBundleExecutable").and_then(|executable| {
+                executable_archs(&path.join("Contents/MacOS").join(executable))
+            });
+
+            InstalledApp {
+                path,
+                bundle_id,
+                archs,
+                category,
+            }
+        })
+        .collect()
+}
+
+fn collect_apps(dir: &Path, depth: usize, paths: &mut Vec<PathBuf>) {
+    let Some(entries) = fs::read_dir(dir).ok() else {
+        return;
+    };
+
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        if path.extension().is_some_and(|ext| ext == "app") {
+            paths.push(path);
+        } else if depth > 1 && path.is_dir() {
+            collect_apps(&path, depth - 1, paths);
+        }
+    }
+}
+
+/// The bundle identifier of the app at `app`, e.g. `com.valvesoftware.steam`.
+pub fn bundle_id(app: &Path) -> Option<String> {
+    read_plist_key(&app.join("Contents/Info.plist"), "CFBundleIdentifier")
+}
+
+/// Remembers the bundle identifiers of `apps`, so they're found by [`locate_moved`]
+/// after a reinstall or move. Apps that aren't there keep what was remembered before.
+pub fn remember_bundle_ids(apps: &[PathBuf]) -> Result<()> {
+    let mut bundle_ids = remembered_bundle_ids();
+    let mut changed = false;
+    for app in apps.iter().filter(|app| app.exists()) {
+        if let Some(bundle_id) = bundle_id(app) {
+            changed |= bundle_ids.insert(app.clone(), bundle_id.clone()) != Some(bundle_id);
+        }
+    }
+    if !changed {
+        return Ok(());
+    }
+
+    fs::create_dir_all(data_dir())?;
+    fs::write(bundle_ids_path(), serde_yml::to_string(&bundle_ids)?)?;
+    Ok(())
+}
+
+/// Whether [`locate_moved`] could look for `app`, its bundle identifier was seen before.
+pub fn has_remembered_bundle_id(app: &Path) -> bool {
+    remembered_bundle_ids().contains_key(app)
+}
+
+/// Where the app last seen at `app` is now, looked up through Spotlight by its
+/// remembered bundle identifier.
+pub fn locate_moved(app: &Path) -> Option<PathBuf> {
+    let bundle_id = remembered_bundle_ids().remove(app)?;
+    let output = Command::new("mdfind")
+        .arg(format!("kMDItemCFBundleIdentifier == '{}'", bundle_id))
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(PathBuf::from)
+        .find(|path| path.extension().is_some_and(|ext| ext == "app") && path.exists())
+}
+
+fn remembered_bundle_ids() -> BTreeMap<PathBuf, String> {
+    fs::read_to_string(bundle_ids_path())
+        .ok()
+        .and_then(|content| serde_yml::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn bundle_ids_path() -> PathBuf {
+    data_dir().join(format!("{}.{}", BUNDLE_IDS_FILE, invoking_uid()))
+}
+
+fn read_plist_key(plist: &Path, key: &str) -> Option<String> {
+    let output = Command::new("plutil")
+        .args(["-extract", key, "raw", "-o", "-"])
+        .arg(plist)
+        .output()
+        .ok()?;
+
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn executable_archs(executable: &Path) -> Option<String> {
+    let output = Command::new("lipo")
+        .arg("-archs")
+        .arg(executable)
+        .output()
+        .ok()?;
+
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Parses a selection like `1,3,5-7` into zero-based indices below `len`.
+pub fn parse_selection(input: &str, len: usize) -> Result<Vec<usize>> {
+    let mut indices = Vec::new();
+    for part in input
+        .split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+    {
+        let (start, end) = match part.split_once('-') {
+            Some((start, end)) => (start.trim(), end.trim()),
+            None => (part, part),
+        };
+
+        let start = start
+            .parse::<usize>()
+            .with_context(|| format!("Invalid selection `{}`", part))?;
+        let end = end
+            .parse::<usize>()
+            .with_context(|| format!("Invalid selection `{}`", part))?;
+
+        if start == 0 || end > len || start > end {
+            anyhow::bail!("Selection `{}` is out of range (1-{})", part, len);
+        }
+
+        indices.extend((start - 1)..end);
+    }
+
+    indices.sort();
+    indices.dedup();
+    Ok(indices)
+}
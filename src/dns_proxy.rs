@@ -0,0 +1,465 @@
+use anyhow::{Context, Ok, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    net::{Ipv4Addr, SocketAddr, UdpSocket},
+    path::{Path, PathBuf},
+    sync::Arc,
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use crate::{at_rest, block::BlockManager, sandbox};
+
+/// Where the proxy listens, and what the system resolvers are pointed at
+const LISTEN_ADDR: (Ipv4Addr, u16) = (Ipv4Addr::LOCALHOST, 53);
+const DOMAINS_FILE: &str = "dns_proxy_domains";
+/// The resolvers answering what isn't blocked, the ones in use before the block
+const UPSTREAMS_FILE: &str = "dns_proxy_upstreams";
+const SAVED_RESOLVERS_FILE: &str = "dns_proxy_saved.yaml";
+/// Lists the resolvers in use, on Linux and (generated by configd) on macOS
+const RESOLV_CONF: &str = "/etc/resolv.conf";
+const UPSTREAM_TIMEOUT: Duration = Duration::from_secs(3);
+/// Big enough for EDNS answers, which clients would otherwise retry over TCP
+const MAX_PACKET: usize = 4096;
+const TYPE_A: u16 = 1;
+const TYPE_AAAA: u16 = 28;
+const RCODE_NXDOMAIN: u16 = 3;
+
+/// How the system resolved names before the block, put back at the unblock.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SavedResolvers {
+    /// DNS servers of each macOS network service, none for the ones set by DHCP
+    #[serde(default)]
+    services: BTreeMap<String, Vec<String>>,
+    /// Where `/etc/resolv.conf` linked to on Linux, like systemd-resolved's stub file
+    #[serde(default)]
+    resolv_conf_link: Option<PathBuf>,
+    /// What `/etc/resolv.conf` held on Linux, when it wasn't a link
+    #[serde(default)]
+    resolv_conf: Option<String>,
+}
+
+/// The local DNS server of a block, answering `0.0.0.0` for blocked domains and all
+/// their subdomains, and the system resolvers pointed at it. Both are tracked in the
+/// state directory so the unblock can put the resolvers back.
+pub struct DnsProxy {
+    domains_path: PathBuf,
+    upstreams_path: PathBuf,
+    saved_path: PathBuf,
+}
+
+impl DnsProxy {
+    pub fn new(state_dir: &Path) -> Self {
+        Self {
+            domains_path: state_dir.join(DOMAINS_FILE),
+            upstreams_path: state_dir.join(UPSTREAMS_FILE),
+            saved_path: state_dir.join(SAVED_RESOLVERS_FILE),
+        }
+    }
+
+    /// Blocks `domains` through the proxy, pointing the resolvers that don't go through
+    /// it yet at it, like ones of a network that came up since. Returns `true` if
+    /// anything changed.
+    pub fn apply(&self, domains: &[String]) -> Result<bool> {
+        // subdomains are covered anyway, `www.` included
+        let content = domains
+            .iter()
+            .map(|domain| domain.trim_start_matches("www."))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>()
+            .join("\n");
+        // compared opened, a sealed copy differs every time
+        let mut changed = fs::read_to_string(&self.domains_path)
+            .ok()
+            .and_then(|written| at_rest::open(written).ok())
+            .as_ref()
+            != Some(&content);
+        if changed {
+            fs::write(&self.domains_path, at_rest::seal(&content)?)?;
+        }
+
+        if !self.upstreams_path.exists() {
+            let upstreams = nameservers();
+            if upstreams.is_empty() {
+                anyhow::bail!(
+                    "No DNS servers in {} to answer what isn't blocked",
+                    RESOLV_CONF
+                );
+            }
+            fs::write(&self.upstreams_path, upstreams.join("\n"))?;
+        }
+
+        let mut saved = self.saved()?;
+        if point_resolvers(&mut saved)? {
+            fs::write(&self.saved_path, serde_yml::to_string(&saved)?)?;
+            changed = true;
+        }
+
+        Ok(changed)
+    }
+
+    /// Puts the resolvers back as they were before the block.
+    pub fn clear(&self) -> Result<()> {
+        if self.saved_path.exists() {
+            restore_resolvers(&self.saved()?)?;
+            fs::remove_file(&self.saved_path)?;
+        }
+        for path in [&self.domains_path, &self.upstreams_path] {
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn saved(&self) -> Result<SavedResolvers> {
+        if !self.saved_path.exists() {
+            return Ok(SavedResolvers::default());
+        }
+        Ok(serde_yml::from_str(&fs::read_to_string(&self.saved_path)?)?)
+    }
+}
+
+/// Answers lookups on 127.0.0.1 until killed, blocked names right away and the rest
+/// through the resolvers used before the block. Run by the daemon for blocks with the
+/// `dns-proxy` backend.
+pub fn serve() -> Result<()> {
+    let state_dir = BlockManager::new().state_dir;
+    let proxy = DnsProxy::new(&state_dir);
+    let socket = UdpSocket::bind(LISTEN_ADDR)
+        .with_context(|| format!("Failed to listen on {}:{}", LISTEN_ADDR.0, LISTEN_ADDR.1))?;
+    let upstreams = fs::read_to_string(&proxy.upstreams_path)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|server| format!("{}:53", server).parse::<SocketAddr>().ok())
+        .collect::<Vec<_>>();
+    let upstreams = Arc::new(upstreams);
+
+    // reread when the block changes, like when a domain is allowed for a while
+    let modified = || {
+        fs::metadata(&proxy.domains_path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    };
+    let mut blocked = (modified(), read_domains(&proxy.domains_path)?);
+    let mut query = [0; MAX_PACKET];
+    loop {
+        let Some((len, client)) = socket.recv_from(&mut query).ok() else {
+            continue;
+        };
+        let modified = modified();
+        if modified != blocked.0 {
+            // a list that can't be read, say without the key, doesn't let everything
+            // through, the one from before stays
+            match read_domains(&proxy.domains_path) {
+                Result::Ok(domains) => blocked = (modified, domains),
+                Err(err) => {
+                    eprintln!("Keeping the blocked domains from before: {:#}", err);
+                    blocked.0 = modified;
+                }
+            }
+        }
+
+        let query = query[..len].to_vec();
+        if let Some(answer) = answer(&query, &blocked.1) {
+            let _ = socket.send_to(&answer, client);
+            continue;
+        }
+        let socket = socket.try_clone()?;
+        let upstreams = Arc::clone(&upstreams);
+        thread::spawn(move || {
+            if let Some(reply) = forward(&query, &upstreams) {
+                let _ = socket.send_to(&reply, client);
+            }
+        });
+    }
+}
+
+/// The blocked domains, none while there's no list. One that can't be read or decrypted
+/// is an error.
+fn read_domains(path: &Path) -> Result<Vec<String>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(at_rest::open(content)?
+        .lines()
+        .map(|domain| domain.trim().to_lowercase())
+        .filter(|domain| !domain.is_empty())
+        .collect())
+}
+
+/// The answer to `query` if it asks for a blocked name or a subdomain of one: `0.0.0.0`
+/// or `::` for addresses, no such name for anything else. `None` for the rest, and for
+/// what isn't a plain query.
+fn answer(query: &[u8], blocked: &[String]) -> Option<Vec<u8>> {
+    let field = |at: usize| Some(u16::from_be_bytes([*query.get(at)?, *query.get(at + 1)?]));
+    let flags = field(2)?;
+    // standard queries of one question only
+    if flags & 0x8000 != 0 || flags & 0x7800 != 0 || field(4)? != 1 {
+        return None;
+    }
+
+    let mut labels = Vec::new();
+    let mut at = 12;
+    loop {
+        let len = *query.get(at)? as usize;
+        at += 1;
+        if len == 0 {
+            break;
+        }
+        // compression has nothing to point back to in a question
+        if len > 63 {
+            return None;
+        }
+        labels.push(String::from_utf8_lossy(query.get(at..at + len)?).to_lowercase());
+        at += len;
+    }
+    let name = labels.join(".");
+    let qtype = field(at)?;
+    let question_end = at + 4;
+    query.get(..question_end)?;
+
+    let is_blocked = blocked.iter().any(|domain| {
+        name == *domain
+            || name
+                .strip_suffix(domain.as_str())
+                .is_some_and(|rest| rest.ends_with('.'))
+    });
+    if !is_blocked {
+        return None;
+    }
+
+    let address: &[u8] = match qtype {
+        TYPE_A => &[0; 4],
+        TYPE_AAAA => &[0; 16],
+        _ => &[],
+    };
+    // a response, recursion desired as asked and available, an answer only for addresses
+    let rcode = if address.is_empty() {
+        RCODE_NXDOMAIN
+    } else {
+        0
+    };
+    let mut response = Vec::with_capacity(question_end + 16 + address.len());
+    response.extend_from_slice(&query[..2]);
+    response.extend_from_slice(&(0x8080 | flags & 0x0100 | rcode).to_be_bytes());
+    response.extend_from_slice(&[0, 1, 0, u8::from(!address.is_empty()), 0, 0, 0, 0]);
+    response.extend_from_slice(&query[12..question_end]);
+    if !address.is_empty() {
+        // the name of the question, class IN, not to be cached past the block
+        response.extend_from_slice(&[0xc0, 12]);
+        response.extend_from_slice(&qtype.to_be_bytes());
+        response.extend_from_slice(&[0, 1, 0, 0, 0, 0]);
+        response.extend_from_slice(&(address.len() as u16).to_be_bytes());
+        response.extend_from_slice(address);
+    }
+    Some(response)
+}
+
+/// Asks the upstream resolvers in turn, returning the first reply.
+fn forward(query: &[u8], upstreams: &[SocketAddr]) -> Option<Vec<u8>> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).ok()?;
+    socket.set_read_timeout(Some(UPSTREAM_TIMEOUT)).ok()?;
+    let mut reply = [0; MAX_PACKET];
+    for upstream in upstreams {
+        if socket.send_to(query, upstream).is_err() {
+            continue;
+        }
+        let started = SystemTime::now();
+        while started.elapsed().unwrap_or_default() < UPSTREAM_TIMEOUT {
+            let Some((len, from)) = socket.recv_from(&mut reply).ok() else {
+                break;
+            };
+            // replies to other queries, or from elsewhere, aren't ours to relay
+            if from == *upstream && len >= 2 && reply[..2] == query[..2] {
+                return Some(reply[..len].to_vec());
+            }
+        }
+    }
+    None
+}
+
+/// The name servers of `/etc/resolv.conf`, but the proxy's own address.
+fn nameservers() -> Vec<String> {
+    fs::read_to_string(sandbox::path(RESOLV_CONF))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("nameserver"))
+        .map(|server| server.trim().to_string())
+        .filter(|server| !server.is_empty() && server != "127.0.0.1" && server != "::1")
+        .collect()
+}
+
+/// Points every network service's DNS servers at the proxy, saving the ones that
+/// didn't yet. Returns whether any did.
+#[cfg(target_os = "macos")]
+fn point_resolvers(saved: &mut SavedResolvers) -> Result<bool> {
+    let proxy = LISTEN_ADDR.0.to_string();
+    let mut changed = false;
+    for service in network_services()? {
+        let servers = dns_servers(&service)?;
+        if servers == [proxy.as_str()] {
+            continue;
+        }
+        saved.services.entry(service.clone()).or_insert(servers);
+        networksetup(&["-setdnsservers", &service, &proxy])?;
+        changed = true;
+    }
+    Ok(changed)
+}
+
+#[cfg(target_os = "macos")]
+fn restore_resolvers(saved: &SavedResolvers) -> Result<()> {
+    for (service, servers) in &saved.services {
+        let mut args = vec!["-setdnsservers", service.as_str()];
+        if servers.is_empty() {
+            // back to the servers DHCP hands out
+            args.push("Empty");
+        }
+        args.extend(servers.iter().map(String::as_str));
+        if let Err(err) = networksetup(&args) {
+            eprintln!(
+                "Warning: couldn't put back the DNS servers of {}: {:#}",
+                service, err
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Enabled network services, disabled ones are listed with a `*`.
+#[cfg(target_os = "macos")]
+fn network_services() -> Result<Vec<String>> {
+    Ok(networksetup(&["-listallnetworkservices"])?
+        .lines()
+        // the first line explains the asterisk
+        .skip(1)
+        .filter(|service| !service.is_empty() && !service.starts_with('*'))
+        .map(str::to_string)
+        .collect())
+}
+
+/// The DNS servers set for `service`, none when they come from DHCP.
+#[cfg(target_os = "macos")]
+fn dns_servers(service: &str) -> Result<Vec<String>> {
+    Ok(networksetup(&["-getdnsservers", service])?
+        .lines()
+        .map(str::trim)
+        .filter(|server| server.parse::<std::net::IpAddr>().is_ok())
+        .map(str::to_string)
+        .collect())
+}
+
+#[cfg(target_os = "macos")]
+fn networksetup(args: &[&str]) -> Result<String> {
+    let output = sandbox::command("networksetup").args(args).output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("networksetup {} failed: {}", args[0], stderr.trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Points `/etc/resolv.conf` at the proxy, saving what it was unless it was saved
+/// already. Returns whether it didn't point there yet.
+#[cfg(target_os = "linux")]
+fn point_resolvers(saved: &mut SavedResolvers) -> Result<bool> {
+    let path = sandbox::path(RESOLV_CONF);
+    let ours = format!(
+        "# Blocking through enough's DNS proxy until the block ends\nnameserver {}\n",
+        LISTEN_ADDR.0
+    );
+    if fs::read_to_string(&path).ok().as_ref() == Some(&ours) && !path.is_symlink() {
+        return Ok(false);
+    }
+
+    if saved.resolv_conf_link.is_none() && saved.resolv_conf.is_none() {
+        match fs::read_link(&path).ok() {
+            Some(link) => saved.resolv_conf_link = Some(link),
+            None => saved.resolv_conf = Some(fs::read_to_string(&path).unwrap_or_default()),
+        }
+    }
+    // replacing the link rather than writing through it, into systemd-resolved's file
+    if path.is_symlink() {
+        fs::remove_file(&path)?;
+    }
+    fs::write(&path, ours).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(true)
+}
+
+#[cfg(target_os = "linux")]
+fn restore_resolvers(saved: &SavedResolvers) -> Result<()> {
+    let path = sandbox::path(RESOLV_CONF);
+    if let Some(link) = &saved.resolv_conf_link {
+        if path.exists() || path.is_symlink() {
+            fs::remove_file(&path)?;
+        }
+        std::os::unix::fs::symlink(link, &path)
+            .with_context(|| format!("Failed to link {} back", path.display()))?;
+    } else if let Some(content) = &saved.resolv_conf {
+        fs::write(&path, content).with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use uuid::Uuid;
+
+    /// A query for `name` of `qtype`, with recursion desired.
+    fn query(name: &str, qtype: u16) -> Vec<u8> {
+        let mut query = vec![0xab, 0xcd, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0];
+        for label in name.split('.') {
+            query.push(label.len() as u8);
+            query.extend_from_slice(label.as_bytes());
+        }
+        query.push(0);
+        query.extend_from_slice(&qtype.to_be_bytes());
+        query.extend_from_slice(&[0, 1]);
+        query
+    }
+
+    #[test]
+    fn blocked_domains_and_their_subdomains_are_sunk() {
+        let blocked = ["youtube.com".to_string()];
+
+        let a = answer(&query("music.YouTube.com", TYPE_A), &blocked).unwrap();
+        assert_eq!(a[..4], [0xab, 0xcd, 0x81, 0x80]);
+        // one question, one answer
+        assert_eq!(a[4..8], [0, 1, 0, 1]);
+        assert_eq!(a[a.len() - 6..], [0, 4, 0, 0, 0, 0]);
+
+        let aaaa = answer(&query("youtube.com", TYPE_AAAA), &blocked).unwrap();
+        assert_eq!(aaaa[aaaa.len() - 18..aaaa.len() - 16], [0, 16]);
+
+        let https = answer(&query("www.youtube.com", 65), &blocked).unwrap();
+        assert_eq!(https[3] & 0x0f, RCODE_NXDOMAIN as u8);
+        assert_eq!(https[6..8], [0, 0]);
+
+        assert!(answer(&query("notyoutube.com", TYPE_A), &blocked).is_none());
+        assert!(answer(&query("youtube.com.example", TYPE_A), &blocked).is_none());
+        assert!(answer(&[0xab, 0xcd, 0x81, 0x80], &blocked).is_none());
+    }
+
+    #[test]
+    fn a_list_that_can_t_be_decrypted_is_an_error_not_an_empty_one() -> Result<()> {
+        let path = env::temp_dir().join(format!("enough-dns-proxy-{}", Uuid::new_v4()));
+        assert!(read_domains(&path)?.is_empty());
+
+        fs::write(&path, "YouTube.com\n\nreddit.com\n")?;
+        assert_eq!(read_domains(&path)?, ["youtube.com", "reddit.com"]);
+
+        fs::write(&path, "enough-encrypted:1\nnot base64 from openssl")?;
+        let read = read_domains(&path);
+        fs::remove_file(&path)?;
+        assert!(read.is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,150 @@
+use anyhow::{Ok, Result};
+use std::fmt::Display;
+
+use crate::{
+    block::{self, BlockManager},
+    config::WebsitesBackend,
+    daemon::{EnoughDaemon, UnblockingDaemon as _},
+    hosts::{self, HostsSection},
+    output,
+    resolver::ResolverFiles,
+    steps::Step,
+    third_party,
+    user::invoking_uid,
+};
+
+/// One aspect of the system that should match the block state.
+pub struct Check {
+    pub name: &'static str,
+    /// What's wrong, `None` when the check passed
+    pub problem: Option<String>,
+    /// How `enough doctor --fix` puts it right, `None` when it can't
+    pub repair: Option<Repair>,
+}
+
+pub enum Repair {
+    /// Running these steps again, see [`BlockManager::repair`]
+    Steps(&'static [Step]),
+    /// Finishing the run that stopped halfway, see [`BlockManager::recover`]
+    Recover,
+}
+
+impl Display for Check {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.problem {
+            Some(problem) => write!(
+                f,
+                "{} {}: {}",
+                output::flagged("Failed:"),
+                self.name,
+                problem
+            ),
+            None if output::is_plain() => write!(f, "Passed: {}", self.name),
+            None => write!(f, "✓ {}", self.name),
+        }
+    }
+}
+
+/// Checks that what's applied on the system matches the invoking user's block state:
+/// everything in place during a block, nothing left behind otherwise. Also looks for
+/// other blockers that could be fighting over the hosts file.
+pub fn checks() -> Result<Vec<Check>> {
+    let block_manager = BlockManager::new();
+    let backend = block_manager.websites_backend()?;
+    let uid = invoking_uid();
+
+    let section = HostsSection::new(&block_manager.state_dir, uid);
+    let hosts_present = section.is_present()?;
+    let resolver = ResolverFiles::new(&block_manager.state_dir, uid);
+    let persistent = block::persistent_jobs(uid);
+    let mut jobs = EnoughDaemon::installed_jobs()?;
+    jobs.retain(|job| !persistent.contains(job));
+    let missing_jobs = EnoughDaemon::missing_jobs(&block_manager.state_dir);
+    let others = third_party::active_tools();
+    let unfinished = block_manager.unfinished_run()?;
+
+    let hosts_section = match backend {
+        Some(WebsitesBackend::Hosts) if section.is_managed()? && !hosts_present => {
+            match hosts::check_editable() {
+                Err(err) => Some(format!("{:#}", err)),
+                _ => Some(format!("missing from {}", hosts::HOSTS_FILE)),
+            }
+        }
+        None if hosts_present => Some("left behind by an earlier block".to_string()),
+        _ => None,
+    };
+
+    let missing = resolver.missing()?;
+    let orphaned = resolver.orphaned()?;
+    let resolver_files = if !missing.is_empty() {
+        Some(format!("missing for {}", missing.join(", ")))
+    } else if !orphaned.is_empty() {
+        Some(format!(
+            "{} left behind by an earlier block",
+            orphaned.len()
+        ))
+    } else {
+        None
+    };
+
+    let scheduled_jobs = match backend {
+        Some(_) if jobs.is_empty() => Some("the block has no scheduled unblock".to_string()),
+        Some(_) if !missing_jobs.is_empty() => Some(format!(
+            "{} of the block's jobs were removed",
+            missing_jobs.len()
+        )),
+        None if !jobs.is_empty() => Some(format!("{} left behind by an earlier block", jobs.len())),
+        _ => None,
+    };
+
+    Ok(vec![
+        Check {
+            name: "last run",
+            problem: unfinished.map(|record| record.to_string()),
+            repair: Some(Repair::Recover),
+        },
+        Check {
+            name: "hosts section",
+            problem: hosts_section,
+            repair: Some(Repair::Steps(&[Step::Hosts, Step::DnsFlush])),
+        },
+        Check {
+            name: "resolver files",
+            problem: resolver_files,
+            repair: Some(Repair::Steps(&[Step::Hosts, Step::DnsFlush])),
+        },
+        Check {
+            name: "scheduled jobs",
+            problem: scheduled_jobs,
+            // rescheduling takes the saved state with it
+            repair: Some(Repair::Steps(&[Step::Daemon, Step::State])),
+        },
+        Check {
+            name: "other blockers",
+            problem: (!others.is_empty()).then(|| {
+                let others = others.iter().map(ToString::to_string).collect::<Vec<_>>();
+                format!("{} may be blocking too", others.join(", "))
+            }),
+            repair: None,
+        },
+    ])
+}
+
+/// Repairs the failed `checks` that can be, through the same steps blocks and unblocks
+/// go through. Returns the names of the checks it repaired.
+pub fn fix(checks: &[Check]) -> Result<Vec<&'static str>> {
+    let block_manager = BlockManager::new();
+    let mut repaired = Vec::new();
+    for check in checks.iter().filter(|check| check.problem.is_some()) {
+        match &check.repair {
+            Some(Repair::Recover) => {
+                block_manager.recover()?;
+            }
+            Some(Repair::Steps(steps)) => block_manager.repair(steps)?,
+            None => continue,
+        }
+        repaired.push(check.name);
+    }
+
+    Ok(repaired)
+}
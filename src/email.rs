@@ -0,0 +1,82 @@
+use anyhow::{Context, Ok, Result};
+use std::{
+    io::Write as _,
+    process::{Command, Stdio},
+};
+use uuid::Uuid;
+
+use crate::config::EmailConfig;
+
+/// Sends a message with both a text and an HTML part, through the configured SMTP
+/// server or the local `sendmail` without one.
+pub fn send(config: &EmailConfig, to: &str, subject: &str, text: &str, html: &str) -> Result<()> {
+    let from = config.from.as_deref().unwrap_or(to);
+    let boundary = format!("enough-{}", Uuid::new_v4());
+    let message = format!(
+        "From: {from}\nTo: {to}\nSubject: {subject}\nMIME-Version: 1.0\n\
+        Content-Type: multipart/alternative; boundary=\"{boundary}\"\n\n\
+        --{boundary}\nContent-Type: text/plain; charset=utf-8\n\n{text}\n\
+        --{boundary}\nContent-Type: text/html; charset=utf-8\n\n{html}\n\
+        --{boundary}--\n"
+    );
+
+    let Some(smtp) = &config.smtp else {
+        let mut command = Command::new("sendmail");
+        command.arg("-t");
+        return pipe(command, &message, "sendmail");
+    };
+
+    let mut command = Command::new("curl");
+    command
+        .args(["--silent", "--show-error", "--ssl-reqd", "--max-time", "30"])
+        .args(["--url", smtp.as_str()])
+        .args(["--mail-from", from, "--mail-rcpt", to])
+        .args(["--upload-file", "-"]);
+    if let Some(username) = &config.username {
+        let password = match &config.password_command {
+            Some(password_command) => smtp_password(password_command)?,
+            None => String::new(),
+        };
+        command.args(["--user", &format!("{}:{}", username, password)]);
+    }
+
+    // SMTP wants CRLF line endings, which sendmail adds itself
+    pipe(command, &message.replace('\n', "\r\n"), "curl")
+}
+
+/// Runs `password_command` through the shell, so the password doesn't sit in the config.
+fn smtp_password(password_command: &str) -> Result<String> {
+    let output = Command::new("sh")
+        .args(["-c", password_command])
+        .output()
+        .with_context(|| "Failed to run the SMTP password command")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("SMTP password command failed: {}", stderr.trim());
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn pipe(mut command: Command, input: &str, program: &str) -> Result<()> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run {}", program))?;
+    child
+        .stdin
+        .take()
+        .with_context(|| format!("Failed to write to {}", program))?
+        .write_all(input.as_bytes())?;
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Failed to send the email: {}", stderr.trim());
+    }
+
+    Ok(())
+}
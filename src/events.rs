@@ -0,0 +1,188 @@
+use anyhow::{Context, Ok, Result};
+use chrono::{DateTime, Local, TimeDelta};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{BufRead as _, BufReader, Seek as _, SeekFrom, Write as _},
+    path::PathBuf,
+    thread,
+    time::Duration,
+};
+
+use crate::{block::data_dir, user::invoking_uid};
+
+const EVENTS_FILE: &str = "events";
+/// Bumped whenever a field is renamed, removed or changes meaning. Adding fields doesn't
+/// count, so processors should ignore the ones they don't know.
+pub const SCHEMA_VERSION: u32 = 1;
+const FOLLOW_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A line of the event log, for `enough events tail` and whatever else wants to follow
+/// the blocks.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Event {
+    pub schema: u32,
+    pub time: DateTime<Local>,
+    #[serde(flatten)]
+    pub kind: EventKind,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum EventKind {
+    BlockStarted {
+        profile_name: String,
+        unblock_time: DateTime<Local>,
+        websites: usize,
+        apps: usize,
+    },
+    /// Something undid part of the block before its time
+    TamperDetected {
+        profile_name: String,
+        /// What was undone: `hosts_section`, `clock`, `app`, `app_reinstall`, `config` or
+        /// `job`
+        target: String,
+        /// The app moved to the Trash, deleted or reinstalled, or the config for `config`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        path: Option<PathBuf>,
+    },
+    /// The scheduled unblock failed and will be retried
+    UnblockFailed {
+        profile_name: Option<String>,
+        attempt: u32,
+        error: String,
+    },
+}
+
+fn events_path() -> PathBuf {
+    data_dir().join(format!("{}.{}.jsonl", EVENTS_FILE, invoking_uid()))
+}
+
+/// Appends an event to the invoking user's log. Logging is best-effort, failing to
+/// write an event shouldn't fail what it describes.
+pub fn emit(kind: EventKind) {
+    let event = Event {
+        schema: SCHEMA_VERSION,
+        time: Local::now(),
+        kind,
+    };
+    let result = (|| {
+        fs::create_dir_all(data_dir())?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(events_path())?;
+        writeln!(file, "{}", serde_json::to_string(&event)?)?;
+        Ok(())
+    })();
+
+    if let Err(err) = result {
+        eprintln!("Warning: couldn't log the event: {:#}", err);
+    }
+}
+
+/// Removes the events older than `older_than`, every one without it. Returns how many
+/// were removed.
+pub fn purge(older_than: Option<Duration>) -> Result<usize> {
+    let path = events_path();
+    if !path.exists() {
+        return Ok(0);
+    }
+    let cutoff = older_than
+        .and_then(|older_than| TimeDelta::from_std(older_than).ok())
+        .map(|older_than| Local::now() - older_than);
+
+    let content = fs::read_to_string(&path)?;
+    // lines of events from later versions still have their time
+    let (removed, kept) = content.lines().partition::<Vec<_>, _>(|line| {
+        let time = serde_json::from_str::<serde_json::Value>(line)
+            .ok()
+            .and_then(|event| event["time"].as_str()?.parse::<DateTime<Local>>().ok());
+        match (cutoff, time) {
+            (None, _) => true,
+            (Some(cutoff), Some(time)) => time < cutoff,
+            (Some(_), None) => false,
+        }
+    });
+    if !removed.is_empty() {
+        let kept = kept
+            .iter()
+            .map(|line| format!("{}\n", line))
+            .collect::<String>();
+        fs::write(&path, kept)?;
+    }
+    Ok(removed.len())
+}
+
+/// Prints the last `count` events as JSON lines, then any new ones as they come in when
+/// `follow`.
+pub fn tail(count: usize, follow: bool) -> Result<()> {
+    let path = events_path();
+    if !path.exists() && !follow {
+        return Ok(());
+    }
+
+    let content = fs::read_to_string(&path).unwrap_or_default();
+    let lines = content.lines().collect::<Vec<_>>();
+    for line in &lines[lines.len().saturating_sub(count)..] {
+        println!("{}", line);
+    }
+    if !follow {
+        return Ok(());
+    }
+
+    let mut position = content.len() as u64;
+    loop {
+        thread::sleep(FOLLOW_INTERVAL);
+        let Some(mut file) = File::open(&path).ok() else {
+            continue;
+        };
+        // the log was removed and started over
+        if file.metadata()?.len() < position {
+            position = 0;
+        }
+
+        file.seek(SeekFrom::Start(position))?;
+        let mut reader = BufReader::new(file);
+        let mut line = String::new();
+        while reader
+            .read_line(&mut line)
+            .with_context(|| format!("Failed to read {}", path.display()))?
+            > 0
+        {
+            // a line still being written is picked up once it's complete
+            if !line.ends_with('\n') {
+                break;
+            }
+            position += line.len() as u64;
+            print!("{}", line);
+            line.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_keep_their_schema() {
+        let event = Event {
+            schema: SCHEMA_VERSION,
+            time: DateTime::parse_from_rfc3339("2025-03-01T09:00:00+01:00")
+                .unwrap()
+                .into(),
+            kind: EventKind::TamperDetected {
+                profile_name: "lock-in".to_string(),
+                target: "hosts_section".to_string(),
+                path: None,
+            },
+        };
+
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["schema"], 1);
+        assert_eq!(json["event"], "tamper_detected");
+        assert_eq!(json["profile_name"], "lock-in");
+        assert_eq!(json["target"], "hosts_section");
+    }
+}
@@ -0,0 +1,53 @@
+use anyhow::{Context, Ok, Result};
+use std::{fs, path::Path};
+
+use crate::{config::SilenceNotifications, user::as_invoking_user};
+
+/// Remembers which shortcut turns the Focus back off, since unblocking doesn't read the
+/// profile
+const FOCUS_OFF_FILE: &str = "focus_off";
+
+/// Runs the user's "on" shortcut with the apps to silence as input. The shortcut is
+/// expected to turn on a Focus filtering notifications from those apps.
+pub fn silence(silence: &SilenceNotifications, state_dir: &Path) -> Result<()> {
+    let input_path = state_dir.join("silenced_apps.txt");
+    fs::write(&input_path, silence.apps.join("\n"))?;
+
+    run_shortcut(&silence.on_shortcut, Some(&input_path))?;
+    fs::write(state_dir.join(FOCUS_OFF_FILE), &silence.off_shortcut)?;
+
+    Ok(())
+}
+
+/// Runs the "off" shortcut recorded when notifications were silenced, if any.
+pub fn restore(state_dir: &Path) -> Result<()> {
+    let off_file = state_dir.join(FOCUS_OFF_FILE);
+    if !off_file.exists() {
+        return Ok(());
+    }
+
+    let off_shortcut = fs::read_to_string(&off_file)?;
+    run_shortcut(off_shortcut.trim(), None)?;
+    fs::remove_file(off_file)?;
+
+    Ok(())
+}
+
+fn run_shortcut(name: &str, input_path: Option<&Path>) -> Result<()> {
+    let mut command = as_invoking_user("shortcuts");
+    command.args(["run", name]);
+    if let Some(input_path) = input_path {
+        command.arg("--input-path").arg(input_path);
+    }
+
+    let output = command
+        .output()
+        .with_context(|| "Failed to run the shortcuts command")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Shortcut `{}` failed: {}", name, stderr.trim());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,254 @@
+use anyhow::{Context, Ok, Result};
+use std::{
+    collections::HashMap,
+    io::{self, BufRead as _, BufReader, Write as _},
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+use uuid::Uuid;
+
+use crate::{block::BlockManager, config::Friction, history, output, time_format::TimeFormat};
+
+/// Where soft-blocked domains resolve to, both have to be listened on
+const LISTEN_ADDRS: &[&str] = &["127.0.0.1:80", "[::1]:80"];
+const CONTINUE_PATH: &str = "/__enough/continue";
+/// What the `enough unblock-early` phrase is made of, short words that are easy to read
+/// and tedious to type in a row
+const PHRASE_WORDS: [&str; 64] = [
+    "amber", "anchor", "apple", "arrow", "autumn", "badge", "basket", "beacon", "birch", "blanket",
+    "bottle", "branch", "breeze", "bridge", "candle", "canyon", "carpet", "castle", "cedar",
+    "cherry", "cloud", "copper", "coral", "cotton", "desert", "dinner", "dragon", "feather",
+    "forest", "garden", "ginger", "glacier", "hammer", "harbor", "helmet", "island", "jacket",
+    "jungle", "kettle", "ladder", "lantern", "lemon", "marble", "meadow", "mirror", "monkey",
+    "needle", "orange", "paddle", "pepper", "pillow", "planet", "pocket", "puzzle", "rabbit",
+    "ribbon", "river", "saddle", "silver", "spider", "tunnel", "velvet", "window", "winter",
+];
+/// Words in the `enough unblock-early` phrase
+const PHRASE_LENGTH: usize = 12;
+
+/// Serves the countdown page for soft-blocked websites until killed. Run by the daemon
+/// for blocks with `friction`.
+///
+/// Only plain HTTP can be answered, HTTPS-only websites fail to load instead, like they
+/// would in a hard block.
+pub fn serve() -> Result<()> {
+    let listeners = LISTEN_ADDRS
+        .iter()
+        .map(|addr| {
+            TcpListener::bind(addr).with_context(|| format!("Failed to listen on {}", addr))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    // when each host's countdown started, so continuing can't skip it
+    let countdowns = Arc::new(Mutex::new(HashMap::new()));
+    let handles = listeners
+        .into_iter()
+        .map(|listener| {
+            let countdowns = Arc::clone(&countdowns);
+            thread::spawn(move || {
+                for stream in listener.incoming().filter_map(|stream| stream.ok()) {
+                    if let Err(err) = handle(stream, &countdowns) {
+                        eprintln!("{:#}", err);
+                    }
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for handle in handles {
+        let _ = handle.join();
+    }
+
+    Ok(())
+}
+
+fn handle(mut stream: TcpStream, countdowns: &Mutex<HashMap<String, Instant>>) -> Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let path = request_line
+        .split_whitespace()
+        .nth(1)
+        .unwrap_or("/")
+        .to_string();
+
+    let mut host = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':')
+            && name.eq_ignore_ascii_case("host")
+        {
+            // dropping the port, the domains are blocked on every port
+            let value = value.trim();
+            host = Some(value.split(':').next().unwrap_or(value).to_string());
+        }
+    }
+
+    let block_manager = BlockManager::new();
+    let (Some(host), Some(friction)) = (host, block_manager.friction()?) else {
+        return respond(
+            &mut stream,
+            "404 Not Found",
+            "text/plain",
+            "Nothing to see here",
+        );
+    };
+
+    let mut countdowns = countdowns
+        .lock()
+        .map_err(|_| anyhow::anyhow!("A request handler panicked"))?;
+    let counted_down = countdowns
+        .get(&host)
+        .is_some_and(|start| start.elapsed() >= friction.delay);
+    if path.starts_with(CONTINUE_PATH) && counted_down {
+        countdowns.remove(&host);
+        block_manager.allow_temp(&host, friction.allowance)?;
+        history::count_continue()?;
+        let location = format!("http://{}/", host);
+        write!(
+            stream,
+            "HTTP/1.1 302 Found\r\nLocation: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+            location
+        )?;
+        return Ok(());
+    }
+
+    // reloading the page doesn't restart the countdown
+    countdowns.entry(host.clone()).or_insert_with(Instant::now);
+    respond(
+        &mut stream,
+        "200 OK",
+        "text/html; charset=utf-8",
+        &page(&host, &friction),
+    )
+}
+
+/// A phrase of random words to type before `enough unblock-early` goes ahead.
+pub fn phrase() -> String {
+    Uuid::new_v4().as_bytes()[..PHRASE_LENGTH]
+        .iter()
+        .map(|byte| PHRASE_WORDS[*byte as usize % PHRASE_WORDS.len()])
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Counts `delay` down on the terminal, a second at a time. Stops early, returning
+/// `false`, as soon as `keep_going` doesn't hold anymore.
+pub fn count_down(
+    delay: Duration,
+    time_format: TimeFormat,
+    keep_going: impl Fn() -> Result<bool>,
+) -> Result<bool> {
+    let start = Instant::now();
+    // told once in plain output, rather than counted down in place
+    let plain = output::is_plain();
+    if plain {
+        eprintln!(
+            "Unblocking in {}, Ctrl-C to stay blocked",
+            time_format.format(delay)
+        );
+    }
+    while let Some(left) = delay.checked_sub(start.elapsed()) {
+        if !keep_going()? {
+            if !plain {
+                eprintln!();
+            }
+            return Ok(false);
+        }
+        if !plain {
+            eprint!(
+                "\r\x1b[KUnblocking in {}, Ctrl-C to stay blocked",
+                time_format.format(left + Duration::from_millis(999))
+            );
+            io::stderr().flush()?;
+        }
+        thread::sleep(left.min(Duration::from_secs(1)));
+    }
+    if !plain {
+        eprintln!();
+    }
+    Ok(true)
+}
+
+fn respond(stream: &mut TcpStream, status: &str, content_type: &str, body: &str) -> Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    Ok(())
+}
+
+fn page(host: &str, friction: &Friction) -> String {
+    let note = friction
+        .note
+        .as_deref()
+        .map(|note| format!("<p class=\"note\">{}</p>", escape_html(note)))
+        .unwrap_or_default();
+
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{host} is soft-blocked</title>
+<style>
+body {{ font-family: -apple-system, sans-serif; max-width: 32em; margin: 20vh auto; text-align: center; color: #333; }}
+.note {{ font-size: 1.3em; }}
+#continue {{ visibility: hidden; }}
+</style>
+</head>
+<body>
+<h1>{host} is soft-blocked</h1>
+{note}
+<p id="countdown">{delay}</p>
+<p id="continue"><a href="{CONTINUE_PATH}">Continue anyway for {allowance} minutes (this is logged)</a></p>
+<script>
+let left = {delay};
+const timer = setInterval(() => {{
+    left -= 1;
+    document.getElementById("countdown").textContent = left;
+    if (left <= 0) {{
+        clearInterval(timer);
+        document.getElementById("countdown").textContent = "";
+        document.getElementById("continue").style.visibility = "visible";
+    }}
+}}, 1000);
+</script>
+</body>
+</html>
+"#,
+        host = escape_html(host),
+        note = note,
+        delay = friction.delay.as_secs(),
+        allowance = friction.allowance.as_secs().div_ceil(60),
+    )
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn phrases_are_random_words() {
+        let phrase = phrase();
+        let words = phrase.split(' ').collect::<Vec<_>>();
+        assert_eq!(words.len(), PHRASE_LENGTH);
+        assert!(words.iter().all(|word| PHRASE_WORDS.contains(word)));
+        assert_ne!(phrase, super::phrase());
+    }
+}
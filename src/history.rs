@@ -0,0 +1,248 @@
+use anyhow::{Context as _, Ok, Result};
+use chrono::{DateTime, Local, NaiveTime, TimeDelta};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, OpenOptions},
+    io::Write as _,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use uuid::Uuid;
+
+use crate::{
+    at_rest,
+    block::data_dir,
+    user::{as_invoking_user, invoking_uid},
+};
+
+const HISTORY_FILE: &str = "history";
+/// Version of the history lines, bumped when a field is renamed, removed or changes
+/// meaning
+pub const HISTORY_SCHEMA_VERSION: u32 = 1;
+/// Abort journal screenshots, a directory per user next to the history
+const SCREENSHOTS_DIR: &str = "screenshots";
+/// Where the screenshot is taken from the user's session, which can't write to the data
+/// directory
+const SCREENSHOT_STAGING_DIR: &str = "/tmp";
+
+/// A block that was started, one JSON object per line in the history file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    /// [`HISTORY_SCHEMA_VERSION`] of the version that wrote it, 0 from before there was
+    /// one
+    #[serde(default)]
+    pub schema_version: u32,
+    pub profile_name: String,
+    pub start: DateTime<Local>,
+    /// When the block ended, or is planned to end while it's active
+    pub end: DateTime<Local>,
+    /// The block was lifted with `enough emergency` before its time
+    #[serde(default)]
+    pub ended_early: bool,
+    /// Times a soft block's countdown page was continued past
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub continues: usize,
+    /// Blocked apps reinstalled during the block, which got blocked again
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reinstalled: Vec<PathBuf>,
+    /// What was caught undoing part of the block, e.g. `hosts_section`, once per time
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tampered: Vec<String>,
+    /// Why the block was lifted early, with the config's `abort-journal`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub abort_reason: Option<String>,
+    /// What was on screen when it was lifted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub abort_screenshot: Option<PathBuf>,
+}
+
+fn is_zero(count: &usize) -> bool {
+    *count == 0
+}
+
+/// Whether `path` is one of the users' history files.
+pub fn is_history_file(path: &Path) -> bool {
+    path.parent() == Some(data_dir().as_path())
+        && path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| {
+                name.starts_with(&format!("{}.", HISTORY_FILE)) && name.ends_with(".jsonl")
+            })
+}
+
+/// Whether `path` is one of the users' screenshot directories.
+pub fn is_screenshots_dir(path: &Path) -> bool {
+    path.parent() == Some(data_dir().as_path())
+        && path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with(&format!("{}.", SCREENSHOTS_DIR)))
+}
+
+fn history_path() -> PathBuf {
+    data_dir().join(format!("{}.{}.jsonl", HISTORY_FILE, invoking_uid()))
+}
+
+/// Appends a session to the invoking user's history.
+pub fn record(session: &Session) -> Result<()> {
+    // an encrypted history is a single blob, so it's rewritten as a whole
+    if at_rest::enabled() {
+        let mut sessions = sessions()?;
+        sessions.push(session.clone());
+        return write(&sessions);
+    }
+
+    fs::create_dir_all(data_dir())?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(history_path())?;
+    writeln!(file, "{}", serde_json::to_string(session)?)?;
+    Ok(())
+}
+
+/// Marks the latest session as ended at `end`, for blocks lifted before their time.
+pub fn end_early(end: DateTime<Local>) -> Result<()> {
+    update_last(|last| {
+        if end < last.end {
+            last.end = end;
+            last.ended_early = true;
+        }
+    })
+}
+
+/// Moves the planned end of the latest session to `end`, for blocks extended while
+/// they're active.
+pub fn extend(end: DateTime<Local>) -> Result<()> {
+    update_last(|last| last.end = end)
+}
+
+/// Counts a continue past the countdown page on the latest session.
+pub fn count_continue() -> Result<()> {
+    update_last(|last| last.continues += 1)
+}
+
+/// Notes a blocked app reinstalled during the latest session.
+pub fn record_reinstall(app: &Path) -> Result<()> {
+    update_last(|last| last.reinstalled.push(app.to_path_buf()))
+}
+
+/// Flags the latest session with tampering caught on `target`.
+pub fn record_tamper(target: &str) -> Result<()> {
+    update_last(|last| last.tampered.push(target.to_string()))
+}
+
+/// Notes why the latest session was lifted early, and the screenshot taken then.
+pub fn record_abort(reason: &str, screenshot: Option<PathBuf>) -> Result<()> {
+    update_last(|last| {
+        last.abort_reason = Some(reason.to_string());
+        last.abort_screenshot = screenshot;
+    })
+}
+
+/// Saves what's on the invoking user's screen next to the history, returning where.
+pub fn take_screenshot() -> Result<PathBuf> {
+    let staged =
+        Path::new(SCREENSHOT_STAGING_DIR).join(format!("enough-screenshot-{}.png", Uuid::new_v4()));
+    let output = as_invoking_user("screencapture")
+        .arg("-x")
+        .arg(&staged)
+        .output()
+        .context("Failed to run screencapture")?;
+    if !output.status.success() || !staged.exists() {
+        anyhow::bail!(
+            "screencapture didn't save one, the terminal may need the Screen Recording permission"
+        );
+    }
+
+    let dir = data_dir().join(format!("{}.{}", SCREENSHOTS_DIR, invoking_uid()));
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("{}.png", Local::now().format("%Y-%m-%d-%H%M%S")));
+    fs::copy(&staged, &path)?;
+    fs::remove_file(&staged)?;
+    Ok(path)
+}
+
+fn update_last(update: impl FnOnce(&mut Session)) -> Result<()> {
+    let mut sessions = sessions()?;
+    let Some(last) = sessions.last_mut() else {
+        return Ok(());
+    };
+    update(last);
+    last.schema_version = HISTORY_SCHEMA_VERSION;
+    write(&sessions)
+}
+
+fn write(sessions: &[Session]) -> Result<()> {
+    let mut content = String::new();
+    for session in sessions {
+        content.push_str(&serde_json::to_string(session)?);
+        content.push('\n');
+    }
+    fs::write(history_path(), at_rest::seal(&content)?)?;
+    Ok(())
+}
+
+/// Removes the sessions that ended longer than `older_than` ago, every one without it,
+/// along with their screenshots. Returns how many were removed.
+pub fn purge(older_than: Option<Duration>) -> Result<usize> {
+    let cutoff = older_than
+        .and_then(|older_than| TimeDelta::from_std(older_than).ok())
+        .map(|older_than| Local::now() - older_than);
+    let (removed, kept) = sessions()?
+        .into_iter()
+        .partition::<Vec<_>, _>(|session| cutoff.is_none_or(|cutoff| session.end < cutoff));
+    if removed.is_empty() {
+        return Ok(0);
+    }
+
+    for screenshot in removed
+        .iter()
+        .filter_map(|session| session.abort_screenshot.as_ref())
+    {
+        if screenshot.exists() {
+            fs::remove_file(screenshot)
+                .with_context(|| format!("Failed to remove {}", screenshot.display()))?;
+        }
+    }
+    write(&kept)?;
+    Ok(removed.len())
+}
+
+/// All recorded sessions, oldest first. Lines that don't parse are skipped.
+pub fn sessions() -> Result<Vec<Session>> {
+    let path = history_path();
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = at_rest::open(fs::read_to_string(path)?)?;
+    Ok(content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// How long blocks have been active so far today, counting only the part of each session
+/// that falls between midnight and now.
+pub fn blocked_today() -> Result<Duration> {
+    let now = Local::now();
+    let midnight = now
+        .date_naive()
+        .and_time(NaiveTime::MIN)
+        .and_local_timezone(Local)
+        .earliest()
+        .unwrap_or(now);
+
+    let blocked = sessions()?
+        .iter()
+        .filter_map(|session| {
+            let start = session.start.max(midnight);
+            let end = session.end.min(now);
+            (end - start).to_std().ok()
+        })
+        .sum();
+    Ok(blocked)
+}
@@ -0,0 +1,221 @@
+use anyhow::{Context, Ok, Result};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path, process::Command};
+
+use crate::{
+    config::{HookAction, Hooks},
+    sandbox,
+    user::as_invoking_user,
+};
+
+/// Remembers what to run at unblock, since unblocking doesn't read the profile
+const END_HOOKS_FILE: &str = "end_hooks.yaml";
+
+/// The macOS setting behind a tweak, changed through `defaults`
+struct Setting {
+    domain: &'static str,
+    key: &'static str,
+    /// `defaults` type flag, e.g. `-bool`
+    kind: &'static str,
+    value: &'static str,
+    /// Process only reading the setting on launch, restarted to apply it
+    restart: &'static str,
+}
+
+fn tweak_setting(action: &HookAction) -> Option<Setting> {
+    match action {
+        HookAction::Grayscale => Some(Setting {
+            domain: "com.apple.universalaccess",
+            key: "grayscale",
+            kind: "-bool",
+            value: "YES",
+            restart: "universalaccessd",
+        }),
+        HookAction::HideDock => Some(Setting {
+            domain: "com.apple.dock",
+            key: "autohide",
+            kind: "-bool",
+            value: "YES",
+            restart: "Dock",
+        }),
+        HookAction::HideNotificationPreviews => Some(Setting {
+            domain: "com.apple.ncprefs",
+            key: "content_visibility",
+            kind: "-int",
+            value: "1",
+            restart: "NotificationCenter",
+        }),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+struct EndHooks {
+    #[serde(default, with = "serde_yml::with::singleton_map_recursive")]
+    actions: Vec<HookAction>,
+    #[serde(default)]
+    restores: Vec<Restore>,
+}
+
+/// A tweaked setting, with its value from before the block (`None` if it wasn't set)
+#[derive(Debug, Serialize, Deserialize)]
+struct Restore {
+    #[serde(with = "serde_yml::with::singleton_map_recursive")]
+    tweak: HookAction,
+    previous: Option<String>,
+}
+
+/// Runs the `on-start` actions, and records the `on-end` ones along with whatever
+/// undoes the start actions (pausing a started playlist, putting tweaked settings
+/// back). A failing action doesn't stop the others.
+pub fn start(hooks: &Hooks, state_dir: &Path) -> Result<()> {
+    let mut end_hooks = EndHooks::default();
+    for action in &hooks.on_start {
+        let previous = tweak_setting(action).map(|setting| read_default(&setting));
+        if let Err(err) = run(action) {
+            eprintln!("Warning: start hook failed: {:#}", err);
+            continue;
+        }
+
+        if let HookAction::AppleMusicPlaylist(_) = action {
+            end_hooks.actions.push(HookAction::Applescript(
+                "tell application \"Music\" to pause".to_string(),
+            ));
+        }
+        if let Some(previous) = previous {
+            end_hooks.restores.push(Restore {
+                tweak: action.clone(),
+                previous,
+            });
+        }
+    }
+    end_hooks.actions.extend(hooks.on_end.iter().cloned());
+
+    if !end_hooks.actions.is_empty() || !end_hooks.restores.is_empty() {
+        fs::write(
+            state_dir.join(END_HOOKS_FILE),
+            serde_yml::to_string(&end_hooks)?,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Puts the tweaked settings back and runs the actions recorded when the block
+/// started, if any.
+pub fn end(state_dir: &Path) -> Result<()> {
+    let end_file = state_dir.join(END_HOOKS_FILE);
+    if !end_file.exists() {
+        return Ok(());
+    }
+
+    let content = fs::read_to_string(&end_file)?;
+    let end_hooks = serde_yml::from_str::<EndHooks>(&content)?;
+    for restore in &end_hooks.restores {
+        if let Some(setting) = tweak_setting(&restore.tweak)
+            && let Err(err) = write_default(&setting, restore.previous.as_deref())
+        {
+            eprintln!("Warning: couldn't restore {:?}: {:#}", restore.tweak, err);
+        }
+    }
+    for action in &end_hooks.actions {
+        if let Err(err) = run(action) {
+            eprintln!("Warning: end hook failed: {:#}", err);
+        }
+    }
+    fs::remove_file(end_file)?;
+
+    Ok(())
+}
+
+fn run(action: &HookAction) -> Result<()> {
+    if let Some(setting) = tweak_setting(action) {
+        return write_default(&setting, Some(setting.value));
+    }
+
+    let mut command = match action {
+        HookAction::Open(target) => {
+            let mut command = as_invoking_user("open");
+            command.arg(target);
+            command
+        }
+        HookAction::Applescript(script) => {
+            let mut command = as_invoking_user("osascript");
+            command.args(["-e", script]);
+            command
+        }
+        HookAction::AppleMusicPlaylist(playlist) => {
+            let mut command = as_invoking_user("osascript");
+            command.args([
+                "-e",
+                &format!(
+                    "tell application \"Music\" to play playlist \"{}\"",
+                    playlist.replace('\\', "\\\\").replace('"', "\\\"")
+                ),
+            ]);
+            command
+        }
+        HookAction::Grayscale | HookAction::HideDock | HookAction::HideNotificationPreviews => {
+            unreachable!("tweaks are applied through their setting")
+        }
+    };
+
+    check_output(&mut command, &format!("{:?}", action))
+}
+
+fn read_default(setting: &Setting) -> Option<String> {
+    let output = as_invoking_user("defaults")
+        .args(["read", setting.domain, setting.key])
+        .output()
+        .ok()?;
+
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Sets `setting` to `value`, or deletes it for `None`, then restarts whatever reads it.
+fn write_default(setting: &Setting, value: Option<&str>) -> Result<()> {
+    let mut command = as_invoking_user("defaults");
+    match value {
+        // `defaults read` gives booleans as 1 and 0, which `-bool` doesn't take
+        Some(value) if setting.kind == "-bool" => {
+            let value = if matches!(value, "1" | "YES") {
+                "YES"
+            } else {
+                "NO"
+            };
+            command.args(["write", setting.domain, setting.key, setting.kind, value]);
+        }
+        Some(value) => {
+            command.args(["write", setting.domain, setting.key, setting.kind, value]);
+        }
+        None => {
+            command.args(["delete", setting.domain, setting.key]);
+        }
+    }
+    check_output(
+        &mut command,
+        &format!("Setting {} {}", setting.domain, setting.key),
+    )?;
+
+    // it may not be running, in which case it picks the setting up on launch
+    let _ = sandbox::command("killall").arg(setting.restart).output();
+
+    Ok(())
+}
+
+fn check_output(command: &mut Command, what: &str) -> Result<()> {
+    let output = command
+        .output()
+        .with_context(|| format!("Failed to run {}", what))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("{} failed: {}", what, stderr.trim());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,458 @@
+use anyhow::{Context, Ok, Result};
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+use url::Url;
+
+use crate::{at_rest, sandbox};
+
+pub const HOSTS_FILE: &str = "/etc/hosts";
+const ENOUGH_MARKER_START: &str = "# ENOUGH BLOCK START";
+const ENOUGH_MARKER_END: &str = "# ENOUGH BLOCK END";
+const MANAGED_FILE_NAME: &str = "hosts.block";
+/// Exists while a strict block keeps the hosts file flagged immutable
+const LOCK_FILE_NAME: &str = "hosts.locked";
+/// Exists while systemd-resolved runs
+#[cfg(target_os = "linux")]
+const RESOLVED_RUNTIME_DIR: &str = "/run/systemd/resolve";
+
+/// Our section of the hosts file.
+///
+/// The lines we own are mirrored in a small managed file inside the state directory,
+/// so updates only have to splice the changed lines into the marked section instead of
+/// rebuilding the whole hosts file.
+///
+/// Each user gets their own section, with the owning uid in the markers, so one user's
+/// unblock never touches another's entries.
+pub struct HostsSection {
+    hosts_path: PathBuf,
+    managed_path: PathBuf,
+    lock_path: PathBuf,
+    markers: Markers,
+}
+
+struct Markers {
+    start: String,
+    end: String,
+}
+
+impl Markers {
+    fn for_uid(uid: u32) -> Self {
+        Self {
+            start: format!("{} (uid {})", ENOUGH_MARKER_START, uid),
+            end: format!("{} (uid {})", ENOUGH_MARKER_END, uid),
+        }
+    }
+
+    /// The markers from before sections were per user, which a block started then left
+    fn legacy() -> Self {
+        Self {
+            start: ENOUGH_MARKER_START.to_string(),
+            end: ENOUGH_MARKER_END.to_string(),
+        }
+    }
+}
+
+impl HostsSection {
+    pub fn new(state_dir: &Path, uid: u32) -> Self {
+        Self {
+            hosts_path: hosts_file(),
+            managed_path: state_dir.join(MANAGED_FILE_NAME),
+            lock_path: state_dir.join(LOCK_FILE_NAME),
+            markers: Markers::for_uid(uid),
+        }
+    }
+
+    /// Makes the marked section contain exactly `entries`, touching the hosts file only
+    /// when something changed. Returns `true` if the hosts file was written.
+    pub fn apply(&self, entries: &[String]) -> Result<bool> {
+        let previous = self.managed_entries()?;
+        let hosts_content = fs::read_to_string(&self.hosts_path)
+            .with_context(|| format!("Failed to read {}", self.hosts_path.display()))?;
+
+        let wanted = entries.iter().map(String::as_str).collect::<HashSet<_>>();
+        let current = match find_section(&hosts_content, &self.markers) {
+            Some((start, end)) => {
+                hosts_content.lines().collect::<Vec<_>>()[start + 1..end].to_vec()
+            }
+            None => Vec::new(),
+        };
+
+        if previous.as_deref() == Some(entries) && current == entries {
+            return Ok(false);
+        }
+
+        // keeping the lines that are still wanted in place, appending only the new ones
+        let mut section = current
+            .iter()
+            .copied()
+            .filter(|line| wanted.contains(line))
+            .collect::<Vec<_>>();
+        let kept = section.iter().copied().collect::<HashSet<_>>();
+        section.extend(
+            entries
+                .iter()
+                .map(String::as_str)
+                .filter(|line| !kept.contains(line)),
+        );
+
+        let new_content = splice_section(&hosts_content, &section, &self.markers);
+        self.write(&new_content)?;
+        fs::write(&self.managed_path, at_rest::seal(&entries.join("\n"))?)?;
+
+        Ok(true)
+    }
+
+    /// Re-applies the entries from the managed copy, restoring the section if it was
+    /// edited or removed. Returns `true` if the hosts file was written.
+    pub fn reapply(&self) -> Result<bool> {
+        match self.managed_entries()? {
+            Some(entries) => self.apply(&entries),
+            None => Ok(false),
+        }
+    }
+
+    /// Removes our section from the hosts file, along with the managed copy and any
+    /// section left with the legacy markers.
+    pub fn clear(&self) -> Result<()> {
+        let hosts_content = fs::read_to_string(&self.hosts_path)
+            .with_context(|| format!("Failed to read {}", self.hosts_path.display()))?;
+
+        if self.lock_path.exists() {
+            fs::remove_file(&self.lock_path)?;
+            set_immutable(&self.hosts_path, false)?;
+        }
+        if let Some(cleaned) = without_sections(&hosts_content, &self.markers) {
+            self.write(&cleaned)?;
+        }
+
+        if self.managed_path.exists() {
+            fs::remove_file(&self.managed_path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Flags the hosts file immutable for the rest of a strict block, so editing the
+    /// section out takes clearing the flag first. Updates of the section clear it and set
+    /// it again.
+    pub fn lock(&self) -> Result<()> {
+        fs::write(&self.lock_path, "")?;
+        set_immutable(&self.hosts_path, true)
+    }
+
+    /// Replaces the hosts file with `content`, through the flag of a locked one.
+    fn write(&self, content: &str) -> Result<()> {
+        let locked = self.lock_path.exists();
+        if locked {
+            set_immutable(&self.hosts_path, false)?;
+        }
+        write_atomically(&self.hosts_path, content)?;
+        if locked {
+            set_immutable(&self.hosts_path, true)?;
+        }
+        Ok(())
+    }
+
+    /// Whether our section is currently in the hosts file.
+    pub fn is_present(&self) -> Result<bool> {
+        let hosts_content = fs::read_to_string(&self.hosts_path)
+            .with_context(|| format!("Failed to read {}", self.hosts_path.display()))?;
+        Ok(find_section(&hosts_content, &self.markers).is_some())
+    }
+
+    /// Whether a block wrote entries the section should be holding.
+    pub fn is_managed(&self) -> Result<bool> {
+        Ok(self
+            .managed_entries()?
+            .is_some_and(|entries| !entries.is_empty()))
+    }
+
+    fn managed_entries(&self) -> Result<Option<Vec<String>>> {
+        if !self.managed_path.exists() {
+            return Ok(None);
+        }
+
+        let content = at_rest::open(fs::read_to_string(&self.managed_path)?)?;
+        Ok(Some(content.lines().map(str::to_string).collect()))
+    }
+}
+
+/// Fails with some guidance when the hosts file can't be edited in place, like when it's
+/// a symlink into the read-only Nix store on nix-darwin. Replacing the symlink (or the
+/// file) would break whatever manages it.
+pub fn check_editable() -> Result<()> {
+    let path = hosts_file();
+    let metadata =
+        fs::symlink_metadata(&path).with_context(|| format!("Failed to read {}", HOSTS_FILE))?;
+
+    let problem = if metadata.file_type().is_symlink() {
+        let target = fs::read_link(&path)?;
+        let managed_by = if target.starts_with("/nix/store") {
+            ", managed by Nix"
+        } else {
+            ""
+        };
+        format!("is a symlink to {}{}", target.display(), managed_by)
+    } else if metadata.permissions().readonly() {
+        "is read-only".to_string()
+    } else {
+        return Ok(());
+    };
+
+    anyhow::bail!(
+        "{} {}, so enough won't edit it. Set `websites-backend: resolver` in the config \
+        to block websites through /etc/resolver instead",
+        HOSTS_FILE,
+        problem
+    )
+}
+
+/// Where [`HOSTS_FILE`] is, moved under the sandbox in one.
+pub fn hosts_file() -> PathBuf {
+    sandbox::path(HOSTS_FILE)
+}
+
+/// The host names covered by blocking `url`: its own host along with its `www.`
+/// counterpart. Ports, paths and a trailing dot don't matter to the hosts file, and IP
+/// addresses can't go in it, so they're left out.
+pub fn hosts_for(url: &Url) -> Vec<String> {
+    let Some(host) = url.domain().map(|domain| domain.trim_end_matches('.')) else {
+        return Vec::new();
+    };
+
+    let other = match host.strip_prefix("www.") {
+        // If host contains www., also block the non-www variant
+        Some(non_www) => non_www.to_string(),
+        None => format!("www.{}", host),
+    };
+
+    vec![host.to_string(), other]
+}
+
+/// Generates the hosts lines sinking each host, over both IPv4 and IPv6.
+pub fn entries_for(hosts: &[String]) -> Vec<String> {
+    hosts
+        .iter()
+        .flat_map(|host| [format!("0.0.0.0 {}", host), format!("::1 {}", host)])
+        .collect()
+}
+
+/// Like [`entries_for`], but resolving to the friction server listening on loopback.
+pub fn loopback_entries_for(hosts: &[String]) -> Vec<String> {
+    hosts
+        .iter()
+        .flat_map(|host| [format!("127.0.0.1 {}", host), format!("::1 {}", host)])
+        .collect()
+}
+
+#[cfg(target_os = "macos")]
+pub fn flush_dns_cache() -> Result<()> {
+    let output = sandbox::command("sudo")
+        .args(["dscacheutil", "-flushcache"])
+        .output()
+        .with_context(|| "Failed to get output for DNS flushing command")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Failed to flush DNS cache: {}", stderr);
+    }
+
+    Ok(())
+}
+
+/// Only systemd-resolved keeps a cache to flush, glibc reads the hosts file on every
+/// lookup. Systems without it (or with another caching resolver) are left alone.
+#[cfg(target_os = "linux")]
+pub fn flush_dns_cache() -> Result<()> {
+    if !sandbox::path(RESOLVED_RUNTIME_DIR).exists() {
+        return Ok(());
+    }
+
+    let output = sandbox::command("resolvectl")
+        .arg("flush-caches")
+        .output()
+        .with_context(|| "Failed to get output for DNS flushing command")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Failed to flush DNS cache: {}", stderr);
+    }
+
+    Ok(())
+}
+
+/// Returns the line indices of the start and end markers, if the section exists.
+fn find_section(content: &str, markers: &Markers) -> Option<(usize, usize)> {
+    let mut lines = content.lines().enumerate();
+    let start = lines
+        .by_ref()
+        .find(|(_, line)| line.trim() == markers.start)?
+        .0;
+    let end = lines.find(|(_, line)| line.trim() == markers.end)?.0;
+    Some((start, end))
+}
+
+/// Replaces the lines between the markers, or appends a new section if there is none.
+fn splice_section(content: &str, section: &[&str], markers: &Markers) -> String {
+    let lines = content.lines().collect::<Vec<_>>();
+    let mut result = Vec::with_capacity(lines.len() + section.len() + 2);
+
+    match find_section(content, markers) {
+        Some((start, end)) => {
+            result.extend_from_slice(&lines[..=start]);
+            result.extend_from_slice(section);
+            result.extend_from_slice(&lines[end..]);
+        }
+        None => {
+            result.extend_from_slice(&lines);
+            result.push("");
+            result.push(&markers.start);
+            result.extend_from_slice(section);
+            result.push(&markers.end);
+        }
+    }
+
+    let mut new_content = result.join("\n");
+    new_content.push('\n');
+    new_content
+}
+
+/// `content` without the section of `markers` and the legacy one, `None` when it has
+/// neither.
+fn without_sections(content: &str, markers: &Markers) -> Option<String> {
+    let legacy = Markers::legacy();
+    if find_section(content, markers).is_none() && find_section(content, &legacy).is_none() {
+        return None;
+    }
+    Some(remove_existing_blocks(
+        &remove_existing_blocks(content, &legacy),
+        markers,
+    ))
+}
+
+fn remove_existing_blocks(content: &str, markers: &Markers) -> String {
+    let lines = content.lines().collect::<Vec<_>>();
+    let mut result = Vec::new();
+    let mut in_block = false;
+
+    for line in lines {
+        if line.trim() == markers.start {
+            in_block = true;
+            continue;
+        }
+
+        if line.trim() == markers.end {
+            in_block = false;
+            continue;
+        }
+
+        if !in_block {
+            result.push(line);
+        }
+    }
+
+    // dropping the blank separator line we added before the section
+    while result.last().is_some_and(|line| line.is_empty()) {
+        result.pop();
+    }
+
+    let mut cleaned = result.join("\n");
+    cleaned.push('\n');
+    cleaned
+}
+
+/// Sets or clears the flag keeping `path` from being edited, replaced or removed, `uchg`
+/// on macOS and `i` on Linux.
+fn set_immutable(path: &Path, immutable: bool) -> Result<()> {
+    #[cfg(target_os = "macos")]
+    let (program, flag) = ("chflags", if immutable { "uchg" } else { "nouchg" });
+    #[cfg(target_os = "linux")]
+    let (program, flag) = ("chattr", if immutable { "+i" } else { "-i" });
+
+    let output = sandbox::command(program)
+        .arg(flag)
+        .arg(path)
+        .output()
+        .with_context(|| format!("Failed to run {}", program))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!(
+            "Failed to {} {}: {}",
+            if immutable { "lock" } else { "unlock" },
+            path.display(),
+            stderr.trim()
+        );
+    }
+
+    Ok(())
+}
+
+/// Writes through a temporary file in the same directory and renames it over the
+/// target, so readers never see a half-written hosts file.
+fn write_atomically(path: &Path, content: &str) -> Result<()> {
+    let tmp_path = path.with_extension("enough.tmp");
+    fs::write(&tmp_path, content)
+        .with_context(|| format!("Failed to write {}", tmp_path.display()))?;
+
+    if path.exists() {
+        fs::set_permissions(&tmp_path, fs::metadata(path)?.permissions())?;
+    }
+
+    fs::rename(&tmp_path, path).with_context(|| format!("Failed to replace {}", path.display()))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splice_keeps_surrounding_lines() {
+        let markers = Markers::for_uid(501);
+        let original = "127.0.0.1 localhost\n";
+        let added = splice_section(original, &["0.0.0.0 reddit.com"], &markers);
+        assert_eq!(
+            added,
+            "127.0.0.1 localhost\n\n# ENOUGH BLOCK START (uid 501)\n0.0.0.0 reddit.com\n# ENOUGH BLOCK END (uid 501)\n"
+        );
+
+        let replaced = splice_section(&added, &["0.0.0.0 youtube.com"], &markers);
+        assert!(replaced.contains("0.0.0.0 youtube.com"));
+        assert!(!replaced.contains("reddit.com"));
+        assert_eq!(remove_existing_blocks(&replaced, &markers), original);
+
+        // another user's section is left alone
+        let other = splice_section(&added, &["0.0.0.0 github.com"], &Markers::for_uid(502));
+        let cleaned = remove_existing_blocks(&other, &markers);
+        assert!(cleaned.contains("github.com"));
+        assert!(!cleaned.contains("reddit.com"));
+
+        // sections from before the uid was in the markers are cleared too
+        let legacy = splice_section(original, &["0.0.0.0 reddit.com"], &Markers::legacy());
+        assert_eq!(
+            without_sections(&legacy, &markers).as_deref(),
+            Some(original)
+        );
+        assert_eq!(without_sections(original, &markers), None);
+    }
+
+    #[test]
+    fn hosts_leave_out_ports_and_paths() {
+        let hosts = |url| hosts_for(&Url::parse(url).unwrap());
+
+        assert_eq!(
+            hosts("https://youtube.com:8443/feed?x=1"),
+            ["youtube.com", "www.youtube.com"]
+        );
+        assert_eq!(
+            hosts("https://www.reddit.com./"),
+            ["www.reddit.com", "reddit.com"]
+        );
+        assert!(hosts("http://192.168.1.10:3000").is_empty());
+    }
+}
@@ -0,0 +1,140 @@
+use anyhow::{Context, Ok, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    env,
+    ffi::{CStr, CString},
+    fs,
+    os::unix::fs::PermissionsExt as _,
+    path::PathBuf,
+};
+use uuid::Uuid;
+
+use crate::block::data_dir;
+
+const HOUSEHOLD_FILE: &str = "household.yaml";
+/// System-wide config installed by the admin, used instead of the child's own config
+const HOUSEHOLD_CONFIG_FILE: &str = "enough.yaml";
+
+/// Parental mode settings, readable and writable by root only.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Household {
+    /// The account whose blocks are managed by the admin
+    pub child: String,
+    passphrase_salt: String,
+    passphrase_hash: String,
+}
+
+impl Household {
+    pub fn path() -> PathBuf {
+        data_dir().join(HOUSEHOLD_FILE)
+    }
+
+    pub fn config_path() -> PathBuf {
+        data_dir().join(HOUSEHOLD_CONFIG_FILE)
+    }
+
+    /// Loads the household settings, if parental mode is set up.
+    pub fn load() -> Result<Option<Self>> {
+        let path = Self::path();
+        // the file is root-only, so unprivileged callers can only find out that it exists
+        let Some(content) = fs::read_to_string(&path).ok() else {
+            return Ok(path.exists().then(Self::unreadable));
+        };
+
+        let household = serde_yml::from_str(&content)
+            .with_context(|| format!("Corrupted household file `{}`", path.display()))?;
+        Ok(Some(household))
+    }
+
+    /// Placeholder for callers that can't read the file. It can't verify any passphrase,
+    /// but still tells everyone parental mode is on.
+    fn unreadable() -> Self {
+        Self {
+            child: String::new(),
+            passphrase_salt: String::new(),
+            passphrase_hash: String::new(),
+        }
+    }
+
+    pub fn setup(child: &str, passphrase: &str) -> Result<Self> {
+        account(child)?;
+        let passphrase_salt = Uuid::new_v4().to_string();
+        let household = Self {
+            child: child.to_string(),
+            passphrase_hash: hash_passphrase(&passphrase_salt, passphrase),
+            passphrase_salt,
+        };
+
+        let path = Self::path();
+        fs::create_dir_all(data_dir())?;
+        fs::write(&path, serde_yml::to_string(&household)?)?;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+
+        Ok(household)
+    }
+
+    /// Makes this process act for the child's account, as `sudo` run by them would: their
+    /// uid picks the block state and data, their home the jobs. Nothing changes for
+    /// callers that can't read the household file.
+    pub fn act_for_child(&self) -> Result<()> {
+        if self.child.is_empty() {
+            return Ok(());
+        }
+
+        let (uid, home) = account(&self.child)?;
+        // only called as the command starts, before any other thread
+        unsafe {
+            env::set_var("SUDO_UID", uid.to_string());
+            env::set_var("SUDO_USER", &self.child);
+            env::set_var("HOME", home);
+        }
+        Ok(())
+    }
+
+    pub fn disable() -> Result<()> {
+        fs::remove_file(Self::path())?;
+        Ok(())
+    }
+
+    fn verify(&self, passphrase: &str) -> bool {
+        !self.passphrase_hash.is_empty()
+            && hash_passphrase(&self.passphrase_salt, passphrase) == self.passphrase_hash
+    }
+
+    /// Prompts for the admin passphrase, failing if it doesn't match.
+    pub fn require_admin(&self, action: &str) -> Result<()> {
+        let passphrase = rpassword::prompt_password(format!(
+            "Parental mode is on, enter the admin passphrase to {}: ",
+            action
+        ))?;
+
+        if !self.verify(&passphrase) {
+            anyhow::bail!("Wrong admin passphrase");
+        }
+
+        Ok(())
+    }
+}
+
+/// The uid and home directory of the account named `name`.
+fn account(name: &str) -> Result<(u32, PathBuf)> {
+    let c_name = CString::new(name)?;
+    let passwd = unsafe { libc::getpwnam(c_name.as_ptr()) };
+    if passwd.is_null() {
+        anyhow::bail!("There's no account named `{}`", name);
+    }
+
+    let (uid, home) = unsafe { ((*passwd).pw_uid, CStr::from_ptr((*passwd).pw_dir)) };
+    Ok((uid, PathBuf::from(home.to_string_lossy().into_owned())))
+}
+
+fn hash_passphrase(salt: &str, passphrase: &str) -> String {
+    let digest = Sha256::new()
+        .chain_update(salt.as_bytes())
+        .chain_update(passphrase.as_bytes())
+        .finalize();
+
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
@@ -0,0 +1,65 @@
+//! The library side of enough, so tools like prompt modules or terminal multiplexer
+//! plugins can read the block status by linking the crate instead of spawning the CLI.
+
+mod app_groups;
+mod at_rest;
+mod audit;
+mod backup;
+mod block;
+mod browsers;
+mod capabilities;
+pub mod cli;
+mod clock;
+mod config;
+mod context;
+mod contract;
+mod daemon;
+mod discover;
+mod dns_proxy;
+mod doctor;
+mod email;
+mod events;
+mod focus;
+mod friction;
+mod history;
+mod hooks;
+mod hosts;
+mod household;
+mod lint;
+#[cfg(feature = "webhooks")]
+mod notify;
+mod output;
+mod partner;
+#[cfg(all(feature = "pf", target_os = "macos"))]
+mod pf;
+mod progress;
+mod report;
+mod resolver;
+mod sandbox;
+mod scheduler;
+mod share;
+mod shell_init;
+mod site_apps;
+mod stats;
+mod steps;
+mod suggest;
+mod tabs;
+mod third_party;
+mod time_format;
+mod together;
+mod uninstall;
+mod usage;
+mod user;
+#[cfg(all(feature = "pf", target_os = "macos"))]
+mod vpn;
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+compile_error!("This application is currently only supported on macOS and Linux.");
+
+pub use block::BlockSnapshot;
+pub use history::Session;
+
+/// Snapshot of the invoking user's active block, `None` when nothing is blocked.
+pub fn get_snapshot() -> anyhow::Result<Option<BlockSnapshot>> {
+    block::BlockManager::new().get_snapshot()
+}
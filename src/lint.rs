@@ -0,0 +1,150 @@
+use std::{collections::HashMap, fmt::Display};
+
+use crate::config::EnoughConfig;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Warning,
+    Note,
+}
+
+/// Something in the config that is redundant or likely not what was meant.
+#[derive(Debug)]
+pub struct Finding {
+    pub severity: Severity,
+    pub profile_name: String,
+    pub message: String,
+    /// How to fix it
+    pub suggestion: String,
+}
+
+impl Display for Finding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        };
+        write!(
+            f,
+            "{}: [{}] {}\n  fix: {}",
+            severity, self.profile_name, self.message, self.suggestion
+        )
+    }
+}
+
+/// Checks the config for duplicate entries and profiles nothing ever starts.
+pub fn lint(config: &EnoughConfig) -> Vec<Finding> {
+    let mut profile_names = config.profiles.keys().collect::<Vec<_>>();
+    profile_names.sort();
+
+    let mut findings = Vec::new();
+    for profile_name in profile_names {
+        let profile = &config.profiles[profile_name];
+
+        // `www.` variants are blocked together, so they count as the same domain
+        let mut seen_hosts = HashMap::new();
+        for website in &profile.websites {
+            let Some(host) = website.url.host_str() else {
+                continue;
+            };
+
+            if let Some(ignored) = website.ignored_part() {
+                findings.push(Finding {
+                    severity: Severity::Warning,
+                    profile_name: profile_name.clone(),
+                    message: format!("{} is blocked as a whole, `{}` is ignored", host, ignored),
+                    suggestion: format!("list it as https://{}", host),
+                });
+            }
+
+            let domain = host.trim_start_matches("www.");
+            match seen_hosts.get(domain) {
+                Some(first) => findings.push(Finding {
+                    severity: Severity::Warning,
+                    profile_name: profile_name.clone(),
+                    message: format!("{} blocks the same domain as {}", website.url, first),
+                    suggestion: format!("remove {} from `websites`", website.url),
+                }),
+                None => {
+                    seen_hosts.insert(domain, website.url.clone());
+                }
+            }
+        }
+
+        let mut seen_apps = Vec::new();
+        for app in &profile.apps {
+            if seen_apps.contains(&app) {
+                findings.push(Finding {
+                    severity: Severity::Warning,
+                    profile_name: profile_name.clone(),
+                    message: format!("{} is listed more than once", app.display()),
+                    suggestion: format!("remove the duplicate {} from `apps`", app.display()),
+                });
+            } else {
+                seen_apps.push(app);
+            }
+        }
+
+        if profile.websites.is_empty() && profile.apps.is_empty() {
+            findings.push(Finding {
+                severity: Severity::Warning,
+                profile_name: profile_name.clone(),
+                message: "blocks nothing".to_string(),
+                suggestion: "add `websites` or `apps`, or remove the profile".to_string(),
+            });
+        }
+
+        for action in profile
+            .hooks
+            .iter()
+            .flat_map(|hooks| &hooks.on_end)
+            .filter(|action| action.is_tweak())
+        {
+            findings.push(Finding {
+                severity: Severity::Warning,
+                profile_name: profile_name.clone(),
+                message: format!("{:?} in `on-end` stays on after the block", action),
+                suggestion: "move it to `on-start`, it's undone at unblock from there".to_string(),
+            });
+        }
+
+        if config.default_profile.as_ref() != Some(profile_name) {
+            findings.push(Finding {
+                severity: Severity::Note,
+                profile_name: profile_name.clone(),
+                message: "isn't the default, so it only runs with `--profile`".to_string(),
+                suggestion: format!(
+                    "set `default-profile: {}` if this is the one you usually run",
+                    profile_name
+                ),
+            });
+        }
+    }
+
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_www_duplicates() {
+        let config = serde_yml::from_str::<EnoughConfig>(
+            "
+default-profile: lock-in
+profiles:
+  lock-in:
+    duration: 1h
+    websites:
+      - https://reddit.com
+      - https://www.reddit.com
+",
+        )
+        .unwrap();
+
+        let findings = lint(&config);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].severity, Severity::Warning);
+    }
+}
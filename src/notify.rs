@@ -0,0 +1,75 @@
+use anyhow::{Ok, Result};
+use serde::Serialize;
+use std::process::Command;
+use url::Url;
+
+use crate::config::{Push, PushEvent};
+
+const PUSHOVER_API: &str = "https://api.pushover.net/1/messages.json";
+
+/// POSTs `payload` as JSON to the given webhook.
+pub fn post_webhook(webhook: &Url, payload: &impl Serialize) -> Result<()> {
+    let body = serde_json::to_string(payload)?;
+    post(
+        &[
+            "--header",
+            "Content-Type: application/json",
+            "--data",
+            &body,
+        ],
+        webhook.as_str(),
+    )
+    .map_err(|e| anyhow::anyhow!("Failed to notify webhook `{}`: {}", webhook, e))
+}
+
+/// Sends `title` and `message` through every provider of `push`, if it wants `event`.
+/// Every provider is tried, the first failure is returned.
+pub fn push(push: &Push, event: PushEvent, title: &str, message: &str) -> Result<()> {
+    if !push.events.contains(&event) {
+        return Ok(());
+    }
+
+    let mut result = Ok(());
+    if let Some(topic) = &push.ntfy
+        && let Err(e) = post(
+            &["--header", &format!("Title: {}", title), "--data", message],
+            topic.as_str(),
+        )
+    {
+        result = Err(anyhow::anyhow!("Failed to push to ntfy: {}", e));
+    }
+    if let Some(pushover) = &push.pushover
+        && let Err(e) = post(
+            &[
+                "--form-string",
+                &format!("token={}", pushover.token),
+                "--form-string",
+                &format!("user={}", pushover.user),
+                "--form-string",
+                &format!("title={}", title),
+                "--form-string",
+                &format!("message={}", message),
+            ],
+            PUSHOVER_API,
+        )
+    {
+        result = result.and(Err(anyhow::anyhow!("Failed to push to Pushover: {}", e)));
+    }
+    result
+}
+
+/// POSTs to `url` through curl with `args`, failing with curl's error.
+fn post(args: &[&str], url: &str) -> Result<()> {
+    let output = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--max-time", "10"])
+        .args(args)
+        .arg(url)
+        .output()?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("{}", stderr.trim());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,23 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static PLAIN: AtomicBool = AtomicBool::new(false);
+
+/// Switches to output for screen readers, from `--plain`: no emojis or symbols,
+/// statuses spelled out, and lines printed once instead of updated in place.
+pub fn set_plain(plain: bool) {
+    PLAIN.store(plain, Ordering::Relaxed);
+}
+
+pub fn is_plain() -> bool {
+    PLAIN.load(Ordering::Relaxed)
+}
+
+/// Starts the lines of a list.
+pub fn bullet() -> &'static str {
+    if is_plain() { "-" } else { "•" }
+}
+
+/// Starts a line of a list that needs attention, saying why with `word` in plain output.
+pub fn flagged(word: &'static str) -> &'static str {
+    if is_plain() { word } else { "✗" }
+}
@@ -0,0 +1,146 @@
+use anyhow::{Context, Ok, Result};
+use sha2::{Digest, Sha256};
+use std::{
+    fs::{self, OpenOptions},
+    io::Write as _,
+    os::unix::fs::OpenOptionsExt as _,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use uuid::Uuid;
+
+use crate::{block::data_dir, user::invoking_uid};
+
+/// How long a code is good for, as in authenticator apps
+const STEP_SECS: u64 = 30;
+/// Steps either side of the current one whose codes are still taken, for clocks a bit
+/// apart and codes read out slowly
+const SKEW_STEPS: u64 = 1;
+/// The last step a code was redeemed for, so each code works once
+const REDEEMED_FILE: &str = "partner_code";
+/// The secret shared with the partner, in the data directory and only readable by root,
+/// so the codes can't be worked out on this side
+pub const SECRET_FILE: &str = "partner_secret";
+const SHA256_BLOCK: usize = 64;
+
+/// A new secret to share with the partner, saved in place of the invoking user's
+/// previous one.
+pub fn new_secret() -> Result<String> {
+    let secret = Uuid::new_v4().simple().to_string();
+    fs::create_dir_all(data_dir())?;
+    write_secret(&secret_path(), &secret)?;
+    Ok(secret)
+}
+
+/// The secret the invoking user shares with their partner, if they made one.
+pub fn saved_secret() -> Result<Option<String>> {
+    let path = secret_path();
+    if !path.exists() {
+        return Ok(None);
+    }
+    let secret =
+        fs::read_to_string(&path).with_context(|| "Reading the partner's secret needs sudo")?;
+    Ok(Some(secret.trim().to_string()))
+}
+
+/// Writes `secret` to `path`, in a new file only readable by root.
+pub fn write_secret(path: &Path, secret: &str) -> Result<()> {
+    if path.exists() {
+        fs::remove_file(path)?;
+    }
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)
+        .with_context(|| format!("Failed to create {}", path.display()))?
+        .write_all(secret.as_bytes())?;
+    Ok(())
+}
+
+/// The code of `secret` at `time`, six digits, TOTP with SHA-256 (RFC 6238).
+pub fn code(secret: &str, time: SystemTime) -> String {
+    format!("{:06}", code_for_step(secret, step(time)))
+}
+
+/// Checks a code the partner read out against `secret`, taking each code once.
+pub fn redeem(secret: &str, code: &str) -> Result<()> {
+    let now = step(SystemTime::now());
+    let redeemed = fs::read_to_string(redeemed_path())
+        .ok()
+        .and_then(|step| step.trim().parse::<u64>().ok());
+    let Some(step) = (now.saturating_sub(SKEW_STEPS)..=now + SKEW_STEPS)
+        .filter(|step| redeemed.is_none_or(|redeemed| *step > redeemed))
+        .find(|step| format!("{:06}", code_for_step(secret, *step)) == code.trim())
+    else {
+        anyhow::bail!("That's not your partner's code, or it expired, the block stays");
+    };
+
+    fs::create_dir_all(data_dir())?;
+    fs::write(redeemed_path(), step.to_string())?;
+    Ok(())
+}
+
+fn secret_path() -> PathBuf {
+    data_dir().join(format!("{}.{}", SECRET_FILE, invoking_uid()))
+}
+
+fn redeemed_path() -> PathBuf {
+    data_dir().join(format!("{}.{}", REDEEMED_FILE, invoking_uid()))
+}
+
+fn step(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        / STEP_SECS
+}
+
+fn code_for_step(secret: &str, step: u64) -> u32 {
+    let mac = hmac_sha256(secret.as_bytes(), &step.to_be_bytes());
+    // dynamic truncation, from RFC 4226
+    let offset = (mac[mac.len() - 1] & 0xf) as usize;
+    let value = u32::from_be_bytes([
+        mac[offset],
+        mac[offset + 1],
+        mac[offset + 2],
+        mac[offset + 3],
+    ]) & 0x7fff_ffff;
+    value % 1_000_000
+}
+
+fn hmac_sha256(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut block = [0; SHA256_BLOCK];
+    if key.len() > SHA256_BLOCK {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let pad = |byte: u8| block.iter().map(|b| b ^ byte).collect::<Vec<_>>();
+
+    let inner = Sha256::new()
+        .chain_update(pad(0x36))
+        .chain_update(message)
+        .finalize();
+    Sha256::new()
+        .chain_update(pad(0x5c))
+        .chain_update(inner)
+        .finalize()
+        .to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn codes_match_the_rfc_6238_vectors() {
+        let secret = "12345678901234567890123456789012";
+        let at = |secs| UNIX_EPOCH + Duration::from_secs(secs);
+        // the RFC's 8 digit codes, last 6 digits
+        assert_eq!(code(secret, at(59)), "119246");
+        assert_eq!(code(secret, at(1111111109)), "084774");
+        assert_eq!(code(secret, at(20000000000)), "737706");
+    }
+}
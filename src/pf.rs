@@ -0,0 +1,206 @@
+use anyhow::{Context, Ok, Result};
+use std::{
+    collections::BTreeSet,
+    fs,
+    io::Write as _,
+    net::ToSocketAddrs as _,
+    path::{Path, PathBuf},
+    process::Stdio,
+};
+
+use crate::sandbox;
+
+/// The stock pf.conf on macOS evaluates every anchor under `com.apple/`, so rules loaded
+/// there take effect without editing the system ruleset.
+const ANCHOR_PREFIX: &str = "com.apple/enough";
+const ANCHORS_FILE: &str = "pf_anchors";
+const TOKEN_FILE: &str = "pf_token";
+
+/// Packet filter rules owned by a block, tracked in the state directory so they can be
+/// removed at unblock.
+pub struct PacketFilter {
+    anchors_path: PathBuf,
+    token_path: PathBuf,
+    uid: u32,
+}
+
+impl PacketFilter {
+    pub fn new(state_dir: &Path, uid: u32) -> Self {
+        Self {
+            anchors_path: state_dir.join(ANCHORS_FILE),
+            token_path: state_dir.join(TOKEN_FILE),
+            uid,
+        }
+    }
+
+    /// Loads `rules` into the anchor called `name`, enabling pf if needed.
+    pub fn load_anchor(&self, name: &str, rules: &str) -> Result<()> {
+        let anchor = format!("{}.{}.{}", ANCHOR_PREFIX, self.uid, name);
+
+        let mut child = sandbox::command("pfctl")
+            .args(["-a", &anchor, "-f", "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| "Failed to run pfctl")?;
+        child
+            .stdin
+            .take()
+            .with_context(|| "Failed to open pfctl's stdin")?
+            .write_all(rules.as_bytes())?;
+        let output = child.wait_with_output()?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!("Failed to load pf anchor `{}`: {}", anchor, stderr.trim());
+        }
+
+        let mut anchors = self.anchors()?;
+        if !anchors.contains(&anchor) {
+            anchors.push(anchor);
+            fs::write(&self.anchors_path, anchors.join("\n"))?;
+        }
+
+        self.enable()
+    }
+
+    /// Flushes the anchor called `name` if we loaded it, leaving the others and pf's
+    /// state alone.
+    pub fn flush_anchor(&self, name: &str) -> Result<()> {
+        let anchor = format!("{}.{}.{}", ANCHOR_PREFIX, self.uid, name);
+        if !self.anchors()?.contains(&anchor) {
+            return Ok(());
+        }
+
+        let output = sandbox::command("pfctl")
+            .args(["-a", &anchor, "-F", "all"])
+            .output()?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!("Failed to flush pf anchor `{}`: {}", anchor, stderr.trim());
+        }
+
+        Ok(())
+    }
+
+    /// Flushes every anchor we loaded and drops our reference on pf being enabled.
+    pub fn clear(&self) -> Result<()> {
+        for anchor in self.anchors()? {
+            let output = sandbox::command("pfctl")
+                .args(["-a", &anchor, "-F", "all"])
+                .output()?;
+
+            if !output.status.success() {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                anyhow::bail!("Failed to flush pf anchor `{}`: {}", anchor, stderr.trim());
+            }
+        }
+
+        if self.token_path.exists() {
+            let token = fs::read_to_string(&self.token_path)?;
+            // pf stays enabled if anything else still holds a reference
+            sandbox::command("pfctl")
+                .args(["-X", token.trim()])
+                .output()?;
+            fs::remove_file(&self.token_path)?;
+        }
+
+        if self.anchors_path.exists() {
+            fs::remove_file(&self.anchors_path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Flushes every anchor belonging to `uid`, including ones no state file knows
+    /// about anymore. Returns the anchors that still have rules afterwards.
+    pub fn flush_all(uid: u32) -> Result<Vec<String>> {
+        let anchor_parent = ANCHOR_PREFIX.split('/').next().unwrap_or_default();
+        let prefix = format!("{}.{}.", ANCHOR_PREFIX, uid);
+        let list_anchors = || -> Result<Vec<String>> {
+            let output = sandbox::command("pfctl")
+                .args(["-a", anchor_parent, "-s", "Anchors"])
+                .output()
+                .with_context(|| "Failed to run pfctl")?;
+            Ok(String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .map(str::trim)
+                .filter(|anchor| anchor.starts_with(&prefix))
+                .map(str::to_string)
+                .collect())
+        };
+
+        for anchor in list_anchors()? {
+            sandbox::command("pfctl")
+                .args(["-a", &anchor, "-F", "all"])
+                .output()?;
+        }
+
+        // flushed anchors stay listed, only the ones with rules left count
+        let remaining = list_anchors()?
+            .into_iter()
+            .filter(|anchor| {
+                sandbox::command("pfctl")
+                    .args(["-a", anchor, "-s", "rules"])
+                    .output()
+                    .is_ok_and(|output| !output.stdout.is_empty())
+            })
+            .collect();
+        Ok(remaining)
+    }
+
+    fn anchors(&self) -> Result<Vec<String>> {
+        if !self.anchors_path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = fs::read_to_string(&self.anchors_path)?;
+        Ok(content.lines().map(str::to_string).collect())
+    }
+
+    /// Enables pf through a reference token, so disabling it later doesn't turn off pf
+    /// for other software relying on it.
+    fn enable(&self) -> Result<()> {
+        if self.token_path.exists() {
+            return Ok(());
+        }
+
+        let output = sandbox::command("pfctl").arg("-E").output()?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!("Failed to enable pf: {}", stderr.trim());
+        }
+
+        // the token is only printed to stderr, as "Token : 1234567890"
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let token = stderr
+            .lines()
+            .find_map(|line| line.strip_prefix("Token : "))
+            .with_context(|| "pfctl didn't return a reference token")?;
+        fs::write(&self.token_path, token.trim())?;
+
+        Ok(())
+    }
+}
+
+/// Resolves `hosts` and generates rules dropping all outgoing traffic to their addresses.
+/// Hosts that don't resolve right now are skipped.
+pub fn rules_blocking(hosts: &[String]) -> String {
+    let addresses = hosts
+        .iter()
+        .filter_map(|host| (host.as_str(), 0).to_socket_addrs().ok())
+        .flatten()
+        .map(|addr| addr.ip().to_string())
+        .collect::<BTreeSet<_>>();
+
+    if addresses.is_empty() {
+        return String::new();
+    }
+
+    let table = addresses.into_iter().collect::<Vec<_>>().join(", ");
+    format!(
+        "table <enough_blocked> const {{ {} }}\nblock drop out quick to <enough_blocked>\n",
+        table
+    )
+}
@@ -0,0 +1,79 @@
+use clap::ValueEnum;
+use serde::Serialize;
+use std::io::{self, Write as _};
+
+use crate::output;
+
+/// Version of the JSON progress events, bumped when a field is renamed, removed or
+/// changes meaning
+pub const PROGRESS_SCHEMA_VERSION: u32 = 1;
+
+/// How progress is reported while a block is being applied.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ProgressFormat {
+    /// A single self-updating line per stage on stderr
+    #[default]
+    Human,
+    /// One JSON event per line on stderr, for frontends drawing their own progress bar
+    Json,
+    /// No progress output at all
+    Quiet,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+struct ProgressEvent<'a> {
+    schema_version: u32,
+    stage: &'a str,
+    done: usize,
+    total: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct Progress {
+    format: ProgressFormat,
+}
+
+impl Progress {
+    pub fn new(format: ProgressFormat) -> Self {
+        Self { format }
+    }
+
+    /// Reports that `done` out of `total` items of `stage` have been processed.
+    pub fn update(&self, stage: &str, done: usize, total: usize) {
+        match self.format {
+            // one line per stage, once it's done
+            ProgressFormat::Human if output::is_plain() => {
+                if done == total {
+                    eprintln!("{} {}/{}", stage, done, total);
+                }
+            }
+            ProgressFormat::Human => {
+                eprint!("\r{} {}/{}", stage, done, total);
+                if done == total {
+                    eprintln!();
+                }
+                io::stderr().flush().ok();
+            }
+            ProgressFormat::Json => {
+                let event = ProgressEvent {
+                    schema_version: PROGRESS_SCHEMA_VERSION,
+                    stage,
+                    done,
+                    total,
+                };
+                if let Ok(json) = serde_json::to_string(&event) {
+                    eprintln!("{}", json);
+                }
+            }
+            ProgressFormat::Quiet => {}
+        }
+    }
+
+    /// Prints a human readable message, unless output is quiet or machine-readable.
+    pub fn message(&self, message: &str) {
+        if self.format == ProgressFormat::Human {
+            eprintln!("{}", message);
+        }
+    }
+}
@@ -0,0 +1,232 @@
+use chrono::{DateTime, Days, Local};
+use std::{collections::BTreeMap, fmt::Write as _, time::Duration};
+
+use crate::{history::Session, stats::Stats, time_format::TimeFormat};
+
+/// A summary of the blocks over a period, rendered as text or HTML for `enough report`.
+#[derive(Debug)]
+pub struct Report {
+    /// Start of the period, `None` to cover the whole history
+    pub since: Option<DateTime<Local>>,
+    pub until: DateTime<Local>,
+    pub stats: Stats,
+    /// Whole-history streak, which a period's sessions alone can't tell
+    pub current_streak: usize,
+    /// Time blocked per profile, in the period
+    pub profiles: BTreeMap<String, Duration>,
+    /// Blocks lifted with `enough emergency`
+    pub ended_early: usize,
+    /// The sessions lifted early with a reason from the abort journal, in the period
+    pub aborts: Vec<Session>,
+    pub time_format: TimeFormat,
+}
+
+impl Report {
+    /// Reports on every session, or only the ones started in the 7 days before `now`
+    /// if `week`.
+    pub fn new(
+        sessions: &[Session],
+        now: DateTime<Local>,
+        week: bool,
+        time_format: TimeFormat,
+    ) -> Self {
+        let since = week.then(|| now.checked_sub_days(Days::new(7))).flatten();
+        let in_period = sessions
+            .iter()
+            .filter(|session| since.is_none_or(|since| session.start >= since))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let mut profiles = BTreeMap::new();
+        for session in &in_period {
+            let blocked = (session.end.min(now) - session.start)
+                .to_std()
+                .unwrap_or_default();
+            *profiles
+                .entry(session.profile_name.clone())
+                .or_insert(Duration::ZERO) += blocked;
+        }
+
+        Self {
+            since,
+            until: now,
+            stats: Stats::from_sessions(&in_period, now),
+            current_streak: Stats::from_sessions(sessions, now).current_streak,
+            profiles,
+            ended_early: in_period
+                .iter()
+                .filter(|session| session.ended_early)
+                .count(),
+            aborts: in_period
+                .iter()
+                .filter(|session| session.abort_reason.is_some())
+                .cloned()
+                .collect(),
+            time_format,
+        }
+    }
+
+    pub fn subject(&self) -> String {
+        match self.since {
+            Some(since) => format!(
+                "Your focus report for {} - {}",
+                since.format("%b %-d"),
+                self.until.format("%b %-d")
+            ),
+            None => "Your focus report".to_string(),
+        }
+    }
+
+    /// The report's lines, shared by the text and HTML renderings
+    fn lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!("{} blocks", self.stats.sessions),
+            format!("{} blocked in total", self.round(self.stats.total)),
+            format!(
+                "Longest session: {}",
+                self.round(self.stats.longest_session)
+            ),
+            format!("Current streak: {} days", self.current_streak),
+        ];
+        if self.ended_early > 0 {
+            lines.push(format!("Ended early {} times", self.ended_early));
+        }
+        if self.stats.continues > 0 {
+            lines.push(format!(
+                "Continued past soft blocks {} times",
+                self.stats.continues
+            ));
+        }
+        if self.stats.tampers > 0 {
+            lines.push(format!(
+                "Caught tampering with blocks {} times",
+                self.stats.tampers
+            ));
+        }
+        lines
+    }
+
+    /// Durations to the minute, seconds are noise in a report
+    fn round(&self, duration: Duration) -> String {
+        if duration < Duration::from_secs(60) && self.time_format == TimeFormat::Human {
+            return "0m".to_string();
+        }
+        self.time_format
+            .format(Duration::from_secs(duration.as_secs() / 60 * 60))
+    }
+
+    pub fn text(&self) -> String {
+        let mut text = format!("{}\n\n", self.subject());
+        for line in self.lines() {
+            let _ = writeln!(text, "• {}", line);
+        }
+
+        if !self.profiles.is_empty() {
+            text.push_str("\nBy profile\n");
+            for (profile_name, blocked) in &self.profiles {
+                let _ = writeln!(text, "• {}: {}", profile_name, self.round(*blocked));
+            }
+        }
+
+        if !self.aborts.is_empty() {
+            text.push_str("\nLifted early\n");
+            for session in &self.aborts {
+                let _ = write!(text, "• {}", abort_line(session));
+                if let Some(screenshot) = &session.abort_screenshot {
+                    let _ = write!(text, " (screenshot: {})", screenshot.display());
+                }
+                text.push('\n');
+            }
+        }
+
+        text
+    }
+
+    pub fn html(&self) -> String {
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html>\n<body style=\"font-family: -apple-system, sans-serif; color: #333;\">\n<h2>{}</h2>\n<ul>\n",
+            escape_html(&self.subject())
+        );
+        for line in self.lines() {
+            let _ = writeln!(html, "<li>{}</li>", escape_html(&line));
+        }
+        html.push_str("</ul>\n");
+
+        if !self.profiles.is_empty() {
+            html.push_str("<h3>By profile</h3>\n<table>\n");
+            for (profile_name, blocked) in &self.profiles {
+                let _ = writeln!(
+                    html,
+                    "<tr><td>{}</td><td>{}</td></tr>",
+                    escape_html(profile_name),
+                    self.round(*blocked)
+                );
+            }
+            html.push_str("</table>\n");
+        }
+
+        if !self.aborts.is_empty() {
+            html.push_str("<h3>Lifted early</h3>\n<ul>\n");
+            for session in &self.aborts {
+                let _ = writeln!(html, "<li>{}</li>", escape_html(&abort_line(session)));
+            }
+            html.push_str("</ul>\n");
+        }
+
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+/// When a session was lifted early and why, e.g. `Mar 3 16:20, lock-in: deploy broke`
+fn abort_line(session: &Session) -> String {
+    format!(
+        "{}, {}: {}",
+        session.end.format("%b %-d %H:%M"),
+        session.profile_name,
+        session.abort_reason.as_deref().unwrap_or_default()
+    )
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::HISTORY_SCHEMA_VERSION;
+    use chrono::TimeZone as _;
+
+    #[test]
+    fn week_only_covers_the_last_seven_days() {
+        let at = |day, hour| Local.with_ymd_and_hms(2025, 3, day, hour, 0, 0).unwrap();
+        let session = |day, hours: u32| Session {
+            schema_version: HISTORY_SCHEMA_VERSION,
+            profile_name: "lock-in".to_string(),
+            start: at(day, 9),
+            end: at(day, 9 + hours),
+            ended_early: false,
+            continues: 0,
+            reinstalled: Vec::new(),
+            tampered: Vec::new(),
+            abort_reason: None,
+            abort_screenshot: None,
+        };
+        let aborted = Session {
+            ended_early: true,
+            abort_reason: Some("deploy broke".to_string()),
+            ..session(12, 1)
+        };
+        let sessions = [session(1, 4), session(10, 2), aborted];
+
+        let report = Report::new(&sessions, at(14, 12), true, TimeFormat::Human);
+        assert_eq!(report.stats.sessions, 2);
+        assert_eq!(report.profiles["lock-in"], Duration::from_secs(3 * 60 * 60));
+        let text = report.text();
+        assert!(text.contains("3h blocked in total"));
+        assert!(text.contains("• Mar 12 10:00, lock-in: deploy broke"));
+    }
+}
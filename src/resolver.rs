@@ -0,0 +1,143 @@
+use anyhow::{Context, Ok, Result};
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{at_rest, sandbox};
+
+/// macOS reads a resolver file per domain from here, applying it to the subdomains too
+const RESOLVER_DIR: &str = "/etc/resolver";
+const RESOLVER_FILES_FILE: &str = "resolver_files";
+/// Nothing listens on the discard port, so lookups fail right away instead of timing out
+const DEAD_NAMESERVER: &str = "nameserver 127.0.0.1\nport 9\n";
+
+/// Resolver files owned by a block, an alternative to the hosts file for systems where
+/// `/etc/hosts` can't be edited. They're tracked in the state directory so the unblock
+/// only removes ours.
+pub struct ResolverFiles {
+    dir: PathBuf,
+    tracked_path: PathBuf,
+    uid: u32,
+}
+
+impl ResolverFiles {
+    pub fn new(state_dir: &Path, uid: u32) -> Self {
+        Self {
+            dir: sandbox::path(RESOLVER_DIR),
+            tracked_path: state_dir.join(RESOLVER_FILES_FILE),
+            uid,
+        }
+    }
+
+    /// Points each of `domains` at a dead nameserver, and removes the files of domains
+    /// that aren't blocked anymore. Returns `true` if anything changed.
+    pub fn apply(&self, domains: &[String]) -> Result<bool> {
+        let wanted = domains
+            .iter()
+            .map(|domain| domain.trim_start_matches("www.").to_string())
+            .collect::<BTreeSet<_>>();
+        let current = self.tracked()?;
+        if wanted == current {
+            return Ok(false);
+        }
+
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+        for domain in current.difference(&wanted) {
+            self.remove(domain)?;
+        }
+        for domain in wanted.difference(&current) {
+            let path = self.dir.join(domain);
+            // a file someone else wrote for the domain is left alone
+            if path.exists() && !self.owns(&path) {
+                eprintln!(
+                    "Warning: {} already exists, {} isn't blocked",
+                    path.display(),
+                    domain
+                );
+                continue;
+            }
+
+            let content = format!("# {}\n{}", self.marker(), DEAD_NAMESERVER);
+            fs::write(&path, content)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+
+        let tracked = wanted.into_iter().collect::<Vec<_>>();
+        fs::write(&self.tracked_path, at_rest::seal(&tracked.join("\n"))?)?;
+
+        Ok(true)
+    }
+
+    /// Removes every resolver file we wrote, orphaned ones included.
+    pub fn clear(&self) -> Result<()> {
+        for domain in self.tracked()? {
+            self.remove(&domain)?;
+        }
+        for path in self.orphaned()? {
+            fs::remove_file(&path)
+                .with_context(|| format!("Failed to remove {}", path.display()))?;
+        }
+
+        if self.tracked_path.exists() {
+            fs::remove_file(&self.tracked_path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Blocked domains whose resolver file is gone or was replaced.
+    pub fn missing(&self) -> Result<Vec<String>> {
+        Ok(self
+            .tracked()?
+            .into_iter()
+            .filter(|domain| !self.owns(&self.dir.join(domain)))
+            .collect())
+    }
+
+    /// Resolver files we wrote that no block tracks anymore.
+    pub fn orphaned(&self) -> Result<Vec<PathBuf>> {
+        let tracked = self.tracked()?;
+        Ok(fs::read_dir(&self.dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| self.owns(path))
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_none_or(|domain| !tracked.contains(domain))
+            })
+            .collect())
+    }
+
+    fn remove(&self, domain: &str) -> Result<()> {
+        let path = self.dir.join(domain);
+        if path.exists() && self.owns(&path) {
+            fs::remove_file(&path)
+                .with_context(|| format!("Failed to remove {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    fn owns(&self, path: &Path) -> bool {
+        fs::read_to_string(path)
+            .is_ok_and(|content| content.lines().next() == Some(&format!("# {}", self.marker())))
+    }
+
+    fn marker(&self) -> String {
+        format!("Written by enough (uid {})", self.uid)
+    }
+
+    fn tracked(&self) -> Result<BTreeSet<String>> {
+        if !self.tracked_path.exists() {
+            return Ok(BTreeSet::new());
+        }
+
+        let content = at_rest::open(fs::read_to_string(&self.tracked_path)?)?;
+        Ok(content.lines().map(str::to_string).collect())
+    }
+}
@@ -0,0 +1,52 @@
+use std::{
+    env,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Points a debug build at a scratch directory instead of the system, for the
+/// end-to-end tests: system paths like `/etc/hosts` are looked up under it, and the
+/// tools changing the system (`launchctl`, `pfctl`, ...) don't run.
+pub const SANDBOX_ENV_VAR: &str = "ENOUGH_SANDBOX";
+
+/// The sandbox directory, `None` outside of one. Release builds ignore the variable, so
+/// it can't be used to get around a block.
+pub fn root() -> Option<PathBuf> {
+    if !cfg!(debug_assertions) {
+        return None;
+    }
+    env::var_os(SANDBOX_ENV_VAR)
+        .filter(|root| !root.is_empty())
+        .map(PathBuf::from)
+}
+
+/// `path` (absolute) moved under the sandbox directory, or as is outside of one.
+pub fn path(path: impl AsRef<Path>) -> PathBuf {
+    let path = path.as_ref();
+    match root() {
+        Some(root) => root.join(path.strip_prefix("/").unwrap_or(path)),
+        None => path.to_path_buf(),
+    }
+}
+
+/// A command running `program`, a no-op that succeeds in a sandbox. For the tools that
+/// change the system, reading it is fine either way.
+pub fn command(program: &str) -> Command {
+    match root() {
+        Some(_) => Command::new("true"),
+        None => Command::new(program),
+    }
+}
+
+/// Puts the unit tests of this process in one sandbox, made by the first test asking
+/// for it. The others wait for it, so none sees the variable change halfway.
+#[cfg(test)]
+pub fn for_tests() -> &'static Path {
+    static ROOT: std::sync::OnceLock<PathBuf> = std::sync::OnceLock::new();
+    ROOT.get_or_init(|| {
+        let root = env::temp_dir().join(format!("enough-unit-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&root).unwrap();
+        unsafe { env::set_var(SANDBOX_ENV_VAR, &root) };
+        root
+    })
+}
@@ -0,0 +1,653 @@
+use anyhow::{Context, Ok, Result};
+use chrono::{
+    DateTime, Datelike as _, Days, Local, NaiveDate, NaiveTime, TimeDelta, Timelike as _,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap, fmt, fs, path::Path, path::PathBuf, process::Command, str::FromStr,
+    time::Duration,
+};
+
+use crate::{
+    block::data_dir,
+    config::{EnoughConfig, sha256_hex},
+    daemon::{EnoughDaemon, UnblockingDaemon},
+    user::invoking_uid,
+};
+
+const SCHEDULER_DIR: &str = "scheduler";
+const STATE_FILE: &str = "state.yaml";
+/// How often the scheduler looks at the schedules, cron goes to the minute too
+pub const SCHEDULER_INTERVAL: Duration = Duration::from_secs(60);
+/// How long before a scheduled block its warm-up reminders go out
+pub const REMINDERS: [Duration; 2] = [Duration::from_secs(10 * 60), Duration::from_secs(2 * 60)];
+/// How far putting a scheduled block off from its reminder moves the start
+pub const DEFER: Duration = Duration::from_secs(10 * 60);
+/// How long a fetched holiday calendar is used before it's fetched again
+const CALENDAR_REFRESH: Duration = Duration::from_secs(24 * 60 * 60);
+const DAY_NAMES: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// When a profile is blocked on its own, as `schedule` in the profile: either days and a
+/// time window, e.g. `weekdays 09:00-17:00` or `mon,wed 21:00-07:00`, or a cron
+/// expression starting a block of the profile's duration, e.g. `30 8 * * 1-5`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Schedule {
+    text: String,
+    kind: ScheduleKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ScheduleKind {
+    Window {
+        /// Bit per weekday, Sunday first, for the day the window starts on
+        days: u64,
+        start: NaiveTime,
+        /// Before `start` for windows going past midnight
+        end: NaiveTime,
+    },
+    Cron {
+        minutes: u64,
+        hours: u64,
+        days_of_month: u64,
+        months: u64,
+        /// Sunday first, `7` is folded into `0`
+        weekdays: u64,
+        /// Whether the days of the month and the weekdays were both given, which cron
+        /// matches either of
+        either_day: bool,
+    },
+}
+
+/// When a schedule blocks its profile once
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Occurrence {
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+}
+
+impl Schedule {
+    /// The occurrences starting from `from` until `until`, in order. Cron occurrences
+    /// last `duration`.
+    pub fn upcoming(
+        &self,
+        from: DateTime<Local>,
+        until: DateTime<Local>,
+        duration: Duration,
+    ) -> Vec<Occurrence> {
+        let mut occurrences = match self.kind {
+            ScheduleKind::Window { days, start, end } => from
+                .date_naive()
+                .iter_days()
+                .take_while(|day| *day <= until.date_naive())
+                .filter(|day| days & 1 << day.weekday().num_days_from_sunday() != 0)
+                .filter_map(|day| {
+                    let end_day = if end > start { day } else { day.succ_opt()? };
+                    Some(Occurrence {
+                        start: local(day, start)?,
+                        end: local(end_day, end)?,
+                    })
+                })
+                .collect::<Vec<_>>(),
+            ScheduleKind::Cron { .. } => {
+                let Some(first) = from
+                    .with_second(0)
+                    .and_then(|minute| minute.with_nanosecond(0))
+                else {
+                    return Vec::new();
+                };
+                (0..)
+                    .map_while(|minutes| {
+                        Some(first + TimeDelta::minutes(minutes)).filter(|time| *time < until)
+                    })
+                    .filter(|time| self.matches(*time))
+                    .map(|start| Occurrence {
+                        start,
+                        end: start + TimeDelta::seconds(duration.as_secs() as i64),
+                    })
+                    .collect()
+            }
+        };
+        occurrences.retain(|occurrence| from <= occurrence.start && occurrence.start < until);
+        occurrences
+    }
+
+    /// The occurrence going on at `now`, if one is. Cron occurrences last `duration`.
+    pub fn current(&self, now: DateTime<Local>, duration: Duration) -> Option<Occurrence> {
+        match self.kind {
+            ScheduleKind::Window { days, start, end } => {
+                let today = now.date_naive();
+                [Some(today), today.checked_sub_days(Days::new(1))]
+                    .into_iter()
+                    .flatten()
+                    .filter(|day| days & 1 << day.weekday().num_days_from_sunday() != 0)
+                    .find_map(|day| {
+                        let end_day = if end > start { day } else { day.succ_opt()? };
+                        let start = local(day, start)?;
+                        let end = local(end_day, end)?;
+                        (start <= now && now < end).then_some(Occurrence { start, end })
+                    })
+            }
+            ScheduleKind::Cron { .. } => {
+                let minute = now.with_second(0)?.with_nanosecond(0)?;
+                let minutes = duration.as_secs() / 60;
+                (0..=minutes as i64)
+                    .filter_map(|back| minute.checked_sub_signed(TimeDelta::minutes(back)))
+                    .find(|time| self.matches(*time))
+                    .map(|start| Occurrence {
+                        start,
+                        end: start + TimeDelta::seconds(duration.as_secs() as i64),
+                    })
+                    .filter(|occurrence| occurrence.end > now)
+            }
+        }
+    }
+
+    /// The occurrence going on at `now` or else the first one after it, looking a year
+    /// ahead at most.
+    pub fn next(&self, now: DateTime<Local>, duration: Duration) -> Option<Occurrence> {
+        self.current(now, duration).or_else(|| {
+            // a month at a time, cron schedules are gone through minute by minute
+            (0..12).find_map(|month| {
+                let from = now + TimeDelta::days(31 * month);
+                self.upcoming(from, from + TimeDelta::days(31), duration)
+                    .into_iter()
+                    .next()
+            })
+        })
+    }
+
+    /// Whether a cron schedule fires at the minute of `time`.
+    fn matches(&self, time: DateTime<Local>) -> bool {
+        let ScheduleKind::Cron {
+            minutes,
+            hours,
+            days_of_month,
+            months,
+            weekdays,
+            either_day,
+        } = self.kind
+        else {
+            return false;
+        };
+        let has = |field: u64, value: u32| field & 1 << value != 0;
+
+        let day_of_month = has(days_of_month, time.day());
+        let weekday = has(weekdays, time.weekday().num_days_from_sunday());
+        let day = if either_day {
+            day_of_month || weekday
+        } else {
+            day_of_month && weekday
+        };
+        has(minutes, time.minute()) && has(hours, time.hour()) && has(months, time.month()) && day
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = anyhow::Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let fields = text.split_whitespace().collect::<Vec<_>>();
+        let kind = match fields[..] {
+            [days, window] => {
+                let (start, end) = window.split_once('-').with_context(|| {
+                    format!(
+                        "Expected a time window like `09:00-17:00`, found `{}`",
+                        window
+                    )
+                })?;
+                let time = |time: &str| {
+                    NaiveTime::parse_from_str(time, "%H:%M")
+                        .with_context(|| format!("Invalid time `{}`, expected `HH:MM`", time))
+                };
+                let (start, end) = (time(start)?, time(end)?);
+                if start == end {
+                    anyhow::bail!("The window `{}` is empty", window);
+                }
+                ScheduleKind::Window {
+                    days: parse_days(days)?,
+                    start,
+                    end,
+                }
+            }
+            [minutes, hours, days_of_month, months, weekdays] => {
+                let weekdays = parse_field(weekdays, 0, 7, &DAY_NAMES)?;
+                ScheduleKind::Cron {
+                    minutes: parse_field(minutes, 0, 59, &[])?,
+                    hours: parse_field(hours, 0, 23, &[])?,
+                    days_of_month: parse_field(days_of_month, 1, 31, &[])?,
+                    months: parse_field(months, 1, 12, &[])?,
+                    weekdays: (weekdays | weekdays >> 7) & 0x7f,
+                    either_day: !days_of_month.starts_with('*') && !fields[4].starts_with('*'),
+                }
+            }
+            _ => anyhow::bail!(
+                "Invalid schedule `{}`, expected days and a window like `weekdays 09:00-17:00` or a cron expression like `30 8 * * 1-5`",
+                text
+            ),
+        };
+
+        Ok(Self {
+            text: text.trim().to_string(),
+            kind,
+        })
+    }
+}
+
+impl TryFrom<String> for Schedule {
+    type Error = anyhow::Error;
+
+    fn try_from(text: String) -> Result<Self> {
+        text.parse()
+    }
+}
+
+impl From<Schedule> for String {
+    fn from(schedule: Schedule) -> Self {
+        schedule.text
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// `daily`, `weekdays`, `weekends` or days like `mon-fri` and `mon,wed,fri`.
+fn parse_days(days: &str) -> Result<u64> {
+    match days.to_lowercase().as_str() {
+        "daily" => Ok(0x7f),
+        "weekdays" => Ok(0x3e),
+        "weekends" => Ok(0x41),
+        days => parse_field(days, 0, 6, &DAY_NAMES)
+            .with_context(|| format!("Invalid days `{}`, e.g. `weekdays` or `mon-fri`", days)),
+    }
+}
+
+/// A cron field as a bit per value: `*`, values, ranges and steps, e.g. `1-5`, `*/15`
+/// or `0,30`. `names` stand for the values starting at 0.
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<u64> {
+    let value = |text: &str| {
+        let text = text.to_lowercase();
+        let value = match names.iter().position(|name| *name == text) {
+            Some(index) => index as u32,
+            None => text
+                .parse::<u32>()
+                .with_context(|| format!("Invalid value `{}` in `{}`", text, field))?,
+        };
+        if !(min..=max).contains(&value) {
+            anyhow::bail!(
+                "`{}` in `{}` isn't between {} and {}",
+                value,
+                field,
+                min,
+                max
+            );
+        }
+        Ok(value)
+    };
+
+    let mut bits = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().ok().filter(|step| *step > 0)),
+            None => (part, Some(1)),
+        };
+        let step = step.with_context(|| format!("Invalid step in `{}`", field))?;
+        let (start, end) = match range {
+            "*" => (min, max),
+            range => match range.split_once('-') {
+                Some((start, end)) => (value(start)?, value(end)?),
+                None => {
+                    let start = value(range)?;
+                    (start, if part.contains('/') { max } else { start })
+                }
+            },
+        };
+        for value in (start..=end).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+    Ok(bits)
+}
+
+fn local(day: NaiveDate, time: NaiveTime) -> Option<DateTime<Local>> {
+    day.and_time(time).and_local_timezone(Local).earliest()
+}
+
+/// A day or the days of a range, both ends included, e.g. `2025-12-25` or
+/// `2025-12-24..2026-01-02`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct DateRange {
+    pub first: NaiveDate,
+    pub last: NaiveDate,
+}
+
+impl DateRange {
+    pub fn contains(&self, day: NaiveDate) -> bool {
+        self.first <= day && day <= self.last
+    }
+}
+
+impl FromStr for DateRange {
+    type Err = anyhow::Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let day = |day: &str| {
+            NaiveDate::parse_from_str(day.trim(), "%Y-%m-%d")
+                .with_context(|| format!("Invalid day `{}`, expected `YYYY-MM-DD`", day))
+        };
+        let (first, last) = match text.split_once("..") {
+            Some((first, last)) => (day(first)?, day(last)?),
+            None => (day(text)?, day(text)?),
+        };
+        if last < first {
+            anyhow::bail!("The range `{}` ends before it starts", text);
+        }
+        Ok(Self { first, last })
+    }
+}
+
+impl TryFrom<String> for DateRange {
+    type Error = anyhow::Error;
+
+    fn try_from(text: String) -> Result<Self> {
+        text.parse()
+    }
+}
+
+impl From<DateRange> for String {
+    fn from(range: DateRange) -> Self {
+        if range.first == range.last {
+            return range.first.format("%Y-%m-%d").to_string();
+        }
+        format!(
+            "{}..{}",
+            range.first.format("%Y-%m-%d"),
+            range.last.format("%Y-%m-%d")
+        )
+    }
+}
+
+/// The days scheduled blocks don't start on, from `holidays` and `holiday-calendar` in
+/// the config.
+#[derive(Debug, Default)]
+pub struct Holidays(Vec<DateRange>);
+
+impl Holidays {
+    /// The holidays of `conf`, going without the calendar if it can't be read, so the
+    /// schedules are still followed.
+    pub fn load(conf: &EnoughConfig) -> Self {
+        let mut ranges = conf.holidays.clone();
+        if let Some(calendar) = &conf.holiday_calendar {
+            match read_calendar(calendar) {
+                Result::Ok(content) => ranges.extend(parse_calendar(&content)),
+                Err(err) => eprintln!("Warning: skipping the holiday calendar: {:#}", err),
+            }
+        }
+        Self(ranges)
+    }
+
+    /// Whether an occurrence starting on `day` is skipped.
+    pub fn contains(&self, day: NaiveDate) -> bool {
+        self.0.iter().any(|range| range.contains(day))
+    }
+}
+
+/// The calendar at `calendar`, a file or a feed URL. A feed is kept in the scheduler's
+/// directory in between fetches, and the copy from before is used when it can't be
+/// fetched.
+fn read_calendar(calendar: &str) -> Result<String> {
+    if !calendar.starts_with("https://") && !calendar.starts_with("http://") {
+        return fs::read_to_string(calendar)
+            .with_context(|| format!("Failed to read {}", calendar));
+    }
+
+    let copy = scheduler_dir(invoking_uid()).join(format!(
+        "holidays.{}.ics",
+        &sha256_hex(calendar.as_bytes())[..16]
+    ));
+    let fresh = fs::metadata(&copy)
+        .and_then(|metadata| metadata.modified())
+        .is_ok_and(|modified| modified.elapsed().is_ok_and(|age| age < CALENDAR_REFRESH));
+    if !fresh
+        && let Result::Ok(output) = Command::new("curl")
+            .args(["-fsSL", "--max-time", "20", calendar])
+            .output()
+        && output.status.success()
+    {
+        let content = String::from_utf8_lossy(&output.stdout).to_string();
+        // only root can keep the copy, `enough scheduler preview` fetches it every time
+        let _ = fs::create_dir_all(scheduler_dir(invoking_uid()))
+            .and_then(|_| fs::write(&copy, &content));
+        return Ok(content);
+    }
+    fs::read_to_string(&copy).with_context(|| format!("Failed to fetch {}", calendar))
+}
+
+/// The days the events of an ICS calendar cover. The `DTEND` of an all-day event is the
+/// day after it.
+fn parse_calendar(content: &str) -> Vec<DateRange> {
+    let day = |value: &str| {
+        let date = NaiveDate::parse_from_str(value.get(..8)?, "%Y%m%d").ok()?;
+        Some((date, value.len() == 8))
+    };
+
+    // long lines go on in the next ones starting with a space or a tab
+    let content = content
+        .replace("\r\n", "\n")
+        .replace("\n ", "")
+        .replace("\n\t", "");
+    let mut ranges = Vec::new();
+    let (mut start, mut end) = (None, None);
+    for line in content.lines() {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        match (name.split(';').next().unwrap_or(name), value.trim()) {
+            ("BEGIN", "VEVENT") => (start, end) = (None, None),
+            ("DTSTART", value) => start = day(value),
+            ("DTEND", value) => end = day(value),
+            ("END", "VEVENT") => {
+                let Some((first, _)) = start else {
+                    continue;
+                };
+                let last = match end {
+                    Some((end, true)) => end.pred_opt().unwrap_or(end),
+                    Some((end, false)) => end,
+                    None => first,
+                };
+                ranges.push(DateRange {
+                    first,
+                    last: last.max(first),
+                });
+            }
+            _ => {}
+        }
+    }
+    ranges
+}
+
+/// What the scheduler keeps track of per profile, occurrences going by when they end.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SchedulerState {
+    /// The occurrence each profile was last started for, so a block lifted early isn't
+    /// started again before the next one
+    #[serde(default)]
+    started: BTreeMap<String, DateTime<Local>>,
+    /// The occurrence each profile was put off from its reminder, with when it starts
+    /// instead
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    deferred: BTreeMap<String, (DateTime<Local>, DateTime<Local>)>,
+    /// The occurrence each profile skips, from `enough snooze-schedule`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    snoozed: BTreeMap<String, DateTime<Local>>,
+}
+
+impl SchedulerState {
+    pub fn load(uid: u32) -> Result<Self> {
+        let path = scheduler_dir(uid).join(STATE_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_yml::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, uid: u32) -> Result<()> {
+        let dir = scheduler_dir(uid);
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join(STATE_FILE), serde_yml::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Whether the occurrence of `profile_name` ending at `end` was started already.
+    pub fn was_started(&self, profile_name: &str, end: DateTime<Local>) -> bool {
+        self.started.get(profile_name) == Some(&end)
+    }
+
+    pub fn mark_started(&mut self, profile_name: &str, end: DateTime<Local>) {
+        self.started.insert(profile_name.to_string(), end);
+        self.deferred.remove(profile_name);
+    }
+
+    /// When the occurrence of `profile_name` ending at `end` starts instead, if it was
+    /// put off.
+    pub fn deferred_until(
+        &self,
+        profile_name: &str,
+        end: DateTime<Local>,
+    ) -> Option<DateTime<Local>> {
+        self.deferred
+            .get(profile_name)
+            .filter(|(deferred, _)| *deferred == end)
+            .map(|(_, until)| *until)
+    }
+
+    pub fn defer(&mut self, profile_name: &str, occurrence: Occurrence) {
+        let until = occurrence.start + TimeDelta::seconds(DEFER.as_secs() as i64);
+        self.deferred
+            .insert(profile_name.to_string(), (occurrence.end, until));
+    }
+
+    /// Whether the occurrence of `profile_name` ending at `end` is skipped.
+    pub fn is_snoozed(&self, profile_name: &str, end: DateTime<Local>) -> bool {
+        self.snoozed.get(profile_name) == Some(&end)
+    }
+
+    pub fn snooze(&mut self, profile_name: &str, occurrence: Occurrence) {
+        self.snoozed
+            .insert(profile_name.to_string(), occurrence.end);
+    }
+}
+
+/// Where the scheduler of user `uid` keeps its bookkeeping and logs, since it outlives
+/// blocks and their state directory.
+pub fn scheduler_dir(uid: u32) -> PathBuf {
+    data_dir().join(format!("{}.{}", SCHEDULER_DIR, uid))
+}
+
+/// Starts following the schedules of the config at `config`, replacing the scheduler
+/// running for another config.
+pub fn install(config: &Path) -> Result<()> {
+    EnoughDaemon::watch_schedules(&scheduler_dir(invoking_uid()), config, SCHEDULER_INTERVAL)
+}
+
+/// Stops following the schedules, returning whether they were.
+pub fn uninstall() -> Result<bool> {
+    if !is_installed() {
+        return Ok(false);
+    }
+    EnoughDaemon::unwatch_schedules(&scheduler_dir(invoking_uid()))?;
+    Ok(true)
+}
+
+pub fn is_installed() -> bool {
+    EnoughDaemon::scheduler(&scheduler_dir(invoking_uid())).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone as _;
+
+    #[test]
+    fn windows_and_cron_find_the_current_occurrence() {
+        // a Monday
+        let at = |day, hour, minute| {
+            Local
+                .with_ymd_and_hms(2025, 3, day, hour, minute, 0)
+                .unwrap()
+        };
+        let hour = Duration::from_secs(3600);
+
+        let office = "weekdays 09:00-17:00".parse::<Schedule>().unwrap();
+        let end = |occurrence: Option<Occurrence>| occurrence.map(|occurrence| occurrence.end);
+        assert_eq!(end(office.current(at(3, 10, 0), hour)), Some(at(3, 17, 0)));
+        assert_eq!(office.current(at(3, 17, 0), hour), None);
+        assert_eq!(office.current(at(8, 10, 0), hour), None);
+
+        let night = "fri 22:00-07:00".parse::<Schedule>().unwrap();
+        assert_eq!(end(night.current(at(8, 6, 0), hour)), Some(at(8, 7, 0)));
+        assert_eq!(night.current(at(9, 6, 0), hour), None);
+
+        let mornings = "30 8 * * mon-fri".parse::<Schedule>().unwrap();
+        assert_eq!(end(mornings.current(at(3, 9, 0), hour)), Some(at(3, 9, 30)));
+        assert_eq!(mornings.current(at(3, 9, 30), hour), None);
+        assert_eq!(mornings.current(at(9, 9, 0), hour), None);
+
+        assert!("weekdays 9-17".parse::<Schedule>().is_err());
+        assert!("61 * * * *".parse::<Schedule>().is_err());
+    }
+
+    #[test]
+    fn upcoming_lists_the_occurrences_starting_in_the_range() {
+        // a Monday
+        let at = |day, hour, minute| {
+            Local
+                .with_ymd_and_hms(2025, 3, day, hour, minute, 0)
+                .unwrap()
+        };
+        let hour = Duration::from_secs(3600);
+        let starts = |schedule: &str, from, until| {
+            schedule
+                .parse::<Schedule>()
+                .unwrap()
+                .upcoming(from, until, hour)
+                .into_iter()
+                .map(|occurrence| (occurrence.start, occurrence.end))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            starts("mon,wed 22:00-07:00", at(3, 23, 0), at(6, 0, 0)),
+            [(at(5, 22, 0), at(6, 7, 0))]
+        );
+        assert_eq!(
+            starts("*/30 9 * * *", at(3, 9, 10), at(3, 10, 0)),
+            [(at(3, 9, 30), at(3, 10, 30))]
+        );
+
+        let night = "fri 22:00-07:00".parse::<Schedule>().unwrap();
+        let next = night.next(at(8, 8, 0), hour).unwrap();
+        assert_eq!((next.start, next.end), (at(14, 22, 0), at(15, 7, 0)));
+        let next = night.next(at(15, 6, 0), hour).unwrap();
+        assert_eq!((next.start, next.end), (at(14, 22, 0), at(15, 7, 0)));
+    }
+
+    #[test]
+    fn holidays_cover_the_days_of_ranges_and_calendar_events() {
+        let day = |month, day| NaiveDate::from_ymd_opt(2025, month, day).unwrap();
+        let range = "2025-12-24..2026-01-02".parse::<DateRange>().unwrap();
+        assert!(range.contains(day(12, 31)) && !range.contains(day(12, 23)));
+        assert_eq!(String::from(range), "2025-12-24..2026-01-02");
+        assert!("2026-01-02..2025-12-24".parse::<DateRange>().is_err());
+
+        let calendar = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTART;VALUE=DATE:20251225\r\nDTEND;VALUE=DATE:20251227\r\nSUMMARY:Christmas\r\nEND:VEVENT\r\nBEGIN:VEVENT\r\nDTSTART:20250\r\n 501T090000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        let holidays = Holidays(parse_calendar(calendar));
+        assert!(holidays.contains(day(12, 25)) && holidays.contains(day(12, 26)));
+        assert!(!holidays.contains(day(12, 27)));
+        assert!(holidays.contains(day(5, 1)));
+    }
+}
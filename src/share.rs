@@ -0,0 +1,114 @@
+use anyhow::{Context, Ok, Result};
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+
+use crate::{app_groups, config::Profile};
+
+/// Starts every share string, bumped if what's encoded changes incompatibly
+const SHARE_PREFIX: &str = "enough1:";
+
+/// A profile as passed around by `enough share` and `enough import --share`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SharedProfile {
+    pub profile_name: String,
+    pub profile: Profile,
+}
+
+impl SharedProfile {
+    pub fn new(profile_name: &str, profile: &Profile) -> Self {
+        Self {
+            profile_name: profile_name.to_string(),
+            profile: shareable(profile.clone()),
+        }
+    }
+
+    pub fn encode(&self) -> Result<String> {
+        Ok(format!(
+            "{}{}",
+            SHARE_PREFIX,
+            URL_SAFE_NO_PAD.encode(serde_json::to_vec(self)?)
+        ))
+    }
+
+    /// Reads a share string. What it can't carry over from someone else's machine is left
+    /// out, see [`shareable`], and so are the apps that aren't installed here.
+    pub fn decode(share: &str) -> Result<Self> {
+        let encoded = share
+            .trim()
+            .strip_prefix(SHARE_PREFIX)
+            .with_context(|| format!("Not a share string, those start with `{}`", SHARE_PREFIX))?;
+        let json = URL_SAFE_NO_PAD
+            .decode(encoded)
+            .context("The share string is damaged")?;
+        let mut shared = serde_json::from_slice::<Self>(&json)
+            .context("The share string doesn't hold a profile this version understands")?;
+
+        shared.profile = shareable(shared.profile);
+        shared
+            .profile
+            .apps
+            .retain(|app| app_groups::group_name(app).is_some() || app.exists());
+        Ok(shared)
+    }
+}
+
+/// `profile` without what shouldn't travel: hooks run scripts, which nobody should get
+/// from a string, and the break profile is another profile of the config.
+fn shareable(profile: Profile) -> Profile {
+    Profile {
+        hooks: None,
+        break_profile: None,
+        ..profile
+    }
+}
+
+/// Prints `text` as a QR code on the terminal, through `qrencode`.
+pub fn print_qr(text: &str) -> Result<()> {
+    let status = Command::new("qrencode")
+        .args(["-t", "ANSIUTF8", text])
+        .status()
+        .context("Printing a QR code needs qrencode, install it with `brew install qrencode`")?;
+
+    if !status.success() {
+        anyhow::bail!("qrencode failed");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{HookAction, Hooks};
+    use std::{path::PathBuf, time::Duration};
+    use url::Url;
+
+    #[test]
+    fn shares_round_trip_without_hooks() {
+        let profile = Profile {
+            duration: Duration::from_secs(90 * 60),
+            websites: vec![Url::parse("https://www.youtube.com").unwrap().into()],
+            apps: vec![
+                PathBuf::from("group:games"),
+                PathBuf::from("/Applications/Not Installed.app"),
+            ],
+            hooks: Some(Hooks {
+                on_start: vec![HookAction::Grayscale],
+                on_end: Vec::new(),
+            }),
+            ..Default::default()
+        };
+
+        let share = SharedProfile::new("study", &profile).encode().unwrap();
+        assert!(share.starts_with(SHARE_PREFIX));
+
+        let shared = SharedProfile::decode(&share).unwrap();
+        assert_eq!(shared.profile_name, "study");
+        assert_eq!(shared.profile.duration, profile.duration);
+        assert_eq!(shared.profile.websites.len(), 1);
+        assert_eq!(shared.profile.apps, [PathBuf::from("group:games")]);
+        assert!(shared.profile.hooks.is_none());
+        assert!(SharedProfile::decode("enough1:!!").is_err());
+    }
+}
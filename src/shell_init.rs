@@ -0,0 +1,206 @@
+use clap::ValueEnum;
+use std::{fmt::Write as _, path::Path};
+
+use crate::config::EnoughConfig;
+
+/// Turns the optional first argument of the `focus` functions into a duration override
+const DURATION_ARG: &str = "    if [ $# -gt 0 ]; then set -- -d \"$1\"; fi\n";
+/// Wrapped by the guard, see [`bypass_target`]
+const GUARDED_COMMANDS: [&str; 3] = ["sudo", "launchctl", "systemctl"];
+
+/// Shells `enough shell-init` knows how to set up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum InitShell {
+    Zsh,
+    Bash,
+}
+
+/// Generates the shell functions for the day-to-day invocations:
+/// - `focus [duration]` blocks the default profile
+/// - `focus-<profile> [duration]` blocks that profile, for every profile in `config`
+/// - `focuson <site>... <duration>` blocks only the given sites
+///
+/// and a prompt hook keeping `$ENOUGH_STATUS` up to date, to show in the prompt. With
+/// `guard`, `sudo`, `launchctl` and `systemctl` are wrapped to ask first when they're
+/// used to get around a strict block, see [`bypass_target`].
+pub fn script(
+    shell: InitShell,
+    config: &EnoughConfig,
+    exe: &Path,
+    config_path: Option<&Path>,
+    guard: bool,
+) -> String {
+    let exe = quote(&exe.to_string_lossy());
+    let config_arg = config_path
+        .map(|path| format!(" -c {}", quote(&path.to_string_lossy())))
+        .unwrap_or_default();
+
+    let mut script =
+        String::from("# Generated by `enough shell-init`, from the config's profiles\n\n");
+
+    if config.default_profile.is_some() {
+        let _ = writeln!(
+            script,
+            "focus() {{\n{DURATION_ARG}    sudo {exe} block{config_arg} \"$@\"\n}}\n"
+        );
+    }
+
+    let mut profile_names = config.profiles.keys().collect::<Vec<_>>();
+    profile_names.sort();
+    for profile_name in profile_names
+        .into_iter()
+        .filter(|name| is_function_name(name))
+    {
+        let _ = writeln!(
+            script,
+            "focus-{profile_name}() {{\n{DURATION_ARG}    sudo {exe} block{config_arg} -p {profile_name} \"$@\"\n}}\n"
+        );
+    }
+
+    // the sites go in a config of their own, kept around so the block state can check it
+    let _ = writeln!(
+        script,
+        r#"focuson() {{
+    if [ $# -lt 2 ]; then
+        echo "usage: focuson <site>... <duration>" >&2
+        return 1
+    fi
+
+    local conf="$HOME/.config/enough/focuson.yaml"
+    local site duration=""
+    mkdir -p "$(dirname "$conf")"
+    printf 'default-profile: focuson\nprofiles:\n  focuson:\n    websites:\n' > "$conf"
+    for site in "$@"; do
+        if [ -n "$duration" ]; then
+            case "$duration" in
+                *://*) printf '      - %s\n' "$duration" >> "$conf" ;;
+                *) printf '      - https://%s\n' "$duration" >> "$conf" ;;
+            esac
+        fi
+        duration="$site"
+    done
+    printf '    duration: %s\n' "$duration" >> "$conf"
+
+    sudo {exe} block -c "$conf"
+}}
+"#
+    );
+
+    let _ = writeln!(
+        script,
+        "_enough_prompt() {{\n    ENOUGH_STATUS=\"$({exe} status --line 2>/dev/null)\"\n}}"
+    );
+    script.push_str(match shell {
+        InitShell::Zsh => "precmd_functions+=(_enough_prompt)\n",
+        InitShell::Bash => "PROMPT_COMMAND=\"_enough_prompt${PROMPT_COMMAND:+;$PROMPT_COMMAND}\"\n",
+    });
+
+    if guard {
+        script.push('\n');
+        for command in GUARDED_COMMANDS {
+            let _ = writeln!(
+                script,
+                "{command}() {{\n    {exe} ___zzzbypass {command} \"$@\" || return 1\n    command {command} \"$@\"\n}}"
+            );
+        }
+    }
+
+    script
+}
+
+/// What running `args` in the shell would get around, if it looks like an attempt at
+/// lifting a block early: editing the hosts file, lifting the packet filter or the jobs,
+/// or running the internal commands by hand.
+pub fn bypass_target(args: &[String]) -> Option<&'static str> {
+    let command = args
+        .iter()
+        .map(|arg| arg.rsplit('/').next().unwrap_or(arg))
+        .skip_while(|&program| program == "sudo" || program.starts_with('-'))
+        .collect::<Vec<_>>();
+    let touches = |path: &str| args.iter().any(|arg| arg.starts_with(path));
+    let names_enough = args.iter().any(|arg| arg.contains("enough"));
+
+    match command.first().copied()? {
+        _ if touches("/etc/hosts") => Some("editing the hosts file"),
+        _ if touches("/etc/resolver") => Some("editing the resolver files"),
+        "pfctl" => Some("changing the packet filter"),
+        "launchctl" | "systemctl" if names_enough => Some("removing the block's jobs"),
+        _ if args.iter().any(|arg| arg.starts_with("___zzz")) => {
+            Some("running the block's internal commands")
+        }
+        _ => None,
+    }
+}
+
+/// Profile names that can be used as-is in a function name, without quoting.
+fn is_function_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_profiles_that_arent_function_names() {
+        let config = serde_yml::from_str::<EnoughConfig>(
+            "
+default-profile: lock-in
+profiles:
+  lock-in:
+    duration: 1h
+  deep work:
+    duration: 2h
+",
+        )
+        .unwrap();
+
+        let script = script(
+            InitShell::Zsh,
+            &config,
+            Path::new("/usr/local/bin/enough"),
+            None,
+            false,
+        );
+        assert!(script.contains("focus-lock-in()"));
+        assert!(!script.contains("focus-deep"));
+        assert!(!script.contains("sudo()"));
+    }
+
+    #[test]
+    fn catches_obvious_bypass_attempts() {
+        let target = |command: &str| {
+            let args = command.split(' ').map(String::from).collect::<Vec<_>>();
+            bypass_target(&args)
+        };
+        assert_eq!(
+            target("sudo vim /etc/hosts"),
+            Some("editing the hosts file")
+        );
+        assert_eq!(
+            target("sudo -E nano /etc/resolver/example.com"),
+            Some("editing the resolver files")
+        );
+        assert_eq!(
+            target("sudo /sbin/pfctl -d"),
+            Some("changing the packet filter")
+        );
+        assert_eq!(
+            target("launchctl bootout gui/501 com.enough.unblock.501.x"),
+            Some("removing the block's jobs")
+        );
+        assert_eq!(
+            target("sudo enough ___zzzunblock --fix"),
+            Some("running the block's internal commands")
+        );
+        assert_eq!(target("sudo apt upgrade"), None);
+        assert_eq!(target("launchctl list"), None);
+    }
+}
@@ -0,0 +1,78 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+use url::Url;
+
+/// Where Chromium based browsers install PWAs and app shortcuts, relative to the home dir
+const SITE_APP_DIRS: &[&str] = &[
+    "Applications/Chrome Apps.localized",
+    "Applications/Chromium Apps.localized",
+    "Applications/Edge Apps.localized",
+    "Applications/Brave Browser Apps.localized",
+];
+/// Info.plist key holding the URL a Chromium app shortcut opens
+const SHORTCUT_URL_KEY: &str = "CrAppModeShortcutURL";
+
+/// An installed site-specific app, such as the YouTube PWA.
+#[derive(Debug, Clone)]
+pub struct SiteApp {
+    pub path: PathBuf,
+    pub url: Url,
+}
+
+/// Finds installed site apps opening any of the given websites (or their subdomains).
+pub fn matching(websites: &[Url]) -> Vec<SiteApp> {
+    let blocked_hosts = websites
+        .iter()
+        .filter_map(Url::host_str)
+        .map(|host| host.trim_start_matches("www.").to_string())
+        .collect::<Vec<_>>();
+
+    installed()
+        .into_iter()
+        .filter(|app| {
+            let Some(host) = app.url.host_str() else {
+                return false;
+            };
+            let host = host.trim_start_matches("www.");
+            blocked_hosts
+                .iter()
+                .any(|blocked| host == blocked || host.ends_with(&format!(".{}", blocked)))
+        })
+        .collect()
+}
+
+fn installed() -> Vec<SiteApp> {
+    let Some(home) = env::home_dir() else {
+        return Vec::new();
+    };
+
+    SITE_APP_DIRS
+        .iter()
+        .filter_map(|dir| fs::read_dir(home.join(dir)).ok())
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "app"))
+        .filter_map(|path| {
+            let url = shortcut_url(&path)?;
+            Some(SiteApp { path, url })
+        })
+        .collect()
+}
+
+fn shortcut_url(app: &Path) -> Option<Url> {
+    let output = Command::new("plutil")
+        .args(["-extract", SHORTCUT_URL_KEY, "raw", "-o", "-"])
+        .arg(app.join("Contents/Info.plist"))
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    Url::parse(String::from_utf8_lossy(&output.stdout).trim()).ok()
+}
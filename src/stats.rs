@@ -0,0 +1,255 @@
+use anyhow::{Ok, Result};
+use chrono::{DateTime, Days, Local, NaiveDate};
+use std::{collections::BTreeSet, fmt::Display, fs, path::PathBuf, time::Duration};
+
+use crate::{
+    block::data_dir,
+    config::FocusScoreWeights,
+    history::{self, Session},
+    user::{as_invoking_user, invoking_uid},
+};
+
+/// Achievements that were already announced, one per line
+const ANNOUNCED_FILE: &str = "achievements";
+
+#[derive(Debug, Default)]
+pub struct Stats {
+    pub sessions: usize,
+    /// Time spent in blocks so far, not counting what's left of an active one
+    pub total: Duration,
+    pub longest_session: Duration,
+    /// Consecutive days with a block, up to today (or yesterday, if today has none yet)
+    pub current_streak: usize,
+    pub longest_streak: usize,
+    /// Times a soft block was continued past
+    pub continues: usize,
+    /// Blocked apps reinstalled during a block
+    pub reinstalls: usize,
+    /// Times something was caught undoing part of a block
+    pub tampers: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Achievement {
+    FirstBlock,
+    FourHourSession,
+    WeekStreak,
+    HundredHours,
+}
+
+impl Achievement {
+    const ALL: [Self; 4] = [
+        Self::FirstBlock,
+        Self::FourHourSession,
+        Self::WeekStreak,
+        Self::HundredHours,
+    ];
+
+    fn id(&self) -> &'static str {
+        match self {
+            Self::FirstBlock => "first-block",
+            Self::FourHourSession => "four-hour-session",
+            Self::WeekStreak => "week-streak",
+            Self::HundredHours => "hundred-hours",
+        }
+    }
+
+    fn is_unlocked(&self, stats: &Stats) -> bool {
+        match self {
+            Self::FirstBlock => stats.sessions > 0,
+            Self::FourHourSession => stats.longest_session >= Duration::from_secs(4 * 60 * 60),
+            Self::WeekStreak => stats.longest_streak >= 7,
+            Self::HundredHours => stats.total >= Duration::from_secs(100 * 60 * 60),
+        }
+    }
+}
+
+impl Display for Achievement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::FirstBlock => write!(f, "First block"),
+            Self::FourHourSession => write!(f, "First 4-hour session"),
+            Self::WeekStreak => write!(f, "7-day streak"),
+            Self::HundredHours => write!(f, "100 hours blocked"),
+        }
+    }
+}
+
+impl Stats {
+    pub fn from_sessions(sessions: &[Session], now: DateTime<Local>) -> Self {
+        let mut stats = Self {
+            sessions: sessions.len(),
+            ..Default::default()
+        };
+
+        for session in sessions {
+            let blocked = (session.end.min(now) - session.start)
+                .to_std()
+                .unwrap_or_default();
+            stats.total += blocked;
+            stats.longest_session = stats.longest_session.max(blocked);
+            stats.continues += session.continues;
+            stats.reinstalls += session.reinstalled.len();
+            stats.tampers += session.tampered.len();
+        }
+
+        let days = sessions
+            .iter()
+            .map(|session| session.start.date_naive())
+            .collect::<BTreeSet<_>>();
+        stats.longest_streak = longest_streak(&days);
+        stats.current_streak = current_streak(&days, now.date_naive());
+
+        stats
+    }
+
+    pub fn achievements(&self) -> Vec<Achievement> {
+        Achievement::ALL
+            .into_iter()
+            .filter(|achievement| achievement.is_unlocked(self))
+            .collect()
+    }
+}
+
+/// The focus score of `day`, out of 100, going by the sessions started that day, see
+/// [`FocusScoreWeights`].
+pub fn focus_score(
+    sessions: &[Session],
+    day: NaiveDate,
+    now: DateTime<Local>,
+    weights: &FocusScoreWeights,
+) -> u32 {
+    let mut minutes = 0;
+    let mut penalty = 0;
+    for session in sessions
+        .iter()
+        .filter(|session| session.start.date_naive() == day)
+    {
+        minutes += (session.end.min(now) - session.start).num_minutes().max(0) as u32;
+        if session.ended_early {
+            penalty += weights.abort;
+        }
+        penalty += session.tampered.len() as u32 * weights.tamper;
+        penalty += session.continues as u32 * weights.continues;
+    }
+
+    (minutes * weights.per_hour / 60)
+        .saturating_sub(penalty)
+        .min(100)
+}
+
+fn longest_streak(days: &BTreeSet<NaiveDate>) -> usize {
+    let mut longest = 0;
+    let mut streak = 0;
+    let mut previous: Option<NaiveDate> = None;
+    for &day in days {
+        streak = match previous {
+            Some(previous) if previous.succ_opt() == Some(day) => streak + 1,
+            _ => 1,
+        };
+        longest = longest.max(streak);
+        previous = Some(day);
+    }
+    longest
+}
+
+fn current_streak(days: &BTreeSet<NaiveDate>, today: NaiveDate) -> usize {
+    // today's block may just not have happened yet
+    let mut day = if days.contains(&today) {
+        today
+    } else {
+        match today.checked_sub_days(Days::new(1)) {
+            Some(yesterday) => yesterday,
+            None => return 0,
+        }
+    };
+
+    let mut streak = 0;
+    while days.contains(&day) {
+        streak += 1;
+        match day.pred_opt() {
+            Some(previous) => day = previous,
+            None => break,
+        }
+    }
+    streak
+}
+
+/// Posts a notification for every achievement unlocked since the last announcement.
+pub fn announce_new_achievements() -> Result<()> {
+    let stats = Stats::from_sessions(&history::sessions()?, Local::now());
+    let path = announced_path();
+    let mut announced = fs::read_to_string(&path).unwrap_or_default();
+
+    for achievement in stats.achievements() {
+        if announced.lines().any(|line| line == achievement.id()) {
+            continue;
+        }
+
+        let script = format!(
+            "display notification \"{}\" with title \"enough\" subtitle \"Achievement unlocked\"",
+            achievement
+        );
+        as_invoking_user("osascript")
+            .args(["-e", &script])
+            .output()?;
+
+        announced.push_str(achievement.id());
+        announced.push('\n');
+    }
+
+    fs::create_dir_all(data_dir())?;
+    fs::write(path, announced)?;
+    Ok(())
+}
+
+fn announced_path() -> PathBuf {
+    data_dir().join(format!("{}.{}", ANNOUNCED_FILE, invoking_uid()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::HISTORY_SCHEMA_VERSION;
+    use chrono::TimeZone as _;
+
+    #[test]
+    fn streaks_count_consecutive_days() {
+        let day = |d| NaiveDate::from_ymd_opt(2025, 3, d).unwrap();
+        let days = BTreeSet::from([day(1), day(2), day(3), day(5), day(6)]);
+
+        assert_eq!(longest_streak(&days), 3);
+        assert_eq!(current_streak(&days, day(6)), 2);
+        assert_eq!(current_streak(&days, day(7)), 2);
+        assert_eq!(current_streak(&days, day(8)), 0);
+    }
+
+    #[test]
+    fn focus_scores_take_off_for_aborts_and_tampering() {
+        let at = |d, h| Local.with_ymd_and_hms(2025, 3, d, h, 0, 0).unwrap();
+        let session = |start, end, ended_early, tampered: &[&str]| Session {
+            schema_version: HISTORY_SCHEMA_VERSION,
+            profile_name: "work".to_string(),
+            start,
+            end,
+            ended_early,
+            continues: 0,
+            reinstalled: Vec::new(),
+            tampered: tampered.iter().map(|target| target.to_string()).collect(),
+            abort_reason: None,
+            abort_screenshot: None,
+        };
+        let weights = FocusScoreWeights::default();
+        let sessions = [
+            session(at(1, 9), at(1, 11), false, &[]),
+            session(at(1, 14), at(1, 15), true, &["hosts_section"]),
+            session(at(2, 9), at(2, 16), false, &[]),
+        ];
+        let day = |d| NaiveDate::from_ymd_opt(2025, 3, d).unwrap();
+
+        assert_eq!(focus_score(&sessions, day(1), at(3, 0), &weights), 40);
+        assert_eq!(focus_score(&sessions, day(2), at(3, 0), &weights), 100);
+        assert_eq!(focus_score(&sessions, day(2), at(2, 10), &weights), 25);
+        assert_eq!(focus_score(&sessions, day(3), at(3, 0), &weights), 0);
+    }
+}
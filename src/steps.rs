@@ -0,0 +1,203 @@
+use anyhow::{Ok, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+};
+
+const STEPS_FILE: &str = "steps.yaml";
+
+/// A named part of applying or lifting a block. Every step is idempotent, so a run that
+/// stopped halfway can be resumed, or repeated as a whole, without doing things twice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Step {
+    /// The hosts file section, or the resolver files
+    Hosts,
+    /// The VPN kill switch. Lifting it clears every pf rule, the ones of domains spilled
+    /// over the hosts limit too
+    Pf,
+    DnsFlush,
+    /// Silenced notifications, hooks, the saved work context and closed tabs
+    Session,
+    Apps,
+    /// The scheduled unblock and the jobs keeping the block in place
+    Daemon,
+    State,
+}
+
+impl Step {
+    /// In the order a block is applied in.
+    pub const BLOCK: &[Step] = &[
+        Step::Hosts,
+        Step::Pf,
+        Step::DnsFlush,
+        Step::Session,
+        Step::Apps,
+        Step::Daemon,
+        Step::State,
+    ];
+    /// In the order a block is lifted in, the state going last so a failed unblock is
+    /// retried with everything it needs.
+    pub const UNBLOCK: &[Step] = &[
+        Step::Hosts,
+        Step::DnsFlush,
+        Step::Apps,
+        Step::Session,
+        Step::Pf,
+        Step::Daemon,
+        Step::State,
+    ];
+}
+
+impl Display for Step {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Hosts => "hosts",
+            Self::Pf => "pf",
+            Self::DnsFlush => "dns-flush",
+            Self::Session => "session",
+            Self::Apps => "apps",
+            Self::Daemon => "daemon",
+            Self::State => "state",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Direction {
+    Block,
+    Unblock,
+}
+
+impl Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Block => write!(f, "block"),
+            Self::Unblock => write!(f, "unblock"),
+        }
+    }
+}
+
+/// The steps of the latest run that got to them, kept in the state directory. An unblock
+/// that finished takes its record with it, along with the rest of the state.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StepRecord {
+    pub direction: Direction,
+    pub steps: Vec<StepRun>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StepRun {
+    pub step: Step,
+    /// Why the step failed, `None` when it's done
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl StepRecord {
+    pub fn new(direction: Direction) -> Self {
+        Self {
+            direction,
+            steps: Vec::new(),
+        }
+    }
+
+    pub fn load(state_dir: &Path) -> Result<Option<Self>> {
+        let path = Self::path(state_dir);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        Ok(Some(serde_yml::from_str(&fs::read_to_string(path)?)?))
+    }
+
+    /// Saves the record, unless the run already removed the state directory.
+    pub fn save(&self, state_dir: &Path) -> Result<()> {
+        if !state_dir.exists() {
+            return Ok(());
+        }
+
+        fs::write(Self::path(state_dir), serde_yml::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn is_done(&self, step: Step) -> bool {
+        self.steps
+            .iter()
+            .any(|run| run.step == step && run.error.is_none())
+    }
+
+    /// Whether the run got through its last step.
+    pub fn is_complete(&self) -> bool {
+        self.is_done(Step::State)
+    }
+
+    /// The step the run stopped at, with why.
+    pub fn failed(&self) -> Option<&StepRun> {
+        self.steps.iter().find(|run| run.error.is_some())
+    }
+
+    pub fn record(&mut self, step: Step, error: Option<String>) {
+        self.steps.retain(|run| run.step != step);
+        self.steps.push(StepRun { step, error });
+    }
+
+    fn path(state_dir: &Path) -> PathBuf {
+        state_dir.join(STEPS_FILE)
+    }
+}
+
+impl Display for StepRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.failed() {
+            Some(run) => write!(
+                f,
+                "the {} stopped at the `{}` step: {}",
+                self.direction,
+                run.step,
+                run.error.as_deref().unwrap_or_default()
+            ),
+            None => match self.steps.last() {
+                Some(run) => write!(
+                    f,
+                    "the {} stopped after the `{}` step",
+                    self.direction, run.step
+                ),
+                None => write!(f, "the {} stopped before its first step", self.direction),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_where_a_run_stopped() {
+        let mut record = StepRecord::new(Direction::Unblock);
+        record.record(Step::Hosts, None);
+        record.record(Step::DnsFlush, None);
+        record.record(Step::Apps, Some("chmod failed".to_string()));
+        assert!(!record.is_complete());
+        assert!(record.is_done(Step::Hosts));
+        assert!(!record.is_done(Step::Apps));
+        assert_eq!(
+            record.to_string(),
+            "the unblock stopped at the `apps` step: chmod failed"
+        );
+
+        // resuming retries the failed step
+        record.record(Step::Apps, None);
+        assert!(record.failed().is_none());
+
+        let yaml = serde_yml::to_string(&record).unwrap();
+        let parsed = serde_yml::from_str::<StepRecord>(&yaml).unwrap();
+        assert_eq!(parsed.direction, Direction::Unblock);
+        assert!(parsed.is_done(Step::Apps));
+    }
+}
@@ -0,0 +1,183 @@
+use chrono::{DateTime, Datelike as _, Local, Timelike as _};
+use std::time::Duration;
+
+use crate::{history::Session, user::as_invoking_user};
+
+/// Sessions started further than this from the time of day now don't count
+const TIME_WINDOW_MINUTES: f64 = 90.0;
+/// Age at which a session counts half as much as one started today
+const HALF_LIFE_DAYS: f64 = 30.0;
+/// Sessions on the same day of the week count this much more
+const SAME_WEEKDAY_WEIGHT: f64 = 1.5;
+/// What a calendar event going on now that names a profile counts for
+const CALENDAR_WEIGHT: f64 = 5.0;
+const MINUTES_PER_DAY: f64 = 24.0 * 60.0;
+
+/// A profile likely to be wanted now, for `enough suggest`.
+#[derive(Debug)]
+pub struct Suggestion {
+    pub profile_name: String,
+    /// How long the blocks started around this time usually lasted, `None` to go with
+    /// the profile's duration
+    pub duration: Option<Duration>,
+    /// Why it's suggested, e.g. `started around this time 4 times`
+    pub reasons: Vec<String>,
+    score: f64,
+}
+
+/// The profiles out of `profile_names` going by the sessions started around this time
+/// of day, and by the `events` going on now that name them, most likely first. Profiles
+/// with nothing speaking for them are left out.
+pub fn rank(
+    profile_names: &[String],
+    sessions: &[Session],
+    events: &[String],
+    now: DateTime<Local>,
+) -> Vec<Suggestion> {
+    let mut suggestions = profile_names
+        .iter()
+        .map(|profile_name| {
+            let mut score = 0.0;
+            let mut durations = Vec::new();
+            for session in sessions
+                .iter()
+                .filter(|session| &session.profile_name == profile_name && session.start < now)
+            {
+                let distance = minutes_apart(session.start, now);
+                if distance > TIME_WINDOW_MINUTES {
+                    continue;
+                }
+
+                let age_days = (now - session.start).num_seconds() as f64 / 86400.0;
+                let mut weight = (1.0 - distance / TIME_WINDOW_MINUTES)
+                    * 0.5_f64.powf(age_days / HALF_LIFE_DAYS);
+                if session.start.weekday() == now.weekday() {
+                    weight *= SAME_WEEKDAY_WEIGHT;
+                }
+                score += weight;
+                durations.push((session.end - session.start).to_std().unwrap_or_default());
+            }
+
+            let mut reasons = Vec::new();
+            if !durations.is_empty() {
+                reasons.push(format!(
+                    "started around this time {} times",
+                    durations.len()
+                ));
+            }
+            for event in events.iter().filter(|event| names(event, profile_name)) {
+                score += CALENDAR_WEIGHT;
+                reasons.push(format!("\"{}\" is on the calendar", event));
+            }
+
+            Suggestion {
+                profile_name: profile_name.clone(),
+                duration: median(&mut durations),
+                reasons,
+                score,
+            }
+        })
+        .filter(|suggestion| suggestion.score > 0.0)
+        .collect::<Vec<_>>();
+
+    suggestions.sort_by(|a, b| b.score.total_cmp(&a.score));
+    suggestions
+}
+
+/// Titles of the calendar events going on now. Empty when Calendar can't be asked, like
+/// without the automation permission.
+pub fn current_events() -> Vec<String> {
+    let script = r#"set now to current date
+set titles to {}
+tell application "Calendar"
+    repeat with cal in calendars
+        set titles to titles & (summary of every event of cal whose start date is less than or equal to now and end date is greater than now)
+    end repeat
+end tell
+set AppleScript's text item delimiters to linefeed
+titles as text"#;
+
+    let Some(output) = as_invoking_user("osascript")
+        .args(["-e", script])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+    else {
+        return Vec::new();
+    };
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|title| !title.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Minutes between the times of day of `a` and `b`, across midnight too.
+fn minutes_apart(a: DateTime<Local>, b: DateTime<Local>) -> f64 {
+    let minutes = |time: DateTime<Local>| (time.hour() * 60 + time.minute()) as f64;
+    let distance = (minutes(a) - minutes(b)).abs();
+    distance.min(MINUTES_PER_DAY - distance)
+}
+
+/// Whether an event title names the profile, e.g. `Deep work with Sam` for `deep-work`.
+fn names(title: &str, profile_name: &str) -> bool {
+    let words = |text: &str| {
+        text.to_lowercase()
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    };
+    let title = words(title);
+    let name = words(profile_name);
+    !name.is_empty() && title.windows(name.len()).any(|window| window == name)
+}
+
+/// The median, to the minute.
+fn median(durations: &mut [Duration]) -> Option<Duration> {
+    durations.sort();
+    let median = durations.get(durations.len() / 2)?;
+    Some(Duration::from_secs(median.as_secs().div_ceil(60) * 60))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::HISTORY_SCHEMA_VERSION;
+    use chrono::TimeZone as _;
+
+    #[test]
+    fn history_around_now_and_calendar_rank_profiles() {
+        let at = |day, hour| Local.with_ymd_and_hms(2025, 3, day, hour, 0, 0).unwrap();
+        let session = |profile_name: &str, day, hour, hours| Session {
+            schema_version: HISTORY_SCHEMA_VERSION,
+            profile_name: profile_name.to_string(),
+            start: at(day, hour),
+            end: at(day, hour + hours),
+            ended_early: false,
+            continues: 0,
+            reinstalled: Vec::new(),
+            tampered: Vec::new(),
+            abort_reason: None,
+            abort_screenshot: None,
+        };
+        let sessions = [
+            session("lock-in", 3, 9, 2),
+            session("lock-in", 4, 9, 2),
+            session("wind-down", 4, 21, 1),
+        ];
+        let profile_names = ["lock-in".to_string(), "wind-down".to_string()];
+
+        let morning = rank(&profile_names, &sessions, &[], at(5, 9));
+        assert_eq!(morning.len(), 1);
+        assert_eq!(morning[0].profile_name, "lock-in");
+        assert_eq!(morning[0].duration, Some(Duration::from_secs(2 * 60 * 60)));
+
+        let events = ["Wind down before bed".to_string()];
+        let with_event = rank(&profile_names, &sessions, &events, at(5, 9));
+        assert_eq!(with_event[0].profile_name, "wind-down");
+        assert_eq!(with_event[0].duration, None);
+    }
+}
@@ -0,0 +1,186 @@
+use anyhow::{Context, Ok, Result};
+use std::{
+    fs::{self, OpenOptions},
+    io::Write as _,
+    path::{Path, PathBuf},
+};
+use url::Url;
+
+use crate::{block::data_dir, config::CloseTabs, user::as_invoking_user};
+
+/// Browsers scriptable through the same tab/window dictionary
+const BROWSERS: &[&str] = &["Safari", "Google Chrome"];
+const READ_LATER_FILE: &str = "read-later.txt";
+
+pub struct OpenTab {
+    pub browser: &'static str,
+    window: usize,
+    tab: usize,
+    pub url: Url,
+}
+
+/// Closes open tabs pointing at blocked websites, saving them for later first if asked.
+/// Returns how many tabs were closed.
+pub fn audit(websites: &[Url], mode: CloseTabs) -> Result<usize> {
+    let mut closed = 0;
+    for browser in BROWSERS {
+        let tabs = open_tabs(browser)?
+            .into_iter()
+            .filter(|tab| on_blocked_website(&tab.url, websites))
+            .collect::<Vec<_>>();
+
+        if tabs.is_empty() {
+            continue;
+        }
+
+        if mode == CloseTabs::ReadLater {
+            save_for_later(&tabs)?;
+        }
+        close(browser, &tabs)?;
+        closed += tabs.len();
+    }
+
+    Ok(closed)
+}
+
+/// Lists the tabs open in every supported browser that is running.
+pub fn all_open_tabs() -> Result<Vec<OpenTab>> {
+    let mut tabs = Vec::new();
+    for browser in BROWSERS {
+        tabs.extend(open_tabs(browser)?);
+    }
+    Ok(tabs)
+}
+
+/// The URL of the tab in front, when `app` is one of the supported browsers.
+pub fn front_tab_url(app: &Path) -> Option<Url> {
+    let browser = app.file_stem()?.to_str()?;
+    // Safari calls it the current tab, Chrome the active one
+    let tab = match browser {
+        "Safari" => "current tab",
+        "Google Chrome" => "active tab",
+        _ => return None,
+    };
+
+    let script = format!(r#"tell application "{browser}" to return URL of {tab} of front window"#);
+    Url::parse(run_script(&script).ok()?.trim()).ok()
+}
+
+/// Whether `url` is on one of `websites` or a subdomain of it, `www.` variants included.
+pub fn on_blocked_website(url: &Url, websites: &[Url]) -> bool {
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+
+    let host = host.trim_start_matches("www.");
+    websites
+        .iter()
+        .filter_map(Url::host_str)
+        .map(|blocked| blocked.trim_start_matches("www."))
+        .any(|blocked| host == blocked || host.ends_with(&format!(".{}", blocked)))
+}
+
+/// Where tabs closed in `read-later` mode are kept, one URL per line.
+pub fn read_later_path() -> PathBuf {
+    data_dir().join(READ_LATER_FILE)
+}
+
+fn open_tabs(browser: &'static str) -> Result<Vec<OpenTab>> {
+    // `tab` is a class inside the browser's dictionary, so the separator is set up outside
+    let script = format!(
+        r#"set sep to character id 9
+set output to ""
+if application "{browser}" is running then
+    tell application "{browser}"
+        repeat with w from 1 to count of windows
+            repeat with t from 1 to count of tabs of window w
+                set output to output & w & sep & t & sep & (URL of tab t of window w) & linefeed
+            end repeat
+        end repeat
+    end tell
+end if
+return output"#
+    );
+
+    let output = run_script(&script)?;
+    let tabs = output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            let window = fields.next()?.parse().ok()?;
+            let tab = fields.next()?.parse().ok()?;
+            let url = Url::parse(fields.next()?).ok()?;
+            Some(OpenTab {
+                browser,
+                window,
+                tab,
+                url,
+            })
+        })
+        .collect();
+
+    Ok(tabs)
+}
+
+fn save_for_later(tabs: &[OpenTab]) -> Result<()> {
+    fs::create_dir_all(data_dir())?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(read_later_path())?;
+    for tab in tabs {
+        writeln!(file, "{}", tab.url)?;
+    }
+
+    // Safari also has a proper reading list
+    let safari_tabs = tabs
+        .iter()
+        .filter(|tab| tab.browser == "Safari")
+        .map(|tab| format!("    add reading list item \"{}\"", tab.url))
+        .collect::<Vec<_>>();
+    if !safari_tabs.is_empty() {
+        let script = format!(
+            "tell application \"Safari\"\n{}\nend tell",
+            safari_tabs.join("\n")
+        );
+        run_script(&script)?;
+    }
+
+    Ok(())
+}
+
+fn close(browser: &str, tabs: &[OpenTab]) -> Result<()> {
+    // closing from the last tab backwards, so the remaining indices stay valid
+    let mut positions = tabs
+        .iter()
+        .map(|tab| (tab.window, tab.tab))
+        .collect::<Vec<_>>();
+    positions.sort_unstable_by(|a, b| b.cmp(a));
+
+    let commands = positions
+        .iter()
+        .map(|(window, tab)| format!("    close tab {} of window {}", tab, window))
+        .collect::<Vec<_>>();
+    let script = format!(
+        "tell application \"{}\"\n{}\nend tell",
+        browser,
+        commands.join("\n")
+    );
+    run_script(&script)?;
+
+    Ok(())
+}
+
+fn run_script(script: &str) -> Result<String> {
+    let output = as_invoking_user("osascript")
+        .args(["-e", script])
+        .output()
+        .with_context(|| "Failed to run osascript")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("AppleScript failed: {}", stderr.trim());
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
@@ -0,0 +1,91 @@
+use std::{fmt::Display, fs, process::Command, thread, time::Duration};
+
+use crate::hosts::{self, HOSTS_FILE};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Other blockers that edit the hosts file too, along with how they give themselves away
+struct KnownTool {
+    name: &'static str,
+    /// Start markers of the section they put in the hosts file
+    hosts_markers: &'static [&'static str],
+    /// Processes of the part doing the blocking
+    processes: &'static [&'static str],
+}
+
+const KNOWN_TOOLS: &[KnownTool] = &[
+    KnownTool {
+        name: "SelfControl",
+        hosts_markers: &["# BEGIN SELFCONTROL BLOCK"],
+        processes: &["org.eyebeam.selfcontrold", "selfcontrold"],
+    },
+    KnownTool {
+        name: "Focus",
+        hosts_markers: &[],
+        processes: &["Focus"],
+    },
+    KnownTool {
+        name: "Cold Turkey",
+        hosts_markers: &[],
+        processes: &["Cold Turkey Blocker"],
+    },
+];
+
+/// Another tool that currently looks like it's blocking, or at least could be.
+#[derive(Debug)]
+pub struct ActiveTool {
+    pub name: &'static str,
+    /// What gave it away, e.g. its hosts section
+    pub evidence: Vec<String>,
+}
+
+impl Display for ActiveTool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.name, self.evidence.join(", "))
+    }
+}
+
+/// Known third-party blockers with an active block, going by their hosts sections and
+/// processes.
+pub fn active_tools() -> Vec<ActiveTool> {
+    let hosts_content = fs::read_to_string(hosts::hosts_file()).unwrap_or_default();
+
+    KNOWN_TOOLS
+        .iter()
+        .filter_map(|tool| {
+            let mut evidence = Vec::new();
+            if tool
+                .hosts_markers
+                .iter()
+                .any(|marker| hosts_content.lines().any(|line| line.trim() == *marker))
+            {
+                evidence.push(format!("section in {}", HOSTS_FILE));
+            }
+            evidence.extend(
+                tool.processes
+                    .iter()
+                    .filter(|process| is_running(process))
+                    .map(|process| format!("`{}` running", process)),
+            );
+
+            (!evidence.is_empty()).then_some(ActiveTool {
+                name: tool.name,
+                evidence,
+            })
+        })
+        .collect()
+}
+
+/// Blocks until none of the known tools looks active anymore.
+pub fn wait_until_inactive() {
+    while !active_tools().is_empty() {
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+fn is_running(process: &str) -> bool {
+    Command::new("pgrep")
+        .args(["-x", process])
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
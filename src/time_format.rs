@@ -0,0 +1,232 @@
+use anyhow::{Context, Ok, Result};
+use chrono::{DateTime, Days, Local, NaiveTime};
+use clap::ValueEnum;
+use humantime_serde::re::humantime;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// How durations are printed in the status, stats, reports and notifications.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum TimeFormat {
+    /// `1h 5m 30s`
+    #[default]
+    Human,
+    /// `1h05`, or `5m30` under an hour
+    Compact,
+    /// `01:05:30`
+    Clock,
+}
+
+impl TimeFormat {
+    /// `duration` to the second, in this format.
+    pub fn format(self, duration: Duration) -> String {
+        let secs = duration.as_secs();
+        let (hours, minutes, seconds) = (secs / 3600, secs / 60 % 60, secs % 60);
+        match self {
+            Self::Human => humantime::format_duration(Duration::from_secs(secs)).to_string(),
+            Self::Compact if hours > 0 => format!("{}h{:02}", hours, minutes),
+            Self::Compact if minutes > 0 => format!("{}m{:02}", minutes, seconds),
+            Self::Compact => format!("{}s", seconds),
+            Self::Clock => format!("{:02}:{:02}:{:02}", hours, minutes, seconds),
+        }
+    }
+}
+
+/// Units of fractional durations, in seconds
+const UNITS: &[(&[&str], f64)] = &[
+    (&["s", "sec", "secs", "second", "seconds"], 1.0),
+    (&["m", "min", "mins", "minute", "minutes"], 60.0),
+    (&["h", "hr", "hrs", "hour", "hours"], 3600.0),
+    (&["d", "day", "days"], 86400.0),
+    (&["w", "week", "weeks"], 604800.0),
+];
+
+/// Parses a duration in any of the formats: `1h 5m`, `1h30m`, `90m`, `1.5h`, `1h05`,
+/// `5m30`, `1:05` (hours and minutes) or `01:05:30`. Used for the command line and the
+/// config alike.
+pub fn parse(text: &str) -> Result<Duration> {
+    let text = text.trim();
+    let number = |part: &str| {
+        part.parse::<u64>()
+            .with_context(|| format!("Invalid duration `{}`", text))
+    };
+
+    if text.contains(':') {
+        let parts = text.split(':').map(number).collect::<Result<Vec<_>>>()?;
+        let secs = match parts[..] {
+            [hours, minutes] if minutes < 60 => hours * 3600 + minutes * 60,
+            [hours, minutes, seconds] if minutes < 60 && seconds < 60 => {
+                hours * 3600 + minutes * 60 + seconds
+            }
+            _ => anyhow::bail!(
+                "Invalid duration `{}`, expected `HH:MM` or `HH:MM:SS`",
+                text
+            ),
+        };
+        return Ok(Duration::from_secs(secs));
+    }
+
+    // `1h05` and `5m30`, humantime wants a unit on every number
+    let digits = |part: &str| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit());
+    for (unit, unit_secs) in [('h', 3600), ('m', 60)] {
+        if let Some((whole, rest)) = text.split_once(unit)
+            && digits(whole)
+            && digits(rest)
+            && rest.len() <= 2
+        {
+            let rest = number(rest)?;
+            if rest < 60 {
+                return Ok(Duration::from_secs(
+                    number(whole)? * unit_secs + rest * unit_secs / 60,
+                ));
+            }
+        }
+    }
+
+    if text.contains('.') {
+        return parse_fractional(text);
+    }
+
+    humantime::parse_duration(text).with_context(|| {
+        format!(
+            "Invalid duration `{}`, expected e.g. `1h30m`, `90m` or `1.5h`",
+            text
+        )
+    })
+}
+
+/// `1.5h` or `0.5d 2h`, which humantime doesn't take, rounded to the second.
+fn parse_fractional(text: &str) -> Result<Duration> {
+    let invalid = || anyhow::anyhow!("Invalid duration `{}`, expected e.g. `1.5h`", text);
+    let mut secs = 0.0;
+    let mut rest = text;
+    while !rest.is_empty() {
+        let number_end = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(rest.len());
+        let (number, after) = rest.split_at(number_end);
+        let unit_end = after
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(after.len());
+        let (unit, after) = after.split_at(unit_end);
+
+        let number = number.parse::<f64>().map_err(|_| invalid())?;
+        let unit_secs = UNITS
+            .iter()
+            .find(|(names, _)| names.contains(&unit))
+            .map(|(_, secs)| secs)
+            .ok_or_else(invalid)?;
+        secs += number * unit_secs;
+        rest = after.trim_start();
+    }
+    Ok(Duration::from_secs(secs.round() as u64))
+}
+
+/// Config durations, parsed like the command line's with [`parse`] and written back
+/// the way humantime prints them.
+pub mod duration {
+    use serde::{Deserializer, Serializer, de};
+    use std::{fmt, time::Duration};
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        humantime_serde::serialize(duration, serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        deserializer.deserialize_str(Visitor)
+    }
+
+    struct Visitor;
+
+    impl de::Visitor<'_> for Visitor {
+        type Value = Duration;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a duration like `1h30m`, `90m` or `1.5h`")
+        }
+
+        fn visit_str<E: de::Error>(self, text: &str) -> Result<Duration, E> {
+            super::parse(text).map_err(|e| E::custom(format!("{:#}", e)))
+        }
+    }
+
+    /// The same for optional durations.
+    pub mod option {
+        use serde::{Deserialize as _, Deserializer, Serializer};
+        use std::time::Duration;
+
+        pub fn serialize<S: Serializer>(
+            duration: &Option<Duration>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            humantime_serde::serialize(duration, serializer)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<Duration>, D::Error> {
+            #[derive(serde::Deserialize)]
+            struct Wrapper(#[serde(with = "super")] Duration);
+
+            Ok(Option::<Wrapper>::deserialize(deserializer)?.map(|Wrapper(duration)| duration))
+        }
+    }
+}
+
+/// Parses a time of day, `17:30`.
+pub fn parse_time(text: &str) -> Result<NaiveTime> {
+    NaiveTime::parse_from_str(text.trim(), "%H:%M")
+        .with_context(|| format!("Invalid time `{}`, expected `HH:MM`", text))
+}
+
+/// How long until `time` next comes around, tomorrow if it's already past today.
+pub fn until(time: NaiveTime, now: DateTime<Local>) -> Duration {
+    let today = now.date_naive();
+    [Some(today), today.checked_add_days(Days::new(1))]
+        .into_iter()
+        .flatten()
+        .filter_map(|day| day.and_time(time).and_local_timezone(Local).earliest())
+        .find(|end| *end > now)
+        .and_then(|end| (end - now).to_std().ok())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone as _;
+
+    #[test]
+    fn formats_parse_back() {
+        let duration = Duration::from_secs(3600 + 5 * 60 + 30);
+        assert_eq!(TimeFormat::Human.format(duration), "1h 5m 30s");
+        assert_eq!(TimeFormat::Compact.format(duration), "1h05");
+        assert_eq!(TimeFormat::Compact.format(Duration::from_secs(330)), "5m30");
+        assert_eq!(TimeFormat::Clock.format(duration), "01:05:30");
+
+        assert_eq!(parse("1h 5m 30s").unwrap(), duration);
+        assert_eq!(parse("01:05:30").unwrap(), duration);
+        assert_eq!(parse("1h05").unwrap(), Duration::from_secs(3900));
+        assert_eq!(parse("5m30").unwrap(), Duration::from_secs(330));
+        assert_eq!(parse("1:05").unwrap(), Duration::from_secs(3900));
+        assert_eq!(parse("1h30m").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse("90m").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse("1.5h").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse("0.5d 1.5h").unwrap(), Duration::from_secs(48600));
+        assert!(parse("1:75").is_err());
+        assert!(parse("1.5").is_err());
+        assert!(parse("1.5x").is_err());
+    }
+
+    #[test]
+    fn until_rolls_over_to_tomorrow() {
+        let now = Local.with_ymd_and_hms(2025, 3, 3, 16, 0, 0).unwrap();
+        let time = |text| parse_time(text).unwrap();
+
+        assert_eq!(until(time("17:30"), now), Duration::from_secs(90 * 60));
+        assert_eq!(until(time("09:00"), now), Duration::from_secs(17 * 3600));
+        assert_eq!(until(time("16:00"), now), Duration::from_secs(24 * 3600));
+        assert!(parse_time("25:00").is_err());
+    }
+}
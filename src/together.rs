@@ -0,0 +1,308 @@
+use anyhow::{Context, Ok, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    io::{BufRead as _, BufReader, Read as _, Write as _},
+    net::{TcpListener, TcpStream},
+    process::Command,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use url::Url;
+use uuid::Uuid;
+
+/// Requests with a bigger body than this are cut off, joining only takes a few bytes
+const MAX_BODY: usize = 4096;
+
+/// A room's session, as the relay has it.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Room {
+    /// When everyone in the room unblocks, 0 if nobody is focusing in it
+    pub ends_secs: u64,
+    /// People in the room right now, yourself included
+    pub peers: usize,
+}
+
+impl Room {
+    pub fn remaining(&self) -> Duration {
+        Duration::from_secs(self.ends_secs.saturating_sub(now_secs()))
+    }
+}
+
+/// Being in a room of a relay, kept in the block state so the status can count the
+/// others and the unblock can leave it.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Membership {
+    pub relay: Url,
+    pub room: String,
+    peer: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Join {
+    peer: String,
+    /// How long the session goes if nobody is focusing in the room yet
+    duration_secs: u64,
+}
+
+/// Joins `room` on the relay. The session already going on in it is joined, otherwise
+/// one lasting `duration` starts for everyone joining after.
+pub fn join(relay: &Url, room: &str, duration: Duration) -> Result<(Membership, Room)> {
+    if room.is_empty()
+        || !room
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        anyhow::bail!(
+            "Invalid room `{}`, use letters, digits, `-` and `_` only",
+            room
+        );
+    }
+
+    let membership = Membership {
+        relay: relay.clone(),
+        room: room.to_string(),
+        peer: Uuid::new_v4().to_string(),
+    };
+    let join = Join {
+        peer: membership.peer.clone(),
+        duration_secs: duration.as_secs(),
+    };
+    let response = request(
+        "POST",
+        &membership.url("")?,
+        Some(&serde_json::to_string(&join)?),
+    )?;
+    let room = serde_json::from_str(&response)
+        .with_context(|| format!("Unexpected answer from the relay `{}`", relay))?;
+    Ok((membership, room))
+}
+
+impl Membership {
+    /// The room's session as it is now.
+    pub fn room(&self) -> Result<Room> {
+        let response = request("GET", &self.url("")?, None)?;
+        serde_json::from_str(&response)
+            .with_context(|| format!("Unexpected answer from the relay `{}`", self.relay))
+    }
+
+    /// Leaves the room, so the others stop counting you.
+    pub fn leave(&self) -> Result<()> {
+        request("DELETE", &self.url(&format!("/{}", self.peer))?, None)?;
+        Ok(())
+    }
+
+    fn url(&self, rest: &str) -> Result<Url> {
+        Ok(self.relay.join(&format!("rooms/{}{}", self.room, rest))?)
+    }
+}
+
+fn request(method: &str, url: &Url, body: Option<&str>) -> Result<String> {
+    let mut command = Command::new("curl");
+    command
+        .args(["--silent", "--show-error", "--fail", "--max-time", "10"])
+        .args(["--request", method]);
+    if let Some(body) = body {
+        command
+            .args(["--header", "Content-Type: application/json"])
+            .args(["--data", body]);
+    }
+    let output = command.arg(url.as_str()).output()?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Failed to reach the relay `{}`: {}", url, stderr.trim());
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// A room on the relay's side.
+struct RoomState {
+    ends_secs: u64,
+    peers: HashSet<String>,
+}
+
+/// Runs a relay on `listen`, e.g. `0.0.0.0:7070`, until it's killed. Rooms only live in
+/// memory, and one request is handled at a time, which is plenty for a few rooms.
+pub fn serve(listen: &str) -> Result<()> {
+    let listener =
+        TcpListener::bind(listen).with_context(|| format!("Failed to listen on {}", listen))?;
+    eprintln!("Relaying group sessions on {}", listener.local_addr()?);
+
+    let mut rooms = HashMap::new();
+    for stream in listener.incoming() {
+        let Some(mut stream) = stream.ok() else {
+            continue;
+        };
+        if let Err(e) = handle(&mut stream, &mut rooms) {
+            eprintln!("{:#}", e);
+        }
+    }
+
+    Ok(())
+}
+
+fn handle(stream: &mut TcpStream, rooms: &mut HashMap<String, RoomState>) -> Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header)?;
+        let header = header.trim();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.eq_ignore_ascii_case("content-length")
+        {
+            content_length = value.trim().parse::<usize>()?.min(MAX_BODY);
+        }
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+
+    let (status, response) = route(&request_line, &body, rooms, now_secs());
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        response.len(),
+        response
+    )?;
+    Ok(())
+}
+
+/// Answers one request, `POST /rooms/<room>` to join, `GET /rooms/<room>` and
+/// `DELETE /rooms/<room>/<peer>` to leave.
+fn route(
+    request_line: &str,
+    body: &[u8],
+    rooms: &mut HashMap<String, RoomState>,
+    now_secs: u64,
+) -> (&'static str, String) {
+    rooms.retain(|_, room| room.ends_secs > now_secs);
+    let answer = |room: Option<&RoomState>| {
+        let room = Room {
+            ends_secs: room.map(|room| room.ends_secs).unwrap_or_default(),
+            peers: room.map(|room| room.peers.len()).unwrap_or_default(),
+        };
+        ("200 OK", serde_json::to_string(&room).unwrap_or_default())
+    };
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let path = parts.next().unwrap_or_default();
+    let segments = path.trim_matches('/').split('/').collect::<Vec<_>>();
+    match (method, &segments[..]) {
+        ("POST", ["rooms", room]) => {
+            let Some(join) = serde_json::from_slice::<Join>(body).ok() else {
+                return ("400 Bad Request", "{}".to_string());
+            };
+            let room = rooms.entry(room.to_string()).or_insert_with(|| RoomState {
+                ends_secs: now_secs + join.duration_secs,
+                peers: HashSet::new(),
+            });
+            room.peers.insert(join.peer);
+            answer(Some(room))
+        }
+        ("GET", ["rooms", room]) => answer(rooms.get(*room)),
+        ("DELETE", ["rooms", room, peer]) => {
+            if let Some(state) = rooms.get_mut(*room) {
+                state.peers.remove(*peer);
+            }
+            answer(rooms.get(*room))
+        }
+        _ => ("404 Not Found", "{}".to_string()),
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn peers_share_the_first_session_of_a_room() {
+        let mut rooms = HashMap::new();
+        let join = |peer: &str, duration_secs| {
+            serde_json::to_vec(&Join {
+                peer: peer.to_string(),
+                duration_secs,
+            })
+            .unwrap()
+        };
+        let room = |(status, body): (&str, String)| {
+            assert_eq!(status, "200 OK");
+            serde_json::from_str::<Room>(&body).unwrap()
+        };
+        let (alice, bob) = ("alice", "bob");
+
+        let first = room(route(
+            "POST /rooms/study-hall HTTP/1.1",
+            &join(alice, 3600),
+            &mut rooms,
+            1000,
+        ));
+        assert_eq!((first.ends_secs, first.peers), (4600, 1));
+        let second = room(route(
+            "POST /rooms/study-hall HTTP/1.1",
+            &join(bob, 60),
+            &mut rooms,
+            2000,
+        ));
+        assert_eq!((second.ends_secs, second.peers), (4600, 2));
+
+        let path = format!("DELETE /rooms/study-hall/{} HTTP/1.1", alice);
+        assert_eq!(room(route(&path, &[], &mut rooms, 3000)).peers, 1);
+
+        let over = room(route(
+            "GET /rooms/study-hall HTTP/1.1",
+            &[],
+            &mut rooms,
+            4600,
+        ));
+        assert_eq!((over.ends_secs, over.peers), (0, 0));
+        let next = room(route(
+            "POST /rooms/study-hall HTTP/1.1",
+            &join(bob, 60),
+            &mut rooms,
+            5000,
+        ));
+        assert_eq!(next.ends_secs, 5060);
+    }
+
+    #[test]
+    fn bad_requests_and_room_names_are_turned_away() {
+        let mut rooms = HashMap::new();
+        let (status, _) = route("POST /rooms/study-hall HTTP/1.1", b"{", &mut rooms, 0);
+        assert_eq!(status, "400 Bad Request");
+        let (status, _) = route("GET /peers HTTP/1.1", &[], &mut rooms, 0);
+        assert_eq!(status, "404 Not Found");
+        assert!(rooms.is_empty());
+
+        let relay = Url::parse("http://relay.example.com/").unwrap();
+        for room in ["", "study hall", "../admin"] {
+            assert!(join(&relay, room, Duration::from_secs(60)).is_err());
+        }
+
+        let membership = Membership {
+            relay,
+            room: "study-hall".to_string(),
+            peer: "bob".to_string(),
+        };
+        assert_eq!(
+            membership.url("/bob").unwrap().as_str(),
+            "http://relay.example.com/rooms/study-hall/bob"
+        );
+    }
+}
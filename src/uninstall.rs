@@ -0,0 +1,199 @@
+use anyhow::{Ok, Result};
+use std::{env, fs, path::Path};
+
+#[cfg(all(feature = "pf", target_os = "macos"))]
+use crate::pf::PacketFilter;
+use crate::{
+    audit,
+    block::{BlockManager, data_dir, state_root},
+    browsers,
+    config::EnoughConfig,
+    contract,
+    daemon::{EnoughDaemon, UnblockingDaemon as _},
+    history,
+    hosts::{self, HostsSection},
+    household::Household,
+    resolver::ResolverFiles,
+    user::invoking_uid,
+};
+
+/// Generated by the `focuson` shell function, unlike the user's own config
+const FOCUSON_CONFIG: &str = ".config/enough/focuson.yaml";
+
+/// Removes everything enough set up for the invoking user: the jobs, the hosts section,
+/// pf anchors, the state and their files in the data directory (history aside if
+/// `keep_history`), then checks nothing was left behind. The files shared by every user
+/// go too once no other user has any, but never the ones an admin installed.
+///
+/// Refuses while any block or contract is active, since uninstalling would void them.
+pub fn uninstall(keep_history: bool) -> Result<()> {
+    if let Some(uid) = BlockManager::blocked_uids().first() {
+        anyhow::bail!("User {} has an active block, uninstall once it's over", uid);
+    }
+    if let Some(contract) = contract::active()?.first() {
+        anyhow::bail!(
+            "Contract {} holds until {}, uninstalling would void it",
+            contract.terms.id,
+            contract.terms.expires.format("%Y-%m-%d %H:%M")
+        );
+    }
+
+    BlockManager::new().unblock_all()?;
+    browsers::unharden()?;
+    // jobs and anchors outliving their state file aren't covered by the unblock
+    EnoughDaemon::remove_all(&[])?;
+    #[cfg(all(feature = "pf", target_os = "macos"))]
+    let mut leftovers = PacketFilter::flush_all(invoking_uid())?
+        .into_iter()
+        .map(|anchor| format!("pf anchor {}", anchor))
+        .collect::<Vec<_>>();
+    #[cfg(not(all(feature = "pf", target_os = "macos")))]
+    let mut leftovers = Vec::new();
+
+    let data_dir = data_dir();
+    let uid = invoking_uid();
+    let entries = || {
+        [state_root(), data_dir.clone()]
+            .into_iter()
+            .filter_map(|dir| fs::read_dir(dir).ok())
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+    };
+    let others_stay = entries()
+        .filter_map(|path| owner(&path))
+        .any(|owner| owner != uid);
+    let removed = |path: &Path| is_removed(path, uid, others_stay, keep_history);
+    // the users' own states first, the state directory is one of the shared pieces
+    for path in entries().collect::<Vec<_>>() {
+        if !removed(&path) || !path.exists() {
+            continue;
+        }
+
+        if path.is_dir() {
+            fs::remove_dir_all(&path)?;
+        } else {
+            fs::remove_file(&path)?;
+        }
+    }
+    if data_dir.exists() && fs::read_dir(&data_dir)?.next().is_none() {
+        fs::remove_dir(&data_dir)?;
+    }
+
+    if let Some(home) = env::home_dir() {
+        let focuson = home.join(FOCUSON_CONFIG);
+        if focuson.exists() {
+            fs::remove_file(focuson)?;
+        }
+    }
+
+    leftovers.extend(remaining_traces(removed)?);
+    if !leftovers.is_empty() {
+        anyhow::bail!(
+            "Some traces couldn't be removed:\n  {}",
+            leftovers.join("\n  ")
+        );
+    }
+
+    Ok(())
+}
+
+/// Whether uninstalling for user `uid` removes `path`: their own files, and the shared
+/// ones but an admin's once no other user has any (`others_stay`).
+fn is_removed(path: &Path, uid: u32, others_stay: bool, keep_history: bool) -> bool {
+    let removed = match owner(path) {
+        Some(owner) => owner == uid,
+        None => !others_stay && !is_admin_file(path),
+    };
+    removed && !(keep_history && is_kept_history(path))
+}
+
+/// The uid of the user a file in the data or state directory belongs to, from its name
+/// (`history.501.jsonl`, `lockout.501`, `501.lock`), `None` for the ones every user
+/// shares.
+fn owner(path: &Path) -> Option<u32> {
+    let mut parts = path.file_name()?.to_str()?.split('.');
+    if path.parent() == Some(state_root().as_path()) {
+        return parts.next()?.parse().ok();
+    }
+    parts.nth(1)?.parse().ok()
+}
+
+/// Installed by an admin for every user, which a user's uninstall leaves alone.
+fn is_admin_file(path: &Path) -> bool {
+    path == EnoughConfig::policy_path()
+        || path == Household::path()
+        || path == Household::config_path()
+}
+
+/// The history, its screenshots and the audit log, which `--keep-history` leaves in place.
+fn is_kept_history(path: &Path) -> bool {
+    history::is_history_file(path)
+        || history::is_screenshots_dir(path)
+        || path == audit::audit_log_path()
+}
+
+/// Everything enough could have left on the system that's still there, going by what
+/// should have been `removed` from the data directory.
+fn remaining_traces(removed: impl Fn(&Path) -> bool) -> Result<Vec<String>> {
+    let mut leftovers = Vec::new();
+
+    let state_dir = BlockManager::new().state_dir;
+    if HostsSection::new(&state_dir, invoking_uid()).is_present()? {
+        leftovers.push(format!("hosts section in {}", hosts::HOSTS_FILE));
+    }
+    for path in ResolverFiles::new(&state_dir, invoking_uid()).orphaned()? {
+        leftovers.push(format!("resolver file {}", path.display()));
+    }
+    if state_dir.exists() {
+        leftovers.push(format!("state directory {}", state_dir.display()));
+    }
+    for job_id in EnoughDaemon::installed_jobs()? {
+        leftovers.push(format!("job {}", job_id));
+    }
+    leftovers.extend(
+        fs::read_dir(data_dir())
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| removed(path))
+            .map(|path| path.display().to_string()),
+    );
+
+    Ok(leftovers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn only_the_invoking_users_files_go_while_others_have_some() {
+        let data = data_dir();
+        let state = state_root();
+        assert_eq!(owner(&data.join("history.501.jsonl")), Some(501));
+        assert_eq!(owner(&data.join("lockout.502")), Some(502));
+        assert_eq!(owner(&data.join("scheduler.501")), Some(501));
+        assert_eq!(owner(&state.join("501")), Some(501));
+        assert_eq!(owner(&state.join("502.lock")), Some(502));
+        assert_eq!(owner(&data.join("encrypted")), None);
+        assert_eq!(owner(&data.join("audit.log")), None);
+
+        let removed = |path: PathBuf, others_stay, keep_history| {
+            is_removed(&path, 501, others_stay, keep_history)
+        };
+        assert!(removed(data.join("lockout.501"), true, false));
+        assert!(!removed(data.join("lockout.502"), false, false));
+        assert!(!removed(state.join("502"), false, false));
+        // shared files stay for the other users
+        assert!(!removed(data.join("encrypted"), true, false));
+        assert!(removed(data.join("encrypted"), false, false));
+        assert!(!removed(EnoughConfig::policy_path(), false, false));
+        assert!(!removed(Household::path(), false, false));
+        assert!(!removed(data.join("history.501.jsonl"), false, true));
+        assert!(!removed(audit::audit_log_path(), false, true));
+        assert!(removed(audit::audit_log_path(), false, false));
+    }
+}
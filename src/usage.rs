@@ -0,0 +1,207 @@
+use anyhow::{Ok, Result};
+use chrono::{Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    time::Duration,
+};
+
+use crate::{
+    block::data_dir,
+    daemon::{EnoughDaemon, UnblockingDaemon as _},
+    sandbox,
+    user::{as_invoking_user, invoking_uid},
+};
+
+/// How often the foreground app is sampled
+pub const TRACK_INTERVAL: Duration = Duration::from_secs(60);
+const USAGE_FILE: &str = "app_usage";
+const QUOTA_USAGE_FILE: &str = "quota_usage";
+const QUOTA_WATCHER_DIR: &str = "quota_watcher";
+
+/// Foreground time of apps with a usage limit, for the current day. Kept in the data dir
+/// so starting another block the same day doesn't hand out a fresh allowance.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AppUsage {
+    date: NaiveDate,
+    #[serde(default)]
+    seconds: BTreeMap<PathBuf, u64>,
+}
+
+impl AppUsage {
+    /// Loads today's usage, starting over when the recorded one is from another day.
+    pub fn load(uid: u32) -> Result<Self> {
+        let today = Local::now().date_naive();
+        let path = Self::path(uid);
+        if path.exists() {
+            let usage = serde_yml::from_str::<Self>(&fs::read_to_string(path)?)?;
+            if usage.date == today {
+                return Ok(usage);
+            }
+        }
+
+        Ok(Self {
+            date: today,
+            seconds: BTreeMap::new(),
+        })
+    }
+
+    pub fn save(&self, uid: u32) -> Result<()> {
+        fs::create_dir_all(data_dir())?;
+        fs::write(Self::path(uid), serde_yml::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn add(&mut self, app: &Path, time: Duration) {
+        *self.seconds.entry(app.to_path_buf()).or_default() += time.as_secs();
+    }
+
+    pub fn used(&self, app: &Path) -> Duration {
+        Duration::from_secs(self.seconds.get(app).copied().unwrap_or_default())
+    }
+
+    fn path(uid: u32) -> PathBuf {
+        data_dir().join(format!("{}.{}.yaml", USAGE_FILE, uid))
+    }
+}
+
+/// Foreground time counted against each quota, for the period it's in.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct QuotaUsage {
+    #[serde(default)]
+    quotas: BTreeMap<String, QuotaSpent>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct QuotaSpent {
+    /// First day of the period the seconds were counted in
+    since: NaiveDate,
+    seconds: u64,
+}
+
+impl QuotaUsage {
+    pub fn load(uid: u32) -> Result<Self> {
+        let path = Self::path(uid);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_yml::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, uid: u32) -> Result<()> {
+        fs::create_dir_all(data_dir())?;
+        fs::write(Self::path(uid), serde_yml::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Counts `time` for the quota called `name`, starting over when the period starting
+    /// `since` is a new one.
+    pub fn add(&mut self, name: &str, since: NaiveDate, time: Duration) {
+        let spent = self
+            .quotas
+            .entry(name.to_string())
+            .or_insert(QuotaSpent { since, seconds: 0 });
+        if spent.since != since {
+            *spent = QuotaSpent { since, seconds: 0 };
+        }
+        spent.seconds += time.as_secs();
+    }
+
+    /// Time used of the quota called `name` in the period starting `since`.
+    pub fn used(&self, name: &str, since: NaiveDate) -> Duration {
+        let seconds = self
+            .quotas
+            .get(name)
+            .filter(|spent| spent.since == since)
+            .map(|spent| spent.seconds)
+            .unwrap_or_default();
+        Duration::from_secs(seconds)
+    }
+
+    fn path(uid: u32) -> PathBuf {
+        data_dir().join(format!("{}.{}.yaml", QUOTA_USAGE_FILE, uid))
+    }
+}
+
+/// Where the quota watcher of user `uid` keeps its bookkeeping and logs, since it
+/// outlives blocks and their state directory.
+pub fn quota_watcher_dir(uid: u32) -> PathBuf {
+    data_dir().join(format!("{}.{}", QUOTA_WATCHER_DIR, uid))
+}
+
+/// Starts measuring the quotas of the config at `config`, replacing the watcher running
+/// for another config.
+pub fn watch_quotas(config: &Path) -> Result<()> {
+    EnoughDaemon::watch_quotas(&quota_watcher_dir(invoking_uid()), config, TRACK_INTERVAL)
+}
+
+/// Stops measuring the quotas, returning whether they were.
+pub fn unwatch_quotas() -> Result<bool> {
+    let dir = quota_watcher_dir(invoking_uid());
+    if EnoughDaemon::quota_watcher(&dir).is_none() {
+        return Ok(false);
+    }
+    EnoughDaemon::unwatch_quotas(&dir)?;
+    Ok(true)
+}
+
+/// The bundle of the app in the foreground of the invoking user's session.
+pub fn frontmost_app() -> Option<PathBuf> {
+    let output = as_invoking_user("osascript")
+        .args(["-e", "POSIX path of (path to frontmost application)"])
+        .output()
+        .ok()?;
+
+    let path = String::from_utf8_lossy(&output.stdout);
+    let path = path.trim().trim_end_matches('/');
+    (output.status.success() && !path.is_empty()).then(|| PathBuf::from(path))
+}
+
+/// Running processes started from an app bundle, with the outermost bundle they're in,
+/// so helpers count as their app.
+pub fn running_apps() -> Vec<(u32, PathBuf)> {
+    let Some(output) = Command::new("ps")
+        .args(["-axo", "pid=,comm="])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+    else {
+        return Vec::new();
+    };
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (pid, executable) = line.trim().split_once(' ')?;
+            let bundle = Path::new(executable.trim())
+                .ancestors()
+                .filter(|path| path.extension().is_some_and(|ext| ext == "app"))
+                .last()?;
+            Some((pid.parse().ok()?, bundle.to_path_buf()))
+        })
+        .collect()
+}
+
+/// Terminates process `pid`, for apps that don't get the chance to quit on their own.
+pub fn terminate(pid: u32) -> Result<()> {
+    sandbox::command("kill")
+        .args(["-TERM", &pid.to_string()])
+        .output()?;
+    Ok(())
+}
+
+/// Asks a running app to quit, since blocking its bundle doesn't stop it.
+pub fn quit(app: &Path) -> Result<()> {
+    let Some(name) = app.file_stem() else {
+        return Ok(());
+    };
+
+    let script = format!("quit application \"{}\"", name.to_string_lossy());
+    as_invoking_user("osascript")
+        .args(["-e", &script])
+        .output()?;
+    Ok(())
+}
@@ -0,0 +1,28 @@
+use std::{env, process::Command};
+
+use crate::sandbox;
+
+/// The uid of the user a command acts for: whoever invoked `sudo`, or the current user.
+pub fn invoking_uid() -> u32 {
+    env::var("SUDO_UID")
+        .ok()
+        .and_then(|uid| uid.parse().ok())
+        .unwrap_or_else(|| unsafe { libc::getuid() })
+}
+
+/// Builds a command running `program` as the invoking user rather than root, for
+/// anything touching their GUI session (Shortcuts, AppleScript, `open`).
+pub fn as_invoking_user(program: &str) -> Command {
+    if sandbox::root().is_some() {
+        return sandbox::command(program);
+    }
+
+    match env::var("SUDO_USER") {
+        Ok(user) => {
+            let mut command = Command::new("sudo");
+            command.args(["-u", &user, program]);
+            command
+        }
+        Err(_) => Command::new(program),
+    }
+}
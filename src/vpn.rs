@@ -0,0 +1,30 @@
+use crate::{config::VpnKillSwitch, pf::PacketFilter, sandbox};
+use anyhow::{Ok, Result};
+
+const PF_ANCHOR_NAME: &str = "vpn";
+/// OpenVPN, WireGuard, IPsec/IKE, L2TP and PPTP, which cover nearly every consumer VPN app
+const VPN_RULES: &str = "\
+block drop out quick proto udp to any port { 1194, 51820, 500, 4500, 1701 }
+block drop out quick proto tcp to any port { 1194, 1723 }
+";
+
+/// Drops VPN traffic through pf and kills the configured VPN processes. The pf rules are
+/// flushed with the rest of the block's anchors at unblock.
+pub fn engage(kill_switch: &VpnKillSwitch, pf: &PacketFilter) -> Result<()> {
+    pf.load_anchor(PF_ANCHOR_NAME, VPN_RULES)?;
+
+    for process in &kill_switch.processes {
+        // pkill exits with 1 when nothing matched, which is fine here
+        let output = sandbox::command("pkill").args(["-x", process]).output()?;
+        if output.status.code().is_some_and(|code| code > 1) {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!(
+                "Failed to kill VPN process `{}`: {}",
+                process,
+                stderr.trim()
+            );
+        }
+    }
+
+    Ok(())
+}
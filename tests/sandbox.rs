@@ -0,0 +1,152 @@
+//! End-to-end runs of the CLI in a sandbox (see `ENOUGH_SANDBOX`), which needs neither
+//! sudo nor touches the real hosts file, apps or launchd jobs.
+
+use std::{
+    env, fs,
+    os::unix::fs::PermissionsExt as _,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+use uuid::Uuid;
+
+const HOSTS: &str = "127.0.0.1 localhost\n";
+/// Where the scheduled jobs end up, relative to the sandbox
+#[cfg(target_os = "macos")]
+const JOBS_DIR: &str = "home/Library/LaunchAgents";
+#[cfg(target_os = "linux")]
+const JOBS_DIR: &str = "etc/systemd/system";
+
+struct Sandbox {
+    root: PathBuf,
+}
+
+impl Sandbox {
+    /// A sandbox with a hosts file, a home dir and an app to block.
+    fn new() -> Self {
+        let root = env::temp_dir().join(format!("enough-sandbox-{}", Uuid::new_v4()));
+        fs::create_dir_all(root.join("etc")).unwrap();
+        fs::write(root.join("etc/hosts"), HOSTS).unwrap();
+        fs::create_dir_all(root.join(JOBS_DIR)).unwrap();
+
+        let sandbox = Self { root };
+        let executable = sandbox.app().join("Contents/MacOS/Game");
+        fs::create_dir_all(executable.parent().unwrap()).unwrap();
+        fs::write(&executable, "").unwrap();
+        fs::set_permissions(&executable, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let config = format!(
+            "default-profile: focus\nprofiles:\n  focus:\n    duration: 1h\n    websites:\n      - https://example.com/\n    apps:\n      - {}\n",
+            sandbox.app().display()
+        );
+        fs::write(sandbox.config(), config).unwrap();
+        sandbox
+    }
+
+    fn app(&self) -> PathBuf {
+        self.root.join("Applications/Game.app")
+    }
+
+    fn config(&self) -> PathBuf {
+        self.root.join("enough.yaml")
+    }
+
+    fn hosts(&self) -> String {
+        fs::read_to_string(self.root.join("etc/hosts")).unwrap()
+    }
+
+    /// Runs the CLI, which has to succeed.
+    fn enough(&self, args: &[&str]) -> Output {
+        let output = self.try_enough(args);
+        assert!(
+            output.status.success(),
+            "`enough {}` failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        output
+    }
+
+    fn try_enough(&self, args: &[&str]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_enough"))
+            .args(args)
+            .env("ENOUGH_SANDBOX", &self.root)
+            .env("HOME", self.root.join("home"))
+            .env_remove("SUDO_USER")
+            .env_remove("SUDO_UID")
+            .output()
+            .unwrap()
+    }
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+fn mode(path: &Path) -> u32 {
+    fs::metadata(path).unwrap().permissions().mode() & 0o7777
+}
+
+#[test]
+fn block_then_status_then_unblock() {
+    let sandbox = Sandbox::new();
+    let config = sandbox.config();
+    sandbox.enough(&["block", "-c", config.to_str().unwrap(), "-q"]);
+
+    let status = sandbox.enough(&["status", "--json"]);
+    let status = String::from_utf8_lossy(&status.stdout);
+    assert!(status.contains("\"profile_name\":\"focus\""));
+    assert!(status.contains("\"schema_version\":1"));
+    assert!(sandbox.hosts().contains("0.0.0.0 example.com"));
+    assert_eq!(mode(&sandbox.app()), 0);
+    assert!(
+        fs::read_dir(sandbox.root.join(JOBS_DIR))
+            .unwrap()
+            .next()
+            .is_some()
+    );
+
+    // what the scheduled unblock runs
+    sandbox.enough(&["___zzzunblock", "--fix"]);
+
+    let status = sandbox.enough(&["status", "--json"]);
+    assert!(!String::from_utf8_lossy(&status.stdout).contains("profile_name"));
+    assert_eq!(sandbox.hosts(), HOSTS);
+    assert_eq!(mode(&sandbox.app()), 0o755);
+    assert_eq!(mode(&sandbox.app().join("Contents/MacOS/Game")), 0o755);
+}
+
+#[test]
+fn strict_blocks_only_give_way_to_an_emergency_which_locks_out_the_day() {
+    let sandbox = Sandbox::new();
+    let config = sandbox.config();
+    let mut strict = fs::read_to_string(&config).unwrap();
+    strict.push_str("    strict: true\n");
+    fs::write(&config, strict).unwrap();
+    let config = config.to_str().unwrap();
+    sandbox.enough(&["block", "-c", config, "-q"]);
+
+    assert!(
+        !sandbox
+            .try_enough(&["unblock-early", "-c", config])
+            .status
+            .success()
+    );
+    assert!(
+        !sandbox
+            .try_enough(&["___zzzunblock", "--fix"])
+            .status
+            .success()
+    );
+    assert!(sandbox.hosts().contains("0.0.0.0 example.com"));
+
+    sandbox.enough(&["emergency", "-r", "flat tire", "-c", config]);
+    assert_eq!(sandbox.hosts(), HOSTS);
+    assert_eq!(mode(&sandbox.app()), 0o755);
+
+    let locked_out = sandbox.try_enough(&["block", "-c", config, "-q"]);
+    assert!(!locked_out.status.success());
+    assert!(String::from_utf8_lossy(&locked_out.stderr).contains("locked out"));
+    assert_eq!(sandbox.hosts(), HOSTS);
+}